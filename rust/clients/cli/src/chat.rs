@@ -213,6 +213,9 @@ impl Chat {
                             + " size: "
                             + file_content.file_size.to_string().as_str(),
                     );
+                    if file_content.pruned {
+                        res.push(" file deleted from the file store".to_string());
+                    }
                     return Ok(res);
                 }
                 Some(proto::chat_content_message::Message::GroupEvent(group_event)) => {
@@ -8,7 +8,9 @@
 use super::ble::Ble;
 use super::chat::Chat;
 use super::chatfile::ChatFile;
+use super::config::Config;
 use super::connections::Connections;
+use super::contacts::Contacts;
 use super::debug::Debug;
 use super::dtn::Dtn;
 use super::feed::Feed;
@@ -16,6 +18,7 @@ use super::group::Group;
 use super::node::Node;
 use super::router::Router;
 use super::rtc::Rtc;
+use super::storage::Storage;
 use super::user_accounts::UserAccounts;
 use super::users::Users;
 
@@ -38,6 +41,10 @@ impl Cli {
             cmd if cmd.starts_with("users ") => {
                 Users::cli(cmd.strip_prefix("users ").unwrap());
             }
+            // contact book functions
+            cmd if cmd.starts_with("contacts ") => {
+                Contacts::cli(cmd.strip_prefix("contacts ").unwrap());
+            }
             // router functions
             cmd if cmd.starts_with("router ") => {
                 Router::cli(cmd.strip_prefix("router ").unwrap());
@@ -58,6 +65,10 @@ impl Cli {
             cmd if cmd.starts_with("ble ") => {
                 Ble::cli(cmd.strip_prefix("ble ").unwrap());
             }
+            // configuration functions
+            cmd if cmd.starts_with("config ") => {
+                Config::cli(cmd.strip_prefix("config ").unwrap());
+            }
             // debugging functions
             cmd if cmd.starts_with("debug ") => {
                 Debug::cli(cmd.strip_prefix("debug ").unwrap());
@@ -78,6 +89,10 @@ impl Cli {
             cmd if cmd.starts_with("dtn ") => {
                 Dtn::cli(cmd.strip_prefix("dtn ").unwrap());
             }
+            // storage functions
+            cmd if cmd.starts_with("storage ") => {
+                Storage::cli(cmd.strip_prefix("storage ").unwrap());
+            }
             // unknown command
             _ => log::error!("unknown command"),
         }
@@ -0,0 +1,266 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Configuration Functions
+//!
+//! * display the configuration of libqaul
+//! * display the errors of the configuration file
+//! * change the routing options & the link quality metric
+//! * change the storage maintenance options
+//! * activate or deactivate multipath messaging
+
+use super::rpc::Rpc;
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.config.rs");
+}
+
+/// configuration function handling
+pub struct Config {}
+
+impl Config {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of the configuration module are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // display configuration
+            cmd if cmd.starts_with("show") => {
+                Self::config_request();
+            }
+            // display errors of the configuration file
+            cmd if cmd.starts_with("errors") => {
+                Self::startup_errors_request();
+            }
+            // change routing options
+            cmd if cmd.starts_with("routing ") => {
+                let mut iter = cmd.strip_prefix("routing ").unwrap().split_whitespace();
+                let args: Vec<u64> = iter
+                    .by_ref()
+                    .take(4)
+                    .filter_map(|arg| arg.parse::<u64>().ok())
+                    .collect();
+
+                // the link quality metric is optional
+                let metric = match iter.next() {
+                    None => Some(proto::LinkQualityMetric::HopCount),
+                    Some(name) => proto::LinkQualityMetric::from_str_name(&name.to_uppercase()),
+                };
+
+                match (args.len(), metric) {
+                    (4, Some(metric)) => {
+                        Self::config_update(proto::ConfigUpdate {
+                            routing: Some(proto::RoutingConfig {
+                                sending_table_period: args[0],
+                                ping_neighbour_period: args[1],
+                                hop_count_penalty: args[2],
+                                maintain_period_limit: args[3],
+                                link_quality_metric: metric as i32,
+                            }),
+                            ..Default::default()
+                        });
+                    }
+                    _ => log::error!("usage: config routing {{sending table period}} {{ping neighbour period}} {{hop count penalty}} {{maintain period limit}} [hop_count|etx|bandwidth]"),
+                }
+            }
+            // change storage maintenance options
+            cmd if cmd.starts_with("maintenance ") => {
+                let args: Vec<u64> = cmd
+                    .strip_prefix("maintenance ")
+                    .unwrap()
+                    .split_whitespace()
+                    .filter_map(|arg| arg.parse::<u64>().ok())
+                    .collect();
+
+                if args.len() == 2 {
+                    Self::config_update(proto::ConfigUpdate {
+                        maintenance: Some(proto::MaintenanceConfig {
+                            period: args[0],
+                            feed_max_messages: args[1],
+                        }),
+                        ..Default::default()
+                    });
+                } else {
+                    log::error!("usage: config maintenance {{period}} {{feed max messages}}");
+                }
+            }
+            // activate or deactivate multipath messaging
+            cmd if cmd.starts_with("multipath ") => {
+                let multipath = match cmd.strip_prefix("multipath ").unwrap().trim() {
+                    "on" => true,
+                    "off" => false,
+                    _ => {
+                        log::error!("usage: config multipath {{on|off}}");
+                        return;
+                    }
+                };
+
+                Self::config_update(proto::ConfigUpdate {
+                    messaging: Some(proto::MessagingConfig { multipath }),
+                    ..Default::default()
+                });
+            }
+            // unknown command
+            _ => log::error!("unknown config command"),
+        }
+    }
+
+    /// request the configuration from libqaul
+    fn config_request() {
+        Self::send_message(proto::Config {
+            message: Some(proto::config::Message::ConfigRequest(
+                proto::ConfigRequest {},
+            )),
+        });
+    }
+
+    /// request the errors found in the configuration file on startup
+    fn startup_errors_request() {
+        Self::send_message(proto::Config {
+            message: Some(proto::config::Message::StartupErrorsRequest(
+                proto::StartupErrorsRequest {},
+            )),
+        });
+    }
+
+    /// send a configuration update to libqaul
+    fn config_update(update: proto::ConfigUpdate) {
+        Self::send_message(proto::Config {
+            message: Some(proto::config::Message::ConfigUpdate(update)),
+        });
+    }
+
+    /// encode and send a configuration message
+    fn send_message(message: proto::Config) {
+        // encode message
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Config.into(),
+            "".to_string(),
+        );
+    }
+
+    /// print the configuration
+    fn print_configuration(config: proto::Configuration) {
+        println!("Node ID: {}", config.node_id);
+        if let Some(lan) = config.lan {
+            println!("LAN");
+            println!("  active: {}", lan.active);
+            for address in lan.listen {
+                println!("  listen: {}", address);
+            }
+        }
+        if let Some(internet) = config.internet {
+            println!("Internet");
+            println!("  active: {}", internet.active);
+            println!("  do listen: {}", internet.do_listen);
+            for address in internet.listen {
+                println!("  listen: {}", address);
+            }
+            for peer in internet.peers {
+                println!(
+                    "  peer: {} {} enabled: {}",
+                    peer.address, peer.name, peer.enabled
+                );
+            }
+        }
+        println!("User Accounts");
+        for account in config.user_accounts {
+            println!("  {} {}", account.name, account.id);
+            if let Some(storage) = account.storage {
+                println!("    storage size total: {} MB", storage.size_total);
+                println!("    chat retention: {} days", storage.chat_retention_days);
+                println!("    file store size: {} MB", storage.file_store_size);
+                for user in storage.users {
+                    println!("    storage user: {}", user);
+                }
+            }
+        }
+        if let Some(debug) = config.debug {
+            println!("Debug");
+            println!("  log: {}", debug.log);
+        }
+        if let Some(routing) = config.routing {
+            println!("Routing");
+            println!("  sending table period: {}s", routing.sending_table_period);
+            println!(
+                "  ping neighbour period: {}s",
+                routing.ping_neighbour_period
+            );
+            println!("  hop count penalty: {}s", routing.hop_count_penalty);
+            println!(
+                "  maintain period limit: {}s",
+                routing.maintain_period_limit
+            );
+            if let Ok(metric) = proto::LinkQualityMetric::try_from(routing.link_quality_metric) {
+                println!(
+                    "  link quality metric: {}",
+                    metric.as_str_name().to_lowercase()
+                );
+            }
+        }
+        println!("Storage encrypted: {}", config.encryption_active);
+        if let Some(maintenance) = config.maintenance {
+            println!("Storage Maintenance");
+            println!("  period: {}s", maintenance.period);
+            println!("  feed max messages: {}", maintenance.feed_max_messages);
+        }
+        if let Some(messaging) = config.messaging {
+            println!("Messaging");
+            println!("  multipath: {}", messaging.multipath);
+        }
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the configuration module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Config::decode(&data[..]) {
+            Ok(config) => match config.message {
+                Some(proto::config::Message::ConfigResponse(response)) => {
+                    if let Some(configuration) = response.configuration {
+                        Self::print_configuration(configuration);
+                    }
+                }
+                Some(proto::config::Message::ConfigUpdateResult(result)) => {
+                    if result.success {
+                        println!("configuration updated");
+                        if result.restart_required {
+                            println!("some changes take effect after restarting libqaul");
+                        }
+                    } else {
+                        println!("configuration update failed:");
+                        for error in result.errors {
+                            println!("  {}", error);
+                        }
+                    }
+                }
+                Some(proto::config::Message::StartupErrors(startup_errors)) => {
+                    if startup_errors.errors.is_empty() {
+                        println!("no errors in the configuration file");
+                    } else {
+                        println!("errors in the configuration file:");
+                        for error in startup_errors.errors {
+                            println!("  {}", error);
+                        }
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC config message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Contacts Functions
+//!
+//! Manage the local contact book of the user account.
+
+use super::rpc::Rpc;
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.contacts.rs");
+}
+
+/// contacts function handling
+pub struct Contacts {}
+
+impl Contacts {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of the contacts module are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // list contacts
+            cmd if cmd.starts_with("list") => {
+                let filter = cmd.strip_prefix("list").unwrap().trim();
+                let (favourites, group) = match filter {
+                    "favourites" => (true, String::new()),
+                    group => (false, group.to_string()),
+                };
+                Self::send_message(proto::Contacts {
+                    message: Some(proto::contacts::Message::ContactListRequest(
+                        proto::ContactListRequest { favourites, group },
+                    )),
+                });
+            }
+            // add a contact
+            cmd if cmd.starts_with("add ") => {
+                let args = cmd.strip_prefix("add ").unwrap().trim();
+                let (user_id, nickname) = match args.split_once(' ') {
+                    Some((user_id, nickname)) => (user_id, nickname.trim()),
+                    None => (args, ""),
+                };
+                if let Some(user_id) = Self::decode_id(user_id) {
+                    Self::send_message(proto::Contacts {
+                        message: Some(proto::contacts::Message::ContactAdd(proto::ContactAdd {
+                            user_id,
+                            nickname: nickname.to_string(),
+                            notes: String::new(),
+                            favourite: false,
+                            groups: Vec::new(),
+                        })),
+                    });
+                }
+            }
+            // remove a contact
+            cmd if cmd.starts_with("remove ") => {
+                if let Some(user_id) = Self::decode_id(cmd.strip_prefix("remove ").unwrap()) {
+                    Self::send_message(proto::Contacts {
+                        message: Some(proto::contacts::Message::ContactRemove(
+                            proto::ContactRemove { user_id },
+                        )),
+                    });
+                }
+            }
+            // set the nickname of a contact
+            cmd if cmd.starts_with("nickname ") => {
+                let args = cmd.strip_prefix("nickname ").unwrap().trim();
+                let (user_id, nickname) = args.split_once(' ').unwrap_or((args, ""));
+                Self::send_update(
+                    user_id,
+                    proto::contact_update::Update::Nickname(nickname.trim().to_string()),
+                );
+            }
+            // set the notes of a contact
+            cmd if cmd.starts_with("notes ") => {
+                let args = cmd.strip_prefix("notes ").unwrap().trim();
+                let (user_id, notes) = args.split_once(' ').unwrap_or((args, ""));
+                Self::send_update(
+                    user_id,
+                    proto::contact_update::Update::Notes(notes.trim().to_string()),
+                );
+            }
+            // mark a contact as favourite
+            cmd if cmd.starts_with("favourite ") => {
+                Self::send_update(
+                    cmd.strip_prefix("favourite ").unwrap(),
+                    proto::contact_update::Update::Favourite(true),
+                );
+            }
+            // unmark a contact as favourite
+            cmd if cmd.starts_with("unfavourite ") => {
+                Self::send_update(
+                    cmd.strip_prefix("unfavourite ").unwrap(),
+                    proto::contact_update::Update::Favourite(false),
+                );
+            }
+            // add a contact to a contact group
+            cmd if cmd.starts_with("group add ") => {
+                let args = cmd.strip_prefix("group add ").unwrap().trim();
+                match args.split_once(' ') {
+                    Some((user_id, group)) => Self::send_update(
+                        user_id,
+                        proto::contact_update::Update::AddGroup(group.trim().to_string()),
+                    ),
+                    None => log::error!("contacts group add {{User ID}} {{Group}}"),
+                }
+            }
+            // remove a contact from a contact group
+            cmd if cmd.starts_with("group remove ") => {
+                let args = cmd.strip_prefix("group remove ").unwrap().trim();
+                match args.split_once(' ') {
+                    Some((user_id, group)) => Self::send_update(
+                        user_id,
+                        proto::contact_update::Update::RemoveGroup(group.trim().to_string()),
+                    ),
+                    None => log::error!("contacts group remove {{User ID}} {{Group}}"),
+                }
+            }
+            // unknown command
+            _ => log::error!("unknown contacts command"),
+        }
+    }
+
+    /// decode a base58 encoded user id
+    fn decode_id(user_id_base58: &str) -> Option<Vec<u8>> {
+        match bs58::decode(user_id_base58.trim()).into_vec() {
+            Ok(user_id) => Some(user_id),
+            Err(e) => {
+                log::error!("invalid user id: {}", e);
+                None
+            }
+        }
+    }
+
+    /// create and send a contact update message
+    fn send_update(user_id_base58: &str, update: proto::contact_update::Update) {
+        if let Some(user_id) = Self::decode_id(user_id_base58) {
+            Self::send_message(proto::Contacts {
+                message: Some(proto::contacts::Message::ContactUpdate(
+                    proto::ContactUpdate {
+                        user_id,
+                        update: Some(update),
+                    },
+                )),
+            });
+        }
+    }
+
+    /// encode and send a contacts message
+    fn send_message(message: proto::Contacts) {
+        // encode message
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Contacts.into(),
+            "".to_string(),
+        );
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the contacts module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Contacts::decode(&data[..]) {
+            Ok(contacts) => match contacts.message {
+                Some(proto::contacts::Message::ContactList(contact_list)) => {
+                    println!("Contacts");
+                    for contact in contact_list.contacts {
+                        let mut line =
+                            format!("  {}", bs58::encode(&contact.user_id).into_string());
+                        if !contact.nickname.is_empty() {
+                            line.push_str(&format!(" \"{}\"", contact.nickname));
+                        }
+                        if !contact.name.is_empty() {
+                            line.push_str(&format!(" ({})", contact.name));
+                        }
+                        if contact.favourite {
+                            line.push_str(" *");
+                        }
+                        println!("{}", line);
+                        if contact.online {
+                            println!("    online");
+                        } else if contact.last_seen > 0 {
+                            println!("    last seen: {}", contact.last_seen);
+                        } else {
+                            println!("    never seen");
+                        }
+                        if !contact.groups.is_empty() {
+                            println!("    groups: {}", contact.groups.join(", "));
+                        }
+                        if !contact.notes.is_empty() {
+                            println!("    notes: {}", contact.notes);
+                        }
+                    }
+                }
+                Some(proto::contacts::Message::ContactResult(result)) => {
+                    if result.success {
+                        println!(
+                            "contact {} updated",
+                            bs58::encode(result.user_id).into_string()
+                        );
+                    } else {
+                        println!("contact request failed: {}", result.error);
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC contacts message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
@@ -46,7 +46,15 @@ impl Debug {
             // request storage path location
             cmd if cmd.starts_with("path") => {
                 Self::debug_path_send();
-            },            
+            },
+            // check the data bases
+            cmd if cmd.starts_with("storage check") => {
+                Self::storage_check_send(false);
+            },
+            // check and repair the data bases
+            cmd if cmd.starts_with("storage repair") => {
+                Self::storage_check_send(true);
+            },
             // unknown command
             _ => log::error!("unknown debug command"),
         }
@@ -131,6 +139,25 @@ impl Debug {
         Rpc::send_message(buf, super::rpc::proto::Modules::Debug.into(), "".to_string());        
     }
 
+    /// request a check of the data bases
+    ///
+    /// If repair is true, the problems found are repaired.
+    fn storage_check_send(repair: bool) {
+        // create StorageCheckRequest
+        let proto_message = proto::Debug {
+            message: Some(proto::debug::Message::StorageCheckRequest(
+                proto::StorageCheckRequest{ repair }
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message.encode(&mut buf).expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(buf, super::rpc::proto::Modules::Debug.into(), "".to_string());
+    }
+
     /// send a debugging message to libqaul that
     /// let's it panic.
     fn panic() {
@@ -165,6 +192,33 @@ impl Debug {
                         // printout path
                         println!("Storage Path: {}", storage_path_response.storage_path);
                     },
+                    Some(proto::debug::Message::StorageCheckResponse(storage_check_response)) => {
+                        // print problems
+                        for problem in &storage_check_response.problems {
+                            let data_base = match problem.user_account.is_empty() {
+                                true => "node".to_string(),
+                                false => bs58::encode(&problem.user_account).into_string(),
+                            };
+                            let state = match (problem.repairable, problem.repaired) {
+                                (_, true) => "repaired",
+                                (true, false) => "repairable",
+                                (false, false) => "not repairable",
+                            };
+                            println!("{} {} {}: {} ({})",
+                                data_base,
+                                problem.tree,
+                                bs58::encode(&problem.key).into_string(),
+                                problem.description,
+                                state
+                            );
+                        }
+
+                        // print summary
+                        println!("Storage check: {} entries checked, {} problems found",
+                            storage_check_response.entries_checked,
+                            storage_check_response.problems.len()
+                        );
+                    },
                     _ => {
                         log::error!("unprocessable RPC debug message");
                     },
@@ -10,6 +10,7 @@ use async_std::io;
 use futures::prelude::*;
 use futures::{future::FutureExt, pin_mut, select};
 use futures_ticker::Ticker;
+use std::collections::BTreeMap;
 use std::time::Duration;
 
 use libqaul;
@@ -18,6 +19,8 @@ mod ble;
 mod chat;
 mod chatfile;
 mod cli;
+mod config;
+mod contacts;
 mod connections;
 mod debug;
 mod dtn;
@@ -27,6 +30,8 @@ mod node;
 mod router;
 mod rpc;
 mod rtc;
+mod storage;
+mod upgrade;
 mod user_accounts;
 mod users;
 
@@ -46,11 +51,42 @@ async fn main() {
     let path = std::env::current_dir().unwrap();
     let storage_path = path.as_path().to_str().unwrap().to_string();
 
+    // hand over the storage passphrase if provided.
+    // An unencrypted storage gets encrypted with this passphrase.
+    if let Ok(passphrase) = std::env::var("QAUL_STORAGE_PASSPHRASE") {
+        libqaul::api::unlock_storage(passphrase);
+    }
+
+    // test the upgrade of the stored data without changing it
+    let mut config = None;
+    if std::env::var("QAUL_UPGRADE_DRY_RUN").is_ok() {
+        let mut options = BTreeMap::new();
+        options.insert("upgrade_dry_run".to_string(), "true".to_string());
+        config = Some(options);
+    }
+
     // start libqaul in new thread and save configuration file to current working path
-    libqaul::api::start_with_config(storage_path, None);
+    libqaul::api::start_with_config(storage_path, config);
 
     // wait until libqaul finished initializing
     while libqaul::api::initialization_finished() == false {
+        // display the upgrade result and quit if libqaul stopped
+        if libqaul::api::upgrade_stopped() {
+            while let Ok(message) = libqaul::api::receive_rpc() {
+                Rpc::received_message(message);
+            }
+            return;
+        }
+
+        // ask for the passphrase if the storage is encrypted
+        if libqaul::api::storage_locked() {
+            println!("Please enter the storage passphrase:");
+            let mut passphrase = String::new();
+            if std::io::stdin().read_line(&mut passphrase).is_ok() {
+                libqaul::api::unlock_storage(passphrase.trim_end().to_string());
+            }
+        }
+
         // wait a little while
         std::thread::sleep(Duration::from_millis(10));
     }
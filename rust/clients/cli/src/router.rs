@@ -34,6 +34,38 @@ impl Router {
             cmd if cmd.starts_with("connections list") => {
                 Self::request_connections_list();
             }
+            // request routing violations & quarantined neighbours
+            cmd if cmd.starts_with("guard list") => {
+                Self::request_guard_list();
+            }
+            // request dropped messages per neighbour
+            cmd if cmd.starts_with("limits list") => {
+                Self::request_rate_limit_list();
+            }
+            // request the mesh topology graph
+            cmd if cmd.starts_with("topology") => {
+                let format = match cmd.strip_prefix("topology").unwrap().trim() {
+                    "" | "json" => proto::TopologyFormat::Json,
+                    "dot" => proto::TopologyFormat::Dot,
+                    _ => {
+                        log::error!("unknown topology format, use json or dot");
+                        return;
+                    }
+                };
+                Self::request_topology(format);
+            }
+            // subscribe or unsubscribe to the router events
+            cmd if cmd.starts_with("events") => {
+                let subscribe = match cmd.strip_prefix("events").unwrap().trim() {
+                    "" | "on" => true,
+                    "off" => false,
+                    _ => {
+                        log::error!("unknown events option, use on or off");
+                        return;
+                    }
+                };
+                Self::request_event_subscribe(subscribe);
+            }
             // unknown command
             _ => log::error!("unknown router command"),
         }
@@ -78,6 +110,60 @@ impl Router {
         Self::send_message(proto_message);
     }
 
+    /// create rpc request for routing guard list
+    fn request_guard_list() {
+        // create request message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RoutingGuardRequest(
+                proto::RoutingGuardRequest {},
+            )),
+        };
+
+        // send message
+        Self::send_message(proto_message);
+    }
+
+    /// create rpc request for rate limit list
+    fn request_rate_limit_list() {
+        // create request message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RateLimitRequest(
+                proto::RateLimitRequest {},
+            )),
+        };
+
+        // send message
+        Self::send_message(proto_message);
+    }
+
+    /// create rpc request for the mesh topology graph
+    fn request_topology(format: proto::TopologyFormat) {
+        // create request message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::TopologyRequest(
+                proto::TopologyRequest {
+                    format: format as i32,
+                },
+            )),
+        };
+
+        // send message
+        Self::send_message(proto_message);
+    }
+
+    /// create rpc request to subscribe to the router events
+    fn request_event_subscribe(subscribe: bool) {
+        // create request message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RouterEventSubscribe(
+                proto::RouterEventSubscribe { subscribe },
+            )),
+        };
+
+        // send message
+        Self::send_message(proto_message);
+    }
+
     /// Encode and send protobuf message
     fn send_message(message: proto::Router) {
         // encode message
@@ -128,6 +214,7 @@ impl Router {
                                         Ok(proto::ConnectionModule::Internet) => "Internet",
                                         Ok(proto::ConnectionModule::Ble) => "Ble",
                                         Ok(proto::ConnectionModule::Local) => "Local",
+                                        Ok(proto::ConnectionModule::Virtual) => "Virtual",
                                         Err(_) => "Unknown",
                                     };
 
@@ -155,6 +242,12 @@ impl Router {
 
                         println!("Internet Neighbours");
                         Self::rpc_display_neighbours_list(proto_message.internet);
+
+                        // only simulated nodes have virtual neighbours
+                        if !proto_message.virtual_net.is_empty() {
+                            println!("Virtual Neighbours");
+                            Self::rpc_display_neighbours_list(proto_message.virtual_net);
+                        }
                     }
                     Some(proto::router::Message::ConnectionsList(proto_message)) => {
                         println!("");
@@ -166,6 +259,84 @@ impl Router {
 
                         println!("Internet Connections");
                         Self::rpc_display_connections_list(proto_message.internet);
+
+                        // only simulated nodes have virtual connections
+                        if !proto_message.virtual_net.is_empty() {
+                            println!("Virtual Connections");
+                            Self::rpc_display_connections_list(proto_message.virtual_net);
+                        }
+                    }
+                    Some(proto::router::Message::RoutingGuardList(proto_message)) => {
+                        println!("");
+                        println!("Routing Guard - Routing Violations of Neighbours");
+                        println!("");
+
+                        println!("Quarantined Neighbours");
+                        println!("Node Id | violations | quarantined until");
+                        for neighbour in proto_message.quarantined {
+                            println!(
+                                "{} | {} | {}",
+                                bs58::encode(neighbour.node_id).into_string(),
+                                neighbour.violations,
+                                neighbour.quarantined_until
+                            );
+                        }
+                        println!("");
+
+                        println!("Latest Violations");
+                        println!("Time | Node Id | violation | User q8id");
+                        for event in proto_message.events {
+                            let violation = match proto::RoutingViolation::try_from(event.violation)
+                            {
+                                Ok(violation) => violation.as_str_name(),
+                                Err(_) => "UNKNOWN",
+                            };
+                            println!(
+                                "{} | {} | {} | {}",
+                                event.time,
+                                bs58::encode(event.node_id).into_string(),
+                                violation,
+                                bs58::encode(event.user_q8id).into_string()
+                            );
+                        }
+                        println!("");
+                    }
+                    Some(proto::router::Message::RateLimitList(proto_message)) => {
+                        println!("");
+                        println!("Rate Limits - Dropped Messages per Neighbour");
+                        println!("");
+
+                        println!("Node Id | router info | feed | feed requests | user requests");
+                        for neighbour in proto_message.neighbours {
+                            println!(
+                                "{} | {} | {} | {} | {}",
+                                bs58::encode(neighbour.node_id).into_string(),
+                                neighbour.router_info,
+                                neighbour.feed,
+                                neighbour.feed_request,
+                                neighbour.user_request
+                            );
+                        }
+                        println!("");
+                    }
+                    Some(proto::router::Message::Topology(proto_message)) => {
+                        println!("{}", proto_message.graph);
+                    }
+                    Some(proto::router::Message::RouterEvent(proto_message)) => {
+                        let event = match proto::RouterEventType::try_from(proto_message.event) {
+                            Ok(event) => event.as_str_name(),
+                            Err(_) => "UNKNOWN",
+                        };
+                        let module = match proto::ConnectionModule::try_from(proto_message.module) {
+                            Ok(module) => module.as_str_name(),
+                            Err(_) => "UNKNOWN",
+                        };
+                        let user = if proto_message.user_id.is_empty() {
+                            bs58::encode(proto_message.user_q8id).into_string()
+                        } else {
+                            bs58::encode(proto_message.user_id).into_string()
+                        };
+                        println!("router event {} {} {}", event, user, module);
                     }
                     _ => {
                         log::error!("unprocessable RPC router message");
@@ -182,9 +353,11 @@ impl Router {
     fn rpc_display_neighbours_list(neighbours_list: Vec<proto::NeighboursEntry>) {
         for entry in neighbours_list {
             println!(
-                "{}, {} rtt",
+                "{}, {} rtt, {} jitter, {:.0}% loss",
                 bs58::encode(entry.node_id).into_string(),
-                entry.rtt
+                entry.rtt,
+                entry.jitter,
+                entry.loss * 100.0
             );
         }
         println!("");
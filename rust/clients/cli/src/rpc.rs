@@ -112,6 +112,18 @@ impl Rpc {
                     Ok(proto::Modules::Dtn) => {
                         super::dtn::Dtn::rpc(message.data);
                     }
+                    Ok(proto::Modules::Config) => {
+                        super::config::Config::rpc(message.data);
+                    }
+                    Ok(proto::Modules::Upgrade) => {
+                        super::upgrade::Upgrade::rpc(message.data);
+                    }
+                    Ok(proto::Modules::Storage) => {
+                        super::storage::Storage::rpc(message.data);
+                    }
+                    Ok(proto::Modules::Contacts) => {
+                        super::contacts::Contacts::rpc(message.data);
+                    }
                     Ok(proto::Modules::None) => {}
                     Err(_) => {}
                 }
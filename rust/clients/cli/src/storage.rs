@@ -0,0 +1,105 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage Functions
+//!
+//! * display the storage usage of the user accounts
+//! * run the storage maintenance
+
+use super::rpc::Rpc;
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.storage.rs");
+}
+
+/// storage function handling
+pub struct Storage {}
+
+impl Storage {
+    /// CLI command interpretation
+    ///
+    /// The CLI commands of the storage module are processed here
+    pub fn cli(command: &str) {
+        match command {
+            // display storage usage
+            cmd if cmd.starts_with("usage") => {
+                Self::send_message(proto::Storage {
+                    message: Some(proto::storage::Message::StorageUsageRequest(
+                        proto::StorageUsageRequest {},
+                    )),
+                });
+            }
+            // run storage maintenance
+            cmd if cmd.starts_with("maintenance") => {
+                Self::send_message(proto::Storage {
+                    message: Some(proto::storage::Message::MaintenanceRequest(
+                        proto::MaintenanceRequest {},
+                    )),
+                });
+            }
+            // unknown command
+            _ => log::error!("unknown storage command"),
+        }
+    }
+
+    /// encode and send a storage message
+    fn send_message(message: proto::Storage) {
+        // encode message
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Storage.into(),
+            "".to_string(),
+        );
+    }
+
+    /// format a size in bytes
+    fn format_size(size: u64) -> String {
+        if size >= 1024 * 1024 {
+            format!("{:.1} MB", size as f64 / (1024.0 * 1024.0))
+        } else if size >= 1024 {
+            format!("{:.1} KB", size as f64 / 1024.0)
+        } else {
+            format!("{} B", size)
+        }
+    }
+
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the storage module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Storage::decode(&data[..]) {
+            Ok(storage) => match storage.message {
+                Some(proto::storage::Message::StorageUsageResponse(response)) => {
+                    println!("Storage Usage");
+                    for account in response.accounts {
+                        println!("  {}", bs58::encode(account.user_id).into_string());
+                        println!("    chat: {}", Self::format_size(account.chat));
+                        println!("    files: {}", Self::format_size(account.files));
+                        println!("    feed: {}", Self::format_size(account.feed));
+                        println!("    dtn: {}", Self::format_size(account.dtn));
+                        println!("    crypto: {}", Self::format_size(account.crypto));
+                    }
+                    println!(
+                        "  feed messages of all users: {}",
+                        Self::format_size(response.feed_total)
+                    );
+                }
+                _ => {
+                    log::error!("unprocessable RPC storage message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
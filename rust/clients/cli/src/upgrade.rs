@@ -0,0 +1,67 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Upgrade Functions
+//!
+//! Display the progress of the upgrade of the stored data.
+
+use prost::Message;
+
+/// include generated protobuf RPC rust definition file
+mod proto {
+    include!("../../../libqaul/src/rpc/protobuf_generated/rust/qaul.rpc.upgrade.rs");
+}
+
+/// upgrade function handling
+pub struct Upgrade {}
+
+impl Upgrade {
+    /// Process received RPC message
+    ///
+    /// Decodes received protobuf encoded binary RPC message
+    /// of the upgrade module.
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Upgrade::decode(&data[..]) {
+            Ok(upgrade) => match upgrade.message {
+                Some(proto::upgrade::Message::UpgradeProgress(progress)) => {
+                    let dry_run = match progress.dry_run {
+                        true => " (dry run)",
+                        false => "",
+                    };
+
+                    match proto::UpgradeState::try_from(progress.state) {
+                        Ok(proto::UpgradeState::Started) => println!(
+                            "upgrade{} from {} to {}: {} steps",
+                            dry_run, progress.from_version, progress.to_version, progress.steps
+                        ),
+                        Ok(proto::UpgradeState::StepStarted) => println!(
+                            "upgrade{} step {}/{}: upgrade to {}",
+                            dry_run, progress.step, progress.steps, progress.step_version
+                        ),
+                        Ok(proto::UpgradeState::StepFinished) => println!(
+                            "upgrade{} step {}/{}: finished",
+                            dry_run, progress.step, progress.steps
+                        ),
+                        Ok(proto::UpgradeState::Finished) => {
+                            println!("upgrade{} finished successfully", dry_run)
+                        }
+                        Ok(proto::UpgradeState::Failed) => {
+                            println!("upgrade{} failed: {}", dry_run, progress.error)
+                        }
+                        Ok(proto::UpgradeState::RolledBack) => println!(
+                            "the stored data was restored to version {}",
+                            progress.from_version
+                        ),
+                        Err(_) => log::error!("unknown upgrade state"),
+                    }
+                }
+                _ => {
+                    log::error!("unprocessable RPC upgrade message");
+                }
+            },
+            Err(error) => {
+                log::error!("{:?}", error);
+            }
+        }
+    }
+}
@@ -71,6 +71,44 @@ impl UserAccounts {
             cmd if cmd.starts_with("create ") => {
                 Self::create_user_account(cmd.strip_prefix("create ").unwrap().to_string());
             }
+            // export user account to an archive file
+            cmd if cmd.starts_with("export ") => {
+                let args: Vec<&str> = cmd
+                    .strip_prefix("export ")
+                    .unwrap()
+                    .split_whitespace()
+                    .collect();
+
+                if args.len() == 2 || args.len() == 3 {
+                    match bs58::decode(args[0]).into_vec() {
+                        Ok(user_id) => Self::export_user_account(
+                            user_id,
+                            args[1].to_string(),
+                            args.get(2).unwrap_or(&"").to_string(),
+                        ),
+                        Err(e) => log::error!("invalid user ID: {}", e),
+                    }
+                } else {
+                    log::error!("usage: account export {{User ID}} {{Path}} [{{Passphrase}}]");
+                }
+            }
+            // import user account from an archive file
+            cmd if cmd.starts_with("import ") => {
+                let args: Vec<&str> = cmd
+                    .strip_prefix("import ")
+                    .unwrap()
+                    .split_whitespace()
+                    .collect();
+
+                if args.len() == 1 || args.len() == 2 {
+                    Self::import_user_account(
+                        args[0].to_string(),
+                        args.get(1).unwrap_or(&"").to_string(),
+                    );
+                } else {
+                    log::error!("usage: account import {{Path}} [{{Passphrase}}]");
+                }
+            }
             // unknown command
             _ => log::error!("unknown account command"),
         }
@@ -99,6 +137,44 @@ impl UserAccounts {
         );
     }
 
+    /// Export a user account to an archive file
+    fn export_user_account(user_id: Vec<u8>, path: String, passphrase: String) {
+        Self::send_message(proto::UserAccounts {
+            message: Some(proto::user_accounts::Message::ExportUserAccount(
+                proto::ExportUserAccount {
+                    user_id,
+                    path,
+                    passphrase,
+                },
+            )),
+        });
+    }
+
+    /// Import a user account from an archive file
+    fn import_user_account(path: String, passphrase: String) {
+        Self::send_message(proto::UserAccounts {
+            message: Some(proto::user_accounts::Message::ImportUserAccount(
+                proto::ImportUserAccount { path, passphrase },
+            )),
+        });
+    }
+
+    /// encode and send a user accounts message
+    fn send_message(proto_message: proto::UserAccounts) {
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Useraccounts.into(),
+            "".to_string(),
+        );
+    }
+
     /// Request default user account
     fn request_default_account() {
         // create info request message
@@ -182,6 +258,27 @@ impl UserAccounts {
                         user_accounts.my_user_account = Some(proto_myuseraccount);
                         user_accounts.initialiation = MyUserAccountInitialiation::Initialized;
                     }
+                    Some(proto::user_accounts::Message::ExportUserAccountResult(result)) => {
+                        if result.success {
+                            println!("User account exported to {}", result.path);
+                        } else {
+                            println!("User account export failed: {}", result.error);
+                        }
+                    }
+                    Some(proto::user_accounts::Message::ImportUserAccountResult(result)) => {
+                        if result.success {
+                            if let Some(my_user_account) = result.my_user_account {
+                                println!("User account imported:");
+                                println!(
+                                    "{}, ID[{}]",
+                                    my_user_account.name, my_user_account.id_base58
+                                );
+                                println!("    public key: {}", my_user_account.key_base58);
+                            }
+                        } else {
+                            println!("User account import failed: {}", result.error);
+                        }
+                    }
                     _ => {
                         log::error!("unprocessable RPC user accounts message");
                     }
@@ -31,6 +31,15 @@ impl Users {
             cmd if cmd.starts_with("online") => {
                 Self::request_online_user_list();
             }
+            // query a page of users
+            cmd if cmd.starts_with("query") => {
+                let args = cmd.strip_prefix("query").unwrap();
+                Self::send_user_query(args);
+            }
+            // request list of all blocked users
+            cmd if cmd.starts_with("blocked") => {
+                Self::request_blocked_user_list();
+            }
             // verify a user
             cmd if cmd.starts_with("verify ") => {
                 let user_id = cmd.strip_prefix("verify ").unwrap();
@@ -43,11 +52,45 @@ impl Users {
 
                 Self::send_user_update(user_id, false, true);
             }
+            // update the profile of the user account
+            cmd if cmd.starts_with("profile ") => {
+                let args = cmd.strip_prefix("profile ").unwrap().trim();
+                let (name, status) = match args.split_once(' ') {
+                    Some((name, status)) => (name, status.trim()),
+                    None => (args, ""),
+                };
+                Self::send_profile_update(name, status);
+            }
+            // export the identity code of the user account
+            cmd if cmd.starts_with("identity export") => {
+                Self::send_message(proto::users::Message::IdentityExportRequest(
+                    proto::IdentityExportRequest {},
+                ));
+            }
+            // import the identity code of another user
+            cmd if cmd.starts_with("identity import ") => {
+                let code = cmd.strip_prefix("identity import ").unwrap().trim();
+                Self::send_message(proto::users::Message::IdentityImport(
+                    proto::IdentityImport {
+                        code: code.to_string(),
+                    },
+                ));
+            }
             // security number for a user
             cmd if cmd.starts_with("secure ") => {
                 let user_id = cmd.strip_prefix("secure ").unwrap();
                 Self::send_user_secure_number(user_id);
             }
+            // test the reachability of a user
+            cmd if cmd.starts_with("ping ") => {
+                let args = cmd.strip_prefix("ping ").unwrap();
+                Self::send_user_ping(args, false);
+            }
+            // test the reachability of a user and trace the route
+            cmd if cmd.starts_with("traceroute ") => {
+                let args = cmd.strip_prefix("traceroute ").unwrap();
+                Self::send_user_ping(args, true);
+            }
             // unknown command
             _ => log::error!("unknown users command"),
         }
@@ -96,6 +139,161 @@ impl Users {
         );
     }
 
+    /// encode and send a users message
+    fn send_message(message: proto::users::Message) {
+        let proto_message = proto::Users {
+            message: Some(message),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+        );
+    }
+
+    /// create rpc user query message
+    ///
+    /// The arguments are space separated options:
+    /// `name={Text}`, `verified`, `unverified`, `blocked`, `unblocked`,
+    /// `online`, `offline`, `module={Module}`, `sort=name|seen`,
+    /// `offset={Offset}` and `limit={Limit}`
+    fn send_user_query(args: &str) {
+        let mut query = proto::UserQuery {
+            offset: 0,
+            limit: 0,
+            search: String::new(),
+            verified: proto::UserFilter::Any as i32,
+            blocked: proto::UserFilter::Any as i32,
+            online: proto::UserFilter::Any as i32,
+            module: proto::ConnectionModule::None as i32,
+            sort: proto::UserSort::Name as i32,
+        };
+
+        for arg in args.split_whitespace() {
+            match arg {
+                "verified" => query.verified = proto::UserFilter::Only as i32,
+                "unverified" => query.verified = proto::UserFilter::Exclude as i32,
+                "blocked" => query.blocked = proto::UserFilter::Only as i32,
+                "unblocked" => query.blocked = proto::UserFilter::Exclude as i32,
+                "online" => query.online = proto::UserFilter::Only as i32,
+                "offline" => query.online = proto::UserFilter::Exclude as i32,
+                "sort=name" => query.sort = proto::UserSort::Name as i32,
+                "sort=seen" => query.sort = proto::UserSort::LastSeen as i32,
+                arg if arg.starts_with("name=") => {
+                    query.search = arg.strip_prefix("name=").unwrap().to_string();
+                }
+                arg if arg.starts_with("module=") => {
+                    let module = arg.strip_prefix("module=").unwrap().to_uppercase();
+                    match proto::ConnectionModule::from_str_name(&module) {
+                        Some(module) => query.module = module as i32,
+                        None => {
+                            log::error!("unknown connection module {}", module);
+                            return;
+                        }
+                    }
+                }
+                arg if arg.starts_with("offset=") => {
+                    match arg.strip_prefix("offset=").unwrap().parse() {
+                        Ok(offset) => query.offset = offset,
+                        Err(e) => {
+                            log::error!("invalid offset: {}", e);
+                            return;
+                        }
+                    }
+                }
+                arg if arg.starts_with("limit=") => {
+                    match arg.strip_prefix("limit=").unwrap().parse() {
+                        Ok(limit) => query.limit = limit,
+                        Err(e) => {
+                            log::error!("invalid limit: {}", e);
+                            return;
+                        }
+                    }
+                }
+                arg => {
+                    log::error!("unknown users query option {}", arg);
+                    return;
+                }
+            }
+        }
+
+        // create request message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::UserQuery(query)),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+        );
+    }
+
+    /// create rpc request for blocked user list
+    fn request_blocked_user_list() {
+        // create request message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::BlockedUsersRequest(
+                proto::BlockedUsersRequest {},
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+        );
+    }
+
+    /// create rpc profile update message
+    fn send_profile_update(name: &str, status: &str) {
+        // create request message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::UserProfileUpdate(
+                proto::UserProfileUpdate {
+                    name: name.to_string(),
+                    status: status.to_string(),
+                    avatar: Vec::new(),
+                },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+        );
+    }
+
     /// create rpc user security number message
     fn send_user_secure_number(user_id_base58: &str) {
         let user_id = bs58::decode(user_id_base58).into_vec().unwrap();
@@ -121,6 +319,72 @@ impl Users {
         );
     }
 
+    /// create rpc user ping request
+    ///
+    /// The arguments are the user id and an optional probe count.
+    fn send_user_ping(args: &str, trace: bool) {
+        let mut iter = args.split_whitespace();
+        let user_id = match iter.next().map(|id| bs58::decode(id).into_vec()) {
+            Some(Ok(user_id)) => user_id,
+            _ => {
+                log::error!("invalid user id");
+                return;
+            }
+        };
+        let count = match iter.next() {
+            Some(count) => match count.parse::<u32>() {
+                Ok(count) => count,
+                Err(_) => {
+                    log::error!("invalid probe count");
+                    return;
+                }
+            },
+            None => 0,
+        };
+
+        // create request message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::UserPingRequest(
+                proto::UserPingRequest {
+                    user_id,
+                    count,
+                    trace,
+                },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            super::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+        );
+    }
+
+    /// print a traced probe route
+    fn print_route(route: Vec<proto::UserPingHop>) {
+        println!("    hop | module | rtt | node id");
+        for (hop, entry) in route.into_iter().enumerate() {
+            let module = match proto::ConnectionModule::try_from(entry.module) {
+                Ok(module) => module.as_str_name(),
+                Err(_) => "UNKNOWN",
+            };
+            println!(
+                "    {} | {} | {} | {}",
+                hop,
+                module,
+                entry.rtt,
+                bs58::encode(entry.node_id).into_string()
+            );
+        }
+    }
+
     /// create rpc user update message
     fn send_user_update(user_id_base58: &str, verified: bool, blocked: bool) {
         let user_id = bs58::decode(user_id_base58).into_vec().unwrap();
@@ -136,6 +400,10 @@ impl Users {
                 verified,
                 blocked,
                 connections: vec![],
+                status: String::from(""),
+                avatar: Vec::new(),
+                profile_revision: 0,
+                last_seen: 0,
             })),
         };
 
@@ -153,6 +421,79 @@ impl Users {
         );
     }
 
+    /// print a list of users
+    ///
+    /// The users are numbered starting with `first`.
+    fn print_users(users: Vec<proto::UserEntry>, first: usize) {
+        let mut line = first;
+        println!("No. | User Name | User Id | Veryfied | Blocked | Connectivity");
+        println!("    | Group ID | Public Key");
+
+        for user in users {
+            let mut verified = "N";
+            let mut blocked = "N";
+            let mut onlined = "Offline";
+
+            if user.verified {
+                verified = "Y";
+            }
+            if user.blocked {
+                blocked = "Y";
+            }
+            if user.connectivity == 1 {
+                onlined = "Online";
+            }
+            println!(
+                "{} | {} | {:?} | {} | {} | {}",
+                line,
+                user.name,
+                bs58::encode(user.id).into_string(),
+                verified,
+                blocked,
+                onlined
+            );
+            let group_uuid;
+            match Uuid::from_slice(&user.group_id) {
+                Ok(uuid) => {
+                    group_uuid = uuid;
+                    println!(
+                        "   | {} | {}",
+                        group_uuid.hyphenated().to_string(),
+                        user.key_base58
+                    );
+                }
+                Err(e) => log::error!("{}", e),
+            }
+            if user.profile_revision > 0 {
+                println!(
+                    "   | Profile revision {} | {} | {} bytes avatar",
+                    user.profile_revision,
+                    user.status,
+                    user.avatar.len()
+                );
+            }
+            if user.connections.len() > 0 {
+                println!("  Connections: module | hc | rtt | via");
+                for cnn in user.connections {
+                    let module = proto::ConnectionModule::try_from(cnn.module)
+                        .unwrap()
+                        .as_str_name();
+                    println!(
+                        "      {} | {} | {} | {}",
+                        module,
+                        cnn.hop_count,
+                        cnn.rtt,
+                        bs58::encode(cnn.via.clone()).into_string()
+                    );
+                }
+            }
+            if user.connectivity != 1 && user.last_seen > 0 {
+                println!("   | Last seen {}", user.last_seen);
+            }
+            line += 1;
+        }
+    }
+
     /// Process received RPC message
     ///
     /// Decodes received protobuf encoded binary RPC message
@@ -161,65 +502,20 @@ impl Users {
         match proto::Users::decode(&data[..]) {
             Ok(users) => match users.message {
                 Some(proto::users::Message::UserList(proto_userlist)) => {
-                    let mut line = 1;
                     println!("");
                     println!("All known Users");
-                    println!("No. | User Name | User Id | Veryfied | Blocked | Connectivity");
-                    println!("    | Group ID | Public Key");
-
-                    for user in proto_userlist.user {
-                        let mut verified = "N";
-                        let mut blocked = "N";
-                        let mut onlined = "Offline";
-
-                        if user.verified {
-                            verified = "Y";
-                        }
-                        if user.blocked {
-                            blocked = "Y";
-                        }
-                        if user.connectivity == 1 {
-                            onlined = "Online";
-                        }
-                        println!(
-                            "{} | {} | {:?} | {} | {} | {}",
-                            line,
-                            user.name,
-                            bs58::encode(user.id).into_string(),
-                            verified,
-                            blocked,
-                            onlined
-                        );
-                        let group_uuid;
-                        match Uuid::from_slice(&user.group_id) {
-                            Ok(uuid) => {
-                                group_uuid = uuid;
-                                println!(
-                                    "   | {} | {}",
-                                    group_uuid.hyphenated().to_string(),
-                                    user.key_base58
-                                );
-                            }
-                            Err(e) => log::error!("{}", e),
-                        }
-                        if user.connections.len() > 0 {
-                            println!("  Connections: module | hc | rtt | via");
-                            for cnn in user.connections {
-                                let module = proto::ConnectionModule::try_from(cnn.module)
-                                    .unwrap()
-                                    .as_str_name();
-                                println!(
-                                    "      {} | {} | {} | {}",
-                                    module,
-                                    cnn.hop_count,
-                                    cnn.rtt,
-                                    bs58::encode(cnn.via.clone()).into_string()
-                                );
-                            }
-                        }
-                        line += 1;
-                    }
-
+                    Self::print_users(proto_userlist.user, 1);
+                    println!("");
+                }
+                Some(proto::users::Message::UserQueryResult(result)) => {
+                    println!("");
+                    println!(
+                        "{} of {} matching Users, offset {}",
+                        result.user.len(),
+                        result.total,
+                        result.offset
+                    );
+                    Self::print_users(result.user, result.offset as usize + 1);
                     println!("");
                 }
                 Some(proto::users::Message::SecurityNumberResponse(resp)) => {
@@ -234,6 +530,63 @@ impl Users {
                     }
                     println!("");
                 }
+                Some(proto::users::Message::UserPingResult(result)) => {
+                    println!("");
+                    println!(
+                        "Ping {}",
+                        bs58::encode(result.user_id.clone()).into_string()
+                    );
+                    println!(
+                        "{} probes sent, {} replies received, {:.1}% loss",
+                        result.sent,
+                        result.received,
+                        result.loss * 100.0
+                    );
+                    if result.received > 0 {
+                        println!(
+                            "rtt min/avg/max = {}/{}/{} ms",
+                            result.rtt_min, result.rtt_avg, result.rtt_max
+                        );
+                    }
+                    if !result.forward_route.is_empty() {
+                        println!("  Route to user, rtt to previous hop in micro seconds:");
+                        Self::print_route(result.forward_route);
+                    }
+                    if !result.return_route.is_empty() {
+                        println!("  Route back, rtt to previous hop in micro seconds:");
+                        Self::print_route(result.return_route);
+                    }
+                    println!("");
+                }
+                Some(proto::users::Message::IdentityExport(export)) => {
+                    if export.error.is_empty() {
+                        println!(
+                            "Identity code of {}",
+                            bs58::encode(export.user_id).into_string()
+                        );
+                        println!("{}", export.code);
+                    } else {
+                        println!("Identity export failed: {}", export.error);
+                    }
+                }
+                Some(proto::users::Message::IdentityImportResult(result)) => {
+                    if result.success {
+                        println!(
+                            "Identity of {} {} imported and verified",
+                            result.name,
+                            bs58::encode(result.user_id).into_string()
+                        );
+                    } else {
+                        println!("Identity import failed: {}", result.error);
+                    }
+                }
+                Some(proto::users::Message::UserProfileUpdateResult(result)) => {
+                    if result.success {
+                        println!("Profile updated to revision {}", result.revision);
+                    } else {
+                        println!("Profile update failed: {}", result.error);
+                    }
+                }
                 _ => {
                     log::error!("unprocessable RPC users message");
                 }
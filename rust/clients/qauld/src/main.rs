@@ -12,6 +12,7 @@ use std::collections::BTreeMap;
 use std::{thread, time::Duration};
 
 use libqaul;
+use libqaul::utilities::instance::Instance;
 
 /// qauld - qaul daemon : CLI Arguments
 #[derive(Parser)]
@@ -22,6 +23,12 @@ struct CliArguments {
     /// Port Number
     #[arg(short, long)]
     port: Option<u16>,
+    /// Keep all data in memory, nothing is saved to disk
+    #[arg(short, long)]
+    ephemeral: bool,
+    /// Simulate all nodes of a mesh network topology file in this process
+    #[arg(short, long)]
+    simulation: Option<String>,
 }
 
 /// create a default user account for zero configuration Community Node startups
@@ -37,6 +44,41 @@ pub fn create_default_named() -> String {
     user_name
 }
 
+/// start all nodes of a simulation topology
+///
+/// Each node gets a user account named after the node.
+fn simulate(storage_path: String, topology_path: String) {
+    let simulation = match libqaul::api::start_simulation(storage_path, topology_path) {
+        Ok(simulation) => simulation,
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+
+    for (name, node) in simulation.nodes() {
+        // wait until the node finished initializing
+        while node.initialization_finished() == false {
+            std::thread::sleep(Duration::from_millis(10));
+        }
+
+        // create the user account of the node
+        Instance::with(node.id(), || {
+            if libqaul::node::user_accounts::UserAccounts::len() == 0 {
+                libqaul::node::user_accounts::UserAccounts::create(name.clone());
+            }
+        });
+
+        println!("simulated node {} started", name);
+    }
+
+    // advance the simulated clock in real time
+    loop {
+        thread::sleep(Duration::from_millis(10));
+        simulation.advance(10);
+    }
+}
+
 #[async_std::main]
 async fn main() {
     // get current working directory
@@ -45,6 +87,13 @@ async fn main() {
 
     // parse parameters and create default config
     let cli_arguments = CliArguments::parse();
+
+    // run a mesh network simulation
+    if let Some(topology_path) = cli_arguments.simulation {
+        simulate(storage_path, topology_path);
+        return;
+    }
+
     let mut def_config: BTreeMap<String, String> = BTreeMap::new();
     {
         if let Some(v) = cli_arguments.name.as_deref() {
@@ -53,6 +102,9 @@ async fn main() {
         if let Some(v) = cli_arguments.port {
             def_config.insert("port".to_string(), v.to_string());
         }
+        if cli_arguments.ephemeral {
+            def_config.insert("storage".to_string(), "memory".to_string());
+        }
     }
 
     // start libqaul in new thread and save configuration file to current working path
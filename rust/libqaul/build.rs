@@ -41,6 +41,10 @@ fn main() {
         "Confirmation",
         "#[derive(serde::Serialize, serde::Deserialize)]",
     );
+    prost_build.type_attribute(
+        "TraceHop",
+        "#[derive(serde::Serialize, serde::Deserialize)]",
+    );
 
     // make group messages serializable
     // in order to save them in the data base
@@ -66,6 +70,9 @@ fn main() {
         &[
             "rpc/qaul_rpc.proto",
             "rpc/debug.proto",
+            "rpc/config.proto",
+            "rpc/storage.proto",
+            "utilities/upgrade/upgrade.proto",
             "connections/connections.proto",
             "node/node.proto",
             "node/user_accounts.proto",
@@ -81,6 +88,7 @@ fn main() {
             "services/chat/chat.proto",
             "services/chat/chatfile_net.proto",
             "services/chat/chatfile_rpc.proto",
+            "services/contacts/contacts.proto",
             "connections/ble/ble.proto",
             "connections/ble/ble_net.proto",
             "connections/ble/ble_rpc.proto",
@@ -107,6 +115,21 @@ fn main() {
                 to.join("qaul.rpc.debug.rs"),
             )
             .unwrap();
+            fs::copy(
+                Path::new(&out_dir).join("qaul.rpc.config.rs"),
+                to.join("qaul.rpc.config.rs"),
+            )
+            .unwrap();
+            fs::copy(
+                Path::new(&out_dir).join("qaul.rpc.storage.rs"),
+                to.join("qaul.rpc.storage.rs"),
+            )
+            .unwrap();
+            fs::copy(
+                Path::new(&out_dir).join("qaul.rpc.upgrade.rs"),
+                to.join("qaul.rpc.upgrade.rs"),
+            )
+            .unwrap();
             fs::copy(
                 Path::new(&out_dir).join("qaul.rpc.connections.rs"),
                 to.join("qaul.rpc.connections.rs"),
@@ -147,6 +170,11 @@ fn main() {
                 to.join("qaul.rpc.chatfile.rs"),
             )
             .unwrap();
+            fs::copy(
+                Path::new(&out_dir).join("qaul.rpc.contacts.rs"),
+                to.join("qaul.rpc.contacts.rs"),
+            )
+            .unwrap();
             fs::copy(
                 Path::new(&out_dir).join("qaul.rpc.group.rs"),
                 to.join("qaul.rpc.group.rs"),
@@ -227,6 +255,21 @@ fn main() {
                 Path::new("src/rpc/qaul.rpc.debug.rs"),
             )
             .unwrap();
+            fs::copy(
+                &Path::new(&out_dir).join("qaul.rpc.config.rs"),
+                Path::new("src/rpc/qaul.rpc.config.rs"),
+            )
+            .unwrap();
+            fs::copy(
+                &Path::new(&out_dir).join("qaul.rpc.storage.rs"),
+                Path::new("src/rpc/qaul.rpc.storage.rs"),
+            )
+            .unwrap();
+            fs::copy(
+                &Path::new(&out_dir).join("qaul.rpc.upgrade.rs"),
+                Path::new("src/utilities/upgrade/qaul.rpc.upgrade.rs"),
+            )
+            .unwrap();
             fs::copy(
                 &Path::new(&out_dir).join("qaul.rpc.connections.rs"),
                 Path::new("src/connections/qaul.rpc.connections.rs"),
@@ -267,6 +310,11 @@ fn main() {
                 Path::new("src/services/chat/qaul.rpc.chat.rs"),
             )
             .unwrap();
+            fs::copy(
+                &Path::new(&out_dir).join("qaul.rpc.contacts.rs"),
+                Path::new("src/services/contacts/qaul.rpc.contacts.rs"),
+            )
+            .unwrap();
             fs::copy(
                 &Path::new(&out_dir).join("qaul.rpc.group.rs"),
                 Path::new("src/services/group/qaul.rpc.group.rs"),
@@ -66,6 +66,49 @@ pub extern "C" fn initialized() -> i32 {
     0
 }
 
+/// unlock the encrypted storage
+///
+/// Provide the passphrase of the encrypted storage.
+/// If the storage is not encrypted yet, it will be
+/// encrypted with this passphrase during startup.
+#[no_mangle]
+pub extern "C" fn unlock_storage(s: *const c_char) {
+    let c_str = unsafe {
+        assert!(!s.is_null());
+
+        CStr::from_ptr(s)
+    };
+
+    let r_str = c_str.to_str().unwrap();
+    super::unlock_storage(r_str.to_string());
+}
+
+/// check if libqaul waits for the storage passphrase
+///
+/// Returns 1 when libqaul waits for the passphrase, otherwise 0.
+#[no_mangle]
+pub extern "C" fn storage_locked() -> i32 {
+    if super::storage_locked() {
+        return 1;
+    }
+
+    0
+}
+
+/// check if libqaul was stopped by the upgrade of the stored data
+///
+/// Returns 1 when the upgrade failed or after the dry run
+/// of an upgrade, otherwise 0.
+/// libqaul will not finish initializing in this case.
+#[no_mangle]
+pub extern "C" fn upgrade_stopped() -> i32 {
+    if super::upgrade_stopped() {
+        return 1;
+    }
+
+    0
+}
+
 /// Yields the total number of messages queued to be received.
 #[no_mangle]
 pub extern "C" fn receivequeue() -> i32 {
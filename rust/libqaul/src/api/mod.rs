@@ -9,15 +9,26 @@
 //! This setup is to decouple the GUI thread from
 //! libqaul.
 //! The communication will happen via protobuf rpc messages.
+//!
+//! Several libqaul instances can be started in the same process.
+//! Each start returns a `Libqaul` handle, which communicates
+//! with its own instance.
+//! The functions of this module communicate with the
+//! first instance started.
 
 use crossbeam_channel::TryRecvError;
 use directories::ProjectDirs;
 use futures::executor::block_on;
 use std::collections::BTreeMap;
+use std::path::Path;
 use std::thread;
 
+use crate::connections::virtual_net::{topology::Topology, VirtualNet, VirtualNetwork};
 use crate::rpc::sys::Sys;
 use crate::rpc::Rpc;
+use crate::storage::encryption::StorageEncryption;
+use crate::utilities::instance::Instance;
+use crate::utilities::upgrade::Upgrade;
 
 /// C API module
 mod c;
@@ -27,11 +38,102 @@ mod c;
 #[cfg(target_os = "android")]
 pub mod android;
 
+/// Libqaul Instance Handle
+///
+/// Handle to communicate with a libqaul instance
+/// running in its own thread.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Libqaul {
+    id: u32,
+}
+
+impl Libqaul {
+    /// get the instance ID
+    pub fn id(&self) -> u32 {
+        self.id
+    }
+
+    /// Check if this instance finished initializing
+    pub fn initialization_finished(&self) -> bool {
+        Instance::with(self.id, initialization_finished)
+    }
+
+    /// Check if this instance was stopped by the upgrade
+    pub fn upgrade_stopped(&self) -> bool {
+        Instance::with(self.id, upgrade_stopped)
+    }
+
+    /// unlock the encrypted storage of this instance
+    pub fn unlock_storage(&self, passphrase: String) {
+        Instance::with(self.id, || unlock_storage(passphrase))
+    }
+
+    /// Check if this instance waits for the storage passphrase
+    pub fn storage_locked(&self) -> bool {
+        Instance::with(self.id, storage_locked)
+    }
+
+    /// send an RPC message to this instance
+    pub fn send_rpc(&self, binary_message: Vec<u8>) {
+        Instance::with(self.id, || send_rpc(binary_message))
+    }
+
+    /// receive an RPC message from this instance
+    pub fn receive_rpc(&self) -> Result<Vec<u8>, TryRecvError> {
+        Instance::with(self.id, receive_rpc)
+    }
+
+    /// count of rpc messages to receive in the queue of this instance
+    pub fn receive_rpc_queued(&self) -> usize {
+        Instance::with(self.id, receive_rpc_queued)
+    }
+
+    /// count of rpc messages sent to this instance
+    pub fn send_rpc_count(&self) -> i32 {
+        Instance::with(self.id, send_rpc_count)
+    }
+
+    /// send a SYS message to this instance
+    pub fn send_sys(&self, binary_message: Vec<u8>) {
+        Instance::with(self.id, || send_sys(binary_message))
+    }
+
+    /// receive a SYS message from this instance
+    pub fn receive_sys(&self) -> Result<Vec<u8>, TryRecvError> {
+        Instance::with(self.id, receive_sys)
+    }
+}
+
+/// Simulated Mesh Network
+///
+/// Handle to the nodes of a simulation
+/// and their virtual network.
+pub struct Simulation {
+    network: VirtualNetwork,
+    nodes: Vec<(String, Libqaul)>,
+}
+
+impl Simulation {
+    /// get the name and the handle of each node
+    pub fn nodes(&self) -> &Vec<(String, Libqaul)> {
+        &self.nodes
+    }
+
+    /// advance the simulated clock of the virtual network
+    ///
+    /// The latency, bandwidth and partitions of the simulated links,
+    /// as well as all timers of the simulated nodes, run on this clock.
+    /// It has to be advanced by the simulation harness, in milli seconds.
+    pub fn advance(&self, milliseconds: u64) {
+        self.network.advance(milliseconds);
+    }
+}
+
 /// start libqaul in an own thread
 ///
 /// Provide the location for storage, all data of qaul will be saved there.
-pub fn start(storage_path: String) {
-    self::start_with_config(storage_path, None);
+pub fn start(storage_path: String) -> Libqaul {
+    self::start_with_config(storage_path, None)
 }
 
 /// start libqaul in an own thread
@@ -40,14 +142,51 @@ pub fn start(storage_path: String) {
 /// * Optionally provide some configuration options, to initially configure libqaul to your needs.
 ///   the following options can be provided:
 ///   * Internet module listening port. By default this port is randomly assigned.
-pub fn start_with_config(storage_path: String, config: Option<BTreeMap<String, String>>) {
+///   * `storage`: set it to `memory` to run libqaul ephemerally.
+///     All data bases and the configuration are then only held in memory.
+///     No log file is written and the stored data is not upgraded.
+///   * `simulation` & `simulation_node`: path to a simulation topology file
+///     and the name of this node in it, see `start_simulation()`.
+///   * `upgrade_dry_run`: set it to `true` to test the upgrade of the stored data
+///     on a copy, without changing it. libqaul stops after the dry run of an upgrade.
+///
+/// Every call starts a new libqaul instance and returns its handle.
+/// Each instance needs its own storage path.
+pub fn start_with_config(
+    storage_path: String,
+    config: Option<BTreeMap<String, String>>,
+) -> Libqaul {
+    self::spawn(storage_path, config, None)
+}
+
+/// start a libqaul instance in an own thread
+///
+/// A simulated instance is attached to its virtual network
+/// before it is started.
+fn spawn(
+    storage_path: String,
+    config: Option<BTreeMap<String, String>>,
+    network: Option<VirtualNetwork>,
+) -> Libqaul {
+    // create instance
+    let id = Instance::create();
+
     // Spawn new thread
     thread::spawn(move || {
+        // all state of this thread belongs to the instance
+        Instance::set_current(id);
+
+        if let Some(network) = network {
+            VirtualNet::attach(network);
+        }
+
         block_on(async move {
             // start libqaul
             crate::start(storage_path, config).await;
         })
     });
+
+    Libqaul { id }
 }
 
 /// start libqaul on a desktop platform (Linux, Mac, Windows)
@@ -97,6 +236,44 @@ pub fn start_android(storage_path: String) {
     self::start_with_config(storage_path, None);
 }
 
+/// start all nodes of a simulated mesh network
+///
+/// Every node of the topology file is started as an ephemeral
+/// libqaul instance in this process.
+/// The instances are connected via the virtual connection module
+/// according to the links of the topology.
+/// Each node gets a sub folder of `storage_path` named after the node
+/// as its storage path, nothing is written to it.
+///
+/// Every simulation has its own virtual network and clock,
+/// several simulations can therefore run in the same process.
+/// Simulated nodes don't use the LAN and Internet modules.
+///
+/// Returns the handle of the simulation.
+pub fn start_simulation(storage_path: String, topology_path: String) -> Result<Simulation, String> {
+    let topology = Topology::load(&topology_path)?;
+    let network = VirtualNetwork::new();
+
+    let mut nodes = Vec::new();
+    for node in topology.nodes {
+        let path = Path::new(&storage_path).join(&node.name);
+
+        let mut config = BTreeMap::new();
+        config.insert("storage".to_string(), "memory".to_string());
+        config.insert("simulation".to_string(), topology_path.clone());
+        config.insert("simulation_node".to_string(), node.name.clone());
+
+        let libqaul = self::spawn(
+            path.to_str().unwrap().to_string(),
+            Some(config),
+            Some(network.clone()),
+        );
+        nodes.push((node.name, libqaul));
+    }
+
+    Ok(Simulation { network, nodes })
+}
+
 /// Check if libqaul finished initializing
 ///
 /// The initialization of libqaul can take several seconds.
@@ -110,6 +287,36 @@ pub fn initialization_finished() -> bool {
     false
 }
 
+/// Check if libqaul was stopped by the upgrade of the stored data
+///
+/// This is the case if the upgrade failed and the data was restored
+/// to the old version, or after the dry run of an upgrade.
+/// libqaul will not finish initializing then.
+/// The upgrade progress messages can be read via `receive_rpc()`.
+pub fn upgrade_stopped() -> bool {
+    Upgrade::is_stopped()
+}
+
+/// Unlock the encrypted storage
+///
+/// Provide the passphrase of the encrypted storage.
+/// This function can be called before libqaul is started,
+/// or when `storage_locked()` returns true.
+///
+/// If the storage is not encrypted yet, the existing storage
+/// will be encrypted with this passphrase during startup.
+pub fn unlock_storage(passphrase: String) {
+    StorageEncryption::set_passphrase(passphrase);
+}
+
+/// Check if libqaul waits for the storage passphrase
+///
+/// libqaul does not finish initializing until the encrypted
+/// storage has been unlocked via `unlock_storage()`.
+pub fn storage_locked() -> bool {
+    StorageEncryption::is_locked()
+}
+
 /// send an RPC message to libqaul
 pub fn send_rpc(binary_message: Vec<u8>) {
     Rpc::send_to_libqaul(binary_message);
@@ -139,3 +346,100 @@ pub fn send_sys(binary_message: Vec<u8>) {
 pub fn receive_sys() -> Result<Vec<u8>, TryRecvError> {
     Sys::receive_from_libqaul()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::node::{user_accounts::UserAccounts, Node};
+    use crate::router::table::RoutingTable;
+    use std::time::Duration;
+
+    /// line topology with message loss
+    const TOPOLOGY: &str = "seed: 7
+nodes:
+  - name: a
+  - name: b
+  - name: c
+  - name: d
+links:
+  - from: a
+    to: b
+    latency: 20
+    loss: 5
+  - from: b
+    to: c
+    latency: 50
+    loss: 10
+  - from: c
+    to: d
+    latency: 20
+    loss: 5
+";
+
+    /// run a simulation and return the routing table of each node
+    ///
+    /// The routing tables contain the hop count and the name
+    /// of the next hop node for each user name.
+    fn simulate(name: &str) -> BTreeMap<String, BTreeMap<String, (u8, String)>> {
+        let folder = std::env::temp_dir().join(format!("qaul_simulation_{}", name));
+        std::fs::create_dir_all(&folder).unwrap();
+        let topology_path = folder.join("topology.yaml");
+        std::fs::write(&topology_path, TOPOLOGY).unwrap();
+
+        let simulation = start_simulation(
+            folder.to_str().unwrap().to_string(),
+            topology_path.to_str().unwrap().to_string(),
+        )
+        .unwrap();
+
+        // create a user account per node
+        let mut node_names = BTreeMap::new();
+        let mut user_names = BTreeMap::new();
+        for (name, node) in simulation.nodes() {
+            while !node.initialization_finished() {
+                std::thread::sleep(Duration::from_millis(10));
+            }
+            Instance::with(node.id(), || {
+                let user = UserAccounts::create(name.clone());
+                node_names.insert(Node::get_id(), name.clone());
+                user_names.insert(
+                    crate::utilities::qaul_id::QaulId::to_q8id(user.id),
+                    name.clone(),
+                );
+            });
+        }
+
+        // run 60 seconds of simulated time
+        for _ in 0..600 {
+            std::thread::sleep(Duration::from_millis(10));
+            simulation.advance(100);
+        }
+
+        let mut tables = BTreeMap::new();
+        for (name, node) in simulation.nodes() {
+            let users = Instance::with(node.id(), RoutingTable::get_online_users_info);
+            let mut table = BTreeMap::new();
+            for (user_id, connections) in users {
+                let user = user_names.get(&user_id).cloned().unwrap_or_default();
+                let next_hop = node_names
+                    .get(&connections[0].node)
+                    .cloned()
+                    .unwrap_or_default();
+                table.insert(user, (connections[0].hc, next_hop));
+            }
+            tables.insert(name.clone(), table);
+        }
+        tables
+    }
+
+    #[test]
+    fn same_seed_gives_same_routing_table() {
+        let first = simulate("first");
+        let second = simulate("second");
+
+        // all nodes of the line are reachable
+        assert_eq!(first["a"]["d"], (3, "b".to_string()));
+        assert_eq!(first["d"]["a"], (3, "c".to_string()));
+        assert_eq!(first, second);
+    }
+}
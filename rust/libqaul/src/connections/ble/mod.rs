@@ -12,7 +12,6 @@
 
 use libp2p::{floodsub::Topic, PeerId};
 use prost::Message;
-use state::InitCell;
 use std::{collections::BTreeMap, fmt, sync::RwLock};
 use uuid::Uuid;
 
@@ -21,6 +20,7 @@ use crate::node::Node;
 use crate::router::neighbours::Neighbours;
 use crate::rpc::{sys::Sys, Rpc};
 use crate::services::{feed, messaging};
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::{qaul_id::QaulId, timestamp::Timestamp};
 
 /// Protobuf BLE system communication with BLE module
@@ -40,14 +40,14 @@ pub mod proto_rpc {
 }
 
 /// Module State
-static BLE: InitCell<RwLock<Ble>> = InitCell::new();
+static BLE: InstanceCell<RwLock<Ble>> = InstanceCell::new();
 /// List of detected BLE nodes needing ID confirmation
-static TO_CONFIRM: InitCell<RwLock<BTreeMap<Vec<u8>, ToConfirm>>> = InitCell::new();
+static TO_CONFIRM: InstanceCell<RwLock<BTreeMap<Vec<u8>, ToConfirm>>> = InstanceCell::new();
 /// List of discovered and available BLE nodes
 ///
 /// This structure contains a translation table from
 /// the BLE ID to the BLE ID
-static NODES: InitCell<RwLock<BTreeMap<Vec<u8>, BleNode>>> = InitCell::new();
+static NODES: InstanceCell<RwLock<BTreeMap<Vec<u8>, BleNode>>> = InstanceCell::new();
 
 /// Detected BLE node, which is not known yet
 /// and therefore its ID needs to be confirmed.
@@ -82,6 +82,7 @@ pub fn ping_event(event: Event, module: ConnectionModule) {
             connection: _,
         } => {
             log::debug!("PingFailure::Timeout to {}", peer);
+            Neighbours::ping_failed(module, peer);
         }
         Event {
             peer,
@@ -89,6 +90,7 @@ pub fn ping_event(event: Event, module: ConnectionModule) {
             connection: _,
         } => {
             log::debug!("PingFailure::Other {} error: {}", peer, error);
+            Neighbours::ping_failed(module, peer);
         }
         Event {
             peer,
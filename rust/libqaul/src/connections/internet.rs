@@ -36,18 +36,19 @@ use libp2p::{
     tcp, yamux, Multiaddr, PeerId, SwarmBuilder,
 };
 use prost::Message;
-use state::InitCell;
 use std::time::Duration;
 use std::{
     collections::{BTreeMap, HashMap},
     sync::RwLock,
 };
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{events, virtual_net::VirtualNet, ConnectionModule};
 use crate::node::Node;
+use crate::router::neighbours::Neighbours;
 use crate::services::feed::proto_net;
 use crate::services::feed::Feed;
 use crate::storage::configuration::Configuration;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp::Timestamp;
 use qaul_info::{QaulInfo, QaulInfoEvent};
 use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
@@ -113,6 +114,9 @@ impl QaulInternetBehaviour {
                 log::trace!("  protocols: {:?}", info.protocols);
                 log::trace!("  observed address: {:?}", info.observed_addr);
                 log::trace!("  connection ID: {:?}", connection_id);
+
+                // remember the addresses for the routing snapshot
+                Neighbours::add_addresses(ConnectionModule::Internet, peer_id, info.listen_addrs);
             }
             identify::Event::Sent {
                 peer_id,
@@ -173,8 +177,8 @@ pub struct InternetReConnection {
 pub struct InternetReConnections {
     peers: HashMap<Multiaddr, InternetReConnection>,
 }
-static INTERNETRECONNECTIONS: InitCell<RwLock<InternetReConnections>> = InitCell::new();
-static INTERNETCONNECTIONS: InitCell<RwLock<BTreeMap<String, PeerId>>> = InitCell::new();
+static INTERNETRECONNECTIONS: InstanceCell<RwLock<InternetReConnections>> = InstanceCell::new();
+static INTERNETCONNECTIONS: InstanceCell<RwLock<BTreeMap<String, PeerId>>> = InstanceCell::new();
 
 #[derive(Debug)]
 pub enum QaulInternetEvent {
@@ -279,8 +283,20 @@ impl Internet {
         // the configuration array config.internet.listen
         let config = Configuration::get();
 
+        // simulated nodes are only connected via the virtual module
+        if VirtualNet::is_simulated() {
+            return Internet { swarm };
+        }
+
         for listen in &config.internet.listen {
-            match Swarm::listen_on(&mut swarm, listen.parse().expect("can get a local socket")) {
+            let address: Multiaddr = match listen.parse() {
+                Ok(address) => address,
+                Err(e) => {
+                    log::error!("Invalid INTERNET listening address `{}`: {}", listen, e);
+                    continue;
+                }
+            };
+            match Swarm::listen_on(&mut swarm, address) {
                 Ok(listener_id) => {
                     log::info!(
                         "INTERNET listening on `{}` with ID {:?}",
@@ -24,14 +24,15 @@ use libp2p::{
     floodsub::{Floodsub, FloodsubEvent},
     identity::Keypair,
     mdns, noise, ping,
-    swarm::{NetworkBehaviour, Swarm},
-    tcp, yamux, SwarmBuilder,
+    swarm::{behaviour::toggle::Toggle, NetworkBehaviour, Swarm},
+    tcp, yamux, Multiaddr, SwarmBuilder,
 };
 use prost::Message;
 use std::time::Duration;
 
-use crate::connections::{events, ConnectionModule};
+use crate::connections::{events, virtual_net::VirtualNet, ConnectionModule};
 use crate::node::Node;
+use crate::router::neighbours::Neighbours;
 use crate::services::feed::proto_net;
 use crate::services::feed::Feed;
 use crate::storage::configuration::Configuration;
@@ -42,7 +43,7 @@ use qaul_messaging::{QaulMessaging, QaulMessagingEvent};
 #[behaviour(to_swarm = "QaulLanEvent")]
 pub struct QaulLanBehaviour {
     pub floodsub: Floodsub,
-    pub mdns: mdns::async_io::Behaviour,
+    pub mdns: Toggle<mdns::async_io::Behaviour>,
     pub ping: ping::Behaviour,
     pub qaul_info: QaulInfo,
     pub qaul_messaging: QaulMessaging,
@@ -80,12 +81,15 @@ impl QaulLanBehaviour {
     fn mdsn_event(&mut self, event: mdns::Event) {
         match event {
             mdns::Event::Discovered(discovered_list) => {
-                for (peer_id, _addr) in discovered_list {
+                for (peer_id, addr) in discovered_list {
                     log::trace!(
                         "MdnsEvent::Discovered, peer {:?} to floodsub added",
                         peer_id.clone()
                     );
                     self.floodsub.add_node_to_partial_view(peer_id);
+
+                    // remember the address for the routing snapshot
+                    Neighbours::add_addresses(ConnectionModule::Lan, peer_id, vec![addr]);
                 }
             }
             mdns::Event::Expired(expired_list) => {
@@ -94,7 +98,11 @@ impl QaulLanBehaviour {
                     //
                     // Why should we check again if a node is in discovered list, once it has expired?
                     // Apparently the expired node is not instantly removed from the discovered_nodes list too.
-                    if !self.mdns.discovered_nodes().any(|p| p == &peer) {
+                    let discovered = match self.mdns.as_ref() {
+                        Some(mdns) => mdns.discovered_nodes().any(|p| p == &peer),
+                        None => false,
+                    };
+                    if !discovered {
                         log::trace!("MdnsEvent::Expired, peer {:?} from floodsub removed", peer);
                         self.floodsub.remove_node_from_partial_view(&peer);
                     }
@@ -174,8 +182,14 @@ impl Lan {
         log::trace!("Lan::init() ping_config");
 
         // create MDNS behaviour
+        // simulated nodes don't advertise themselves in the local network
         // TODO create MdnsConfig {ttl: Duration::from_secs(300), query_interval: Duration::from_secs(30) }
-        let mdns = mdns::async_io::Behaviour::new(mdns::Config::default(), Node::get_id()).unwrap();
+        let mdns = match VirtualNet::is_simulated() {
+            true => Toggle::from(None),
+            false => Toggle::from(Some(
+                mdns::async_io::Behaviour::new(mdns::Config::default(), Node::get_id()).unwrap(),
+            )),
+        };
 
         // create behaviour
         let mut behaviour: QaulLanBehaviour = QaulLanBehaviour {
@@ -212,9 +226,20 @@ impl Lan {
         // the configuration array config.lan.listen
         let config = Configuration::get();
 
-        for listen in &config.lan.listen {
-            Swarm::listen_on(&mut swarm, listen.parse().expect("can get a local socket"))
-                .expect("swarm can be started");
+        // simulated nodes are only connected via the virtual module
+        if !VirtualNet::is_simulated() {
+            for listen in &config.lan.listen {
+                let address: Multiaddr = match listen.parse() {
+                    Ok(address) => address,
+                    Err(e) => {
+                        log::error!("Invalid LAN listening address `{}`: {}", listen, e);
+                        continue;
+                    }
+                };
+                if let Err(e) = Swarm::listen_on(&mut swarm, address) {
+                    log::error!("Error LAN start listening on `{}`: {}", listen, e);
+                }
+            }
         }
 
         let lan = Lan { swarm };
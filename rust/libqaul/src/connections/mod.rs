@@ -9,6 +9,7 @@ pub mod ble;
 pub mod events;
 pub mod internet;
 pub mod lan;
+pub mod virtual_net;
 
 use libp2p::Multiaddr;
 use prost::Message;
@@ -21,6 +22,7 @@ use crate::storage::configuration::InternetPeer;
 use ble::Ble;
 use internet::Internet;
 use lan::Lan;
+use virtual_net::VirtualNet;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -44,6 +46,9 @@ pub enum ConnectionModule {
     Ble,
     /// no connection module known for this
     None,
+    /// Virtual module, connects simulated nodes
+    /// within the same process.
+    Virtual,
 }
 
 impl ConnectionModule {
@@ -59,6 +64,7 @@ impl ConnectionModule {
             ConnectionModule::Internet => "INTERNET",
             ConnectionModule::Ble => "BLE",
             ConnectionModule::Local => "LOCAL",
+            ConnectionModule::Virtual => "VIRTUAL",
         }
     }
 
@@ -69,6 +75,7 @@ impl ConnectionModule {
             ConnectionModule::Internet => 2,
             ConnectionModule::Ble => 3,
             ConnectionModule::Local => 4,
+            ConnectionModule::Virtual => 5,
         }
     }
 }
@@ -86,6 +93,11 @@ impl Connections {
         // get node keys
         let node_keys = Node::get_keys();
 
+        // initialize virtual module for simulated nodes
+        // simulated nodes don't listen, dial or advertise
+        // themselves via the LAN and Internet modules
+        VirtualNet::init();
+
         // initialize Lan module
         let lan = Lan::init(&node_keys).await;
 
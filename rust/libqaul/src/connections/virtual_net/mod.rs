@@ -0,0 +1,589 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Virtual Connection Module
+//!
+//! **Simulate a mesh network of several libqaul instances in one process.**
+//!
+//! The libqaul instances of a simulation are connected via an in-process
+//! virtual network, which is created by the simulation harness and
+//! attached to each instance of the simulation.
+//! Several independent simulations can run in the same process.
+//! The links between the nodes are defined in the topology file,
+//! see `topology` for the file format.
+//!
+//! Each link simulates:
+//!
+//! * latency
+//! * message loss
+//! * bandwidth
+//! * network partitions
+//!
+//! The message loss is drawn from a random number generator per link
+//! direction, seeded from the topology seed.
+//! The same topology therefore drops the same messages on every run.
+//!
+//! Latency, bandwidth and partitions run on a simulated clock,
+//! which is owned by the virtual network and advanced by the
+//! simulation harness via `VirtualNetwork::advance()`.
+//! All time reads of an attached instance return the simulated time.
+//!
+//! The module is activated with the following default configuration options:
+//!
+//! * `simulation`: path to the topology file
+//! * `simulation_node`: name of this node in the topology
+//!
+//! Simulated nodes don't listen or connect via the LAN and Internet modules.
+
+pub mod topology;
+
+use libp2p::{floodsub::Topic, PeerId};
+use prost::Message;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use crate::connections::ConnectionModule;
+use crate::node::Node;
+use crate::router::{info::RouterInfo, neighbours::Neighbours};
+use crate::services::{feed, messaging::Messaging};
+use crate::utilities::{instance::InstanceCell, timestamp::Timestamp};
+use topology::Topology;
+
+/// Virtual network this instance is attached to
+static NETWORK: InstanceCell<VirtualNetwork> = InstanceCell::new();
+
+/// Module state of this instance
+static VIRTUAL: InstanceCell<RwLock<VirtualNet>> = InstanceCell::new();
+
+/// interval to check the state of the links in milli seconds
+const LINK_CHECK_INTERVAL: u64 = 1000;
+
+/// start time of the simulated clock
+///
+/// milli seconds since UNIX_EPOCH
+const SIMULATION_EPOCH: u64 = 1_600_000_000_000;
+
+/// Handle to a virtual network
+///
+/// The handle is created by the simulation harness
+/// and shared by all instances of the simulation.
+#[derive(Clone)]
+pub struct VirtualNetwork {
+    /// simulated clock
+    ///
+    /// milli seconds since UNIX_EPOCH
+    clock: Arc<AtomicU64>,
+    /// network state
+    network: Arc<RwLock<Network>>,
+}
+
+impl Default for VirtualNetwork {
+    fn default() -> Self {
+        VirtualNetwork {
+            clock: Arc::new(AtomicU64::new(SIMULATION_EPOCH)),
+            network: Arc::new(RwLock::new(Network {
+                sequence: 0,
+                nodes: BTreeMap::new(),
+            })),
+        }
+    }
+}
+
+impl VirtualNetwork {
+    /// create a new virtual network
+    ///
+    /// The simulated clock starts at the same time
+    /// for every network.
+    pub fn new() -> VirtualNetwork {
+        Self::default()
+    }
+
+    /// advance the simulated clock of the virtual network
+    ///
+    /// The clock is shared by all nodes of the simulation.
+    /// Messages are delivered, once the clock has reached
+    /// their delivery time.
+    pub fn advance(&self, milliseconds: u64) {
+        self.clock.fetch_add(milliseconds, Ordering::SeqCst);
+    }
+
+    /// milli seconds since the start of the simulation
+    pub fn elapsed(&self) -> u64 {
+        self.clock
+            .load(Ordering::SeqCst)
+            .saturating_sub(SIMULATION_EPOCH)
+    }
+}
+
+/// Virtual Network
+struct Network {
+    /// sequence number of the last sent message
+    sequence: u64,
+    /// all attached nodes by name
+    nodes: BTreeMap<String, NetworkNode>,
+}
+
+/// Node attached to the virtual network
+struct NetworkNode {
+    /// node id of the libqaul instance
+    id: PeerId,
+    /// messages in transit to this node
+    ///
+    /// The key is the delivery time and the sequence number of the message.
+    inbox: BTreeMap<(u64, u64), VirtualMessage>,
+}
+
+/// Message in transit
+struct VirtualMessage {
+    /// name of the sending node
+    from_name: String,
+    /// node id of the sending node
+    from: PeerId,
+    /// message content
+    content: VirtualContent,
+}
+
+/// Message content types
+enum VirtualContent {
+    /// routing information
+    Info(Vec<u8>),
+    /// feed message
+    Feed(Vec<u8>),
+    /// messaging message
+    Messaging(Vec<u8>),
+}
+
+impl VirtualContent {
+    /// size of the content in bytes
+    fn len(&self) -> usize {
+        match self {
+            VirtualContent::Info(data) => data.len(),
+            VirtualContent::Feed(data) => data.len(),
+            VirtualContent::Messaging(data) => data.len(),
+        }
+    }
+}
+
+/// Outgoing link to a neighbour node
+struct VirtualLink {
+    /// latency in milli seconds
+    latency: u64,
+    /// message loss in percent
+    loss: f64,
+    /// bandwidth in bytes per second, 0 is unlimited
+    bandwidth: u64,
+    /// random number generator of the loss simulation
+    rng: StdRng,
+    /// time until the link is busy sending
+    busy_until: u64,
+}
+
+/// Virtual Connection Module
+pub struct VirtualNet {
+    /// name of this node in the topology
+    name: String,
+    /// network topology
+    topology: Topology,
+    /// outgoing links by neighbour name
+    links: BTreeMap<String, VirtualLink>,
+    /// connected neighbours by name
+    neighbours: BTreeMap<String, PeerId>,
+    /// simulated time of the last link check
+    checked_at: Option<u64>,
+}
+
+impl VirtualNet {
+    /// attach this instance to a virtual network
+    ///
+    /// This function needs to be called before libqaul is initialized.
+    /// From then on, all time reads of this instance use the
+    /// simulated clock of the network.
+    pub fn attach(network: VirtualNetwork) {
+        Timestamp::simulate(network.clock.clone());
+        NETWORK.set(network);
+    }
+
+    /// initialize the virtual connection module
+    ///
+    /// The module is only started if a simulation
+    /// topology has been configured and the instance
+    /// has been attached to a virtual network.
+    pub fn init() {
+        let topology_path;
+        match crate::get_default_config("simulation") {
+            Some(path) => topology_path = path,
+            None => return,
+        }
+
+        let name;
+        match crate::get_default_config("simulation_node") {
+            Some(node_name) => name = node_name,
+            None => {
+                log::error!("no simulation node name configured");
+                return;
+            }
+        }
+
+        // load topology
+        let topology;
+        match Topology::load(&topology_path) {
+            Ok(t) => topology = t,
+            Err(e) => {
+                log::error!("{}", e);
+                return;
+            }
+        }
+        if !topology.contains(&name) {
+            log::error!("simulation node {} is not in the topology", name);
+            return;
+        }
+
+        let network;
+        match NETWORK.try_get() {
+            Some(n) => network = n,
+            None => {
+                log::error!("simulation node {} is not attached to a network", name);
+                return;
+            }
+        }
+
+        // create outgoing links
+        let mut links = BTreeMap::new();
+        for (neighbour, link) in topology.links_of(&name) {
+            let seed = Self::link_seed(topology.seed, &name, &neighbour);
+            links.insert(
+                neighbour,
+                VirtualLink {
+                    latency: link.latency,
+                    loss: link.loss,
+                    bandwidth: link.bandwidth,
+                    rng: StdRng::seed_from_u64(seed),
+                    busy_until: 0,
+                },
+            );
+        }
+
+        // add node to the virtual network
+        {
+            let mut network = network.network.write().unwrap();
+            network.nodes.insert(
+                name.clone(),
+                NetworkNode {
+                    id: Node::get_id(),
+                    inbox: BTreeMap::new(),
+                },
+            );
+        }
+
+        log::info!("virtual network node {} started", name);
+
+        VIRTUAL.set(RwLock::new(VirtualNet {
+            name,
+            topology,
+            links,
+            neighbours: BTreeMap::new(),
+            checked_at: None,
+        }));
+    }
+
+    /// Returns true if this node is part of a simulation
+    pub fn is_active() -> bool {
+        VIRTUAL.try_get().is_some()
+    }
+
+    /// Returns true if this node was started for a simulation
+    ///
+    /// Simulated nodes don't use the LAN and Internet modules,
+    /// even if the virtual module couldn't be started.
+    pub fn is_simulated() -> bool {
+        crate::get_default_config("simulation").is_some()
+    }
+
+    /// create the seed of a link direction
+    ///
+    /// The seed only depends on the topology seed and the node names,
+    /// every link direction therefore drops the same messages on every run.
+    fn link_seed(seed: u64, from: &str, to: &str) -> u64 {
+        let mut hash = seed ^ 0xcbf2_9ce4_8422_2325;
+        for byte in from.bytes().chain([0u8]).chain(to.bytes()) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+
+    /// Process the virtual network
+    ///
+    /// This function is called periodically from the event loop.
+    /// It updates the neighbours and delivers all messages,
+    /// which have arrived.
+    pub fn process() {
+        if !Self::is_active() {
+            return;
+        }
+
+        Self::check_links();
+
+        // get all arrived messages
+        let name = VIRTUAL.get().read().unwrap().name.clone();
+        let arrived;
+        {
+            let now = NETWORK.get().elapsed();
+            let mut network = NETWORK.get().network.write().unwrap();
+            match network.nodes.get_mut(&name) {
+                Some(node) => {
+                    let in_transit = node.inbox.split_off(&(now + 1, 0));
+                    arrived = std::mem::replace(&mut node.inbox, in_transit);
+                }
+                None => return,
+            }
+        }
+
+        for (_key, message) in arrived {
+            // messages in transit are lost when the link goes down
+            if !Self::is_link_up(&message.from_name) {
+                log::trace!("virtual message from {} lost", message.from_name);
+                continue;
+            }
+
+            Self::received(message);
+        }
+    }
+
+    /// check if the link to a neighbour is up
+    fn is_link_up(neighbour: &str) -> bool {
+        let virtual_net = VIRTUAL.get().read().unwrap();
+        if !virtual_net.links.contains_key(neighbour) {
+            return false;
+        }
+
+        let elapsed = NETWORK.get().elapsed();
+        !virtual_net
+            .topology
+            .is_partitioned(&virtual_net.name, neighbour, elapsed)
+    }
+
+    /// update the neighbours table according to the state of the links
+    fn check_links() {
+        let mut virtual_net = VIRTUAL.get().write().unwrap();
+        let elapsed = NETWORK.get().elapsed();
+        let network = NETWORK.get().network.read().unwrap();
+        if let Some(checked_at) = virtual_net.checked_at {
+            if elapsed < checked_at.saturating_add(LINK_CHECK_INTERVAL) {
+                return;
+            }
+        }
+        virtual_net.checked_at = Some(elapsed);
+        let virtual_net = &mut *virtual_net;
+
+        for (neighbour, link) in &virtual_net.links {
+            // a link is up, when the neighbour is attached to the network
+            // and the nodes are not separated by a partition
+            let mut neighbour_id = None;
+            if !virtual_net
+                .topology
+                .is_partitioned(&virtual_net.name, neighbour, elapsed)
+            {
+                if let Some(node) = network.nodes.get(neighbour) {
+                    neighbour_id = Some(node.id);
+                }
+            }
+
+            match neighbour_id {
+                Some(id) => {
+                    // rtt is in micro seconds
+                    let rtt = (link.latency * 2 * 1000).min(u32::MAX as u64) as u32;
+                    Neighbours::update_node(ConnectionModule::Virtual, id, rtt);
+                    virtual_net.neighbours.insert(neighbour.clone(), id);
+                }
+                None => {
+                    if let Some(id) = virtual_net.neighbours.remove(neighbour) {
+                        log::trace!("virtual link to {} is down", neighbour);
+                        Neighbours::delete(ConnectionModule::Virtual, id);
+                    }
+                }
+            }
+        }
+    }
+
+    /// send a message via the virtual link to a neighbour
+    fn send(receiver: PeerId, content: VirtualContent) {
+        let mut virtual_net = VIRTUAL.get().write().unwrap();
+        let virtual_net = &mut *virtual_net;
+
+        // find neighbour
+        let neighbour;
+        match virtual_net
+            .neighbours
+            .iter()
+            .find(|(_name, id)| **id == receiver)
+        {
+            Some((name, _id)) => neighbour = name.clone(),
+            None => {
+                log::error!("virtual neighbour not found: {}", receiver.to_base58());
+                return;
+            }
+        }
+
+        let now = NETWORK.get().elapsed();
+        let mut network = NETWORK.get().network.write().unwrap();
+
+        // check partitions
+        if virtual_net
+            .topology
+            .is_partitioned(&virtual_net.name, &neighbour, now)
+        {
+            log::trace!("virtual link to {} is partitioned", neighbour);
+            return;
+        }
+
+        let link;
+        match virtual_net.links.get_mut(&neighbour) {
+            Some(l) => link = l,
+            None => return,
+        }
+
+        // simulate message loss
+        if link.loss > 0.0 && link.rng.random::<f64>() * 100.0 < link.loss {
+            log::trace!("virtual message to {} dropped", neighbour);
+            return;
+        }
+
+        // simulate bandwidth
+        // messages are sent one after the other
+        let mut sent_at = now.max(link.busy_until);
+        if link.bandwidth > 0 {
+            sent_at += content.len() as u64 * 1000 / link.bandwidth;
+        }
+        link.busy_until = sent_at;
+
+        // simulate latency
+        let deliver_at = sent_at + link.latency;
+
+        network.sequence += 1;
+        let sequence = network.sequence;
+        if let Some(node) = network.nodes.get_mut(&neighbour) {
+            node.inbox.insert(
+                (deliver_at, sequence),
+                VirtualMessage {
+                    from_name: virtual_net.name.clone(),
+                    from: Node::get_id(),
+                    content,
+                },
+            );
+        }
+    }
+
+    /// send routing info message
+    pub fn send_routing_info(node_id: PeerId, data: Vec<u8>) {
+        if !Self::is_active() {
+            return;
+        }
+        Self::send(node_id, VirtualContent::Info(data));
+    }
+
+    /// send messaging message
+    pub fn send_messaging_message(node_id: PeerId, data: Vec<u8>) {
+        if !Self::is_active() {
+            return;
+        }
+        Self::send(node_id, VirtualContent::Messaging(data));
+    }
+
+    /// send feed message to all virtual neighbours
+    pub fn send_feed_message(_topic: Topic, data: Vec<u8>) {
+        if !Self::is_active() {
+            return;
+        }
+
+        let neighbours: Vec<PeerId> = {
+            let virtual_net = VIRTUAL.get().read().unwrap();
+            virtual_net.neighbours.values().cloned().collect()
+        };
+
+        for node_id in neighbours {
+            Self::send(node_id, VirtualContent::Feed(data.clone()));
+        }
+    }
+
+    /// distribute a received message
+    fn received(message: VirtualMessage) {
+        match message.content {
+            VirtualContent::Info(data) => {
+                let received = qaul_info::QaulInfoReceived {
+                    received_from: message.from,
+                    data,
+                };
+                RouterInfo::received(received);
+            }
+            VirtualContent::Feed(data) => match feed::proto_net::FeedContainer::decode(&data[..]) {
+                Ok(feed_container) => {
+                    feed::Feed::received(ConnectionModule::Virtual, message.from, feed_container);
+                }
+                Err(e) => {
+                    log::error!("virtual feed message decoding error: {}", e);
+                }
+            },
+            VirtualContent::Messaging(data) => {
+                let received = qaul_messaging::QaulMessagingReceived {
+                    received_from: message.from,
+                    data,
+                };
+                Messaging::received(received);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::instance::Instance;
+
+    #[test]
+    fn networks_have_independent_clocks() {
+        let first = VirtualNetwork::new();
+        let second = VirtualNetwork::new();
+
+        first.advance(1500);
+        first.clone().advance(500);
+
+        assert_eq!(first.elapsed(), 2000);
+        assert_eq!(second.elapsed(), 0);
+    }
+
+    #[test]
+    fn attached_instance_reads_simulated_time() {
+        let network = VirtualNetwork::new();
+
+        Instance::with(Instance::create(), || {
+            VirtualNet::attach(network.clone());
+            assert_eq!(Timestamp::get_timestamp(), SIMULATION_EPOCH);
+
+            network.advance(250);
+            assert_eq!(Timestamp::get_timestamp(), SIMULATION_EPOCH + 250);
+        });
+    }
+
+    #[test]
+    fn link_seed_depends_on_direction() {
+        assert_eq!(
+            VirtualNet::link_seed(42, "a", "b"),
+            VirtualNet::link_seed(42, "a", "b")
+        );
+        assert_ne!(
+            VirtualNet::link_seed(42, "a", "b"),
+            VirtualNet::link_seed(42, "b", "a")
+        );
+        assert_ne!(
+            VirtualNet::link_seed(42, "a", "b"),
+            VirtualNet::link_seed(43, "a", "b")
+        );
+    }
+}
@@ -0,0 +1,216 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Topology of the Virtual Network
+//!
+//! The topology file is a yaml file describing all simulated
+//! nodes and the links between them.
+//!
+//! ```yaml
+//! # seed of the random loss simulation
+//! seed: 42
+//! nodes:
+//!   - name: a
+//!   - name: b
+//!   - name: c
+//! links:
+//!   # latency in milli seconds
+//!   # loss in percent
+//!   # bandwidth in bytes per second, 0 is unlimited
+//!   - from: a
+//!     to: b
+//!     latency: 20
+//!     loss: 0
+//!     bandwidth: 0
+//!   - from: b
+//!     to: c
+//!     latency: 150
+//!     loss: 10
+//!     bandwidth: 12500
+//! partitions:
+//!   # separate node `c` from all other nodes
+//!   # from second 30 to second 60 of the simulation.
+//!   # times are in milli seconds since the start of the simulation,
+//!   # an end of 0 lasts until the end of the simulation.
+//!   - start: 30000
+//!     end: 60000
+//!     nodes: [c]
+//! ```
+//!
+//! All links are bidirectional.
+
+use serde::{Deserialize, Serialize};
+use std::fs;
+
+/// Simulated Node
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct TopologyNode {
+    /// unique name of the node
+    pub name: String,
+}
+
+/// Link between two simulated nodes
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct TopologyLink {
+    /// name of the first node
+    pub from: String,
+    /// name of the second node
+    pub to: String,
+    /// latency in milli seconds
+    #[serde(default)]
+    pub latency: u64,
+    /// message loss in percent
+    #[serde(default)]
+    pub loss: f64,
+    /// bandwidth in bytes per second
+    ///
+    /// A bandwidth of 0 is unlimited.
+    #[serde(default)]
+    pub bandwidth: u64,
+}
+
+/// Network Partition
+///
+/// During the partition, the listed nodes are separated
+/// from all other nodes of the network.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct TopologyPartition {
+    /// start time in milli seconds since simulation start
+    pub start: u64,
+    /// end time in milli seconds since simulation start
+    ///
+    /// An end of 0 lasts until the end of the simulation.
+    #[serde(default)]
+    pub end: u64,
+    /// names of the separated nodes
+    pub nodes: Vec<String>,
+}
+
+/// Topology of the Virtual Network
+#[derive(Debug, Deserialize, Clone, Serialize)]
+pub struct Topology {
+    /// seed of the random loss simulation
+    #[serde(default)]
+    pub seed: u64,
+    /// all simulated nodes
+    pub nodes: Vec<TopologyNode>,
+    /// links between the nodes
+    #[serde(default)]
+    pub links: Vec<TopologyLink>,
+    /// network partitions
+    #[serde(default)]
+    pub partitions: Vec<TopologyPartition>,
+}
+
+impl Topology {
+    /// load and validate a topology file
+    pub fn load(path: &str) -> Result<Topology, String> {
+        let content;
+        match fs::read_to_string(path) {
+            Ok(c) => content = c,
+            Err(e) => return Err(format!("can't read topology file {}: {}", path, e)),
+        }
+
+        let topology: Topology;
+        match serde_yaml::from_str(&content) {
+            Ok(t) => topology = t,
+            Err(e) => return Err(format!("invalid topology file {}: {}", path, e)),
+        }
+
+        topology.validate()?;
+
+        Ok(topology)
+    }
+
+    /// check the topology for consistency
+    fn validate(&self) -> Result<(), String> {
+        // node names need to be unique
+        for (i, node) in self.nodes.iter().enumerate() {
+            if node.name.is_empty() {
+                return Err("topology node without name".to_string());
+            }
+            if self.nodes[..i].iter().any(|n| n.name == node.name) {
+                return Err(format!("topology node {} is defined twice", node.name));
+            }
+        }
+
+        // links need to connect defined nodes
+        for link in &self.links {
+            if !self.contains(&link.from) || !self.contains(&link.to) {
+                return Err(format!(
+                    "topology link {} - {} connects an unknown node",
+                    link.from, link.to
+                ));
+            }
+            if link.from == link.to {
+                return Err(format!("topology link {} connects to itself", link.from));
+            }
+            if !(0.0..=100.0).contains(&link.loss) {
+                return Err(format!(
+                    "topology link {} - {} loss needs to be between 0 and 100",
+                    link.from, link.to
+                ));
+            }
+        }
+
+        // partitions need to contain defined nodes
+        for partition in &self.partitions {
+            for name in &partition.nodes {
+                if !self.contains(name) {
+                    return Err(format!("topology partition contains unknown node {}", name));
+                }
+            }
+            if partition.end != 0 && partition.end <= partition.start {
+                return Err(format!(
+                    "topology partition starting at {} ends before it starts",
+                    partition.start
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// check if a node is defined in this topology
+    pub fn contains(&self, name: &str) -> bool {
+        self.nodes.iter().any(|node| node.name == name)
+    }
+
+    /// get all links of a node
+    ///
+    /// Returns the name of the neighbour node and the link.
+    pub fn links_of(&self, name: &str) -> Vec<(String, TopologyLink)> {
+        let mut links = Vec::new();
+
+        for link in &self.links {
+            if link.from == name {
+                links.push((link.to.clone(), link.clone()));
+            } else if link.to == name {
+                links.push((link.from.clone(), link.clone()));
+            }
+        }
+
+        links
+    }
+
+    /// check if two nodes are separated by a partition
+    ///
+    /// * `elapsed`: milli seconds since the start of the simulation
+    pub fn is_partitioned(&self, a: &str, b: &str, elapsed: u64) -> bool {
+        for partition in &self.partitions {
+            // check if partition is active
+            if elapsed < partition.start || (partition.end != 0 && elapsed >= partition.end) {
+                continue;
+            }
+
+            // the nodes are separated, if only one of them is in the partition
+            let a_inside = partition.nodes.iter().any(|n| n == a);
+            let b_inside = partition.nodes.iter().any(|n| n == b);
+            if a_inside != b_inside {
+                return true;
+            }
+        }
+
+        false
+    }
+}
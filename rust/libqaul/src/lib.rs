@@ -9,7 +9,6 @@ use filetime::FileTime;
 use futures::prelude::*;
 use futures::{future::FutureExt, pin_mut, select};
 use futures_ticker::Ticker;
-use state::InitCell;
 use std::collections::BTreeMap;
 use std::fs::File;
 use std::path::Path;
@@ -25,24 +24,30 @@ mod services;
 pub mod storage;
 pub mod utilities;
 
-use connections::{ble::Ble, internet::Internet, ConnectionModule, Connections};
+use connections::{
+    ble::Ble, internet::Internet, virtual_net::VirtualNet, ConnectionModule, Connections,
+};
 use node::Node;
 use router::{
-    feed_requester, flooder, info::RouterInfo, neighbours::Neighbours, user_requester, Router,
+    feed_requester, flooder, info::RouterInfo, neighbours::Neighbours, snapshot::RoutingSnapshot,
+    user_requester, Router,
 };
 use rpc::sys::Sys;
 use rpc::Rpc;
 use services::messaging::Messaging;
 use services::Services;
+use storage::configuration::Configuration;
+use storage::maintenance::StorageMaintenance;
 use utilities::filelogger::FileLogger;
+use utilities::instance::InstanceCell;
 use utilities::timestamp::Timestamp;
 use utilities::upgrade;
 
 /// check this when the library finished initializing
-static INITIALIZED: InitCell<bool> = InitCell::new();
+static INITIALIZED: InstanceCell<bool> = InstanceCell::new();
 
 /// default configs
-static DEFCONFIGS: InitCell<BTreeMap<String, String>> = InitCell::new();
+static DEFCONFIGS: InstanceCell<BTreeMap<String, String>> = InstanceCell::new();
 
 /// To see logs on android we need the android logger
 #[cfg(target_os = "android")]
@@ -76,6 +81,10 @@ enum EventType {
     RoutingTable,
     Messaging,
     Retransmit,
+    Virtual,
+    Maintenance,
+    RoutingSnapshot,
+    Probe,
 }
 
 /// initialize and start libqaul with a optional custom configuration options
@@ -86,17 +95,13 @@ enum EventType {
 /// * Provide a path where libqaul can save all data.
 /// * Optionally you can provide the following configuration values:
 ///   * listening port of the Internet connection module (default = randomly assigned)
+///   * storage backend: `memory` for an ephemeral node (default = sled data base on disk)
+///   * simulation topology file and node name for simulated nodes (default = no simulation)
+///   * `upgrade_dry_run`: run the upgrade of the stored data on a copy (default = false)
 pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, String>>) -> () {
     // print storage path
     println!("storage path: {}", storage_path);
 
-    // check if we need to upgrade our stored data
-    if upgrade::Upgrade::init(storage_path.clone()) == false {
-        println!("upgrade to new version failed");
-        // restart node
-        std::process::exit(0);
-    }
-
     // check configuration options
     if let Some(def_cfg) = def_config {
         DEFCONFIGS.set(def_cfg.clone());
@@ -105,52 +110,65 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     }
 
     // initialize rpc system
+    // the upgrade reports its progress via RPC
     let libqaul_rpc_receive = Rpc::init();
     let libqaul_sys_receive = Sys::init();
 
-    // initialize storage module.
-    // This will initialize configuration & data base
-    storage::Storage::init(storage_path.clone());
-
-    // --- initialize logger ---
-    // prepare logger path
-    // the path of the log file follows the following naming convention:
-    // error_234324232.log
-    let path = Path::new(&storage_path);
-    let log_path = path.join("logs");
-
-    // create log directory if missing
-    std::fs::create_dir_all(&log_path).unwrap();
-
-    // create log file name
-    let log_file_name: String =
-        "error_".to_string() + Timestamp::get_timestamp().to_string().as_str() + ".log";
-    let log_file_path = log_path.join(log_file_name);
-
-    // maintain log files
-    let paths = std::fs::read_dir(log_path).unwrap();
-    // --- logger init-end ---
+    // ephemeral instances don't touch the disk
+    let ephemeral = storage::Storage::is_ephemeral();
 
-    let mut logfiles: BTreeMap<i64, String> = BTreeMap::new();
-    let mut logfile_times: Vec<i64> = vec![];
-    for path in paths {
-        let filename = String::from(path.as_ref().unwrap().path().to_str().unwrap());
-        let metadata = std::fs::metadata(filename.clone()).unwrap();
-        //print!("path={}", path.unwrap().path().display());
-        let mtime = FileTime::from_last_modification_time(&metadata);
-        //println!("{}", mtime.seconds());
-        logfile_times.push(mtime.seconds());
-        logfiles.insert(mtime.seconds(), filename);
+    // check if we need to upgrade our stored data
+    let upgrade_dry_run = get_default_config("upgrade_dry_run") == Some("true".to_string());
+    if !ephemeral && upgrade::Upgrade::init(storage_path.clone(), upgrade_dry_run) == false {
+        // libqaul can't run on data of an older version.
+        // The upgrade progress stays in the RPC queue for the UI.
+        println!("libqaul stopped after the upgrade");
+        return;
     }
-    logfile_times.sort();
 
-    if logfile_times.len() > 2 {
-        for i in 0..(logfile_times.len() - 2) {
-            if let Some(filename) = logfiles.get(&logfile_times[i]) {
-                std::fs::remove_file(std::path::Path::new(filename)).unwrap();
+    // --- initialize logger ---
+    // ephemeral instances don't write a log file
+    let mut log_file_path = None;
+    if !ephemeral {
+        // prepare logger path
+        // the path of the log file follows the following naming convention:
+        // error_234324232.log
+        let path = Path::new(&storage_path);
+        let log_path = path.join("logs");
+
+        // create log directory if missing
+        std::fs::create_dir_all(&log_path).unwrap();
+
+        // create log file name
+        let log_file_name: String =
+            "error_".to_string() + Timestamp::get_timestamp().to_string().as_str() + ".log";
+        log_file_path = Some(log_path.join(log_file_name));
+
+        // maintain log files
+        let paths = std::fs::read_dir(log_path).unwrap();
+
+        let mut logfiles: BTreeMap<i64, String> = BTreeMap::new();
+        let mut logfile_times: Vec<i64> = vec![];
+        for path in paths {
+            let filename = String::from(path.as_ref().unwrap().path().to_str().unwrap());
+            let metadata = std::fs::metadata(filename.clone()).unwrap();
+            //print!("path={}", path.unwrap().path().display());
+            let mtime = FileTime::from_last_modification_time(&metadata);
+            //println!("{}", mtime.seconds());
+            logfile_times.push(mtime.seconds());
+            logfiles.insert(mtime.seconds(), filename);
+        }
+        logfile_times.sort();
+
+        if logfile_times.len() > 2 {
+            for i in 0..(logfile_times.len() - 2) {
+                if let Some(filename) = logfiles.get(&logfile_times[i]) {
+                    std::fs::remove_file(std::path::Path::new(filename)).unwrap();
+                }
             }
         }
     }
+    // --- logger init-end ---
 
     // logging on android with android logger
     #[cfg(target_os = "android")]
@@ -158,13 +176,17 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
         let env_logger = Box::new(android_logger::AndroidLogger::new(
             Config::default().with_max_level(log::LevelFilter::Info),
         ));
-        let w_logger = FileLogger::new(*simplelog::WriteLogger::new(
-            simplelog::LevelFilter::Error,
-            simplelog::Config::default(),
-            File::create(log_file_path).unwrap(),
-        ));
-        multi_log::MultiLogger::init(vec![env_logger, Box::new(w_logger)], log::Level::Info)
-            .unwrap();
+        let mut loggers: Vec<Box<dyn log::Log>> = vec![env_logger];
+        if let Some(log_file_path) = log_file_path {
+            let w_logger = FileLogger::new(*simplelog::WriteLogger::new(
+                simplelog::LevelFilter::Error,
+                simplelog::Config::default(),
+                File::create(log_file_path).unwrap(),
+            ));
+            loggers.push(Box::new(w_logger));
+        }
+        // the logger is process wide, it is only set by the first libqaul instance
+        multi_log::MultiLogger::init(loggers, log::Level::Info).ok();
     }
 
     // logging on ios
@@ -175,13 +197,17 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 .filter(None, log::LevelFilter::Info)
                 .build(),
         );
-        let w_logger = FileLogger::new(*simplelog::WriteLogger::new(
-            simplelog::LevelFilter::Error,
-            simplelog::Config::default(),
-            File::create(log_file_path).unwrap(),
-        ));
-        multi_log::MultiLogger::init(vec![env_logger, Box::new(w_logger)], log::Level::Info)
-            .unwrap();
+        let mut loggers: Vec<Box<dyn log::Log>> = vec![env_logger];
+        if let Some(log_file_path) = log_file_path {
+            let w_logger = FileLogger::new(*simplelog::WriteLogger::new(
+                simplelog::LevelFilter::Error,
+                simplelog::Config::default(),
+                File::create(log_file_path).unwrap(),
+            ));
+            loggers.push(Box::new(w_logger));
+        }
+        // the logger is process wide, it is only set by the first libqaul instance
+        multi_log::MultiLogger::init(loggers, log::Level::Info).ok();
     }
 
     // only use the simple logger on desktop systems
@@ -213,19 +239,40 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 .filter(None, level_filter)
                 .build(),
         );
-        let w_logger = FileLogger::new(*simplelog::WriteLogger::new(
-            simplelog::LevelFilter::Error,
-            simplelog::Config::default(),
-            File::create(log_file_path).unwrap(),
-        ));
-        multi_log::MultiLogger::init(vec![env_logger, Box::new(w_logger)], log::Level::Info)
-            .unwrap();
+        let mut loggers: Vec<Box<dyn log::Log>> = vec![env_logger];
+        if let Some(log_file_path) = log_file_path {
+            let w_logger = FileLogger::new(*simplelog::WriteLogger::new(
+                simplelog::LevelFilter::Error,
+                simplelog::Config::default(),
+                File::create(log_file_path).unwrap(),
+            ));
+            loggers.push(Box::new(w_logger));
+        }
+        // the logger is process wide, it is only set by the first libqaul instance
+        multi_log::MultiLogger::init(loggers, log::Level::Info).ok();
     }
 
     log::trace!("test log to ensure that logging is working");
 
+    // initialize storage module.
+    // This will initialize configuration & data base
+    if let Err(e) = storage::Storage::init(storage_path.clone()) {
+        log::error!("storage encryption failed: {}", e);
+        log::error!("libqaul stopped");
+        return;
+    }
+
     // initialize node & user accounts
-    Node::init();
+    let node_initialized = Node::init();
+
+    // report the errors of the configuration file
+    // including the errors found in the private keys
+    rpc::config::Config::report_startup_errors();
+
+    if !node_initialized {
+        log::error!("libqaul stopped, the node can't be initialized");
+        return;
+    }
 
     // initialize router
     Router::init();
@@ -235,6 +282,19 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     let mut internet = conn.internet.unwrap();
     let mut lan = conn.lan.unwrap();
 
+    // dial the neighbours restored from the routing snapshot
+    // simulated nodes are only connected via the virtual module
+    if !VirtualNet::is_simulated() {
+        for address in Neighbours::restored_addresses(ConnectionModule::Internet) {
+            Internet::peer_dial(address, &mut internet.swarm);
+        }
+        for address in Neighbours::restored_addresses(ConnectionModule::Lan) {
+            if let Err(e) = lan.swarm.dial(address.clone()) {
+                log::trace!("lan neighbour {} dial error: {:?}", address, e);
+            }
+        }
+    }
+
     // initialize services
     Services::init();
 
@@ -280,6 +340,23 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
     // manage the message retransmit
     let mut retransmit_ticker = Ticker::new(Duration::from_millis(1000));
 
+    // deliver the messages of the virtual network
+    // the ticker only runs for simulated nodes
+    let mut virtual_ticker = match VirtualNet::is_active() {
+        true => Some(Ticker::new(Duration::from_millis(10))),
+        false => None,
+    };
+
+    // enforce the storage retention policies periodically
+    let maintenance_period = Configuration::get().maintenance.period;
+    let mut maintenance_ticker = Ticker::new(Duration::from_secs(maintenance_period));
+
+    // save the routing snapshot periodically
+    let mut routing_snapshot_ticker = Ticker::new(Duration::from_secs(RoutingSnapshot::INTERVAL));
+
+    // send the reachability probes
+    let mut probe_ticker = Ticker::new(Duration::from_millis(1000));
+
     // set initialized flag
     INITIALIZED.set(true);
 
@@ -301,6 +378,16 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
             let routing_table_fut = routing_table_ticker.next().fuse();
             let messaging_fut = messaging_ticker.next().fuse();
             let retransmit_fut = retransmit_ticker.next().fuse();
+            let virtual_fut = async {
+                match virtual_ticker.as_mut() {
+                    Some(ticker) => ticker.next().await,
+                    None => futures::future::pending().await,
+                }
+            }
+            .fuse();
+            let maintenance_fut = maintenance_ticker.next().fuse();
+            let routing_snapshot_fut = routing_snapshot_ticker.next().fuse();
+            let probe_fut = probe_ticker.next().fuse();
 
             // This Macro is shown wrong by Rust-Language-Server > 0.2.400
             // You need to downgrade to version 0.2.400 if this happens to you
@@ -319,14 +406,21 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 routing_table_fut,
                 messaging_fut,
                 retransmit_fut,
+                virtual_fut,
+                maintenance_fut,
+                routing_snapshot_fut,
+                probe_fut,
             );
 
             select! {
                 lan_event = lan_fut => {
                     //log::trace!("Unhandled lan connection module event: {:?}", lan_event);
                     match lan_event.unwrap() {
-                        libp2p::swarm::SwarmEvent::ConnectionEstablished{peer_id,  ..} => {
+                        libp2p::swarm::SwarmEvent::ConnectionEstablished{peer_id, endpoint, ..} => {
                             log::trace!("lan connection established: {:?}", peer_id);
+                            if let libp2p::core::ConnectedPoint::Dialer{address, ..} = endpoint {
+                                Neighbours::add_addresses(ConnectionModule::Lan, peer_id, vec![address]);
+                            }
                         }
                         libp2p::swarm::SwarmEvent::ConnectionClosed{peer_id, ..} => {
                             //remove from neighbour table, after then scheduler will auto remove this neighbour
@@ -375,6 +469,7 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                                     log::info!("connection established! peer={}, endpoint={}", peer_id.to_base58(), address.to_string());
                                     Internet::remove_reconnection(address.clone());
                                     Internet::add_connection(address.to_string(), &peer_id);
+                                    Neighbours::add_addresses(ConnectionModule::Internet, peer_id, vec![address]);
                                 }
                                 _ => {}
                             }
@@ -419,6 +514,10 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                 _routing_table_event = routing_table_fut => Some(EventType::RoutingTable),
                 _messaging_event = messaging_fut => Some(EventType::Messaging),
                 _retransmit_event = retransmit_fut => Some(EventType::Retransmit),
+                _virtual_event = virtual_fut => Some(EventType::Virtual),
+                _maintenance_event = maintenance_fut => Some(EventType::Maintenance),
+                _routing_snapshot_event = routing_snapshot_fut => Some(EventType::RoutingSnapshot),
+                _probe_event = probe_fut => Some(EventType::Probe),
             }
         };
 
@@ -466,6 +565,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                                 .floodsub
                                 .publish(msg.topic.clone(), msg.message.clone());
                         }
+                        if !matches!(msg.incoming_via, ConnectionModule::Virtual) {
+                            VirtualNet::send_feed_message(msg.topic.clone(), msg.message.clone());
+                        }
                         if !matches!(msg.incoming_via, ConnectionModule::Ble) {
                             Ble::send_feed_message(msg.topic, msg.message);
                         }
@@ -501,6 +603,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             ConnectionModule::Ble => {
                                 Ble::send_routing_info(request.neighbour_id, data);
                             }
+                            ConnectionModule::Virtual => {
+                                VirtualNet::send_routing_info(request.neighbour_id, data);
+                            }
                             ConnectionModule::Local => {}
                             ConnectionModule::None => {}
                         }
@@ -536,6 +641,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             ConnectionModule::Ble => {
                                 Ble::send_routing_info(request.neighbour_id, data);
                             }
+                            ConnectionModule::Virtual => {
+                                VirtualNet::send_routing_info(request.neighbour_id, data);
+                            }
                             ConnectionModule::Local => {}
                             ConnectionModule::None => {}
                         }
@@ -572,6 +680,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             ConnectionModule::Ble => {
                                 Ble::send_routing_info(request.neighbour_id, data);
                             }
+                            ConnectionModule::Virtual => {
+                                VirtualNet::send_routing_info(request.neighbour_id, data);
+                            }
                             ConnectionModule::Local => {}
                             ConnectionModule::None => {}
                         }
@@ -608,6 +719,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             ConnectionModule::Ble => {
                                 Ble::send_routing_info(request.neighbour_id, data);
                             }
+                            ConnectionModule::Virtual => {
+                                VirtualNet::send_routing_info(request.neighbour_id, data);
+                            }
                             ConnectionModule::Local => {}
                             ConnectionModule::None => {}
                         }
@@ -641,6 +755,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             ConnectionModule::Ble => {
                                 Ble::send_routing_info(neighbour_id, data);
                             }
+                            ConnectionModule::Virtual => {
+                                VirtualNet::send_routing_info(neighbour_id, data);
+                            }
                             ConnectionModule::Local => {}
                             ConnectionModule::None => {}
                         }
@@ -658,16 +775,14 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                     router::connections::ConnectionTable::create_routing_table();
                 }
                 EventType::Messaging => {
-                    // send scheduled messages
-                    if let Some((neighbour_id, connection_module, data)) =
-                        Messaging::check_scheduler()
-                    {
+                    // send scheduled messages over all their routes
+                    for (neighbour_id, connection_module, data) in Messaging::check_scheduler() {
                         log::trace!(
                             "sending messaging message via {:?} to {}",
                             connection_module,
                             neighbour_id.to_base58()
                         );
-                        // send messaging message via the best module
+                        // send messaging message via the module of the route
                         match connection_module {
                             ConnectionModule::Lan => {
                                 lan.swarm
@@ -685,6 +800,9 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                             ConnectionModule::Ble => {
                                 Ble::send_messaging_message(neighbour_id, data);
                             }
+                            ConnectionModule::Virtual => {
+                                VirtualNet::send_messaging_message(neighbour_id, data);
+                            }
                             ConnectionModule::Local => {
                                 let message = qaul_messaging::types::QaulMessagingReceived {
                                     received_from: neighbour_id,
@@ -704,6 +822,22 @@ pub async fn start(storage_path: String, def_config: Option<BTreeMap<String, Str
                     // check if there are messages to retransmit
                     services::messaging::retransmit::MessagingRetransmit::process();
                 }
+                EventType::Virtual => {
+                    // process the virtual network of simulated nodes
+                    VirtualNet::process();
+                }
+                EventType::Maintenance => {
+                    // enforce the storage retention policies
+                    StorageMaintenance::run();
+                }
+                EventType::RoutingSnapshot => {
+                    // persist the routing tables
+                    RoutingSnapshot::save();
+                }
+                EventType::Probe => {
+                    // send the due reachability probes
+                    services::messaging::probe::MessagingProbe::process();
+                }
             }
         }
     }
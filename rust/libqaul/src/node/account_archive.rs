@@ -0,0 +1,494 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # User Account Archive
+//!
+//! Export a user account into a single archive file
+//! and import it on another node.
+//!
+//! The archive contains:
+//!
+//! * the keys of the user account
+//! * the storage options of the user account
+//! * all trees of the user data base (groups, chats,
+//!   crypto sessions, file history, ...)
+//! * all files in the user account folder
+//!
+//! The archive is signed with the key of the user account.
+//! Optionally it is encrypted with a passphrase.
+//! As the archive contains the private key of the user account,
+//! it should always be encrypted when it leaves the device.
+//!
+//! An import is written to a staging folder next to the account
+//! folder, which is only moved into place once it is complete.
+//! Ephemeral nodes import the data base into memory, without the files.
+
+use bincode::Options;
+use libp2p::{
+    identity::{ed25519, Keypair},
+    PeerId,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use sled::IVec;
+use std::{
+    collections::BTreeSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use super::user_accounts::{UserAccount, UserAccounts};
+use crate::services::chat::ChatStorage;
+use crate::storage::configuration::{Configuration, StorageOptions};
+use crate::storage::database::DataBase;
+use crate::storage::encryption::StorageEncryption;
+use crate::storage::tree::Db;
+use crate::storage::Storage;
+
+/// prefix of every account archive file
+const MAGIC: &[u8; 4] = b"QACC";
+
+/// version of the archive format
+const VERSION: u32 = 1;
+
+/// name of the user data base folder inside the account folder
+const DB_FOLDER: &str = "user.db";
+
+/// extension of the staging folder of an import
+const STAGING_EXTENSION: &str = "import";
+
+/// Signed Archive Container
+///
+/// This structure is saved to the archive file.
+#[derive(Serialize, Deserialize)]
+struct ArchiveContainer {
+    /// version of the archive format
+    version: u32,
+    /// the content is encrypted with a passphrase
+    encrypted: bool,
+    /// bincode encoded `ArchiveContent`
+    content: Vec<u8>,
+}
+
+/// Archive Content
+#[derive(Serialize, Deserialize)]
+struct ArchiveContent {
+    /// bincode encoded `ArchiveAccount`
+    account: Vec<u8>,
+    /// signature of the account data with the user account key
+    signature: Vec<u8>,
+}
+
+/// User Account Data
+#[derive(Serialize, Deserialize)]
+struct ArchiveAccount {
+    /// user ID
+    id: Vec<u8>,
+    /// user name
+    name: String,
+    /// ed25519 key pair
+    keys: Vec<u8>,
+    /// storage options
+    storage: StorageOptions,
+    /// all trees of the user data base
+    trees: Vec<ArchiveTree>,
+    /// all files of the user account folder
+    files: Vec<ArchiveFile>,
+}
+
+/// Data Base Tree
+#[derive(Serialize, Deserialize)]
+struct ArchiveTree {
+    /// name of the tree
+    name: Vec<u8>,
+    /// key value pairs of the tree
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+}
+
+/// File of the User Account Folder
+#[derive(Serialize, Deserialize)]
+struct ArchiveFile {
+    /// path components relative to the account folder
+    path: Vec<String>,
+    /// file content
+    content: Vec<u8>,
+}
+
+/// User Account Archive Module
+pub struct AccountArchive {}
+
+impl AccountArchive {
+    /// Export a user account to an archive file
+    ///
+    /// If a passphrase is provided, the archive is encrypted.
+    pub fn export(account_id: PeerId, path: &str, passphrase: Option<&str>) -> Result<(), String> {
+        let user_account;
+        match UserAccounts::get_by_id(account_id) {
+            Some(account) => user_account = account,
+            None => return Err(format!("user account {} not found", account_id)),
+        }
+
+        // collect account data
+        let storage = match Configuration::get_user(account_id.to_string()) {
+            Some(account) => account.storage,
+            None => StorageOptions::default(),
+        };
+        let keys = match user_account.keys.clone().try_into_ed25519() {
+            Ok(keys) => keys.to_bytes().to_vec(),
+            Err(e) => return Err(format!("unsupported user account key: {}", e)),
+        };
+        let mut files = Vec::new();
+        let account_path = Storage::get_account_path(account_id);
+        if account_path.exists() {
+            if let Err(e) = Self::collect_files(&account_path, &mut Vec::new(), &mut files) {
+                return Err(format!("error reading user account files: {}", e));
+            }
+        }
+        let account = ArchiveAccount {
+            id: account_id.to_bytes(),
+            name: user_account.name.clone(),
+            keys,
+            storage,
+            trees: Self::export_trees(account_id)?,
+            files,
+        };
+
+        // sign account data
+        let account_bytes = bincode::serialize(&account).map_err(|e| e.to_string())?;
+        let signature = match user_account.keys.sign(&account_bytes) {
+            Ok(signature) => signature,
+            Err(e) => return Err(format!("error signing archive: {}", e)),
+        };
+        let mut content = bincode::serialize(&ArchiveContent {
+            account: account_bytes,
+            signature,
+        })
+        .map_err(|e| e.to_string())?;
+
+        // encrypt content
+        if let Some(passphrase) = passphrase {
+            match StorageEncryption::encrypt_with_passphrase(passphrase, &content) {
+                Some(encrypted) => content = encrypted,
+                None => return Err("error encrypting archive".to_string()),
+            }
+        }
+
+        // write archive file
+        let container = ArchiveContainer {
+            version: VERSION,
+            encrypted: passphrase.is_some(),
+            content,
+        };
+        let mut archive = MAGIC.to_vec();
+        archive.extend(bincode::serialize(&container).map_err(|e| e.to_string())?);
+        if let Err(e) = fs::write(path, archive) {
+            return Err(format!("error writing archive {}: {}", path, e));
+        }
+
+        log::info!("user account {} exported to {}", account_id, path);
+
+        Ok(())
+    }
+
+    /// Import a user account from an archive file
+    ///
+    /// The passphrase is needed if the archive is encrypted.
+    /// The imported account is registered on this node.
+    pub fn import(path: &str, passphrase: Option<&str>) -> Result<UserAccount, String> {
+        // read archive file
+        let archive = match fs::read(path) {
+            Ok(archive) => archive,
+            Err(e) => return Err(format!("error reading archive {}: {}", path, e)),
+        };
+        if !archive.starts_with(MAGIC) {
+            return Err(format!("{} is not a user account archive", path));
+        }
+        let container: ArchiveContainer = Self::decode(&archive[MAGIC.len()..])?;
+        if container.version != VERSION {
+            return Err(format!("unsupported archive version {}", container.version));
+        }
+
+        // decrypt content
+        let mut content_bytes = container.content;
+        if container.encrypted {
+            let passphrase = match passphrase {
+                Some(passphrase) => passphrase,
+                None => return Err("archive is encrypted, a passphrase is needed".to_string()),
+            };
+            match StorageEncryption::decrypt_with_passphrase(passphrase, &content_bytes) {
+                Some(decrypted) => content_bytes = decrypted,
+                None => return Err("wrong archive passphrase".to_string()),
+            }
+        }
+        let content: ArchiveContent = Self::decode(&content_bytes)?;
+        let account: ArchiveAccount = Self::decode(&content.account)?;
+
+        // check keys and signature
+        let mut key_bytes = account.keys.clone();
+        let keys = match ed25519::Keypair::try_from_bytes(&mut key_bytes) {
+            Ok(keys) => Keypair::from(keys),
+            Err(e) => return Err(format!("invalid user account key: {}", e)),
+        };
+        let account_id = PeerId::from(keys.public());
+        if account_id.to_bytes() != account.id {
+            return Err("user ID does not match the user account key".to_string());
+        }
+        if !keys.public().verify(&content.account, &content.signature) {
+            return Err("invalid archive signature".to_string());
+        }
+
+        // check if the account already exists on this node
+        if UserAccounts::is_account(account_id) {
+            return Err(format!(
+                "user account {} already exists on this node",
+                account_id
+            ));
+        }
+
+        // ephemeral nodes don't touch the disk,
+        // the data base is imported into memory
+        if Storage::is_ephemeral() {
+            Self::import_memory(account_id, account.files, account.trees)?;
+        } else {
+            Self::import_disk(account_id, account.files, account.trees)?;
+        }
+
+        // register account
+        let user_account = UserAccounts::register(keys, account.name, account.storage);
+
+        log::info!("user account {} imported from {}", account_id, path);
+
+        Ok(user_account)
+    }
+
+    /// import the files and the data base of a user account to disk
+    ///
+    /// The import is written to a staging folder,
+    /// which is moved into place once it is complete.
+    fn import_disk(
+        account_id: PeerId,
+        files: Vec<ArchiveFile>,
+        trees: Vec<ArchiveTree>,
+    ) -> Result<(), String> {
+        // the account folder is created by the import
+        let account_path = Storage::get_account_path(account_id);
+        if account_path.exists() {
+            return Err(format!(
+                "user account folder {:?} already exists",
+                account_path
+            ));
+        }
+
+        // check file paths before anything is written
+        let staging_path = account_path.with_extension(STAGING_EXTENSION);
+        let mut file_paths = Vec::new();
+        for file in files {
+            match Self::file_path(&staging_path, &file.path) {
+                Some(file_path) => file_paths.push((file_path, file.content)),
+                None => return Err(format!("invalid file path {:?}", file.path)),
+            }
+        }
+
+        // remove the remains of an interrupted import
+        if staging_path.exists() {
+            if let Err(e) = fs::remove_dir_all(&staging_path) {
+                return Err(format!(
+                    "error removing staging folder {:?}: {}",
+                    staging_path, e
+                ));
+            }
+        }
+
+        // write files & data base to the staging folder
+        // the data base is closed before it is moved
+        if let Err(e) = Self::stage(&staging_path, file_paths, trees) {
+            Self::remove_staging(&staging_path);
+            return Err(e);
+        }
+
+        // move the account folder into place
+        if staging_path.exists() {
+            if let Err(e) = fs::rename(&staging_path, &account_path) {
+                Self::remove_staging(&staging_path);
+                return Err(format!("error moving imported user account: {}", e));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// import the data base of a user account into memory
+    ///
+    /// Ephemeral nodes don't store files.
+    /// The files of the archive are therefore not imported,
+    /// and their chat messages are marked as pruned.
+    fn import_memory(
+        account_id: PeerId,
+        files: Vec<ArchiveFile>,
+        trees: Vec<ArchiveTree>,
+    ) -> Result<(), String> {
+        let db = Db::memory();
+        Self::import_trees(&db, trees)?;
+        DataBase::add_user_db(account_id, db);
+
+        if !files.is_empty() {
+            log::warn!(
+                "{} files of user account {} are not imported into the ephemeral node",
+                files.len(),
+                account_id
+            );
+
+            // the files are saved as `files/{file_id}.{extension}`
+            let file_ids: BTreeSet<u64> = files
+                .iter()
+                .filter_map(|file| match file.path.as_slice() {
+                    [folder, name] if folder == "files" => name.split('.').next()?.parse().ok(),
+                    _ => None,
+                })
+                .collect();
+            ChatStorage::mark_files_pruned(&account_id, &file_ids);
+        }
+
+        Ok(())
+    }
+
+    /// read all trees of the user data base
+    fn export_trees(account_id: PeerId) -> Result<Vec<ArchiveTree>, String> {
+        let db = DataBase::get_user_db(account_id);
+
+        let mut trees = Vec::new();
+        for name in db.tree_names() {
+            let tree = db.open_tree(&name).map_err(|e| e.to_string())?;
+
+            let mut entries = Vec::new();
+            for res in tree.iter() {
+                let (key, value) = res.map_err(|e| e.to_string())?;
+                entries.push((key.to_vec(), value.to_vec()));
+            }
+
+            trees.push(ArchiveTree {
+                name: name.to_vec(),
+                entries,
+            });
+        }
+
+        Ok(trees)
+    }
+
+    /// decode an untrusted bincode encoded archive structure
+    ///
+    /// The decoding is limited to the size of the encoded data,
+    /// for that forged length prefixes can't allocate without bound.
+    fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, String> {
+        bincode::options()
+            .with_fixint_encoding()
+            .allow_trailing_bytes()
+            .with_limit(bytes.len() as u64)
+            .deserialize(bytes)
+            .map_err(|e| e.to_string())
+    }
+
+    /// write the files and the data base of an import
+    /// to the staging folder
+    fn stage(
+        staging_path: &Path,
+        files: Vec<(PathBuf, Vec<u8>)>,
+        trees: Vec<ArchiveTree>,
+    ) -> Result<(), String> {
+        // write files
+        for (file_path, file_content) in files {
+            if let Some(folder) = file_path.parent() {
+                if let Err(e) = fs::create_dir_all(folder) {
+                    return Err(format!("error creating folder {:?}: {}", folder, e));
+                }
+            }
+            if let Err(e) = fs::write(&file_path, file_content) {
+                return Err(format!("error writing file {:?}: {}", file_path, e));
+            }
+        }
+
+        // write data base
+        let db = DataBase::open_staged(&staging_path.join(DB_FOLDER)).map_err(|e| e.to_string())?;
+        Self::import_trees(&db, trees)
+    }
+
+    /// remove the staging folder of a failed import
+    fn remove_staging(staging_path: &Path) {
+        if staging_path.exists() {
+            if let Err(e) = fs::remove_dir_all(staging_path) {
+                log::error!("error removing staging folder {:?}: {}", staging_path, e);
+            }
+        }
+    }
+
+    /// write all trees to the user data base
+    fn import_trees(db: &Db, trees: Vec<ArchiveTree>) -> Result<(), String> {
+        for archive_tree in trees {
+            let tree = db
+                .open_tree(&archive_tree.name)
+                .map_err(|e| e.to_string())?;
+            for (key, value) in archive_tree.entries {
+                tree.insert(key, IVec::from(value))
+                    .map_err(|e| e.to_string())?;
+            }
+            tree.flush().map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    /// collect all files of a folder recursively
+    ///
+    /// The user data base folder is skipped,
+    /// the data base is exported tree by tree.
+    fn collect_files(
+        folder: &Path,
+        relative: &mut Vec<String>,
+        files: &mut Vec<ArchiveFile>,
+    ) -> std::io::Result<()> {
+        for entry in fs::read_dir(folder)? {
+            let entry = entry?;
+            let name = entry.file_name().to_string_lossy().to_string();
+            if relative.is_empty() && name == DB_FOLDER {
+                continue;
+            }
+
+            relative.push(name);
+            if entry.file_type()?.is_dir() {
+                Self::collect_files(&entry.path(), relative, files)?;
+            } else {
+                files.push(ArchiveFile {
+                    path: relative.clone(),
+                    content: fs::read(entry.path())?,
+                });
+            }
+            relative.pop();
+        }
+
+        Ok(())
+    }
+
+    /// create the path of an imported file
+    ///
+    /// Returns `None` if the path would leave the account folder
+    /// or overwrite the user data base.
+    fn file_path(account_path: &Path, components: &[String]) -> Option<PathBuf> {
+        if components.is_empty() || components[0] == DB_FOLDER {
+            return None;
+        }
+
+        let mut path = account_path.to_path_buf();
+        for component in components {
+            if component.is_empty()
+                || component == "."
+                || component == ".."
+                || component.contains('/')
+                || component.contains('\\')
+            {
+                return None;
+            }
+            path.push(component);
+        }
+
+        Some(path)
+    }
+}
@@ -8,23 +8,24 @@
 //! local state.
 //! Provides state information of the local node to libqaul.
 
+pub mod account_archive;
 pub mod user_accounts;
 
 use base64;
 use base64::Engine;
-use libp2p::identity::ed25519;
 use libp2p::{floodsub::Topic, identity::Keypair, PeerId};
 use prost::Message;
-use state;
 
 use crate::connections::{internet::Internet, lan::Lan};
 use crate::rpc::Rpc;
 use crate::storage::configuration::Configuration;
+use crate::storage::encryption::StorageEncryption;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::qaul_id::QaulId;
 use user_accounts::UserAccounts;
 
 /// central state of this instances Node struct
-static NODE: state::InitCell<Node> = state::InitCell::new();
+static NODE: InstanceCell<Node> = InstanceCell::new();
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -41,7 +42,9 @@ pub struct Node {
 
 impl Node {
     /// start an existing node from the config parameters
-    pub fn init() {
+    ///
+    /// Returns false if the node can't be initialized.
+    pub fn init() -> bool {
         // initialize users of this node
         UserAccounts::init();
 
@@ -51,10 +54,11 @@ impl Node {
                 // create a new node and save it to configuration
                 log::trace!("Create a new node.");
                 Self::new();
+                true
             } else {
                 // instantiate node from configuration
                 log::trace!("Setup node from configuration.");
-                Self::from_config();
+                Self::from_config()
             }
         }
     }
@@ -74,8 +78,9 @@ impl Node {
         // save node to configuration file
         {
             let mut config = Configuration::get_mut();
-            config.node.keys = base64::engine::general_purpose::STANDARD
+            let keys_config = base64::engine::general_purpose::STANDARD
                 .encode(keys_ed25519.clone().try_into_ed25519().unwrap().to_bytes());
+            config.node.keys = StorageEncryption::encrypt_config_value(&keys_config);
             config.node.id = id.to_string();
             config.node.initialized = 1;
         }
@@ -89,28 +94,37 @@ impl Node {
     }
 
     /// start an existing node from the config parameters
-    fn from_config() {
+    fn from_config() -> bool {
         let config = Configuration::get();
-        let mut basedecode = base64::engine::general_purpose::STANDARD
-            .decode(&config.node.keys)
-            .unwrap();
-        let ed25519_keys = ed25519::Keypair::try_from_bytes(&mut basedecode).unwrap();
-        let keys = Keypair::from(ed25519_keys);
-        let id = PeerId::from(keys.public());
-        let topic = Topic::new("pages");
+        let keys_config;
+        match StorageEncryption::decrypt_config_value(&config.node.keys) {
+            Some(my_keys_config) => keys_config = my_keys_config,
+            None => {
+                log::error!("the node keys can't be decrypted");
+                drop(config);
+                Configuration::add_startup_error("node: keys can't be decrypted".to_string());
+                return false;
+            }
+        }
 
-        // check if saved ID and the id from the keypair are equal
-        if id.to_string() == config.node.id {
-            log::trace!("id's match {}", config.node.id);
-        } else {
-            log::error!("------------------------------------");
-            log::error!("ERROR: id's are not equal");
-            log::error!("{}  {}", id.to_string(), config.node.id);
-            log::error!("------------------------------------");
+        // decode the keys and check them against the saved ID
+        let keys;
+        match Configuration::decode_keys("node", &config.node.id, &keys_config) {
+            Ok(my_keys) => keys = my_keys,
+            Err(e) => {
+                log::error!("configuration error: {}", e);
+                drop(config);
+                Configuration::add_startup_error(e);
+                return false;
+            }
         }
+        let id = PeerId::from(keys.public());
+        let topic = Topic::new("pages");
 
         let node = Node { id, keys, topic };
         NODE.set(node);
+
+        true
     }
 
     /// get a cloned PeerId
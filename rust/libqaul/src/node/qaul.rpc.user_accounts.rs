@@ -2,7 +2,7 @@
 /// user account rpc message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UserAccounts {
-    #[prost(oneof = "user_accounts::Message", tags = "1, 2, 3, 4")]
+    #[prost(oneof = "user_accounts::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8")]
     pub message: ::core::option::Option<user_accounts::Message>,
 }
 /// Nested message and enum types in `UserAccounts`.
@@ -17,6 +17,14 @@ pub mod user_accounts {
         DefaultUserAccount(super::DefaultUserAccount),
         #[prost(message, tag = "4")]
         MyUserAccount(super::MyUserAccount),
+        #[prost(message, tag = "5")]
+        ExportUserAccount(super::ExportUserAccount),
+        #[prost(message, tag = "6")]
+        ExportUserAccountResult(super::ExportUserAccountResult),
+        #[prost(message, tag = "7")]
+        ImportUserAccount(super::ImportUserAccount),
+        #[prost(message, tag = "8")]
+        ImportUserAccountResult(super::ImportUserAccountResult),
     }
 }
 /// create a new user on this node
@@ -49,3 +57,54 @@ pub struct MyUserAccount {
     #[prost(string, tag = "6")]
     pub key_base58: ::prost::alloc::string::String,
 }
+/// export a user account to an archive file
+///
+/// The archive contains the private key of the user account.
+/// It is encrypted if a passphrase is provided.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportUserAccount {
+    /// user ID of the account to export
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// path of the archive file to write
+    #[prost(string, tag = "2")]
+    pub path: ::prost::alloc::string::String,
+    /// passphrase to encrypt the archive
+    /// the archive is not encrypted if empty
+    #[prost(string, tag = "3")]
+    pub passphrase: ::prost::alloc::string::String,
+}
+/// result of the user account export
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ExportUserAccountResult {
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message if the export failed
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// path of the written archive file
+    #[prost(string, tag = "3")]
+    pub path: ::prost::alloc::string::String,
+}
+/// import a user account from an archive file
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportUserAccount {
+    /// path of the archive file
+    #[prost(string, tag = "1")]
+    pub path: ::prost::alloc::string::String,
+    /// passphrase of an encrypted archive
+    #[prost(string, tag = "2")]
+    pub passphrase: ::prost::alloc::string::String,
+}
+/// result of the user account import
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ImportUserAccountResult {
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message if the import failed
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// the imported user account
+    #[prost(message, optional, tag = "3")]
+    pub my_user_account: ::core::option::Option<MyUserAccount>,
+}
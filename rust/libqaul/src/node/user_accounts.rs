@@ -11,17 +11,19 @@
 
 use base64::Engine;
 use libp2p::{
-    identity::{ed25519, Keypair, PublicKey},
+    identity::{Keypair, PublicKey},
     PeerId,
 };
 use prost::Message;
-use state::InitCell;
 use std::sync::RwLock;
 
+use super::account_archive::AccountArchive;
 use crate::router;
 use crate::rpc::Rpc;
 use crate::storage::configuration;
 use crate::storage::configuration::Configuration;
+use crate::storage::encryption::StorageEncryption;
+use crate::utilities::instance::InstanceCell;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -30,7 +32,7 @@ pub mod proto {
 }
 
 /// mutable state of users table
-static USERACCOUNTS: InitCell<RwLock<UserAccounts>> = InitCell::new();
+static USERACCOUNTS: InstanceCell<RwLock<UserAccounts>> = InstanceCell::new();
 
 #[derive(Clone)]
 pub struct UserAccount {
@@ -48,27 +50,34 @@ impl UserAccounts {
         let mut accounts = UserAccounts { users: Vec::new() };
 
         // check if there are users defined in configuration
-        let config = Configuration::get();
-        let config_users = config.user_accounts.clone();
+        let config_users = Configuration::get().user_accounts.clone();
         let mut iter = IntoIterator::into_iter(config_users);
 
         while let Some(user) = iter.next() {
-            let mut basedecode = base64::engine::general_purpose::STANDARD
-                .decode(&user.keys)
-                .unwrap();
-            let ed25519_keys = ed25519::Keypair::try_from_bytes(&mut basedecode).unwrap();
-            let keys = Keypair::from(ed25519_keys);
-            let id = PeerId::from(keys.public());
+            let keys_config;
+            match StorageEncryption::decrypt_config_value(&user.keys) {
+                Some(my_keys_config) => keys_config = my_keys_config,
+                None => {
+                    log::error!("keys of user account {} can't be decrypted", user.id);
+                    Configuration::add_startup_error(format!(
+                        "user_accounts: keys of {} can't be decrypted, account skipped",
+                        user.id
+                    ));
+                    continue;
+                }
+            }
 
-            // check if saved ID and the id from the keypair are equal
-            if id.to_string() == user.id {
-                log::trace!("user id's of '{}' match {}", user.name, user.id);
-            } else {
-                log::error!("------------------------------------");
-                log::error!("ERROR: user id's of '{}' are not equal", user.name);
-                log::error!("{}  {}", id.to_string(), user.id);
-                log::error!("------------------------------------");
+            // decode the keys and check them against the saved ID
+            let keys;
+            match Configuration::decode_keys("user_accounts", &user.id, &keys_config) {
+                Ok(my_keys) => keys = my_keys,
+                Err(e) => {
+                    log::error!("configuration error: {}, account skipped", e);
+                    Configuration::add_startup_error(format!("{}, account skipped", e));
+                    continue;
+                }
             }
+            let id = PeerId::from(keys.public());
 
             // push to user accounts table
             accounts.users.push(UserAccount {
@@ -86,8 +95,6 @@ impl UserAccounts {
     pub fn create(name: String) -> UserAccount {
         // create user
         let keys_ed25519 = Keypair::generate_ed25519();
-        let keys_config = base64::engine::general_purpose::STANDARD
-            .encode(keys_ed25519.clone().try_into_ed25519().unwrap().to_bytes());
         /*
         {
             // questions:
@@ -123,16 +130,37 @@ impl UserAccounts {
             log::info!("==============================");
         }
          */
-        let id = PeerId::from(keys_ed25519.public());
+        let user = Self::register(keys_ed25519, name, configuration::StorageOptions::default());
+
+        // display id
+        log::trace!("created user account '{}' {:?}", user.name, user.id);
+
+        user
+    }
+
+    /// register a user account on this node
+    ///
+    /// The account is saved to state and configuration,
+    /// and added to the users list and the routing table.
+    pub fn register(
+        keys: Keypair,
+        name: String,
+        storage: configuration::StorageOptions,
+    ) -> UserAccount {
+        let keys_config = base64::engine::general_purpose::STANDARD
+            .encode(keys.clone().try_into_ed25519().unwrap().to_bytes());
+        let id = PeerId::from(keys.public());
         let user = UserAccount {
             id,
-            keys: keys_ed25519.clone(),
+            keys: keys.clone(),
             name: name.clone(),
         };
 
         // save it to state
-        let mut users = USERACCOUNTS.get().write().unwrap();
-        users.users.push(user.clone());
+        {
+            let mut users = USERACCOUNTS.get().write().unwrap();
+            users.users.push(user.clone());
+        }
 
         // save it to config
         {
@@ -140,24 +168,48 @@ impl UserAccounts {
             config.user_accounts.push(configuration::UserAccount {
                 name: name.clone(),
                 id: id.to_string(),
-                keys: keys_config,
-                storage: configuration::StorageOptions::default(),
+                keys: StorageEncryption::encrypt_config_value(&keys_config),
+                storage,
             });
         }
         Configuration::save();
 
         // add it to users list
-        crate::router::users::Users::add(id, keys_ed25519.public(), name.clone(), false, false);
+        crate::router::users::Users::add(id, keys.public(), name, false, false);
 
         // add user to routing table / connections table
         crate::router::connections::ConnectionTable::add_local_user(id);
 
-        // display id
-        log::trace!("created user account '{}' {:?}", name, id);
-
         user
     }
 
+    /// change the name of a user account
+    ///
+    /// The name is saved to state and configuration.
+    pub fn set_name(account_id: &PeerId, name: String) {
+        // save it to state
+        {
+            let mut users = USERACCOUNTS.get().write().unwrap();
+            for user in users.users.iter_mut() {
+                if user.id == *account_id {
+                    user.name = name.clone();
+                }
+            }
+        }
+
+        // save it to config
+        {
+            let mut config = Configuration::get_mut();
+            let id = account_id.to_string();
+            for user in config.user_accounts.iter_mut() {
+                if user.id == id {
+                    user.name = name.clone();
+                }
+            }
+        }
+        Configuration::save();
+    }
+
     /// get user account by id
     pub fn get_by_id(account_id: PeerId) -> Option<UserAccount> {
         // get state
@@ -215,6 +267,7 @@ impl UserAccounts {
                 name: user.name.clone(),
                 verified: false,
                 blocked: false,
+                profile: None,
             });
         }
 
@@ -340,6 +393,74 @@ impl UserAccounts {
                             Vec::new(),
                         );
                     }
+                    Some(proto::user_accounts::Message::ExportUserAccount(export)) => {
+                        let mut result = proto::ExportUserAccountResult {
+                            success: false,
+                            error: "".to_string(),
+                            path: export.path.clone(),
+                        };
+
+                        let passphrase = match export.passphrase.is_empty() {
+                            true => None,
+                            false => Some(export.passphrase.as_str()),
+                        };
+                        match PeerId::from_bytes(&export.user_id) {
+                            Ok(account_id) => {
+                                match AccountArchive::export(account_id, &export.path, passphrase) {
+                                    Ok(()) => result.success = true,
+                                    Err(e) => result.error = e,
+                                }
+                            }
+                            Err(e) => result.error = format!("invalid user ID: {}", e),
+                        }
+                        if !result.success {
+                            log::error!("user account export failed: {}", result.error);
+                        }
+
+                        Self::rpc_send_message(proto::UserAccounts {
+                            message: Some(proto::user_accounts::Message::ExportUserAccountResult(
+                                result,
+                            )),
+                        });
+                    }
+                    Some(proto::user_accounts::Message::ImportUserAccount(import)) => {
+                        let mut result = proto::ImportUserAccountResult {
+                            success: false,
+                            error: "".to_string(),
+                            my_user_account: None,
+                        };
+
+                        let passphrase = match import.passphrase.is_empty() {
+                            true => None,
+                            false => Some(import.passphrase.as_str()),
+                        };
+                        match AccountArchive::import(&import.path, passphrase) {
+                            Ok(user_account) => {
+                                let (key_type, key_base58) =
+                                    Self::get_protobuf_public_key(user_account.keys.public());
+
+                                result.success = true;
+                                result.my_user_account = Some(proto::MyUserAccount {
+                                    name: user_account.name,
+                                    id: user_account.id.to_bytes(),
+                                    id_base58: user_account.id.to_base58(),
+                                    key: user_account.keys.public().encode_protobuf(),
+                                    key_type,
+                                    key_base58,
+                                });
+                            }
+                            Err(e) => {
+                                log::error!("user account import failed: {}", e);
+                                result.error = e;
+                            }
+                        }
+
+                        Self::rpc_send_message(proto::UserAccounts {
+                            message: Some(proto::user_accounts::Message::ImportUserAccountResult(
+                                result,
+                            )),
+                        });
+                    }
                     _ => {}
                 }
             }
@@ -349,6 +470,23 @@ impl UserAccounts {
         }
     }
 
+    /// encode and send a user accounts RPC message
+    fn rpc_send_message(proto_message: proto::UserAccounts) {
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Useraccounts.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
     /// create the qaul RPC definitions of a public key
     ///
     /// Returns a tuple with the key type & the base58 encoded
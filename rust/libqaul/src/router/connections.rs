@@ -11,10 +11,16 @@
 //! * Each user has an entry for each node over which it can be reached.
 //! * Out of this information the global table is constructed,
 //!   containing only the best entry.
+//! * Received routing information is checked for impossible
+//!   routes by the routing guard before it is entered.
+//! * The tables are restored from the last routing snapshot
+//!   at startup. Restored entries are stale until they are
+//!   confirmed by the routing information of their neighbour.
+//!   Unconfirmed entries expire after the maintain period limit,
+//!   as the routing information is sent incrementally.
 
 use libp2p::PeerId;
 use prost::Message;
-use state::InitCell;
 use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::sync::RwLock;
@@ -24,19 +30,34 @@ use crate::connections::ConnectionModule;
 use crate::node;
 use crate::router::router_net_proto;
 use crate::router::{
+    guard::{RoutingGuard, RoutingViolation},
+    info::PROPAGATION_INTERVAL,
+    metric::{self, LinkInfo},
     neighbours::Neighbours,
+    snapshot::{SnapshotConnection, SnapshotUser},
     table::{RoutingConnectionEntry, RoutingTable, RoutingUserEntry},
 };
 use crate::rpc::Rpc;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::qaul_id::QaulId;
 use crate::utilities::timestamp::Timestamp;
 
 /// Mutable module state
 /// Tables with all stats for each connection module
-static LOCAL: InitCell<RwLock<RoutingTable>> = InitCell::new();
-static INTERNET: InitCell<RwLock<ConnectionTable>> = InitCell::new();
-static LAN: InitCell<RwLock<ConnectionTable>> = InitCell::new();
-static BLE: InitCell<RwLock<ConnectionTable>> = InitCell::new();
+static LOCAL: InstanceCell<RwLock<RoutingTable>> = InstanceCell::new();
+static INTERNET: InstanceCell<RwLock<ConnectionTable>> = InstanceCell::new();
+static LAN: InstanceCell<RwLock<ConnectionTable>> = InstanceCell::new();
+static BLE: InstanceCell<RwLock<ConnectionTable>> = InstanceCell::new();
+static VIRTUAL: InstanceCell<RwLock<ConnectionTable>> = InstanceCell::new();
+
+/// Each further hop of an advertised route is assumed to take
+/// at least this fraction of the measured round trip time
+/// to the advertising neighbour.
+const MIN_HOP_RTT_DIVISOR: u32 = 4;
+
+/// Number of propagation IDs a user's propagation ID can jump
+/// further than it could have been increased since its last update.
+const PGID_JUMP_TOLERANCE: u64 = 3;
 
 /// Connection entry for UserEntry
 struct NeighbourEntry {
@@ -50,6 +71,9 @@ struct NeighbourEntry {
     lq: u32,
     /// time when the node was last updated
     last_update: u64,
+    /// entry was restored from the routing snapshot
+    /// and wasn't yet confirmed by the neighbour
+    stale: bool,
 }
 
 /// user entry for ConnectionTable
@@ -98,6 +122,11 @@ impl ConnectionTable {
             };
             BLE.set(RwLock::new(ble));
 
+            let virtual_net = ConnectionTable {
+                table: HashMap::new(),
+            };
+            VIRTUAL.set(RwLock::new(virtual_net));
+
             let local = RoutingTable {
                 table: HashMap::new(),
             };
@@ -158,6 +187,15 @@ impl ConnectionTable {
         //     log::trace!("receive_routing_info user={}, hc={}, propg_id={}", userid, inf.hc[0], inf.pgid);
         // }
 
+        // ignore routing information of quarantined neighbours
+        if RoutingGuard::is_quarantined(&neighbour_id) {
+            log::debug!(
+                "routing info of quarantined neighbour {} ignored",
+                neighbour_id.to_base58()
+            );
+            return;
+        }
+
         // try Lan module
         if let Some(rtt) = Neighbours::get_rtt(&neighbour_id, &ConnectionModule::Lan) {
             Self::fill_received_routing_info(ConnectionModule::Lan, neighbour_id, rtt, info);
@@ -172,6 +210,11 @@ impl ConnectionTable {
         if let Some(rtt) = Neighbours::get_rtt(&neighbour_id, &ConnectionModule::Ble) {
             Self::fill_received_routing_info(ConnectionModule::Ble, neighbour_id, rtt, info);
         }
+
+        // try virtual module
+        if let Some(rtt) = Neighbours::get_rtt(&neighbour_id, &ConnectionModule::Virtual) {
+            Self::fill_received_routing_info(ConnectionModule::Virtual, neighbour_id, rtt, info);
+        }
     }
 
     /// populate connection table with incoming routing information
@@ -182,24 +225,61 @@ impl ConnectionTable {
         info: &Vec<router_net_proto::RoutingInfoEntry>,
     ) {
         log::trace!("fill_received_routing_info {}", info.len());
+        // get the ping loss ratio of the link to the neighbour
+        let loss = Neighbours::get_loss(&neighbour_id, &conn).unwrap_or(0.0);
+
         // loop through results and enter them to the table
         for entry in info {
+            // an advertised route needs a hop count
+            let advertised_hc = match entry.hc.first() {
+                Some(hc) => *hc,
+                None => {
+                    RoutingGuard::report(
+                        neighbour_id,
+                        conn,
+                        RoutingViolation::InvalidHopCount,
+                        entry.user.clone(),
+                    );
+                    continue;
+                }
+            };
+
             // calculate hop count
-            // if hop count is > 255, return
+            // if hop count is > 255, skip the entry
             let hc;
-            if entry.hc[0] < 255 {
-                hc = entry.hc[0] + 1;
+            if advertised_hc < 255 {
+                hc = advertised_hc + 1;
             } else {
-                return;
+                continue;
+            }
+
+            // a route over further hops can't have a round trip time of zero,
+            // unless the links are free of latency, like in a simulation.
+            // Such entries are clamped by the bound below and only counted
+            // as violation if the link to the neighbour has a latency.
+            if advertised_hc > 0 && entry.rtt == 0 && rtt > 0 {
+                RoutingGuard::report(
+                    neighbour_id,
+                    conn,
+                    RoutingViolation::ImpossibleRtt,
+                    entry.user.clone(),
+                );
+                continue;
             }
 
+            // bound the advertised round trip time
+            // by the measured round trip time to the neighbour
+            let min_rtt = (advertised_hc as u32).saturating_mul(rtt / MIN_HOP_RTT_DIVISOR);
+            let route_rtt = entry.rtt.max(min_rtt).saturating_add(rtt);
+
             // fill structure
             let neighbour = NeighbourEntry {
                 id: neighbour_id,
-                rtt: entry.rtt + rtt,
+                rtt: route_rtt,
                 hc,
-                lq: Self::calculate_linkquality(entry.rtt + rtt, hc),
+                lq: Self::calculate_linkquality(route_rtt, hc, conn, loss),
                 last_update: Timestamp::get_timestamp(),
+                stale: false,
             };
 
             // add it to state
@@ -211,21 +291,27 @@ impl ConnectionTable {
     ///
     /// returns the calculated link quality for a connection.
     ///
-    /// The link quality is currently calculated using the
-    /// round trip time (rtt) and adding a penalty for each hop
-    /// according hop count (hc).
+    /// The link quality is calculated by the link quality metric
+    /// configured in the routing options, from the
+    /// round trip time (rtt), the hop count (hc),
+    /// the connection module and the ping loss ratio
+    /// of the link to the neighbour.
     ///
     /// The smaller the value is better is the link quality.
-    pub fn calculate_linkquality(rtt: u32, hc: u8) -> u32 {
+    pub fn calculate_linkquality(rtt: u32, hc: u8, module: ConnectionModule, loss: f32) -> u32 {
         // get the router configuration
         let config = super::Router::get_configuration();
 
-        // calculate link quality
-        // `hop_count_penalty` is seconds unit, thus it must be converted micro seconds
-        let lq = rtt + (hc as u32 * (config.hop_count_penalty as u32) * 1000_000);
+        // the ping loss ratio is the loss of the round trip
+        let link = LinkInfo {
+            rtt,
+            hc,
+            module,
+            loss,
+        };
 
-        // return link quality
-        lq
+        // calculate link quality
+        metric::get_metric(&config.link_quality_metric).calculate(&link, &config)
     }
 
     /// add connection to local state
@@ -241,6 +327,7 @@ impl ConnectionTable {
             ConnectionModule::Internet => connection_table = INTERNET.get().write().unwrap(),
             ConnectionModule::Lan => connection_table = LAN.get().write().unwrap(),
             ConnectionModule::Ble => connection_table = BLE.get().write().unwrap(),
+            ConnectionModule::Virtual => connection_table = VIRTUAL.get().write().unwrap(),
             ConnectionModule::Local => return,
             ConnectionModule::None => return,
         }
@@ -248,6 +335,31 @@ impl ConnectionTable {
         let now_ts = Timestamp::get_timestamp();
         // check if user already exists
         if let Some(user) = connection_table.table.get_mut(&user_q8id) {
+            // The propagation ID of a user can't increase faster
+            // than once per propagation interval.
+            // The propagation ID of a restored user is
+            // outdated by the downtime of this node.
+            let restored =
+                !user.connections.is_empty() && user.connections.values().all(|conn| conn.stale);
+            if pgid > user.pgid && !restored {
+                let max_jump = now_ts.saturating_sub(user.pgid_update) / PROPAGATION_INTERVAL
+                    + PGID_JUMP_TOLERANCE;
+                if (pgid - user.pgid) as u64 > max_jump {
+                    // Only a direct neighbour is accountable for the
+                    // propagation ID of its own user. A relay may have
+                    // learned a forged propagation ID in good faith.
+                    if connection.hc == 1 {
+                        RoutingGuard::report(
+                            connection.id,
+                            module,
+                            RoutingViolation::PropagationIdJump,
+                            user_q8id.clone(),
+                        );
+                    }
+                    return;
+                }
+            }
+
             if connection.hc == 1 || pgid > user.pgid {
                 user.pgid = pgid;
                 user.pgid_update = now_ts;
@@ -264,7 +376,7 @@ impl ConnectionTable {
                     user.connections.remove(&connection.id);
                     user.connections.insert(connection.id, connection);
                 } else if let Some(conn) = user.connections.get_mut(&connection.id) {
-                    if connection.lq < conn.lq {
+                    if connection.lq < conn.lq || conn.stale {
                         conn.lq = connection.lq;
                         conn.hc = connection.hc;
                         conn.last_update = now_ts;
@@ -336,6 +448,9 @@ impl ConnectionTable {
         // calculate from ble module
         table = Self::calculate_intermediary_table(table, ConnectionModule::Ble);
 
+        // calculate from virtual module
+        table = Self::calculate_intermediary_table(table, ConnectionModule::Virtual);
+
         // set table as new active routing table
         RoutingTable::set(table);
     }
@@ -367,13 +482,14 @@ impl ConnectionTable {
             ConnectionModule::Internet => connection_table = INTERNET.get().write().unwrap(),
             ConnectionModule::Lan => connection_table = LAN.get().write().unwrap(),
             ConnectionModule::Ble => connection_table = BLE.get().write().unwrap(),
+            ConnectionModule::Virtual => connection_table = VIRTUAL.get().write().unwrap(),
             ConnectionModule::Local => return table,
             ConnectionModule::None => return table,
         }
 
         // iterate over connection table
         for (user_id, user) in connection_table.table.iter_mut() {
-            let (b_expired_pgid, connection_entry) = Self::find_best_connection(user);
+            let (b_expired_pgid, connection_entry) = Self::find_best_connection(user, conn);
             if b_expired_pgid == false {
                 if let Some(connection) = connection_entry {
                     // fill entry into routing table
@@ -433,7 +549,13 @@ impl ConnectionTable {
 
     /// find best entry
     /// and remove all old entries
-    fn find_best_connection(user: &mut UserEntry) -> (bool, Option<NeighbourEntry>) {
+    ///
+    /// Stale entries are only used when their neighbour is
+    /// connected, and expire after the maintain period limit.
+    fn find_best_connection(
+        user: &mut UserEntry,
+        module: ConnectionModule,
+    ) -> (bool, Option<NeighbourEntry>) {
         // initialize helper variables
         let mut expired_connections: Vec<PeerId> = Vec::new();
         let mut return_entry = None;
@@ -454,17 +576,32 @@ impl ConnectionTable {
             for (key, value) in &user.connections {
                 let mut expired = true;
 
+                // routes via quarantined neighbours are removed
+                let quarantined = RoutingGuard::is_quarantined(&value.id);
+
                 // check if entry is expired
                 // entry expires after 20 seconds, unit is mili seconds
                 let now = Timestamp::get_timestamp();
-                //if now - value.last_update < (20 * 1000 * (value.hc as u64)){
-                if now - value.last_update
-                    //< (2 * (config.sending_table_period * 1000) * (value.hc as u64))
-                    < (config.sending_table_period * 1000 * (value.hc as u64 + 1))
-                {
+                let (valid, usable) = if value.stale {
+                    // stale entries expire after the maintain period limit
+                    // and are only used when their neighbour is connected
+                    (
+                        now - value.last_update < config.maintain_period_limit * 1000,
+                        Neighbours::get_rtt(&value.id, &module).is_some(),
+                    )
+                } else {
+                    //if now - value.last_update < (20 * 1000 * (value.hc as u64)){
+                    (
+                        now - value.last_update
+                        //< (2 * (config.sending_table_period * 1000) * (value.hc as u64))
+                        < (config.sending_table_period * 1000 * (value.hc as u64 + 1)),
+                        true,
+                    )
+                };
+                if !quarantined && valid {
                     expired = false;
 
-                    if value.lq < lq {
+                    if usable && value.lq < lq {
                         lq = value.lq;
                         entry_found = Some(value);
                     }
@@ -488,6 +625,7 @@ impl ConnectionTable {
                     hc: entry.hc,
                     lq: entry.lq,
                     last_update: entry.last_update.clone(),
+                    stale: entry.stale,
                 })
             }
         }
@@ -500,6 +638,98 @@ impl ConnectionTable {
         (false, return_entry)
     }
 
+    /// create a snapshot of the connection table of a module
+    pub fn snapshot(module: ConnectionModule) -> Vec<SnapshotUser> {
+        // get access to the connection table
+        let connection_table;
+        match module {
+            ConnectionModule::Internet => connection_table = INTERNET.get().read().unwrap(),
+            ConnectionModule::Lan => connection_table = LAN.get().read().unwrap(),
+            ConnectionModule::Ble => connection_table = BLE.get().read().unwrap(),
+            ConnectionModule::Virtual => connection_table = VIRTUAL.get().read().unwrap(),
+            ConnectionModule::Local => return Vec::new(),
+            ConnectionModule::None => return Vec::new(),
+        }
+
+        connection_table
+            .table
+            .values()
+            .map(|user| SnapshotUser {
+                module,
+                q8id: user.id.clone(),
+                pgid: user.pgid,
+                pgid_update_hc: user.pgid_update_hc,
+                online_time: user.online_time,
+                connections: user
+                    .connections
+                    .values()
+                    .map(|connection| SnapshotConnection {
+                        id: connection.id.to_bytes(),
+                        rtt: connection.rtt,
+                        hc: connection.hc,
+                        lq: connection.lq,
+                    })
+                    .collect(),
+            })
+            .collect()
+    }
+
+    /// restore users from the routing snapshot
+    ///
+    /// The restored connections are entered as stale entries,
+    /// as if they were last updated now.
+    /// Users that are already known are not overwritten.
+    pub fn restore(users: Vec<SnapshotUser>) {
+        let now_ts = Timestamp::get_timestamp();
+
+        for snapshot_user in users {
+            // get access to the connection table
+            let mut connection_table;
+            match snapshot_user.module {
+                ConnectionModule::Internet => connection_table = INTERNET.get().write().unwrap(),
+                ConnectionModule::Lan => connection_table = LAN.get().write().unwrap(),
+                ConnectionModule::Ble => connection_table = BLE.get().write().unwrap(),
+                ConnectionModule::Virtual => connection_table = VIRTUAL.get().write().unwrap(),
+                ConnectionModule::Local => continue,
+                ConnectionModule::None => continue,
+            }
+
+            if connection_table.table.contains_key(&snapshot_user.q8id) {
+                continue;
+            }
+
+            let mut connections = BTreeMap::new();
+            for connection in snapshot_user.connections {
+                if let Ok(id) = PeerId::from_bytes(&connection.id) {
+                    connections.insert(
+                        id,
+                        NeighbourEntry {
+                            id,
+                            rtt: connection.rtt,
+                            hc: connection.hc,
+                            lq: connection.lq,
+                            last_update: now_ts,
+                            stale: true,
+                        },
+                    );
+                }
+            }
+            if connections.is_empty() {
+                continue;
+            }
+
+            let user = UserEntry {
+                id: snapshot_user.q8id.clone(),
+                pgid: snapshot_user.pgid,
+                pgid_update: now_ts,
+                pgid_update_hc: snapshot_user.pgid_update_hc,
+                online_time: snapshot_user.online_time,
+                connections,
+            };
+            connection_table.table.insert(snapshot_user.q8id, user);
+        }
+    }
+
     /// send protobuf RPC connections list
     pub fn rpc_send_connections_list() {
         // create connections list
@@ -508,6 +738,7 @@ impl ConnectionTable {
             internet: Self::rpc_create_connection_module_list(ConnectionModule::Internet),
             ble: Self::rpc_create_connection_module_list(ConnectionModule::Ble),
             local: Self::rpc_create_connection_module_list(ConnectionModule::Local),
+            virtual_net: Self::rpc_create_connection_module_list(ConnectionModule::Virtual),
         };
 
         // create rpc connections list protobuf message
@@ -543,6 +774,7 @@ impl ConnectionTable {
             ConnectionModule::Lan => connection_table = LAN.get().read().unwrap(),
             ConnectionModule::Internet => connection_table = INTERNET.get().read().unwrap(),
             ConnectionModule::Ble => connection_table = BLE.get().read().unwrap(),
+            ConnectionModule::Virtual => connection_table = VIRTUAL.get().read().unwrap(),
             ConnectionModule::Local => return connections_list,
             ConnectionModule::None => return connections_list,
         }
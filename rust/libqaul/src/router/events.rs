@@ -0,0 +1,180 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Router Events
+//!
+//! Publishes the connectivity changes of the users.
+//!
+//! Every time a new routing table is set, the best route
+//! of each user is compared with the one of the previous table.
+//! The following events are pushed over RPC, once the UI
+//! subscribed to them with a `RouterEventSubscribe` request:
+//!
+//! * a user became reachable
+//! * a user became unreachable
+//! * the connection module of the best route to a user changed
+//!
+//! When a user becomes reachable, the messages waiting
+//! for its confirmation are retransmitted immediately.
+//! The last seen time of the users and the contacts is updated
+//! whenever a user becomes reachable or unreachable.
+
+use prost::Message;
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use super::{proto, users::Users};
+use crate::connections::ConnectionModule;
+use crate::rpc::Rpc;
+use crate::services::contacts::Contacts;
+use crate::services::messaging::retransmit::MessagingRetransmit;
+use crate::utilities::instance::InstanceCell;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the router events
+static EVENTS: InstanceCell<RwLock<RouterEvents>> = InstanceCell::new();
+
+/// Router Events
+pub struct RouterEvents {
+    /// connection module of the best route per reachable user
+    ///
+    /// q8id => connection module
+    best_modules: BTreeMap<Vec<u8>, ConnectionModule>,
+    /// the UI subscribed to the router events
+    ///
+    /// Events are only pushed to the RPC queue while subscribed,
+    /// for that they don't pile up if nobody reads the queue.
+    subscribed: bool,
+}
+
+impl RouterEvents {
+    /// initialize the router events state
+    pub fn init() {
+        let events = RouterEvents {
+            best_modules: BTreeMap::new(),
+            subscribed: false,
+        };
+        EVENTS.set(RwLock::new(events));
+    }
+
+    /// subscribe or unsubscribe the UI to the router events
+    pub fn subscribe(subscribe: bool) {
+        let mut state = EVENTS.get().write().unwrap();
+        state.subscribed = subscribe;
+    }
+
+    /// compare the best routes of a new routing table
+    /// with the previous ones and publish the changes
+    pub fn update(best_modules: BTreeMap<Vec<u8>, ConnectionModule>) {
+        let mut events = Vec::new();
+
+        {
+            let mut state = EVENTS.get().write().unwrap();
+
+            for (q8id, module) in &best_modules {
+                match state.best_modules.get(q8id) {
+                    None => events.push((
+                        q8id.clone(),
+                        proto::RouterEventType::UserReachable,
+                        *module,
+                        ConnectionModule::None,
+                    )),
+                    Some(previous) if previous != module => events.push((
+                        q8id.clone(),
+                        proto::RouterEventType::BestModuleChanged,
+                        *module,
+                        *previous,
+                    )),
+                    _ => {}
+                }
+            }
+            for (q8id, previous) in &state.best_modules {
+                if !best_modules.contains_key(q8id) {
+                    events.push((
+                        q8id.clone(),
+                        proto::RouterEventType::UserUnreachable,
+                        ConnectionModule::None,
+                        *previous,
+                    ));
+                }
+            }
+
+            state.best_modules = best_modules;
+        }
+
+        // flush the messages waiting for the reachable users
+        let reachable: Vec<Vec<u8>> = events
+            .iter()
+            .filter(|(_, event, _, _)| *event == proto::RouterEventType::UserReachable)
+            .map(|(q8id, _, _, _)| q8id.clone())
+            .collect();
+        if !reachable.is_empty() {
+            MessagingRetransmit::users_reachable(&reachable);
+        }
+
+        // update the last seen time of the users and the contacts
+        let seen: Vec<Vec<u8>> = events
+            .iter()
+            .filter(|(_, event, _, _)| *event != proto::RouterEventType::BestModuleChanged)
+            .map(|(q8id, _, _, _)| q8id.clone())
+            .collect();
+        if !seen.is_empty() {
+            Users::users_seen(&seen);
+            Contacts::users_seen(&seen);
+        }
+
+        // publish the events
+        for (q8id, event, module, previous_module) in events {
+            Self::rpc_send_event(q8id, event, module, previous_module);
+        }
+    }
+
+    /// send protobuf RPC router event
+    fn rpc_send_event(
+        q8id: Vec<u8>,
+        event: proto::RouterEventType,
+        module: ConnectionModule,
+        previous_module: ConnectionModule,
+    ) {
+        log::trace!(
+            "router event {:?} of user {}",
+            event,
+            bs58::encode(&q8id).into_string()
+        );
+
+        if !EVENTS.get().read().unwrap().subscribed {
+            return;
+        }
+
+        let user_id = match Users::get_user_id_by_q8id(q8id.clone()) {
+            Some(user_id) => user_id.to_bytes(),
+            None => Vec::new(),
+        };
+
+        // create event message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RouterEvent(proto::RouterEvent {
+                user_q8id: q8id,
+                user_id,
+                event: event as i32,
+                module: module.as_int(),
+                previous_module: previous_module.as_int(),
+                time: Timestamp::get_timestamp(),
+            })),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Router.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
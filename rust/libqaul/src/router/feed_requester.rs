@@ -12,15 +12,16 @@
 //! advertised and can be requested from the sending node.
 
 use libp2p::PeerId;
-use state::InitCell;
 use std::collections::VecDeque;
 use std::sync::RwLock;
 
+use crate::utilities::instance::InstanceCell;
+
 /// mutable state of feed requester
-pub static FEEDREQUESTER: InitCell<RwLock<FeedRequester>> = InitCell::new();
+pub static FEEDREQUESTER: InstanceCell<RwLock<FeedRequester>> = InstanceCell::new();
 
 /// mutable state of the feed responser
-pub static FEEDRESPONSER: InitCell<RwLock<FeedResponser>> = InitCell::new();
+pub static FEEDRESPONSER: InstanceCell<RwLock<FeedResponser>> = InstanceCell::new();
 
 /// Feed Request Structure
 pub struct FeedRequest {
@@ -43,6 +44,9 @@ impl FeedRequester {
     }
 
     /// Add a message to the ring buffer for sending.
+    ///
+    /// The requests of this node are not rate limited,
+    /// only the requests received from the neighbours are.
     pub fn add(neighbour_id: &PeerId, feed_ids: &Vec<Vec<u8>>) {
         let msg = FeedRequest {
             neighbour_id: neighbour_id.clone(),
@@ -10,13 +10,13 @@
 //! that need to be flooded via the other interfaces.
 
 use crate::connections::ConnectionModule;
+use crate::utilities::instance::InstanceCell;
 use libp2p::floodsub::Topic;
-use state::InitCell;
 use std::collections::VecDeque;
 use std::sync::RwLock;
 
 // mutable state of feed messages
-pub static FLOODER: InitCell<RwLock<Flooder>> = InitCell::new();
+pub static FLOODER: InstanceCell<RwLock<Flooder>> = InstanceCell::new();
 
 pub struct FloodMessageContainer {
     pub message: Vec<u8>,
@@ -0,0 +1,375 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Routing Guard
+//!
+//! Protects the router against route hijacking.
+//!
+//! Received routing information is checked for impossible routes:
+//!
+//! * the routing information container is not signed by the
+//!   sending neighbour node
+//! * an advertised route has no hop count
+//! * an advertised route over further hops has a round trip time of zero
+//! * the propagation ID of a user jumps further than it could
+//!   have been increased since the last update
+//!
+//! Impossible routes are dropped and registered as violation
+//! of the advertising neighbour.
+//! Neighbours with too many violations within a short time are
+//! quarantined: their routing information is ignored, and all
+//! routes via them are removed, until the quarantine ends.
+//!
+//! The latest violations and the quarantined neighbours
+//! can be requested over RPC.
+
+use libp2p::{identity::PublicKey, PeerId};
+use prost::Message;
+use std::collections::{HashMap, VecDeque};
+use std::sync::RwLock;
+
+use super::proto;
+use crate::connections::ConnectionModule;
+use crate::rpc::Rpc;
+use crate::utilities::instance::InstanceCell;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the routing guard
+static GUARD: InstanceCell<RwLock<RoutingGuard>> = InstanceCell::new();
+
+/// number of violations within the violation window,
+/// after which a neighbour is quarantined
+const QUARANTINE_THRESHOLD: u32 = 10;
+
+/// violation window in milli seconds
+///
+/// The violation count of a neighbour is reset
+/// when there was no violation within this time.
+const VIOLATION_WINDOW: u64 = 60 * 1000;
+
+/// duration of a quarantine in milli seconds
+const QUARANTINE_DURATION: u64 = 10 * 60 * 1000;
+
+/// maximal number of stored violation events
+const MAX_EVENTS: usize = 100;
+
+/// Routing Violation
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RoutingViolation {
+    /// the routing information is not signed by the neighbour
+    InvalidSignature,
+    /// an advertised route has no hop count
+    InvalidHopCount,
+    /// an advertised route has an impossible round trip time
+    ImpossibleRtt,
+    /// implausible jump of the propagation ID
+    PropagationIdJump,
+}
+
+/// Violation event
+#[derive(Clone, Debug)]
+pub struct ViolationEvent {
+    /// node ID of the advertising neighbour
+    pub neighbour: PeerId,
+    /// connection module the routing information was received on
+    pub module: ConnectionModule,
+    /// violation
+    pub violation: RoutingViolation,
+    /// q8id of the advertised user, empty if the
+    /// violation concerns the entire routing information
+    pub user: Vec<u8>,
+    /// time of the violation
+    pub time: u64,
+}
+
+/// Violation state of a neighbour
+struct NeighbourViolations {
+    /// number of violations within the violation window
+    count: u32,
+    /// time of the last violation
+    last_violation: u64,
+    /// end of the quarantine,
+    /// 0 if the neighbour isn't quarantined
+    quarantined_until: u64,
+}
+
+/// Routing Guard
+pub struct RoutingGuard {
+    /// violation state per neighbour node
+    neighbours: HashMap<PeerId, NeighbourViolations>,
+    /// latest violation events
+    events: VecDeque<ViolationEvent>,
+}
+
+impl RoutingGuard {
+    /// Initialize the routing guard
+    pub fn init() {
+        let guard = RoutingGuard {
+            neighbours: HashMap::new(),
+            events: VecDeque::new(),
+        };
+        GUARD.set(RwLock::new(guard));
+    }
+
+    /// verify the signature of received routing information
+    ///
+    /// The routing information needs to be signed by the
+    /// node key of the sending neighbour.
+    /// The public key is contained in the node ID.
+    pub fn verify_signature(node_id: &PeerId, message: &[u8], signature: &[u8]) -> bool {
+        // identity multihash of the node ID
+        let multihash = AsRef::<libp2p::multiaddr::multihash::Multihash<64>>::as_ref(node_id);
+        if multihash.code() != 0x00 {
+            return false;
+        }
+
+        match PublicKey::try_decode_protobuf(multihash.digest()) {
+            Ok(key) => key.verify(message, signature),
+            Err(_) => false,
+        }
+    }
+
+    /// register a violation of a neighbour
+    ///
+    /// The neighbour is quarantined if it reaches
+    /// the quarantine threshold.
+    pub fn report(
+        neighbour: PeerId,
+        module: ConnectionModule,
+        violation: RoutingViolation,
+        user: Vec<u8>,
+    ) {
+        let now = Timestamp::get_timestamp();
+        let mut guard = GUARD.get().write().unwrap();
+
+        // count violation
+        let entry = guard
+            .neighbours
+            .entry(neighbour)
+            .or_insert(NeighbourViolations {
+                count: 0,
+                last_violation: 0,
+                quarantined_until: 0,
+            });
+        if now.saturating_sub(entry.last_violation) > VIOLATION_WINDOW {
+            entry.count = 0;
+        }
+        entry.count += 1;
+        entry.last_violation = now;
+
+        log::debug!(
+            "routing violation {:?} of neighbour {}",
+            violation,
+            neighbour.to_base58()
+        );
+
+        // quarantine neighbour
+        if entry.count >= QUARANTINE_THRESHOLD && entry.quarantined_until < now {
+            entry.quarantined_until = now + QUARANTINE_DURATION;
+            log::warn!(
+                "neighbour {} quarantined after {} routing violations",
+                neighbour.to_base58(),
+                entry.count
+            );
+        }
+
+        // save event
+        if guard.events.len() >= MAX_EVENTS {
+            guard.events.pop_front();
+        }
+        guard.events.push_back(ViolationEvent {
+            neighbour,
+            module,
+            violation,
+            user,
+            time: now,
+        });
+    }
+
+    /// check if a neighbour is quarantined
+    pub fn is_quarantined(neighbour: &PeerId) -> bool {
+        let guard = GUARD.get().read().unwrap();
+        match guard.neighbours.get(neighbour) {
+            Some(entry) => entry.quarantined_until > Timestamp::get_timestamp(),
+            None => false,
+        }
+    }
+
+    /// send protobuf RPC routing guard list
+    pub fn rpc_send_guard_list() {
+        let now = Timestamp::get_timestamp();
+        let guard = GUARD.get().read().unwrap();
+
+        // latest violations
+        let events = guard
+            .events
+            .iter()
+            .map(|event| proto::RoutingViolationEvent {
+                node_id: event.neighbour.to_bytes(),
+                module: Self::module_to_proto(&event.module) as i32,
+                violation: match event.violation {
+                    RoutingViolation::InvalidSignature => proto::RoutingViolation::InvalidSignature,
+                    RoutingViolation::InvalidHopCount => proto::RoutingViolation::InvalidHopCount,
+                    RoutingViolation::ImpossibleRtt => proto::RoutingViolation::ImpossibleRtt,
+                    RoutingViolation::PropagationIdJump => {
+                        proto::RoutingViolation::PropagationIdJump
+                    }
+                } as i32,
+                user_q8id: event.user.clone(),
+                time: event.time,
+            })
+            .collect();
+
+        // quarantined neighbours
+        let quarantined = guard
+            .neighbours
+            .iter()
+            .filter(|(_id, entry)| entry.quarantined_until > now)
+            .map(|(id, entry)| proto::QuarantinedNeighbour {
+                node_id: id.to_bytes(),
+                violations: entry.count,
+                quarantined_until: entry.quarantined_until,
+            })
+            .collect();
+
+        // create guard list message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RoutingGuardList(
+                proto::RoutingGuardList {
+                    events,
+                    quarantined,
+                },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Router.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// convert connection module to protobuf enum
+    fn module_to_proto(module: &ConnectionModule) -> proto::ConnectionModule {
+        match module {
+            ConnectionModule::Lan => proto::ConnectionModule::Lan,
+            ConnectionModule::Internet => proto::ConnectionModule::Internet,
+            ConnectionModule::Ble => proto::ConnectionModule::Ble,
+            ConnectionModule::Local => proto::ConnectionModule::Local,
+            ConnectionModule::Virtual => proto::ConnectionModule::Virtual,
+            ConnectionModule::None => proto::ConnectionModule::None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::utilities::instance::Instance;
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use std::sync::Arc;
+
+    /// initialize the routing guard on a simulated clock
+    fn init_guard() -> Arc<AtomicU64> {
+        let clock = Arc::new(AtomicU64::new(1_600_000_000_000));
+        Timestamp::simulate(clock.clone());
+        RoutingGuard::init();
+        clock
+    }
+
+    fn report(neighbour: PeerId, count: u32) {
+        for _ in 0..count {
+            RoutingGuard::report(
+                neighbour,
+                ConnectionModule::Lan,
+                RoutingViolation::ImpossibleRtt,
+                Vec::new(),
+            );
+        }
+    }
+
+    #[test]
+    fn quarantined_at_threshold() {
+        Instance::with(Instance::create(), || {
+            init_guard();
+            let neighbour = PeerId::random();
+            let other = PeerId::random();
+
+            report(neighbour, QUARANTINE_THRESHOLD - 1);
+            assert!(!RoutingGuard::is_quarantined(&neighbour));
+
+            report(neighbour, 1);
+            assert!(RoutingGuard::is_quarantined(&neighbour));
+            assert!(!RoutingGuard::is_quarantined(&other));
+        });
+    }
+
+    #[test]
+    fn violations_are_counted_within_window() {
+        Instance::with(Instance::create(), || {
+            let clock = init_guard();
+            let neighbour = PeerId::random();
+
+            // violations spread within the window add up
+            for _ in 0..QUARANTINE_THRESHOLD - 1 {
+                report(neighbour, 1);
+                clock.fetch_add(VIOLATION_WINDOW, Ordering::SeqCst);
+            }
+            report(neighbour, 1);
+            assert!(RoutingGuard::is_quarantined(&neighbour));
+        });
+    }
+
+    #[test]
+    fn violation_count_is_reset_after_window() {
+        Instance::with(Instance::create(), || {
+            let clock = init_guard();
+            let neighbour = PeerId::random();
+
+            report(neighbour, QUARANTINE_THRESHOLD - 1);
+            clock.fetch_add(VIOLATION_WINDOW + 1, Ordering::SeqCst);
+
+            report(neighbour, QUARANTINE_THRESHOLD - 1);
+            assert!(!RoutingGuard::is_quarantined(&neighbour));
+
+            report(neighbour, 1);
+            assert!(RoutingGuard::is_quarantined(&neighbour));
+        });
+    }
+
+    #[test]
+    fn quarantine_ends() {
+        Instance::with(Instance::create(), || {
+            let clock = init_guard();
+            let neighbour = PeerId::random();
+
+            report(neighbour, QUARANTINE_THRESHOLD);
+            clock.fetch_add(QUARANTINE_DURATION, Ordering::SeqCst);
+            assert!(!RoutingGuard::is_quarantined(&neighbour));
+        });
+    }
+
+    #[test]
+    fn events_are_limited() {
+        Instance::with(Instance::create(), || {
+            init_guard();
+            let first = PeerId::random();
+            let second = PeerId::random();
+
+            report(first, 1);
+            report(second, MAX_EVENTS as u32);
+
+            let guard = GUARD.get().read().unwrap();
+            assert_eq!(guard.events.len(), MAX_EVENTS);
+            assert!(guard.events.iter().all(|event| event.neighbour == second));
+        });
+    }
+}
@@ -0,0 +1,151 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Identity Cards
+//!
+//! Out-of-band exchange of user identities.
+//!
+//! An identity card contains the user id, the public key
+//! and the name of a user account. It is signed with the key
+//! of the user account and encoded as compact text code,
+//! which can be shared as text or rendered as QR code.
+//!
+//! Importing an identity card adds the user to the users table
+//! and marks it as verified, without the users ever having
+//! met on the network.
+
+use libp2p::{identity::PublicKey, PeerId};
+use prost::Message;
+
+use super::profile::MAX_NAME_LENGTH;
+use super::router_net_proto;
+use super::users::Users;
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+
+/// prefix of the identity card text codes
+pub const CODE_PREFIX: &str = "qaul:";
+
+/// Identity Card
+#[derive(Clone, Debug)]
+pub struct IdentityCard {
+    /// user id
+    pub id: PeerId,
+    /// public key of the user
+    pub key: PublicKey,
+    /// user name
+    pub name: String,
+}
+
+impl IdentityCard {
+    /// create the signed identity card text code of a user account
+    pub fn export(user_account: &UserAccount) -> Result<String, String> {
+        let content = router_net_proto::IdentityCardContent {
+            id: user_account.id.to_bytes(),
+            key: user_account.keys.public().encode_protobuf(),
+            name: user_account.name.clone(),
+        };
+        Self::validate_name(&content.name)?;
+
+        let content_bytes = content.encode_to_vec();
+        let signature = match user_account.keys.sign(&content_bytes) {
+            Ok(signature) => signature,
+            Err(e) => return Err(format!("signing the identity card failed: {}", e)),
+        };
+
+        let card = router_net_proto::IdentityCard {
+            content: content_bytes,
+            signature,
+        };
+
+        Ok(format!(
+            "{}{}",
+            CODE_PREFIX,
+            bs58::encode(card.encode_to_vec()).into_string()
+        ))
+    }
+
+    /// decode an identity card text code and verify its signature
+    ///
+    /// The card needs to be signed by the key it contains,
+    /// and the user id needs to belong to this key.
+    pub fn decode(code: &str) -> Result<IdentityCard, String> {
+        let encoded = match code.trim().strip_prefix(CODE_PREFIX) {
+            Some(encoded) => encoded,
+            None => return Err("not a qaul identity code".to_string()),
+        };
+        let card_bytes = match bs58::decode(encoded).into_vec() {
+            Ok(card_bytes) => card_bytes,
+            Err(e) => return Err(format!("identity code decoding error: {}", e)),
+        };
+        let card = match router_net_proto::IdentityCard::decode(&card_bytes[..]) {
+            Ok(card) => card,
+            Err(e) => return Err(format!("identity card decoding error: {}", e)),
+        };
+        let content = match router_net_proto::IdentityCardContent::decode(&card.content[..]) {
+            Ok(content) => content,
+            Err(e) => return Err(format!("identity card decoding error: {}", e)),
+        };
+
+        let key = match PublicKey::try_decode_protobuf(&content.key) {
+            Ok(key) => key,
+            Err(e) => return Err(format!("invalid public key: {}", e)),
+        };
+        if !key.verify(&card.content, &card.signature) {
+            return Err("invalid identity card signature".to_string());
+        }
+        let id = match PeerId::from_bytes(&content.id) {
+            Ok(id) => id,
+            Err(e) => return Err(format!("invalid user id: {}", e)),
+        };
+        if id != key.to_peer_id() {
+            return Err("user id and key do not match".to_string());
+        }
+        Self::validate_name(&content.name)?;
+
+        Ok(IdentityCard {
+            id,
+            key,
+            name: content.name,
+        })
+    }
+
+    /// import an identity card text code
+    ///
+    /// Unknown users are added to the users table.
+    /// The user is marked as verified, if the key of the card
+    /// matches the known key of the user.
+    pub fn import(code: &str) -> Result<IdentityCard, String> {
+        let card = Self::decode(code)?;
+
+        if UserAccounts::get_by_id(card.id).is_some() {
+            return Err("the identity card belongs to a local user account".to_string());
+        }
+
+        match Users::get_pub_key(&card.id) {
+            Some(key) => {
+                if key != card.key {
+                    return Err("the key does not match the known key of the user".to_string());
+                }
+                Users::set_verified(&card.id);
+            }
+            None => Users::add(card.id, card.key.clone(), card.name.clone(), true, false),
+        }
+
+        Ok(card)
+    }
+
+    /// check the user name of an identity card
+    fn validate_name(name: &str) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("the user name can't be empty".to_string());
+        }
+        if name.len() > MAX_NAME_LENGTH {
+            return Err(format!(
+                "the user name can be at most {} bytes",
+                MAX_NAME_LENGTH
+            ));
+        }
+
+        Ok(())
+    }
+}
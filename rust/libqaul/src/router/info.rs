@@ -20,7 +20,6 @@ use crate::utilities::qaul_id::QaulId;
 use libp2p::PeerId;
 use prost::Message;
 use qaul_info::QaulInfoReceived;
-use state::InitCell;
 use std::{
     collections::HashMap,
     sync::RwLock,
@@ -31,8 +30,13 @@ use crate::{
     connections::ConnectionModule,
     node::Node,
     router::{
-        connections::ConnectionTable, neighbours::Neighbours, router_net_proto,
-        table::RoutingTable, users::Users,
+        connections::ConnectionTable,
+        guard::{RoutingGuard, RoutingViolation},
+        neighbours::Neighbours,
+        rate_limit::{RateLimitClass, RateLimiter},
+        router_net_proto,
+        table::RoutingTable,
+        users::Users,
     },
     utilities::timestamp::Timestamp,
 };
@@ -43,9 +47,14 @@ use crate::services::feed::Feed;
 
 use crate::router::user_requester::UserRequester;
 use crate::router::user_requester::UserResponser;
+use crate::utilities::instance::InstanceCell;
+
+/// interval in milli seconds, in which
+/// the propagation ID is increased
+pub const PROPAGATION_INTERVAL: u64 = 10 * 1000;
 
 /// mutable state of Neighbours table per ConnectionModule
-static SCHEDULER: InitCell<RwLock<Scheduler>> = InitCell::new();
+static SCHEDULER: InstanceCell<RwLock<Scheduler>> = InstanceCell::new();
 
 /// global scheduler state
 #[derive(Clone, Debug)]
@@ -101,6 +110,13 @@ impl RouterInfo {
         SCHEDULER.set(RwLock::new(scheduler));
     }
 
+    /// Set the interval in seconds in which the
+    /// routing information is sent to the neighbours.
+    pub fn set_interval(interval_seconds: u64) {
+        let mut scheduler = SCHEDULER.get().write().unwrap();
+        scheduler.interval = Duration::from_secs(interval_seconds);
+    }
+
     /// This loops over all neighbours
     /// and checks if there is any timeout.
     /// If it finds a timeout it returns the node id
@@ -118,7 +134,7 @@ impl RouterInfo {
 
             // loop over all neighbours
             for (id, ctx) in scheduler.neighbours.iter() {
-                if ctx.timestamp + scheduler.interval < SystemTime::now() {
+                if ctx.timestamp + scheduler.interval < Timestamp::create_time() {
                     found_neighbour = Some(id.clone());
                     neighbour_last_sent = Timestamp::get_timestamp_by(&ctx.timestamp);
                     neighbour_is_first = ctx.is_first;
@@ -132,7 +148,7 @@ impl RouterInfo {
         }
 
         // check if we have to update the propagation ID
-        if Timestamp::get_timestamp() >= propagation_timestamp + 10 * 1000 {
+        if Timestamp::get_timestamp() >= propagation_timestamp + PROPAGATION_INTERVAL {
             propagation_id += 1;
             propagation_timestamp = Timestamp::get_timestamp();
 
@@ -162,7 +178,7 @@ impl RouterInfo {
             } else {
                 // update timer
                 if let Some(entry) = scheduler.neighbours.get_mut(&node_id) {
-                    entry.timestamp = SystemTime::now();
+                    entry.timestamp = Timestamp::create_time();
                     entry.is_first = false;
                 }
 
@@ -194,7 +210,7 @@ impl RouterInfo {
             scheduler.neighbours.insert(
                 node_id,
                 SchedulerEntry {
-                    timestamp: SystemTime::now() - interval,
+                    timestamp: Timestamp::create_time() - interval,
                     is_first: true,
                 },
             );
@@ -458,6 +474,21 @@ impl RouterInfo {
         buf
     }
 
+    /// rate limit class of a received router info message
+    ///
+    /// The routing information and the requests of the neighbour
+    /// are limited per neighbour.
+    /// The responses to the requests of this node are not limited.
+    fn rate_limit_class(module: router_net_proto::RouterInfoModule) -> Option<RateLimitClass> {
+        match module {
+            router_net_proto::RouterInfoModule::RouterInfo => Some(RateLimitClass::RouterInfo),
+            router_net_proto::RouterInfoModule::FeedRequest => Some(RateLimitClass::FeedRequest),
+            router_net_proto::RouterInfoModule::UserRequest => Some(RateLimitClass::UserRequest),
+            router_net_proto::RouterInfoModule::FeedResponse
+            | router_net_proto::RouterInfoModule::UserResponse => None,
+        }
+    }
+
     /// process received qaul_info message
     pub fn received(received: QaulInfoReceived) {
         // decode message to structure
@@ -465,8 +496,24 @@ impl RouterInfo {
 
         match decoding_result {
             Ok(container) => {
-                // TODO: check signature
-                //let signature = container.signature;message.ids
+                // check signature
+                if !RoutingGuard::verify_signature(
+                    &received.received_from,
+                    &container.message,
+                    &container.signature,
+                ) {
+                    log::warn!(
+                        "invalid router info signature from {}",
+                        received.received_from.to_base58()
+                    );
+                    RoutingGuard::report(
+                        received.received_from,
+                        Neighbours::is_neighbour(&received.received_from),
+                        RoutingViolation::InvalidSignature,
+                        Vec::new(),
+                    );
+                    return;
+                }
 
                 // decode message
                 let message_result =
@@ -474,9 +521,20 @@ impl RouterInfo {
 
                 match message_result {
                     Ok(content) => {
-                        match router_net_proto::RouterInfoModule::try_from(
+                        let module = router_net_proto::RouterInfoModule::try_from(
                             content.router_info_module,
-                        ) {
+                        );
+
+                        // limit the messages per neighbour
+                        if let Ok(module) = module {
+                            if let Some(class) = Self::rate_limit_class(module) {
+                                if !RateLimiter::check(&received.received_from, class) {
+                                    return;
+                                }
+                            }
+                        }
+
+                        match module {
                             Ok(router_net_proto::RouterInfoModule::RouterInfo) => {
                                 let message_info = router_net_proto::RouterInfoMessage::decode(
                                     &content.content[..],
@@ -490,12 +548,12 @@ impl RouterInfo {
 
                                     match routes {
                                         Some(router_net_proto::RoutingInfoTable { entry }) => {
-                                            //check missed user ids
-                                            let mut user_ids: Vec<Vec<u8>> = vec![];
+                                            //check missed user ids and outdated profiles
+                                            let mut user_ids: Vec<(Vec<u8>, u32)> = vec![];
                                             for e in &entry {
-                                                user_ids.push(e.user.clone());
+                                                user_ids.push((e.user.clone(), e.profile_revision));
                                             }
-                                            let missed_users = Users::get_missed_ids(&user_ids);
+                                            let missed_users = Users::get_outdated_ids(&user_ids);
                                             if missed_users.len() > 0 {
                                                 UserRequester::add(
                                                     &received.received_from,
@@ -602,3 +660,83 @@ impl RouterInfo {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::configuration::RateLimitOptions;
+
+    /// apply the rate limit of a received message
+    fn receive(
+        rate_limiter: &mut RateLimiter,
+        neighbour: &PeerId,
+        module: router_net_proto::RouterInfoModule,
+    ) -> bool {
+        match RouterInfo::rate_limit_class(module) {
+            Some(class) => rate_limiter.take(neighbour, class, 0),
+            None => true,
+        }
+    }
+
+    #[test]
+    fn user_responses_do_not_consume_router_info_budget() {
+        let options = RateLimitOptions::default();
+        let burst = options.router_info.burst;
+        let mut rate_limiter = RateLimiter::new(options);
+        let neighbour = PeerId::random();
+
+        // a burst of split user responses is not limited
+        for _ in 0..1000 {
+            assert!(receive(
+                &mut rate_limiter,
+                &neighbour,
+                router_net_proto::RouterInfoModule::UserResponse
+            ));
+        }
+
+        // the routing information budget is untouched
+        for _ in 0..burst {
+            assert!(receive(
+                &mut rate_limiter,
+                &neighbour,
+                router_net_proto::RouterInfoModule::RouterInfo
+            ));
+        }
+        assert!(!receive(
+            &mut rate_limiter,
+            &neighbour,
+            router_net_proto::RouterInfoModule::RouterInfo
+        ));
+    }
+
+    #[test]
+    fn requests_use_their_own_budget() {
+        let options = RateLimitOptions::default();
+        let burst = options.user_request.burst;
+        let mut rate_limiter = RateLimiter::new(options);
+        let neighbour = PeerId::random();
+
+        for _ in 0..burst {
+            assert!(receive(
+                &mut rate_limiter,
+                &neighbour,
+                router_net_proto::RouterInfoModule::UserRequest
+            ));
+        }
+        assert!(!receive(
+            &mut rate_limiter,
+            &neighbour,
+            router_net_proto::RouterInfoModule::UserRequest
+        ));
+        assert!(receive(
+            &mut rate_limiter,
+            &neighbour,
+            router_net_proto::RouterInfoModule::FeedRequest
+        ));
+        assert!(receive(
+            &mut rate_limiter,
+            &neighbour,
+            router_net_proto::RouterInfoModule::RouterInfo
+        ));
+    }
+}
@@ -0,0 +1,215 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Link Quality Metrics
+//!
+//! The link quality of a route is calculated by a link quality metric.
+//! The smaller the link quality value, the better the route.
+//! Link quality values are in micro seconds.
+//!
+//! The metric is selected in the routing options of the configuration:
+//!
+//! * `hop_count`: round trip time plus a penalty per hop (default)
+//! * `etx`: round trip time weighted by the expected transmission count
+//!   of the link to the neighbour, plus a penalty per hop
+//! * `bandwidth`: round trip time plus a penalty per hop, plus the
+//!   transmission time of a data package over the link to the neighbour
+//!
+//! The round trip time is the accumulated smoothed round trip time
+//! of the entire route. Loss and capacity are only known of the link
+//! to the direct neighbour.
+
+use crate::connections::ConnectionModule;
+use crate::storage::configuration::{LinkQualityMetric, RoutingOptions};
+
+/// size of the reference data package in bits
+///
+/// The transmission time of this package is added
+/// by the bandwidth weighted metric.
+/// It is the size of a file chunk.
+const REFERENCE_PACKAGE_BITS: u64 = 64_000 * 8;
+
+/// maximal expected transmission count
+///
+/// Links with a higher loss rate are
+/// considered to be this bad.
+const MAX_ETX: f64 = 100.0;
+
+/// Link information a link quality is calculated from
+pub struct LinkInfo {
+    /// accumulated round trip time of the route in micro seconds
+    pub rtt: u32,
+    /// hop count
+    pub hc: u8,
+    /// connection module of the link to the neighbour
+    pub module: ConnectionModule,
+    /// round trip loss rate of the link to the neighbour,
+    /// from 0.0 (no loss) to 1.0 (everything lost)
+    pub loss: f32,
+}
+
+/// Link Quality Metric
+pub trait LinkMetric: Send + Sync {
+    /// calculate the link quality in micro seconds
+    ///
+    /// The smaller the value the better the link quality.
+    fn calculate(&self, link: &LinkInfo, options: &RoutingOptions) -> u32;
+}
+
+/// get the link quality metric implementation
+pub fn get_metric(metric: &LinkQualityMetric) -> &'static dyn LinkMetric {
+    match metric {
+        LinkQualityMetric::HopCount => &HopCountMetric,
+        LinkQualityMetric::Etx => &EtxMetric,
+        LinkQualityMetric::Bandwidth => &BandwidthMetric,
+    }
+}
+
+/// hop count penalty in micro seconds
+///
+/// The `hop_count_penalty` option is in seconds.
+fn hop_penalty(hc: u8, options: &RoutingOptions) -> u64 {
+    hc as u64 * options.hop_count_penalty * 1_000_000
+}
+
+/// convert a link quality to u32, saturating at u32::MAX
+fn saturate(lq: u64) -> u32 {
+    u32::try_from(lq).unwrap_or(u32::MAX)
+}
+
+/// Hop Count Metric
+///
+/// The link quality is the round trip time
+/// plus a penalty for each hop.
+pub struct HopCountMetric;
+
+impl LinkMetric for HopCountMetric {
+    fn calculate(&self, link: &LinkInfo, options: &RoutingOptions) -> u32 {
+        saturate(link.rtt as u64 + hop_penalty(link.hc, options))
+    }
+}
+
+/// Expected Transmission Count Metric
+///
+/// The round trip time is multiplied with the expected
+/// transmission count (ETX) of the link to the neighbour.
+/// A penalty is added for each hop.
+///
+/// ETX = 1 / (delivery ratio forward * delivery ratio reverse)
+///
+/// The delivery ratio of the round trip is measured by the pings
+/// to the neighbour. A lost ping can't be attributed to a direction,
+/// the product of both delivery ratios is therefore taken directly
+/// from the ping loss ratio.
+pub struct EtxMetric;
+
+impl EtxMetric {
+    /// calculate the expected transmission count of a link
+    fn etx(link: &LinkInfo) -> f64 {
+        let delivery = 1.0 - (link.loss.clamp(0.0, 1.0) as f64);
+
+        if delivery <= 1.0 / MAX_ETX {
+            MAX_ETX
+        } else {
+            1.0 / delivery
+        }
+    }
+}
+
+impl LinkMetric for EtxMetric {
+    fn calculate(&self, link: &LinkInfo, options: &RoutingOptions) -> u32 {
+        let rtt = (link.rtt as f64 * Self::etx(link)) as u64;
+        saturate(rtt + hop_penalty(link.hc, options))
+    }
+}
+
+/// Bandwidth Weighted Metric
+///
+/// The transmission time of a data package over the
+/// link to the neighbour is added to the hop count metric.
+/// Slow links, such as BLE, are avoided if there
+/// is a faster route.
+pub struct BandwidthMetric;
+
+impl BandwidthMetric {
+    /// nominal bandwidth of a connection module in bits per second
+    fn bandwidth(module: &ConnectionModule) -> Option<u64> {
+        match module {
+            ConnectionModule::Lan => Some(100_000_000),
+            ConnectionModule::Internet => Some(10_000_000),
+            ConnectionModule::Virtual => Some(100_000_000),
+            ConnectionModule::Ble => Some(100_000),
+            ConnectionModule::Local => None,
+            ConnectionModule::None => None,
+        }
+    }
+}
+
+impl LinkMetric for BandwidthMetric {
+    fn calculate(&self, link: &LinkInfo, options: &RoutingOptions) -> u32 {
+        // transmission time in micro seconds
+        let transmission = match Self::bandwidth(&link.module) {
+            Some(bandwidth) => REFERENCE_PACKAGE_BITS * 1_000_000 / bandwidth,
+            None => 0,
+        };
+
+        saturate(link.rtt as u64 + hop_penalty(link.hc, options) + transmission)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(rtt: u32, loss: f32) -> LinkInfo {
+        LinkInfo {
+            rtt,
+            hc: 1,
+            module: ConnectionModule::Lan,
+            loss,
+        }
+    }
+
+    #[test]
+    fn etx_weights_rtt_by_loss() {
+        let options = RoutingOptions::default();
+        let penalty = 10_000_000;
+
+        assert_eq!(
+            EtxMetric.calculate(&link(8000, 0.0), &options),
+            8000 + penalty
+        );
+        assert_eq!(
+            EtxMetric.calculate(&link(8000, 0.5), &options),
+            16000 + penalty
+        );
+        assert_eq!(
+            EtxMetric.calculate(&link(8000, 0.75), &options),
+            32000 + penalty
+        );
+    }
+
+    #[test]
+    fn etx_is_capped() {
+        let options = RoutingOptions::default();
+        let capped = 8000 * MAX_ETX as u32 + 10_000_000;
+
+        assert_eq!(EtxMetric.calculate(&link(8000, 0.999), &options), capped);
+        assert_eq!(EtxMetric.calculate(&link(8000, 1.0), &options), capped);
+        assert_eq!(EtxMetric.calculate(&link(8000, 2.0), &options), capped);
+        assert_eq!(
+            EtxMetric.calculate(&link(8000, -1.0), &options),
+            8000 + 10_000_000
+        );
+    }
+
+    #[test]
+    fn etx_saturates() {
+        let options = RoutingOptions::default();
+
+        assert_eq!(
+            EtxMetric.calculate(&link(u32::MAX, 1.0), &options),
+            u32::MAX
+        );
+    }
+}
@@ -7,25 +7,38 @@
 //! qaul router.
 
 use prost::Message;
-use state::InitCell;
 use std::sync::RwLock;
 
 pub mod connections;
+pub mod events;
 pub mod feed_requester;
 pub mod flooder;
+pub mod guard;
+pub mod identity;
 pub mod info;
+pub mod metric;
 pub mod neighbours;
+pub mod profile;
+pub mod rate_limit;
+pub mod snapshot;
 pub mod table;
+pub mod topology;
 pub mod user_requester;
 pub mod users;
 
 use crate::storage::configuration::{Configuration, RoutingOptions};
+use crate::utilities::instance::InstanceCell;
 use connections::ConnectionTable;
+use events::RouterEvents;
 use feed_requester::{FeedRequester, FeedResponser};
 use flooder::Flooder;
+use guard::RoutingGuard;
 use info::RouterInfo;
 use neighbours::Neighbours;
+use rate_limit::RateLimiter;
+use snapshot::RoutingSnapshot;
 use table::RoutingTable;
+use topology::Topology;
 use user_requester::{UserRequester, UserResponser};
 use users::Users;
 
@@ -40,7 +53,7 @@ pub mod router_net_proto {
 
 /// mutable state of router,
 /// used for storing the router configuration
-static ROUTER: InitCell<RwLock<Router>> = InitCell::new();
+static ROUTER: InstanceCell<RwLock<Router>> = InstanceCell::new();
 
 /// qaul community router access
 #[derive(Clone)]
@@ -79,6 +92,15 @@ impl Router {
         // initialize user_response queue
         UserResponser::init();
 
+        // initialize the routing guard
+        RoutingGuard::init();
+
+        // initialize the rate limiter
+        RateLimiter::init();
+
+        // initialize the router events
+        RouterEvents::init();
+
         // initialize the global routing table
         RoutingTable::init();
 
@@ -86,6 +108,11 @@ impl Router {
         // tables per connection module
         ConnectionTable::init();
 
+        // warm start the neighbours and connection tables
+        // from the last routing snapshot
+        RoutingSnapshot::restore();
+        ConnectionTable::create_routing_table();
+
         // initialize RouterInfo submodule that
         // schedules the sending of the routing information
         // to the neighbouring nodes.
@@ -98,6 +125,18 @@ impl Router {
         router.configuration.clone()
     }
 
+    /// Set router configuration
+    ///
+    /// The new options are applied immediately,
+    /// except the neighbour ping period, which is only
+    /// applied when the connection modules are started.
+    pub fn set_configuration(configuration: RoutingOptions) {
+        RouterInfo::set_interval(configuration.sending_table_period);
+
+        let mut router = ROUTER.get().write().unwrap();
+        router.configuration = configuration;
+    }
+
     /// Process incoming RPC request messages and send them to
     /// the submodules
     pub fn rpc(data: Vec<u8>) {
@@ -116,6 +155,24 @@ impl Router {
                         // send neighbours list
                         Neighbours::rpc_send_neighbours_list();
                     }
+                    Some(proto::router::Message::RoutingGuardRequest(_request)) => {
+                        // send routing violations & quarantined neighbours
+                        RoutingGuard::rpc_send_guard_list();
+                    }
+                    Some(proto::router::Message::TopologyRequest(request)) => {
+                        // send mesh topology graph
+                        let format = proto::TopologyFormat::try_from(request.format)
+                            .unwrap_or(proto::TopologyFormat::Json);
+                        Topology::rpc_send_topology(format);
+                    }
+                    Some(proto::router::Message::RateLimitRequest(_request)) => {
+                        // send dropped messages per neighbour
+                        RateLimiter::rpc_send_rate_limit_list();
+                    }
+                    Some(proto::router::Message::RouterEventSubscribe(request)) => {
+                        // subscribe to the router events
+                        RouterEvents::subscribe(request.subscribe);
+                    }
                     _ => {}
                 }
             }
@@ -4,27 +4,46 @@
 //! Table of all direct neighbour nodes
 //!
 //! There is a table per connection module.
+//!
+//! The connectivity of every neighbour is measured by pings.
+//! The round trip time (rtt), the jitter and the ping loss ratio
+//! are smoothed with an exponentially weighted moving average (EWMA),
+//! so that a single slow or lost ping doesn't make the routes flap.
+//! The smoothing follows the rtt estimation of TCP (RFC 6298).
+//!
+//! The statistics and the addresses of the neighbours are saved in
+//! the routing snapshot.
+//! The restored neighbours are dialed at startup. When a restored
+//! neighbour reconnects after a restart, its statistics are continued
+//! instead of starting from scratch.
+//!
+//! Disconnected and restored neighbours are kept with their
+//! statistics and addresses, until they haven't been seen
+//! for `MAX_DISCONNECTED_AGE`.
 
-use libp2p::PeerId;
+use libp2p::{multiaddr::Protocol, Multiaddr, PeerId};
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use sled::Tree;
-use state::InitCell;
 use std::{collections::HashMap, sync::RwLock};
 
 use super::info::RouterInfo;
 use super::proto;
+use super::snapshot::SnapshotNeighbour;
 use crate::connections::ConnectionModule;
 use crate::rpc::Rpc;
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::{qaul_id::QaulId, timestamp::Timestamp};
 
 /// mutable state of Internet neighbour node table
-static INTERNET: InitCell<RwLock<Neighbours>> = InitCell::new();
+static INTERNET: InstanceCell<RwLock<Neighbours>> = InstanceCell::new();
 /// mutable state of LAN neighbour node table
-static LAN: InitCell<RwLock<Neighbours>> = InitCell::new();
+static LAN: InstanceCell<RwLock<Neighbours>> = InstanceCell::new();
 /// mutable state of BLE neighbour node table
-static BLE: InitCell<RwLock<Neighbours>> = InitCell::new();
+static BLE: InstanceCell<RwLock<Neighbours>> = InstanceCell::new();
+/// mutable state of virtual neighbour node table
+static VIRTUAL: InstanceCell<RwLock<Neighbours>> = InstanceCell::new();
 
 /// Data base table of all ever discovered neighbour nodes
 ///
@@ -32,7 +51,21 @@ static BLE: InitCell<RwLock<Neighbours>> = InitCell::new();
 /// used by the BLE module.
 ///
 /// The DB saves the serialized bincode of `Node`
-static NODES: InitCell<Tree> = InitCell::new();
+static NODES: InstanceCell<Tree> = InstanceCell::new();
+
+/// EWMA weight of a new rtt sample
+const RTT_ALPHA: f64 = 1.0 / 8.0;
+/// EWMA weight of a new jitter sample
+const JITTER_BETA: f64 = 1.0 / 4.0;
+/// EWMA weight of a new ping result for the loss ratio
+const LOSS_GAMMA: f32 = 1.0 / 8.0;
+
+/// maximal number of addresses saved per neighbour
+const MAX_ADDRESSES: usize = 8;
+
+/// time in milli seconds after which a disconnected
+/// neighbour is removed
+const MAX_DISCONNECTED_AGE: u64 = 24 * 60 * 60 * 1000;
 
 /// Node entry in the data base
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -49,37 +82,84 @@ pub struct Node {
 /// It represents the per connection module, module state.
 pub struct Neighbours {
     nodes: HashMap<PeerId, Neighbour>,
+    /// statistics of the disconnected neighbours
+    ///
+    /// Contains the neighbours restored from the routing snapshot,
+    /// that haven't reconnected yet, and the neighbours that
+    /// disconnected, until they expire.
+    restored: HashMap<PeerId, Neighbour>,
+    /// addresses via which the neighbours can be dialed
+    addresses: HashMap<PeerId, Vec<Multiaddr>>,
 }
 
 /// Neighbour node connectivity entry
 pub struct Neighbour {
-    /// round trip time in micro seconds
+    /// smoothed round trip time in micro seconds
     rtt: u32,
+    /// smoothed rtt variation in micro seconds
+    jitter: u32,
+    /// smoothed ratio of lost pings,
+    /// from 0.0 (no loss) to 1.0 (all pings lost)
+    loss: f32,
     /// when was this node last seen
     updated_at: u64,
 }
 
+impl Neighbour {
+    /// create a neighbour entry from the first rtt sample
+    fn new(rtt: u32) -> Self {
+        Self {
+            rtt,
+            jitter: rtt / 2,
+            loss: 0.0,
+            updated_at: Timestamp::get_timestamp(),
+        }
+    }
+
+    /// update the statistics with a new rtt sample
+    fn update_rtt(&mut self, rtt: u32) {
+        self.jitter = Neighbours::calculate_jitter(self.jitter, self.rtt, rtt);
+        self.rtt = Neighbours::calculate_rtt(self.rtt, rtt);
+        self.loss = Neighbours::calculate_loss(self.loss, false);
+        self.updated_at = Timestamp::get_timestamp();
+    }
+}
+
 impl Neighbours {
     /// Initialize neighbours module
     pub fn init() {
         // neighbours table for internet connection module
         let internet = Neighbours {
             nodes: HashMap::new(),
+            restored: HashMap::new(),
+            addresses: HashMap::new(),
         };
         INTERNET.set(RwLock::new(internet));
 
         // neighbours table for lan connection module
         let lan = Neighbours {
             nodes: HashMap::new(),
+            restored: HashMap::new(),
+            addresses: HashMap::new(),
         };
         LAN.set(RwLock::new(lan));
 
         // neighbours table for ble connection module
         let ble = Neighbours {
             nodes: HashMap::new(),
+            restored: HashMap::new(),
+            addresses: HashMap::new(),
         };
         BLE.set(RwLock::new(ble));
 
+        // neighbours table for virtual connection module
+        let virtual_net = Neighbours {
+            nodes: HashMap::new(),
+            restored: HashMap::new(),
+            addresses: HashMap::new(),
+        };
+        VIRTUAL.set(RwLock::new(virtual_net));
+
         // get nodes tree from data base and set it to state
         let db = DataBase::get_node_db();
         let tree = db.open_tree("nodes").unwrap();
@@ -88,7 +168,7 @@ impl Neighbours {
 
     /// update table with a new value
     ///
-    /// If the node already exists, it updates it's rtt value.
+    /// If the node already exists, it updates it's rtt statistics.
     /// If the node does not yet exist, it creates it.
     pub fn update_node(module: ConnectionModule, node_id: PeerId, rtt: u32) {
         log::trace!("update_node node {:?}", node_id);
@@ -98,6 +178,7 @@ impl Neighbours {
             ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
             ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
             ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().write().unwrap(),
             ConnectionModule::Local => return,
             ConnectionModule::None => return,
         }
@@ -105,17 +186,18 @@ impl Neighbours {
         // get node from table
         let node_option = neighbours.nodes.get_mut(&node_id);
         if let Some(node) = node_option {
-            node.rtt = Self::calculate_rtt(node.rtt, rtt);
-            node.updated_at = Timestamp::get_timestamp();
+            node.update_rtt(rtt);
         } else {
             log::trace!("add node {:?} to neighbours table", node_id);
-            neighbours.nodes.insert(
-                node_id,
-                Neighbour {
-                    rtt,
-                    updated_at: Timestamp::get_timestamp(),
-                },
-            );
+            // continue the restored statistics of the neighbour
+            let neighbour = match neighbours.restored.remove(&node_id) {
+                Some(mut neighbour) => {
+                    neighbour.update_rtt(rtt);
+                    neighbour
+                }
+                None => Neighbour::new(rtt),
+            };
+            neighbours.nodes.insert(node_id, neighbour);
 
             // add neighbour in RouterInfo neighbours table
             RouterInfo::add_neighbour(node_id);
@@ -145,6 +227,9 @@ impl Neighbours {
     }
 
     /// Delete Neighbour
+    ///
+    /// The statistics and addresses of the neighbour are kept
+    /// until the entry expires, see `expire()`.
     pub fn delete(module: ConnectionModule, node_id: PeerId) {
         // get table
         let mut neighbours;
@@ -152,21 +237,151 @@ impl Neighbours {
             ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
             ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
             ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().write().unwrap(),
+            ConnectionModule::Local => return,
+            ConnectionModule::None => return,
+        }
+
+        // keep entry as disconnected neighbour
+        if let Some(neighbour) = neighbours.nodes.remove(&node_id) {
+            neighbours.restored.insert(node_id, neighbour);
+        }
+    }
+
+    /// remove the disconnected neighbours of a module,
+    /// that haven't been seen for `MAX_DISCONNECTED_AGE`
+    pub fn expire(module: ConnectionModule) {
+        // get table
+        let mut neighbours;
+        match module {
+            ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
+            ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
+            ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().write().unwrap(),
+            ConnectionModule::Local => return,
+            ConnectionModule::None => return,
+        }
+
+        let now = Timestamp::get_timestamp();
+        let expired: Vec<PeerId> = neighbours
+            .restored
+            .iter()
+            .filter(|(_id, neighbour)| {
+                now.saturating_sub(neighbour.updated_at) > MAX_DISCONNECTED_AGE
+            })
+            .map(|(id, _neighbour)| *id)
+            .collect();
+        for id in expired {
+            neighbours.restored.remove(&id);
+            neighbours.addresses.remove(&id);
+        }
+    }
+
+    /// add addresses via which a neighbour can be dialed
+    ///
+    /// Loopback and unspecified addresses are ignored.
+    pub fn add_addresses(module: ConnectionModule, node_id: PeerId, addresses: Vec<Multiaddr>) {
+        // get table
+        let mut neighbours;
+        match module {
+            ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
+            ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
+            ConnectionModule::Ble => return,
+            ConnectionModule::Virtual => return,
+            ConnectionModule::Local => return,
+            ConnectionModule::None => return,
+        }
+
+        let known = neighbours.addresses.entry(node_id).or_default();
+        for address in addresses {
+            let local = address.iter().any(|protocol| match protocol {
+                Protocol::Ip4(ip) => ip.is_loopback() || ip.is_unspecified(),
+                Protocol::Ip6(ip) => ip.is_loopback() || ip.is_unspecified(),
+                _ => false,
+            });
+            if local || known.contains(&address) {
+                continue;
+            }
+            if known.len() >= MAX_ADDRESSES {
+                known.remove(0);
+            }
+            known.push(address);
+        }
+    }
+
+    /// addresses of the disconnected neighbours
+    ///
+    /// At startup, these are the neighbours restored
+    /// from the routing snapshot.
+    pub fn restored_addresses(module: ConnectionModule) -> Vec<Multiaddr> {
+        // get table
+        let neighbours;
+        match module {
+            ConnectionModule::Lan => neighbours = LAN.get().read().unwrap(),
+            ConnectionModule::Internet => neighbours = INTERNET.get().read().unwrap(),
+            ConnectionModule::Ble => return Vec::new(),
+            ConnectionModule::Virtual => return Vec::new(),
+            ConnectionModule::Local => return Vec::new(),
+            ConnectionModule::None => return Vec::new(),
+        }
+
+        neighbours
+            .restored
+            .keys()
+            .filter_map(|id| neighbours.addresses.get(id))
+            .flatten()
+            .cloned()
+            .collect()
+    }
+
+    /// register a lost ping
+    ///
+    /// Increases the loss ratio of the neighbour.
+    /// Unknown neighbours are ignored.
+    pub fn ping_failed(module: ConnectionModule, node_id: PeerId) {
+        // get table
+        let mut neighbours;
+        match module {
+            ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
+            ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
+            ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().write().unwrap(),
             ConnectionModule::Local => return,
             ConnectionModule::None => return,
         }
 
-        // delete entry
-        neighbours.nodes.remove(&node_id);
+        if let Some(node) = neighbours.nodes.get_mut(&node_id) {
+            node.loss = Self::calculate_loss(node.loss, true);
+            log::trace!("ping to {:?} lost, loss ratio {}", node_id, node.loss);
+        }
     }
 
-    /// Calculate average rtt
-    fn calculate_rtt(_old_rtt: u32, new_rtt: u32) -> u32 {
-        // DISCUSSION: how to value history and flatten the curve
-        //             between different results.
-        //             Other possibilities: create small ring buffer with last results
-        //(old_rtt * 3 + new_rtt) / 4
-        new_rtt
+    /// Calculate smoothed rtt
+    ///
+    /// srtt = (1 - alpha) * srtt + alpha * rtt
+    fn calculate_rtt(old_rtt: u32, new_rtt: u32) -> u32 {
+        let rtt = (1.0 - RTT_ALPHA) * old_rtt as f64 + RTT_ALPHA * new_rtt as f64;
+        rtt.round() as u32
+    }
+
+    /// Calculate smoothed jitter
+    ///
+    /// The jitter is the smoothed deviation of the
+    /// rtt samples from the smoothed rtt.
+    ///
+    /// jitter = (1 - beta) * jitter + beta * |srtt - rtt|
+    fn calculate_jitter(old_jitter: u32, old_rtt: u32, new_rtt: u32) -> u32 {
+        let deviation = old_rtt.abs_diff(new_rtt) as f64;
+        let jitter = (1.0 - JITTER_BETA) * old_jitter as f64 + JITTER_BETA * deviation;
+        jitter.round() as u32
+    }
+
+    /// Calculate smoothed loss ratio
+    ///
+    /// loss = (1 - gamma) * loss + gamma * (1 if lost, 0 if answered)
+    fn calculate_loss(old_loss: f32, lost: bool) -> f32 {
+        let sample = if lost { 1.0 } else { 0.0 };
+        ((1.0 - LOSS_GAMMA) * old_loss + LOSS_GAMMA * sample).clamp(0.0, 1.0)
     }
 
     /// get rtt for a neighbour
@@ -180,6 +395,7 @@ impl Neighbours {
             ConnectionModule::Lan => neighbours = LAN.get().read().unwrap(),
             ConnectionModule::Internet => neighbours = INTERNET.get().read().unwrap(),
             ConnectionModule::Ble => neighbours = BLE.get().read().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().read().unwrap(),
             ConnectionModule::Local => return Some(0),
             ConnectionModule::None => return None,
         }
@@ -192,6 +408,107 @@ impl Neighbours {
         }
     }
 
+    /// get ping loss ratio for a neighbour
+    /// returns the smoothed ratio of lost pings to the
+    /// neighbour in the connection module.
+    /// If the neighbour does not exist, it returns None.
+    pub fn get_loss(neighbour_id: &PeerId, module: &ConnectionModule) -> Option<f32> {
+        // get table
+        let neighbours;
+        match module {
+            ConnectionModule::Lan => neighbours = LAN.get().read().unwrap(),
+            ConnectionModule::Internet => neighbours = INTERNET.get().read().unwrap(),
+            ConnectionModule::Ble => neighbours = BLE.get().read().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().read().unwrap(),
+            ConnectionModule::Local => return Some(0.0),
+            ConnectionModule::None => return None,
+        }
+
+        neighbours
+            .nodes
+            .get(neighbour_id)
+            .map(|neighbour| neighbour.loss)
+    }
+
+    /// create a snapshot of the neighbours table of a module
+    ///
+    /// The snapshot contains the connected and the disconnected neighbours.
+    pub fn snapshot(module: ConnectionModule) -> Vec<SnapshotNeighbour> {
+        // get table
+        let neighbours;
+        match module {
+            ConnectionModule::Lan => neighbours = LAN.get().read().unwrap(),
+            ConnectionModule::Internet => neighbours = INTERNET.get().read().unwrap(),
+            ConnectionModule::Ble => neighbours = BLE.get().read().unwrap(),
+            ConnectionModule::Virtual => neighbours = VIRTUAL.get().read().unwrap(),
+            ConnectionModule::Local => return Vec::new(),
+            ConnectionModule::None => return Vec::new(),
+        }
+
+        neighbours
+            .nodes
+            .iter()
+            .chain(neighbours.restored.iter())
+            .map(|(id, neighbour)| SnapshotNeighbour {
+                id: id.to_bytes(),
+                module,
+                rtt: neighbour.rtt,
+                jitter: neighbour.jitter,
+                loss: neighbour.loss,
+                updated_at: neighbour.updated_at,
+                addresses: neighbours
+                    .addresses
+                    .get(id)
+                    .map(|addresses| addresses.iter().map(|a| a.to_string()).collect())
+                    .unwrap_or_default(),
+            })
+            .collect()
+    }
+
+    /// restore the neighbour statistics from the routing snapshot
+    ///
+    /// The restored neighbours are not connected.
+    /// Their statistics are used when they reconnect,
+    /// their addresses are dialed at startup.
+    pub fn restore(restored: Vec<SnapshotNeighbour>) {
+        for entry in restored {
+            let id = match PeerId::from_bytes(&entry.id) {
+                Ok(id) => id,
+                Err(_) => continue,
+            };
+
+            // get table
+            let mut neighbours;
+            match entry.module {
+                ConnectionModule::Lan => neighbours = LAN.get().write().unwrap(),
+                ConnectionModule::Internet => neighbours = INTERNET.get().write().unwrap(),
+                ConnectionModule::Ble => neighbours = BLE.get().write().unwrap(),
+                ConnectionModule::Virtual => neighbours = VIRTUAL.get().write().unwrap(),
+                ConnectionModule::Local => continue,
+                ConnectionModule::None => continue,
+            }
+
+            neighbours.restored.insert(
+                id,
+                Neighbour {
+                    rtt: entry.rtt,
+                    jitter: entry.jitter,
+                    loss: entry.loss,
+                    updated_at: entry.updated_at,
+                },
+            );
+
+            let addresses: Vec<Multiaddr> = entry
+                .addresses
+                .iter()
+                .filter_map(|address| address.parse().ok())
+                .collect();
+            if !addresses.is_empty() {
+                neighbours.addresses.insert(id, addresses);
+            }
+        }
+    }
+
     /// Is this node ID a neighbour in any module?
     /// returns the first found module or `None`
     pub fn is_neighbour(node_id: &PeerId) -> ConnectionModule {
@@ -216,6 +533,13 @@ impl Neighbours {
                 return ConnectionModule::Ble;
             }
         }
+        // check if neighbour exists in virtual table
+        {
+            let virtual_net = VIRTUAL.get().read().unwrap();
+            if virtual_net.nodes.contains_key(node_id) {
+                return ConnectionModule::Virtual;
+            }
+        }
 
         ConnectionModule::None
     }
@@ -306,6 +630,7 @@ impl Neighbours {
         let mut lan_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
         let mut internet_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
         let mut ble_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
+        let mut virtual_neighbours: Vec<proto::NeighboursEntry> = Vec::new();
 
         // fill lan connection module neighbours
         {
@@ -315,6 +640,8 @@ impl Neighbours {
                 lan_neighbours.push(proto::NeighboursEntry {
                     node_id: id.to_bytes(),
                     rtt: value.rtt,
+                    jitter: value.jitter,
+                    loss: value.loss,
                 });
             }
         }
@@ -327,6 +654,8 @@ impl Neighbours {
                 internet_neighbours.push(proto::NeighboursEntry {
                     node_id: id.to_bytes(),
                     rtt: value.rtt,
+                    jitter: value.jitter,
+                    loss: value.loss,
                 });
             }
         }
@@ -339,6 +668,22 @@ impl Neighbours {
                 ble_neighbours.push(proto::NeighboursEntry {
                     node_id: id.to_bytes(),
                     rtt: value.rtt,
+                    jitter: value.jitter,
+                    loss: value.loss,
+                });
+            }
+        }
+
+        // fill virtual connection module neighbours
+        {
+            let virtual_net = VIRTUAL.get().read().unwrap();
+
+            for (id, value) in &virtual_net.nodes {
+                virtual_neighbours.push(proto::NeighboursEntry {
+                    node_id: id.to_bytes(),
+                    rtt: value.rtt,
+                    jitter: value.jitter,
+                    loss: value.loss,
                 });
             }
         }
@@ -350,6 +695,7 @@ impl Neighbours {
                     lan: lan_neighbours,
                     internet: internet_neighbours,
                     ble: ble_neighbours,
+                    virtual_net: virtual_neighbours,
                 },
             )),
         };
@@ -369,3 +715,131 @@ impl Neighbours {
         );
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use crate::utilities::instance::Instance;
+    use std::collections::BTreeMap;
+
+    /// initialize ephemeral neighbours tables
+    fn init_neighbours() {
+        let mut config = BTreeMap::new();
+        config.insert("storage".to_string(), "memory".to_string());
+        crate::DEFCONFIGS.set(config);
+        Storage::init("neighbours_test".to_string()).unwrap();
+        RouterInfo::init(10);
+        Neighbours::init();
+    }
+
+    fn address() -> Multiaddr {
+        "/ip4/192.168.1.2/tcp/9229".parse().unwrap()
+    }
+
+    fn snapshot_entry(id: PeerId, updated_at: u64) -> SnapshotNeighbour {
+        SnapshotNeighbour {
+            id: id.to_bytes(),
+            module: ConnectionModule::Lan,
+            rtt: 8000,
+            jitter: 400,
+            loss: 0.5,
+            updated_at,
+            addresses: vec![address().to_string()],
+        }
+    }
+
+    #[test]
+    fn rtt_is_smoothed() {
+        assert_eq!(Neighbours::calculate_rtt(8000, 16000), 9000);
+        assert_eq!(Neighbours::calculate_rtt(8000, 0), 7000);
+        assert_eq!(Neighbours::calculate_rtt(8000, 8000), 8000);
+    }
+
+    #[test]
+    fn jitter_is_smoothed_deviation() {
+        assert_eq!(Neighbours::calculate_jitter(400, 8000, 8000), 300);
+        assert_eq!(Neighbours::calculate_jitter(400, 8000, 12000), 1300);
+        assert_eq!(Neighbours::calculate_jitter(400, 12000, 8000), 1300);
+    }
+
+    #[test]
+    fn loss_stays_within_bounds() {
+        assert_eq!(Neighbours::calculate_loss(0.0, true), 0.125);
+        assert_eq!(Neighbours::calculate_loss(0.5, false), 0.4375);
+        assert_eq!(Neighbours::calculate_loss(1.0, true), 1.0);
+        assert_eq!(Neighbours::calculate_loss(0.0, false), 0.0);
+
+        let mut loss = 0.0;
+        for _ in 0..100 {
+            loss = Neighbours::calculate_loss(loss, true);
+        }
+        assert!(loss > 0.99 && loss <= 1.0);
+    }
+
+    #[test]
+    fn disconnected_neighbours_are_kept() {
+        Instance::with(Instance::create(), || {
+            init_neighbours();
+            let id = PeerId::random();
+            Neighbours::update_node(ConnectionModule::Lan, id, 1000);
+            Neighbours::add_addresses(ConnectionModule::Lan, id, vec![address()]);
+
+            Neighbours::delete(ConnectionModule::Lan, id);
+
+            assert_eq!(Neighbours::get_rtt(&id, &ConnectionModule::Lan), None);
+            let snapshot = Neighbours::snapshot(ConnectionModule::Lan);
+            assert_eq!(snapshot.len(), 1);
+            assert_eq!(snapshot[0].id, id.to_bytes());
+            assert_eq!(snapshot[0].addresses, vec![address().to_string()]);
+            assert_eq!(
+                Neighbours::restored_addresses(ConnectionModule::Lan),
+                vec![address()]
+            );
+        });
+    }
+
+    #[test]
+    fn restored_neighbours_are_kept_until_they_reconnect() {
+        Instance::with(Instance::create(), || {
+            init_neighbours();
+            let id = PeerId::random();
+            Neighbours::restore(vec![snapshot_entry(id, Timestamp::get_timestamp())]);
+
+            // the restored neighbour is part of the next snapshot
+            let snapshot = Neighbours::snapshot(ConnectionModule::Lan);
+            assert_eq!(snapshot.len(), 1);
+            assert_eq!(snapshot[0].rtt, 8000);
+            assert_eq!(snapshot[0].addresses, vec![address().to_string()]);
+
+            // the statistics are continued on reconnection
+            Neighbours::update_node(ConnectionModule::Lan, id, 16000);
+            assert_eq!(Neighbours::get_rtt(&id, &ConnectionModule::Lan), Some(9000));
+            assert_eq!(Neighbours::snapshot(ConnectionModule::Lan).len(), 1);
+        });
+    }
+
+    #[test]
+    fn disconnected_neighbours_expire() {
+        Instance::with(Instance::create(), || {
+            init_neighbours();
+            let now = Timestamp::get_timestamp();
+            let expired = PeerId::random();
+            let recent = PeerId::random();
+            Neighbours::restore(vec![
+                snapshot_entry(expired, now - MAX_DISCONNECTED_AGE - 1000),
+                snapshot_entry(recent, now),
+            ]);
+
+            Neighbours::expire(ConnectionModule::Lan);
+
+            let snapshot = Neighbours::snapshot(ConnectionModule::Lan);
+            assert_eq!(snapshot.len(), 1);
+            assert_eq!(snapshot[0].id, recent.to_bytes());
+            assert_eq!(
+                Neighbours::restored_addresses(ConnectionModule::Lan),
+                vec![address()]
+            );
+        });
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # User Profiles
+//!
+//! Versioned and signed profiles of the users.
+//!
+//! A profile contains the user name, a status text and a
+//! small avatar image. It is signed with the key of the user,
+//! for that every node can verify it.
+//! Each update of a profile increases its revision.
+//!
+//! The revision of the profile is advertised with the routing
+//! information of the user. Nodes knowing an older revision
+//! request the profile via the user requester from the
+//! advertising neighbour.
+
+use libp2p::{
+    identity::{Keypair, PublicKey},
+    PeerId,
+};
+use prost::Message;
+
+use super::router_net_proto;
+
+/// maximal length of the user name in bytes
+pub const MAX_NAME_LENGTH: usize = 64;
+
+/// maximal length of the status text in bytes
+pub const MAX_STATUS_LENGTH: usize = 256;
+
+/// maximal size of the avatar image in bytes
+pub const MAX_AVATAR_SIZE: usize = 16 * 1024;
+
+/// User Profile
+#[derive(Clone, Debug)]
+pub struct Profile {
+    /// profile revision
+    pub revision: u32,
+    /// user name
+    pub name: String,
+    /// status text
+    pub status: String,
+    /// small avatar image
+    pub avatar: Vec<u8>,
+    /// signed profile, as it is sent to the network
+    pub signed: router_net_proto::UserProfile,
+}
+
+impl Profile {
+    /// create and sign a new profile revision
+    pub fn create(
+        keys: &Keypair,
+        revision: u32,
+        name: String,
+        status: String,
+        avatar: Vec<u8>,
+    ) -> Result<Profile, String> {
+        let content = router_net_proto::UserProfileContent {
+            id: PeerId::from(keys.public()).to_bytes(),
+            revision,
+            name,
+            status,
+            avatar,
+        };
+        Self::validate(&content)?;
+
+        let content_bytes = content.encode_to_vec();
+        let signature = match keys.sign(&content_bytes) {
+            Ok(signature) => signature,
+            Err(e) => return Err(format!("signing the profile failed: {}", e)),
+        };
+
+        Ok(Profile {
+            revision: content.revision,
+            name: content.name,
+            status: content.status,
+            avatar: content.avatar,
+            signed: router_net_proto::UserProfile {
+                content: content_bytes,
+                signature,
+            },
+        })
+    }
+
+    /// verify a signed profile of a user
+    ///
+    /// The profile needs to be signed by the key of the user,
+    /// and needs to belong to the user.
+    pub fn verify(
+        signed: router_net_proto::UserProfile,
+        user_id: &PeerId,
+        key: &PublicKey,
+    ) -> Result<Profile, String> {
+        if !key.verify(&signed.content, &signed.signature) {
+            return Err("invalid profile signature".to_string());
+        }
+
+        let content = match router_net_proto::UserProfileContent::decode(&signed.content[..]) {
+            Ok(content) => content,
+            Err(e) => return Err(format!("profile decoding error: {}", e)),
+        };
+        if content.id != user_id.to_bytes() {
+            return Err("profile of another user".to_string());
+        }
+        if content.revision == 0 {
+            return Err("invalid profile revision".to_string());
+        }
+        Self::validate(&content)?;
+
+        Ok(Profile {
+            revision: content.revision,
+            name: content.name,
+            status: content.status,
+            avatar: content.avatar,
+            signed,
+        })
+    }
+
+    /// check the size limits of the profile fields
+    fn validate(content: &router_net_proto::UserProfileContent) -> Result<(), String> {
+        if content.name.is_empty() {
+            return Err("the user name can't be empty".to_string());
+        }
+        if content.name.len() > MAX_NAME_LENGTH {
+            return Err(format!(
+                "the user name can be at most {} bytes",
+                MAX_NAME_LENGTH
+            ));
+        }
+        if content.status.len() > MAX_STATUS_LENGTH {
+            return Err(format!(
+                "the status text can be at most {} bytes",
+                MAX_STATUS_LENGTH
+            ));
+        }
+        if content.avatar.len() > MAX_AVATAR_SIZE {
+            return Err(format!(
+                "the avatar can be at most {} bytes",
+                MAX_AVATAR_SIZE
+            ));
+        }
+
+        Ok(())
+    }
+}
@@ -2,7 +2,10 @@
 /// router rpc message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Router {
-    #[prost(oneof = "router::Message", tags = "1, 2, 3, 4, 5, 6")]
+    #[prost(
+        oneof = "router::Message",
+        tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14"
+    )]
     pub message: ::core::option::Option<router::Message>,
 }
 /// Nested message and enum types in `Router`.
@@ -21,6 +24,22 @@ pub mod router {
         NeighboursRequest(super::NeighboursRequest),
         #[prost(message, tag = "6")]
         NeighboursList(super::NeighboursList),
+        #[prost(message, tag = "7")]
+        RoutingGuardRequest(super::RoutingGuardRequest),
+        #[prost(message, tag = "8")]
+        RoutingGuardList(super::RoutingGuardList),
+        #[prost(message, tag = "9")]
+        TopologyRequest(super::TopologyRequest),
+        #[prost(message, tag = "10")]
+        Topology(super::Topology),
+        #[prost(message, tag = "11")]
+        RouterEvent(super::RouterEvent),
+        #[prost(message, tag = "12")]
+        RateLimitRequest(super::RateLimitRequest),
+        #[prost(message, tag = "13")]
+        RateLimitList(super::RateLimitList),
+        #[prost(message, tag = "14")]
+        RouterEventSubscribe(super::RouterEventSubscribe),
     }
 }
 /// UI request for routing table list
@@ -81,6 +100,9 @@ pub struct ConnectionsList {
     /// users connected locally (on the same node)
     #[prost(message, repeated, tag = "4")]
     pub local: ::prost::alloc::vec::Vec<ConnectionsUserEntry>,
+    /// users connected via the virtual module of a simulation
+    #[prost(message, repeated, tag = "5")]
+    pub virtual_net: ::prost::alloc::vec::Vec<ConnectionsUserEntry>,
 }
 /// connections entry for a user
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -118,6 +140,8 @@ pub struct NeighboursList {
     pub internet: ::prost::alloc::vec::Vec<NeighboursEntry>,
     #[prost(message, repeated, tag = "3")]
     pub ble: ::prost::alloc::vec::Vec<NeighboursEntry>,
+    #[prost(message, repeated, tag = "4")]
+    pub virtual_net: ::prost::alloc::vec::Vec<NeighboursEntry>,
 }
 /// neighbours entry
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -125,9 +149,158 @@ pub struct NeighboursEntry {
     /// the ID of the neighbour node
     #[prost(bytes = "vec", tag = "1")]
     pub node_id: ::prost::alloc::vec::Vec<u8>,
-    /// rtt to this neighbour
+    /// smoothed rtt to this neighbour in micro seconds
     #[prost(uint32, tag = "2")]
     pub rtt: u32,
+    /// smoothed rtt variation (jitter) in micro seconds
+    #[prost(uint32, tag = "3")]
+    pub jitter: u32,
+    /// smoothed ratio of lost pings
+    /// from 0.0 (no loss) to 1.0 (all pings lost)
+    #[prost(float, tag = "4")]
+    pub loss: f32,
+}
+/// UI request for the routing guard list
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RoutingGuardRequest {}
+/// routing guard list
+///
+/// Contains the latest routing violations
+/// and the currently quarantined neighbours.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoutingGuardList {
+    /// latest routing violations
+    #[prost(message, repeated, tag = "1")]
+    pub events: ::prost::alloc::vec::Vec<RoutingViolationEvent>,
+    /// quarantined neighbours
+    #[prost(message, repeated, tag = "2")]
+    pub quarantined: ::prost::alloc::vec::Vec<QuarantinedNeighbour>,
+}
+/// routing violation of a neighbour
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RoutingViolationEvent {
+    /// the ID of the advertising neighbour node
+    #[prost(bytes = "vec", tag = "1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// the connection module the routing information was received on
+    #[prost(enumeration = "ConnectionModule", tag = "2")]
+    pub module: i32,
+    /// the violation
+    #[prost(enumeration = "RoutingViolation", tag = "3")]
+    pub violation: i32,
+    /// q8id of the advertised user,
+    /// empty if the violation concerns the entire routing information
+    #[prost(bytes = "vec", tag = "4")]
+    pub user_q8id: ::prost::alloc::vec::Vec<u8>,
+    /// time of the violation in milli seconds
+    #[prost(uint64, tag = "5")]
+    pub time: u64,
+}
+/// quarantined neighbour
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct QuarantinedNeighbour {
+    /// the ID of the neighbour node
+    #[prost(bytes = "vec", tag = "1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// number of recent violations
+    #[prost(uint32, tag = "2")]
+    pub violations: u32,
+    /// end of the quarantine in milli seconds
+    #[prost(uint64, tag = "3")]
+    pub quarantined_until: u64,
+}
+/// UI request for the mesh topology graph
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct TopologyRequest {
+    /// format of the graph
+    #[prost(enumeration = "TopologyFormat", tag = "1")]
+    pub format: i32,
+}
+/// mesh topology graph
+///
+/// The view of this node on the mesh.
+/// The vertices are this node, its user accounts,
+/// the neighbour nodes and the remote users.
+/// The edges are annotated with the connection module,
+/// the round trip time, the hop count and the via node.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Topology {
+    /// format of the graph
+    #[prost(enumeration = "TopologyFormat", tag = "1")]
+    pub format: i32,
+    /// encoded graph
+    #[prost(string, tag = "2")]
+    pub graph: ::prost::alloc::string::String,
+}
+/// router event
+///
+/// Pushed to the UI whenever the reachability of a user changes,
+/// after the UI subscribed to the router events.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RouterEvent {
+    /// q8id of the user
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_q8id: ::prost::alloc::vec::Vec<u8>,
+    /// user id, empty if the user is not known
+    #[prost(bytes = "vec", tag = "2")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// event type
+    #[prost(enumeration = "RouterEventType", tag = "3")]
+    pub event: i32,
+    /// connection module of the best route,
+    /// NONE if the user became unreachable
+    #[prost(enumeration = "ConnectionModule", tag = "4")]
+    pub module: i32,
+    /// connection module of the previous best route,
+    /// NONE if the user became reachable
+    #[prost(enumeration = "ConnectionModule", tag = "5")]
+    pub previous_module: i32,
+    /// time of the event in milli seconds
+    #[prost(uint64, tag = "6")]
+    pub time: u64,
+}
+/// UI request to subscribe to the router events
+///
+/// The events are only pushed to the UI
+/// while it is subscribed.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RouterEventSubscribe {
+    /// subscribe or unsubscribe
+    #[prost(bool, tag = "1")]
+    pub subscribe: bool,
+}
+/// UI request for the rate limit list
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RateLimitRequest {}
+/// rate limit list
+///
+/// Contains the number of messages per neighbour,
+/// that were dropped because the neighbour exceeded
+/// the rate limit of their message class.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RateLimitList {
+    /// neighbours with their dropped messages
+    #[prost(message, repeated, tag = "1")]
+    pub neighbours: ::prost::alloc::vec::Vec<RateLimitEntry>,
+}
+/// dropped messages of a neighbour
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct RateLimitEntry {
+    /// the ID of the neighbour node
+    #[prost(bytes = "vec", tag = "1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// dropped routing information messages
+    #[prost(uint64, tag = "2")]
+    pub router_info: u64,
+    /// dropped feed messages
+    #[prost(uint64, tag = "3")]
+    pub feed: u64,
+    /// dropped feed requests of the neighbour
+    #[prost(uint64, tag = "4")]
+    pub feed_request: u64,
+    /// dropped user requests of the neighbour
+    #[prost(uint64, tag = "5")]
+    pub user_request: u64,
 }
 /// Connection modules
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
@@ -138,6 +311,7 @@ pub enum ConnectionModule {
     Internet = 2,
     Ble = 3,
     Local = 4,
+    Virtual = 5,
 }
 impl ConnectionModule {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -151,6 +325,7 @@ impl ConnectionModule {
             Self::Internet => "INTERNET",
             Self::Ble => "BLE",
             Self::Local => "LOCAL",
+            Self::Virtual => "VIRTUAL",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -161,6 +336,106 @@ impl ConnectionModule {
             "INTERNET" => Some(Self::Internet),
             "BLE" => Some(Self::Ble),
             "LOCAL" => Some(Self::Local),
+            "VIRTUAL" => Some(Self::Virtual),
+            _ => None,
+        }
+    }
+}
+/// routing violations
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RoutingViolation {
+    /// the routing information is not signed by the neighbour
+    InvalidSignature = 0,
+    /// an advertised route has no hop count
+    InvalidHopCount = 1,
+    /// an advertised route has an impossible round trip time
+    ImpossibleRtt = 2,
+    /// implausible jump of the propagation ID
+    PropagationIdJump = 3,
+}
+impl RoutingViolation {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::InvalidSignature => "INVALID_SIGNATURE",
+            Self::InvalidHopCount => "INVALID_HOP_COUNT",
+            Self::ImpossibleRtt => "IMPOSSIBLE_RTT",
+            Self::PropagationIdJump => "PROPAGATION_ID_JUMP",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "INVALID_SIGNATURE" => Some(Self::InvalidSignature),
+            "INVALID_HOP_COUNT" => Some(Self::InvalidHopCount),
+            "IMPOSSIBLE_RTT" => Some(Self::ImpossibleRtt),
+            "PROPAGATION_ID_JUMP" => Some(Self::PropagationIdJump),
+            _ => None,
+        }
+    }
+}
+/// topology graph formats
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum TopologyFormat {
+    /// JSON
+    Json = 0,
+    /// Graphviz DOT
+    Dot = 1,
+}
+impl TopologyFormat {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Json => "JSON",
+            Self::Dot => "DOT",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "JSON" => Some(Self::Json),
+            "DOT" => Some(Self::Dot),
+            _ => None,
+        }
+    }
+}
+/// router event types
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum RouterEventType {
+    /// the user became reachable
+    UserReachable = 0,
+    /// the user became unreachable
+    UserUnreachable = 1,
+    /// the connection module of the best route changed
+    BestModuleChanged = 2,
+}
+impl RouterEventType {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::UserReachable => "USER_REACHABLE",
+            Self::UserUnreachable => "USER_UNREACHABLE",
+            Self::BestModuleChanged => "BEST_MODULE_CHANGED",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "USER_REACHABLE" => Some(Self::UserReachable),
+            "USER_UNREACHABLE" => Some(Self::UserUnreachable),
+            "BEST_MODULE_CHANGED" => Some(Self::BestModuleChanged),
             _ => None,
         }
     }
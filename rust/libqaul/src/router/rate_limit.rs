@@ -0,0 +1,277 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Rate Limiter
+//!
+//! Protects the node against neighbours flooding it with messages.
+//!
+//! The messages received from a neighbour are limited per
+//! message class by a token bucket:
+//! every message takes a token from the bucket of its class,
+//! and the bucket is refilled with the configured rate.
+//! Messages arriving at an empty bucket are dropped.
+//!
+//! The limits are configured in the `rate_limits` section
+//! of the configuration.
+//! The number of dropped messages per neighbour
+//! can be requested over RPC.
+
+use libp2p::PeerId;
+use prost::Message;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use super::proto;
+use crate::rpc::Rpc;
+use crate::storage::configuration::{Configuration, RateLimit, RateLimitOptions};
+use crate::utilities::instance::InstanceCell;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the rate limiter
+static RATELIMITER: InstanceCell<RwLock<RateLimiter>> = InstanceCell::new();
+
+/// number of message classes
+const CLASSES: usize = 4;
+
+/// Rate limited message classes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RateLimitClass {
+    /// routing information messages
+    RouterInfo,
+    /// feed messages to flood
+    Feed,
+    /// feed requests received from the neighbour
+    FeedRequest,
+    /// user requests received from the neighbour
+    UserRequest,
+}
+
+impl RateLimitClass {
+    /// index of the class in the neighbour state
+    fn index(&self) -> usize {
+        match self {
+            RateLimitClass::RouterInfo => 0,
+            RateLimitClass::Feed => 1,
+            RateLimitClass::FeedRequest => 2,
+            RateLimitClass::UserRequest => 3,
+        }
+    }
+
+    /// configured limit of the class
+    fn limit(&self, options: &RateLimitOptions) -> RateLimit {
+        match self {
+            RateLimitClass::RouterInfo => options.router_info,
+            RateLimitClass::Feed => options.feed,
+            RateLimitClass::FeedRequest => options.feed_request,
+            RateLimitClass::UserRequest => options.user_request,
+        }
+    }
+}
+
+/// Token bucket
+#[derive(Clone, Copy)]
+struct Bucket {
+    /// available tokens
+    tokens: f64,
+    /// time of the last refill
+    last_refill: u64,
+}
+
+/// Rate limiting state of a neighbour
+struct NeighbourLimits {
+    /// token bucket per message class
+    buckets: [Option<Bucket>; CLASSES],
+    /// number of dropped messages per message class
+    dropped: [u64; CLASSES],
+}
+
+/// Rate Limiter
+pub struct RateLimiter {
+    /// configured limits
+    options: RateLimitOptions,
+    /// rate limiting state per neighbour node
+    neighbours: HashMap<PeerId, NeighbourLimits>,
+}
+
+impl RateLimiter {
+    /// Initialize the rate limiter with the configured limits
+    pub fn init() {
+        let config = Configuration::get();
+        let rate_limiter = RateLimiter::new(config.rate_limits.clone());
+        RATELIMITER.set(RwLock::new(rate_limiter));
+    }
+
+    /// create a rate limiter with the given limits
+    pub fn new(options: RateLimitOptions) -> RateLimiter {
+        RateLimiter {
+            options,
+            neighbours: HashMap::new(),
+        }
+    }
+
+    /// check whether a message of a neighbour can be processed
+    ///
+    /// Takes a token from the bucket of the message class.
+    /// Returns false and counts the message as dropped,
+    /// if the neighbour exceeded its limit.
+    pub fn check(neighbour: &PeerId, class: RateLimitClass) -> bool {
+        let mut rate_limiter = RATELIMITER.get().write().unwrap();
+        rate_limiter.take(neighbour, class, Timestamp::get_timestamp())
+    }
+
+    /// take a token from the bucket of a message class
+    /// at the time `now` in milli seconds
+    ///
+    /// Returns false and counts the message as dropped,
+    /// if the bucket is empty.
+    pub fn take(&mut self, neighbour: &PeerId, class: RateLimitClass, now: u64) -> bool {
+        let limit = class.limit(&self.options);
+        if limit.rate == 0 {
+            return true;
+        }
+
+        let state = self
+            .neighbours
+            .entry(*neighbour)
+            .or_insert(NeighbourLimits {
+                buckets: [None; CLASSES],
+                dropped: [0; CLASSES],
+            });
+
+        // refill the bucket
+        let bucket = state.buckets[class.index()].get_or_insert(Bucket {
+            tokens: limit.burst as f64,
+            last_refill: now,
+        });
+        let elapsed = now.saturating_sub(bucket.last_refill) as f64 / 1000.0;
+        bucket.tokens = (bucket.tokens + elapsed * limit.rate as f64).min(limit.burst as f64);
+        bucket.last_refill = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            return true;
+        }
+
+        state.dropped[class.index()] += 1;
+        log::debug!(
+            "rate limit {:?} exceeded by {}, message dropped",
+            class,
+            neighbour.to_base58()
+        );
+        false
+    }
+
+    /// send protobuf RPC list of the dropped messages per neighbour
+    pub fn rpc_send_rate_limit_list() {
+        let mut neighbours = Vec::new();
+        {
+            let rate_limiter = RATELIMITER.get().read().unwrap();
+            for (node_id, state) in rate_limiter.neighbours.iter() {
+                neighbours.push(proto::RateLimitEntry {
+                    node_id: node_id.to_bytes(),
+                    router_info: state.dropped[RateLimitClass::RouterInfo.index()],
+                    feed: state.dropped[RateLimitClass::Feed.index()],
+                    feed_request: state.dropped[RateLimitClass::FeedRequest.index()],
+                    user_request: state.dropped[RateLimitClass::UserRequest.index()],
+                });
+            }
+        }
+
+        // create rate limit list message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::RateLimitList(
+                proto::RateLimitList { neighbours },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Router.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(rate: u32, burst: u32) -> RateLimitOptions {
+        RateLimitOptions {
+            router_info: RateLimit { rate, burst },
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn burst_is_limited() {
+        let mut rate_limiter = RateLimiter::new(options(1, 10));
+        let neighbour = PeerId::random();
+
+        for _ in 0..10 {
+            assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        }
+        assert!(!rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        assert!(!rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 999));
+
+        let state = rate_limiter.neighbours.get(&neighbour).unwrap();
+        assert_eq!(state.dropped[RateLimitClass::RouterInfo.index()], 2);
+    }
+
+    #[test]
+    fn bucket_is_refilled_with_rate() {
+        let mut rate_limiter = RateLimiter::new(options(2, 10));
+        let neighbour = PeerId::random();
+
+        for _ in 0..10 {
+            assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        }
+
+        // 2 tokens per second
+        assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 1000));
+        assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 1000));
+        assert!(!rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 1000));
+    }
+
+    #[test]
+    fn refill_is_capped_at_burst() {
+        let mut rate_limiter = RateLimiter::new(options(100, 5));
+        let neighbour = PeerId::random();
+
+        assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        for _ in 0..5 {
+            assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 60_000));
+        }
+        assert!(!rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 60_000));
+    }
+
+    #[test]
+    fn classes_and_neighbours_are_independent() {
+        let mut rate_limiter = RateLimiter::new(options(1, 1));
+        let neighbour = PeerId::random();
+        let other = PeerId::random();
+
+        assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        assert!(!rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        assert!(rate_limiter.take(&other, RateLimitClass::RouterInfo, 0));
+        assert!(rate_limiter.take(&neighbour, RateLimitClass::UserRequest, 0));
+    }
+
+    #[test]
+    fn rate_zero_disables_limit() {
+        let mut rate_limiter = RateLimiter::new(options(0, 0));
+        let neighbour = PeerId::random();
+
+        for _ in 0..1000 {
+            assert!(rate_limiter.take(&neighbour, RateLimitClass::RouterInfo, 0));
+        }
+    }
+}
@@ -0,0 +1,182 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Routing Snapshot
+//!
+//! Persists the routing state of the node to the node data base,
+//! for that the router can warm start after a restart.
+//!
+//! The snapshot contains:
+//!
+//! * the connected and disconnected neighbours per connection module
+//!   with their rtt, jitter and ping loss statistics and their addresses
+//! * the users per connection module with their propagation ID
+//!   and all routes to them
+//!
+//! The snapshot is saved periodically from the main loop.
+//! At startup it is restored into the connection tables.
+//! The restored routes are stale: they are only used
+//! when their neighbour is connected again, they are
+//! replaced as soon as the neighbour announces them again,
+//! and they expire after the maintain period limit otherwise.
+//!
+//! The LAN and Internet neighbours of the snapshot are dialed
+//! at startup, in addition to the Internet peers of the configuration
+//! and the LAN peers discovered via mDNS.
+
+use serde::{Deserialize, Serialize};
+
+use super::{connections::ConnectionTable, neighbours::Neighbours};
+use crate::connections::ConnectionModule;
+use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::timestamp::Timestamp;
+
+/// name of the snapshot tree in the node data base
+const TREE_NAME: &str = "routing_snapshot";
+
+/// key of the snapshot in the tree
+const SNAPSHOT_KEY: &[u8] = b"snapshot";
+
+/// maximal age of a snapshot in milli seconds,
+/// older snapshots are not restored
+const MAX_AGE: u64 = 24 * 60 * 60 * 1000;
+
+/// connection modules contained in the snapshot
+const MODULES: [ConnectionModule; 4] = [
+    ConnectionModule::Lan,
+    ConnectionModule::Internet,
+    ConnectionModule::Ble,
+    ConnectionModule::Virtual,
+];
+
+/// Routing Snapshot saved in the data base
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// time when the snapshot was saved
+    pub saved_at: u64,
+    /// neighbours of all connection modules
+    pub neighbours: Vec<SnapshotNeighbour>,
+    /// users of all connection modules
+    pub users: Vec<SnapshotUser>,
+}
+
+/// Neighbour entry of the snapshot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotNeighbour {
+    /// node id of the neighbour
+    pub id: Vec<u8>,
+    /// connection module
+    pub module: ConnectionModule,
+    /// smoothed round trip time in micro seconds
+    pub rtt: u32,
+    /// smoothed rtt variation in micro seconds
+    pub jitter: u32,
+    /// smoothed ratio of lost pings
+    pub loss: f32,
+    /// time when the neighbour was last seen
+    pub updated_at: u64,
+    /// multiaddresses via which the neighbour can be dialed
+    pub addresses: Vec<String>,
+}
+
+/// User entry of the snapshot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotUser {
+    /// connection module
+    pub module: ConnectionModule,
+    /// user q8id
+    pub q8id: Vec<u8>,
+    /// propagation id
+    pub pgid: u32,
+    /// hop count of the last propagation id update
+    pub pgid_update_hc: u8,
+    /// online time
+    pub online_time: u64,
+    /// routes to the user
+    pub connections: Vec<SnapshotConnection>,
+}
+
+/// Route entry of the snapshot
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SnapshotConnection {
+    /// node id of the neighbour via which the user is reached
+    pub id: Vec<u8>,
+    /// round trip time
+    pub rtt: u32,
+    /// hop count
+    pub hc: u8,
+    /// link quality
+    pub lq: u32,
+}
+
+/// Routing Snapshot
+pub struct RoutingSnapshot {}
+
+impl RoutingSnapshot {
+    /// interval in seconds in which the snapshot is saved
+    pub const INTERVAL: u64 = 60;
+
+    /// open the snapshot tree of the node data base
+    fn tree() -> Tree {
+        let db = DataBase::get_node_db();
+        db.open_tree(TREE_NAME).unwrap()
+    }
+
+    /// save the current routing state to the data base
+    pub fn save() {
+        let mut snapshot = Snapshot {
+            saved_at: Timestamp::get_timestamp(),
+            neighbours: Vec::new(),
+            users: Vec::new(),
+        };
+        for module in MODULES {
+            Neighbours::expire(module);
+            snapshot.neighbours.extend(Neighbours::snapshot(module));
+            snapshot.users.extend(ConnectionTable::snapshot(module));
+        }
+
+        let tree = Self::tree();
+        let snapshot_bytes = bincode::serialize(&snapshot).unwrap();
+        if let Err(e) = tree.insert(SNAPSHOT_KEY, snapshot_bytes) {
+            log::error!("Error saving routing snapshot to data base: {}", e);
+        } else if let Err(e) = tree.flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+    }
+
+    /// restore the routing state from the data base
+    ///
+    /// The neighbours tables and the connection tables
+    /// need to be initialized before.
+    pub fn restore() {
+        let tree = Self::tree();
+        let snapshot: Snapshot = match tree.get(SNAPSHOT_KEY) {
+            Ok(Some(bytes)) => match bincode::deserialize(&bytes) {
+                Ok(snapshot) => snapshot,
+                Err(e) => {
+                    log::error!("Error deserializing routing snapshot: {}", e);
+                    return;
+                }
+            },
+            Ok(None) => return,
+            Err(e) => {
+                log::error!("Error reading routing snapshot from data base: {}", e);
+                return;
+            }
+        };
+
+        if Timestamp::get_timestamp().saturating_sub(snapshot.saved_at) > MAX_AGE {
+            log::info!("routing snapshot is outdated and not restored");
+            return;
+        }
+
+        log::info!(
+            "restore routing snapshot: {} neighbours, {} users",
+            snapshot.neighbours.len(),
+            snapshot.users.len()
+        );
+        Neighbours::restore(snapshot.neighbours);
+        ConnectionTable::restore(snapshot.users);
+    }
+}
@@ -9,21 +9,29 @@
 //! * There is an entry for each user over which connection modules
 //!   it can be reached. Each connection module only contains
 //!   information of the best node.
+//! * The best route to a user is selected across all connection
+//!   modules by comparing link quality, freshness and hop count.
+//!   The module preference of the routing options is only
+//!   used as a tie-breaker.
 
 use libp2p::PeerId;
 use prost::Message;
-use state::InitCell;
 use std::collections::{BTreeMap, HashMap};
 use std::sync::RwLock;
 
 use super::proto;
 use crate::connections::ConnectionModule;
-use crate::router::router_net_proto;
+use crate::router::{
+    events::RouterEvents, neighbours::Neighbours, router_net_proto, users::Users, Router,
+};
 use crate::rpc::Rpc;
+use crate::storage::configuration::RoutingOptions;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::qaul_id::QaulId;
+use crate::utilities::timestamp::Timestamp;
 
 /// mutable state of table
-static ROUTINGTABLE: InitCell<RwLock<RoutingTable>> = InitCell::new();
+static ROUTINGTABLE: InstanceCell<RwLock<RoutingTable>> = InstanceCell::new();
 
 /// table entry per user
 #[derive(Debug, Clone)]
@@ -85,9 +93,28 @@ impl RoutingTable {
     }
 
     /// set and replace routing table with a new table
+    ///
+    /// The best routes of the new table are passed to the
+    /// router events, which publish the changes of the
+    /// users reachability.
     pub fn set(new_table: RoutingTable) {
-        let mut table = ROUTINGTABLE.get().write().unwrap();
-        table.table = new_table.table;
+        let options = Router::get_configuration();
+        let now = Timestamp::get_timestamp();
+
+        // best connection module per reachable user
+        let mut best_modules: BTreeMap<Vec<u8>, ConnectionModule> = BTreeMap::new();
+        for (user_id, user) in new_table.table.iter() {
+            if let Some(connection) = Self::best_connection(&user.connections, &options, now) {
+                best_modules.insert(user_id.clone(), connection.module);
+            }
+        }
+
+        {
+            let mut table = ROUTINGTABLE.get().write().unwrap();
+            table.table = new_table.table;
+        }
+
+        RouterEvents::update(best_modules);
     }
 
     /// Create routing information for a specific neighbour node,
@@ -97,23 +124,20 @@ impl RoutingTable {
         last_sent: u64,
     ) -> router_net_proto::RoutingInfoTable {
         let mut table = router_net_proto::RoutingInfoTable { entry: Vec::new() };
+        let options = Router::get_configuration();
+        let now = Timestamp::get_timestamp();
+        let profile_revisions = Users::get_profile_revisions();
 
         // get access to routing table
         let routing_table = ROUTINGTABLE.get().read().unwrap();
 
         // loop through routing table
         for (user_id, user) in routing_table.table.iter() {
-            if user.connections.len() == 0 {
-                continue;
-            }
-
-            // choose best link quality
-            let mut min_conn = user.connections[0].clone();
-            for i in 0..user.connections.len() {
-                if user.connections[i].lq < min_conn.lq {
-                    min_conn = user.connections[i].clone();
-                }
-            }
+            // choose best route
+            let min_conn = match Self::best_connection(&user.connections, &options, now) {
+                Some(connection) => connection,
+                None => continue,
+            };
 
             if neighbour != min_conn.node && (min_conn.last_update >= last_sent || min_conn.hc == 0)
             {
@@ -125,6 +149,7 @@ impl RoutingTable {
                     rtt: min_conn.rtt,
                     hc,
                     pgid: user.pgid,
+                    profile_revision: profile_revisions.get(user_id).copied().unwrap_or(0),
                 };
                 table.entry.push(table_entry);
             }
@@ -206,6 +231,7 @@ impl RoutingTable {
                     ConnectionModule::Internet => module = proto::ConnectionModule::Internet as i32,
                     ConnectionModule::Ble => module = proto::ConnectionModule::Ble as i32,
                     ConnectionModule::Local => module = proto::ConnectionModule::Local as i32,
+                    ConnectionModule::Virtual => module = proto::ConnectionModule::Virtual as i32,
                     _ => module = proto::ConnectionModule::None as i32,
                 }
 
@@ -255,35 +281,76 @@ impl RoutingTable {
     /// It selects the best route according to the rank_routing_connection function.
     ///
     pub fn get_route_to_user(user_id: PeerId) -> Option<RoutingConnectionEntry> {
+        let options = Router::get_configuration();
+        let now = Timestamp::get_timestamp();
+
         // get routing table state
         let routing_table = ROUTINGTABLE.get().read().unwrap();
 
         // get q8id for qaul user
         let user_q8id = QaulId::to_q8id(user_id);
 
+        // find user and best route
+        routing_table
+            .table
+            .get(&user_q8id)
+            .and_then(|user_entry| Self::best_connection(&user_entry.connections, &options, now))
+    }
+
+    /// Get the best routes to a specific user
+    ///
+    /// Returns at most `count` routes over different connection
+    /// modules, ordered from the best to the worst route.
+    /// Routes via neighbours which disappeared since the routing
+    /// table was created are skipped.
+    pub fn get_routes_to_user(user_id: PeerId, count: usize) -> Vec<RoutingConnectionEntry> {
+        let options = Router::get_configuration();
+        let now = Timestamp::get_timestamp();
+
+        // get routing table state
+        let routing_table = ROUTINGTABLE.get().read().unwrap();
+
         // find user
-        if let Some(user_entry) = routing_table.table.get(&user_q8id) {
-            let mut compare: Option<&RoutingConnectionEntry> = None;
-
-            // find best route
-            for connection in &user_entry.connections {
-                match compare {
-                    Some(current) => {
-                        if Self::compare_connections(current, connection) {
-                            compare = Some(connection);
-                        }
-                    }
-                    None => compare = Some(connection),
-                }
-            }
+        let user_entry = match routing_table.table.get(&QaulId::to_q8id(user_id)) {
+            Some(user_entry) => user_entry,
+            None => return Vec::new(),
+        };
+
+        // sort available routes by their ranking
+        let mut routes: Vec<&RoutingConnectionEntry> = user_entry
+            .connections
+            .iter()
+            .filter(|connection| {
+                Neighbours::get_rtt(&connection.node, &connection.module).is_some()
+            })
+            .collect();
+        routes.sort_by_key(|connection| Self::rank_routing_connection(connection, &options, now));
+
+        routes.into_iter().take(count).cloned().collect()
+    }
 
-            // return route
+    /// find the best connection of a user entry
+    ///
+    /// Returns `None` if the list of connections is empty.
+    fn best_connection(
+        connections: &[RoutingConnectionEntry],
+        options: &RoutingOptions,
+        now: u64,
+    ) -> Option<RoutingConnectionEntry> {
+        let mut compare: Option<&RoutingConnectionEntry> = None;
+
+        for connection in connections {
             match compare {
-                None => return None,
-                Some(connection) => return Some(connection.to_owned()),
+                Some(current) => {
+                    if Self::compare_connections(current, connection, options, now) {
+                        compare = Some(connection);
+                    }
+                }
+                None => compare = Some(connection),
             }
         }
-        None
+
+        compare.cloned()
     }
 
     /// Compare two routing connections and decides which one is better
@@ -296,31 +363,46 @@ impl RoutingTable {
     /// * returns true, when the new connection is better
     /// * returns false, when the current connection is better
     ///
-    fn compare_connections(current: &RoutingConnectionEntry, new: &RoutingConnectionEntry) -> bool {
-        let current_value = Self::rank_routing_connection(current);
-        let new_value = Self::rank_routing_connection(new);
-
-        if current_value < new_value {
-            return true;
-        }
-
-        false
+    fn compare_connections(
+        current: &RoutingConnectionEntry,
+        new: &RoutingConnectionEntry,
+        options: &RoutingOptions,
+        now: u64,
+    ) -> bool {
+        let current_value = Self::rank_routing_connection(current, options, now);
+        let new_value = Self::rank_routing_connection(new, options, now);
+
+        new_value < current_value
     }
 
     /// give a ranking to the routing connection
     ///
-    /// This function decides which connection to favour based on the following qualities:
+    /// The smaller the ranking, the better the connection.
+    /// This function decides which connection to favour based on the
+    /// following qualities, in this order:
     ///
-    /// * Hierarchy of connection modules in the following order:
-    ///   Local, LAN, Internet, BLE, None
+    /// * link quality, plus a penalty for missed routing updates
+    /// * hop count
+    /// * preference of the connection module,
+    ///   configured in the routing options
     ///
-    fn rank_routing_connection(connection: &RoutingConnectionEntry) -> u8 {
-        match connection.module {
-            ConnectionModule::None => return 0,
-            ConnectionModule::Ble => return 1,
-            ConnectionModule::Internet => return 2,
-            ConnectionModule::Lan => return 3,
-            ConnectionModule::Local => return 4,
-        }
+    fn rank_routing_connection(
+        connection: &RoutingConnectionEntry,
+        options: &RoutingOptions,
+        now: u64,
+    ) -> (u64, u8, usize) {
+        // Routing information is received every sending table period.
+        // Each micro second the entry is older, is added to the link quality.
+        let age = now.saturating_sub(connection.last_update);
+        let staleness = age.saturating_sub(options.sending_table_period * 1000) * 1000;
+
+        // modules which are not listed are least preferred
+        let preference = options
+            .module_preference
+            .iter()
+            .position(|module| *module == connection.module)
+            .unwrap_or(options.module_preference.len());
+
+        (connection.lq as u64 + staleness, connection.hc, preference)
     }
 }
@@ -0,0 +1,335 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Mesh Topology
+//!
+//! Exports the view this node has of the mesh as a graph.
+//!
+//! The vertices of the graph are:
+//!
+//! * this node
+//! * the local user accounts of this node
+//! * the neighbour nodes of all connection modules
+//! * the remote users known by the connection tables
+//!
+//! The edges are annotated with the connection module,
+//! the round trip time, the hop count and the node via which
+//! the target is reached.
+//! Edges to remote users that are selected by the routing table
+//! are marked as best routes.
+//!
+//! The graph is exported as JSON or as Graphviz DOT.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use super::{
+    connections::ConnectionTable, neighbours::Neighbours, proto, table::RoutingTable, users::Users,
+};
+use crate::connections::ConnectionModule;
+use crate::node::{user_accounts::UserAccounts, Node};
+use crate::rpc::Rpc;
+use crate::utilities::qaul_id::QaulId;
+
+/// connection modules contained in the topology
+const MODULES: [ConnectionModule; 4] = [
+    ConnectionModule::Lan,
+    ConnectionModule::Internet,
+    ConnectionModule::Ble,
+    ConnectionModule::Virtual,
+];
+
+/// Vertex kind
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum VertexKind {
+    /// this node
+    Node,
+    /// user account of this node
+    LocalUser,
+    /// neighbour node
+    Neighbour,
+    /// remote user
+    User,
+}
+
+/// Topology vertex
+#[derive(Clone, Debug, Serialize)]
+pub struct Vertex {
+    /// base58 encoded node or user id
+    pub id: String,
+    /// kind of the vertex
+    pub kind: VertexKind,
+    /// user name, empty for nodes and unknown users
+    pub name: String,
+}
+
+/// Topology edge
+#[derive(Clone, Debug, Serialize)]
+pub struct Edge {
+    /// id of the source vertex
+    pub from: String,
+    /// id of the target vertex
+    pub to: String,
+    /// connection module
+    pub module: ConnectionModule,
+    /// round trip time in micro seconds
+    pub rtt: u32,
+    /// hop count
+    pub hc: u8,
+    /// base58 encoded id of the node via which the target is reached
+    pub via: String,
+    /// the route is selected by the routing table
+    pub best: bool,
+}
+
+/// Mesh Topology Graph
+#[derive(Clone, Debug, Serialize)]
+pub struct Topology {
+    /// base58 encoded id of this node
+    pub node: String,
+    /// vertices of the graph
+    pub vertices: Vec<Vertex>,
+    /// edges of the graph
+    pub edges: Vec<Edge>,
+}
+
+impl Topology {
+    /// create the topology graph from the router tables
+    pub fn create() -> Topology {
+        let node_id = Node::get_id().to_base58();
+        let mut vertices: BTreeMap<String, Vertex> = BTreeMap::new();
+        let mut edges = Vec::new();
+
+        vertices.insert(
+            node_id.clone(),
+            Vertex {
+                id: node_id.clone(),
+                kind: VertexKind::Node,
+                name: String::new(),
+            },
+        );
+
+        // local user accounts
+        for user in UserAccounts::get_user_info() {
+            let id = user.id.to_base58();
+            edges.push(Edge {
+                from: node_id.clone(),
+                to: id.clone(),
+                module: ConnectionModule::Local,
+                rtt: 0,
+                hc: 0,
+                via: node_id.clone(),
+                best: true,
+            });
+            vertices.insert(
+                id.clone(),
+                Vertex {
+                    id,
+                    kind: VertexKind::LocalUser,
+                    name: user.name,
+                },
+            );
+        }
+
+        // neighbours
+        for module in MODULES {
+            for neighbour in Neighbours::snapshot(module) {
+                let id = Self::node_id_to_base58(&neighbour.id);
+                edges.push(Edge {
+                    from: node_id.clone(),
+                    to: id.clone(),
+                    module,
+                    rtt: neighbour.rtt,
+                    hc: 1,
+                    via: id.clone(),
+                    best: true,
+                });
+                vertices.entry(id.clone()).or_insert(Vertex {
+                    id,
+                    kind: VertexKind::Neighbour,
+                    name: String::new(),
+                });
+            }
+        }
+
+        // best routes of the routing table
+        let online_users = RoutingTable::get_online_users_info();
+
+        // remote users
+        let mut q8ids = Vec::new();
+        for module in MODULES {
+            for user in ConnectionTable::snapshot(module) {
+                let best_routes = online_users.get(&user.q8id);
+                for connection in user.connections {
+                    let via = Self::node_id_to_base58(&connection.id);
+                    let best = match (best_routes, PeerId::from_bytes(&connection.id)) {
+                        (Some(routes), Ok(via_id)) => routes
+                            .iter()
+                            .any(|route| route.module == module && route.node == via_id),
+                        _ => false,
+                    };
+
+                    // the via node may have disappeared from the neighbours
+                    vertices.entry(via.clone()).or_insert(Vertex {
+                        id: via.clone(),
+                        kind: VertexKind::Neighbour,
+                        name: String::new(),
+                    });
+
+                    edges.push(Edge {
+                        from: via.clone(),
+                        to: bs58::encode(&user.q8id).into_string(),
+                        module,
+                        rtt: connection.rtt,
+                        hc: connection.hc,
+                        via,
+                        best,
+                    });
+                }
+                q8ids.push(user.q8id);
+            }
+        }
+
+        // name the remote users and replace their q8ids by their user ids
+        let mut user_ids: BTreeMap<String, String> = BTreeMap::new();
+        for info in Users::get_user_info_table_by_q8ids(&q8ids).info {
+            if let Ok(id) = PeerId::from_bytes(&info.id) {
+                let q8id = bs58::encode(QaulId::to_q8id(id)).into_string();
+                let id = id.to_base58();
+                user_ids.insert(q8id, id.clone());
+                vertices.entry(id.clone()).or_insert(Vertex {
+                    id,
+                    kind: VertexKind::User,
+                    name: info.name,
+                });
+            }
+        }
+        for edge in edges.iter_mut() {
+            if let Some(id) = user_ids.get(&edge.to) {
+                edge.to = id.clone();
+            }
+        }
+
+        // users unknown to the users table are identified by their q8id
+        for q8id in q8ids {
+            let q8id = bs58::encode(q8id).into_string();
+            if !user_ids.contains_key(&q8id) {
+                vertices.entry(q8id.clone()).or_insert(Vertex {
+                    id: q8id,
+                    kind: VertexKind::User,
+                    name: String::new(),
+                });
+            }
+        }
+
+        Topology {
+            node: node_id,
+            vertices: vertices.into_values().collect(),
+            edges,
+        }
+    }
+
+    /// encode the topology as JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// encode the topology as Graphviz DOT
+    ///
+    /// The best routes are drawn bold.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph qaul {\n");
+
+        for vertex in &self.vertices {
+            let shape = match vertex.kind {
+                VertexKind::Node => "doublecircle",
+                VertexKind::LocalUser => "box",
+                VertexKind::Neighbour => "circle",
+                VertexKind::User => "ellipse",
+            };
+            let label = if vertex.name.is_empty() {
+                Self::escape(Self::short_id(&vertex.id))
+            } else {
+                format!(
+                    "{}\\n{}",
+                    Self::escape(&vertex.name),
+                    Self::escape(Self::short_id(&vertex.id))
+                )
+            };
+            dot.push_str(&format!(
+                "    \"{}\" [label=\"{}\", shape={}];\n",
+                Self::escape(&vertex.id),
+                label,
+                shape
+            ));
+        }
+
+        for edge in &self.edges {
+            dot.push_str(&format!(
+                "    \"{}\" -> \"{}\" [label=\"{:?} rtt {} hc {}\"{}];\n",
+                Self::escape(&edge.from),
+                Self::escape(&edge.to),
+                edge.module,
+                edge.rtt,
+                edge.hc,
+                if edge.best { ", style=bold" } else { "" }
+            ));
+        }
+
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// send protobuf RPC topology graph
+    pub fn rpc_send_topology(format: proto::TopologyFormat) {
+        let topology = Self::create();
+        let graph = match format {
+            proto::TopologyFormat::Json => topology.to_json(),
+            proto::TopologyFormat::Dot => topology.to_dot(),
+        };
+
+        // create topology message
+        let proto_message = proto::Router {
+            message: Some(proto::router::Message::Topology(proto::Topology {
+                format: format as i32,
+                graph,
+            })),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Router.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// base58 encode a binary node id
+    fn node_id_to_base58(id: &[u8]) -> String {
+        match PeerId::from_bytes(id) {
+            Ok(id) => id.to_base58(),
+            Err(_) => bs58::encode(id).into_string(),
+        }
+    }
+
+    /// the last characters of an id, used as label
+    fn short_id(id: &str) -> &str {
+        let start = id.len().saturating_sub(8);
+        &id[start..]
+    }
+
+    /// escape a DOT identifier
+    fn escape(id: &str) -> String {
+        id.replace('\\', "\\\\").replace('"', "\\\"")
+    }
+}
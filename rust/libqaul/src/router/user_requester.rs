@@ -12,15 +12,24 @@
 //! advertised and can be requested from the sending node.
 
 use libp2p::PeerId;
-use state::InitCell;
+use prost::Message;
 use std::collections::VecDeque;
 use std::sync::RwLock;
 
+use crate::utilities::instance::InstanceCell;
+
+/// maximal encoded size of the user info table of a user response
+///
+/// The qaul info frames are limited to 64 KiB.
+/// The rest of the frame is reserved for the signed
+/// router info container around the table.
+const MAX_USER_RESPONSE_SIZE: usize = 48 * 1024;
+
 /// mutable state of user requester
-pub static USERREQUESTER: InitCell<RwLock<UserRequester>> = InitCell::new();
+pub static USERREQUESTER: InstanceCell<RwLock<UserRequester>> = InstanceCell::new();
 
 /// mutable state of the user responser
-pub static USERRESPONSER: InitCell<RwLock<UserResponser>> = InitCell::new();
+pub static USERRESPONSER: InstanceCell<RwLock<UserResponser>> = InstanceCell::new();
 
 /// User Request Structure
 pub struct UserRequest {
@@ -43,6 +52,9 @@ impl UserRequester {
     }
 
     /// Add a message to the ring buffer for sending.
+    ///
+    /// The requests of this node are not rate limited,
+    /// only the requests received from the neighbours are.
     pub fn add(neighbour_id: &PeerId, user_ids: &Vec<Vec<u8>>) {
         let msg = UserRequest {
             neighbour_id: neighbour_id.clone(),
@@ -76,13 +88,32 @@ impl UserResponser {
     }
 
     /// Add a message to the ring buffer for sending.
+    ///
+    /// Tables exceeding the maximal frame size are split
+    /// into several responses.
     pub fn add(neighbour_id: &PeerId, table: &super::router_net_proto::UserInfoTable) {
-        let msg = UserResponse {
-            neighbour_id: neighbour_id.clone(),
-            users: table.clone(),
-        };
-        // add it to sending queue
+        // split the table into responses fitting into a frame
+        let mut responses = Vec::new();
+        let mut users = super::router_net_proto::UserInfoTable { info: Vec::new() };
+        for info in &table.info {
+            users.info.push(info.clone());
+            if users.info.len() > 1 && users.encoded_len() > MAX_USER_RESPONSE_SIZE {
+                let last = users.info.pop().unwrap();
+                responses.push(users);
+                users = super::router_net_proto::UserInfoTable { info: vec![last] };
+            }
+        }
+        if !users.info.is_empty() {
+            responses.push(users);
+        }
+
+        // add them to sending queue
         let mut user_responser = USERRESPONSER.get().write().unwrap();
-        user_responser.to_send.push_back(msg);
+        for users in responses {
+            user_responser.to_send.push_back(UserResponse {
+                neighbour_id: neighbour_id.clone(),
+                users,
+            });
+        }
     }
 }
@@ -4,23 +4,34 @@
 //! # Discovered user table
 //!
 //! This table contains all users known to this node.
+//!
+//! The users can publish a signed profile, which is
+//! propagated with the user info to the neighbours.
+//! Newer profile revisions replace the user name.
+//!
+//! The time when a user was last seen in the network
+//! is updated whenever the user becomes reachable or unreachable.
 
 use libp2p::{identity::PublicKey, PeerId};
 use prost::Message;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha512};
-use state::InitCell;
 use std::cmp::Ordering;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
+use super::identity::IdentityCard;
+use super::profile::Profile;
 use super::router_net_proto;
-use super::table::RoutingTable;
-use crate::node::user_accounts::UserAccounts;
+use super::table::{RoutingConnectionEntry, RoutingTable};
+use crate::node::user_accounts::{UserAccount, UserAccounts};
 use crate::rpc::Rpc;
 use crate::services::group::group_id::GroupId;
+use crate::services::messaging::probe::MessagingProbe;
 use crate::storage::database::DbUsers;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::qaul_id::QaulId;
+use crate::utilities::timestamp::Timestamp;
 
 /// Import protobuf users RPC message definition generated by
 /// the rust module prost-build.
@@ -34,12 +45,22 @@ pub mod proto_net {
 }
 
 /// mutable state of users table
-static USERS: InitCell<RwLock<Users>> = InitCell::new();
+static USERS: InstanceCell<RwLock<Users>> = InstanceCell::new();
+
+/// number of users returned by a user query without limit
+const DEFAULT_QUERY_LIMIT: u32 = 50;
+
+/// maximal number of users returned by a user query
+const MAX_QUERY_LIMIT: u32 = 500;
 
 /// implementation of all known users for routing references
 pub struct Users {
     /// the BTreeMap key is the 8 byte qaul ID (q8id)
     pub users: BTreeMap<Vec<u8>, User>,
+    /// time when the users were last seen in the network
+    ///
+    /// q8id => timestamp
+    last_seen: BTreeMap<Vec<u8>, u64>,
 }
 
 impl Users {
@@ -51,13 +72,36 @@ impl Users {
             // create users table and save it to state
             let users = Users {
                 users: BTreeMap::new(),
+                last_seen: BTreeMap::new(),
             };
             USERS.set(RwLock::new(users));
         }
 
+        // load the user profiles from data base
+        let mut profiles: BTreeMap<Vec<u8>, router_net_proto::UserProfile> = BTreeMap::new();
+        for res in DbUsers::get_profiles_tree().iter() {
+            if let Ok((id, profile_bytes)) = res {
+                if let Ok(profile) = router_net_proto::UserProfile::decode(&profile_bytes[..]) {
+                    profiles.insert(id.to_vec(), profile);
+                }
+            }
+        }
+
         // fill user table with users from data base
         let tree = DbUsers::get_tree();
         let mut users = USERS.get().write().unwrap();
+
+        // load the last seen times from data base
+        for res in DbUsers::get_last_seen_tree().iter() {
+            if let Ok((id, time_bytes)) = res {
+                if let Ok(time) = <[u8; 8]>::try_from(&time_bytes[..]) {
+                    users
+                        .last_seen
+                        .insert(QaulId::bytes_to_q8id(id.to_vec()), u64::from_be_bytes(time));
+                }
+            }
+        }
+
         // iterate over all values in db
         for res in tree.iter() {
             if let Ok((_vec, user_bytes)) = res {
@@ -67,6 +111,17 @@ impl Users {
                 let q8id = QaulId::bytes_to_q8id(user.id.clone());
                 let id = PeerId::from_bytes(&user.id).unwrap();
                 let key = PublicKey::try_decode_protobuf(&user.key).unwrap();
+                // verify the saved profile
+                let profile = match profiles.remove(&user.id) {
+                    Some(signed) => match Profile::verify(signed, &id, &key) {
+                        Ok(profile) => Some(profile),
+                        Err(e) => {
+                            log::error!("invalid profile of user {}: {}", id.to_base58(), e);
+                            None
+                        }
+                    },
+                    None => None,
+                };
                 // fill result into user table
                 users.users.insert(
                     q8id,
@@ -76,6 +131,7 @@ impl Users {
                         name: user.name,
                         verified: user.verified,
                         blocked: user.blocked,
+                        profile,
                     },
                 );
             }
@@ -106,6 +162,7 @@ impl Users {
                 name,
                 verified,
                 blocked,
+                profile: None,
             },
         );
     }
@@ -146,6 +203,140 @@ impl Users {
         return res;
     }
 
+    /// check unknown users and users with outdated profiles
+    ///
+    /// The entries contain the q8id of a user and
+    /// the revision of the profile advertised for it.
+    pub fn get_outdated_ids(entries: &Vec<(Vec<u8>, u32)>) -> Vec<Vec<u8>> {
+        let mut res: Vec<Vec<u8>> = vec![];
+        let users = USERS.get().read().unwrap();
+        for (id, revision) in entries {
+            match users.users.get(id) {
+                Some(user) => {
+                    if *revision > user.profile_revision() {
+                        res.push(id.clone());
+                    }
+                }
+                None => res.push(id.clone()),
+            }
+        }
+        res
+    }
+
+    /// get the profile revisions of all users with a profile
+    ///
+    /// Returns a map with the q8id as key.
+    pub fn get_profile_revisions() -> BTreeMap<Vec<u8>, u32> {
+        let users = USERS.get().read().unwrap();
+        users
+            .users
+            .iter()
+            .filter_map(|(q8id, user)| {
+                user.profile
+                    .as_ref()
+                    .map(|profile| (q8id.clone(), profile.revision))
+            })
+            .collect()
+    }
+
+    /// update the profile of a user with a received signed profile
+    ///
+    /// The profile is only applied, when it is newer than the
+    /// known profile of the user. The user name is replaced
+    /// by the name of the profile.
+    pub fn update_profile(
+        user_id: &PeerId,
+        signed: router_net_proto::UserProfile,
+    ) -> Result<(), String> {
+        let q8id = QaulId::to_q8id(user_id.to_owned());
+
+        // verify the profile
+        let (key, revision) = {
+            let users = USERS.get().read().unwrap();
+            match users.users.get(&q8id) {
+                Some(user) => (user.key.clone(), user.profile_revision()),
+                None => return Err("user is unknown".to_string()),
+            }
+        };
+        let profile = Profile::verify(signed, user_id, &key)?;
+        if profile.revision <= revision {
+            return Ok(());
+        }
+
+        // update the user
+        let user_data;
+        let profile_bytes = profile.signed.encode_to_vec();
+        {
+            let mut users = USERS.get().write().unwrap();
+            let user = match users.users.get_mut(&q8id) {
+                Some(user) => user,
+                None => return Err("user is unknown".to_string()),
+            };
+            user.name = profile.name.clone();
+            user.profile = Some(profile);
+            user_data = UserData {
+                id: user.id.to_bytes(),
+                key: user.key.clone().encode_protobuf(),
+                name: user.name.clone(),
+                verified: user.verified,
+                blocked: user.blocked,
+            };
+        }
+
+        // save to data base
+        DbUsers::add_user(user_data);
+        DbUsers::add_profile(&user_id.to_bytes(), profile_bytes);
+
+        Ok(())
+    }
+
+    /// publish a new profile revision of a user account
+    ///
+    /// Returns the new revision.
+    pub fn set_account_profile(
+        user_account: &UserAccount,
+        name: String,
+        status: String,
+        avatar: Vec<u8>,
+    ) -> Result<u32, String> {
+        let revision = {
+            let users = USERS.get().read().unwrap();
+            match users.users.get(&QaulId::to_q8id(user_account.id)) {
+                Some(user) => user.profile_revision() + 1,
+                None => return Err("user account is unknown".to_string()),
+            }
+        };
+
+        let profile = Profile::create(&user_account.keys, revision, name.clone(), status, avatar)?;
+        Self::update_profile(&user_account.id, profile.signed)?;
+        UserAccounts::set_name(&user_account.id, name);
+
+        Ok(revision)
+    }
+
+    /// mark a known user as verified
+    pub fn set_verified(user_id: &PeerId) {
+        let user_data = {
+            let mut users = USERS.get().write().unwrap();
+            match users.users.get_mut(&QaulId::to_q8id(user_id.to_owned())) {
+                Some(user) => {
+                    user.verified = true;
+                    UserData {
+                        id: user.id.to_bytes(),
+                        key: user.key.clone().encode_protobuf(),
+                        name: user.name.clone(),
+                        verified: user.verified,
+                        blocked: user.blocked,
+                    }
+                }
+                None => return,
+            }
+        };
+
+        // save to data base
+        DbUsers::add_user(user_data);
+    }
+
     /// get the public key of a known user
     pub fn get_pub_key(user_id: &PeerId) -> Option<PublicKey> {
         // get q8id
@@ -165,6 +356,54 @@ impl Users {
         }
     }
 
+    /// check whether a user is blocked
+    ///
+    /// Messages, group invites, files, RTC requests
+    /// and feed posts of blocked users are dropped.
+    pub fn is_blocked(user_id: &PeerId) -> bool {
+        let q8id = QaulId::to_q8id(user_id.to_owned());
+        let store = USERS.get().read().unwrap();
+        match store.users.get(&q8id) {
+            Some(user) => user.blocked,
+            None => false,
+        }
+    }
+
+    /// get the name of a known user
+    pub fn get_name(user_id: &PeerId) -> Option<String> {
+        let q8id = QaulId::to_q8id(user_id.to_owned());
+        let store = USERS.get().read().unwrap();
+        store.users.get(&q8id).map(|user| user.name.clone())
+    }
+
+    /// update the last seen time of users
+    ///
+    /// This function is called by the router events,
+    /// when users become reachable or unreachable.
+    pub fn users_seen(q8ids: &Vec<Vec<u8>>) {
+        let now = Timestamp::get_timestamp();
+        let mut seen = Vec::new();
+        {
+            let mut users = USERS.get().write().unwrap();
+            for q8id in q8ids {
+                if let Some(user) = users.users.get(q8id) {
+                    seen.push(user.id.to_bytes());
+                    users.last_seen.insert(q8id.clone(), now);
+                }
+            }
+        }
+
+        if seen.is_empty() {
+            return;
+        }
+        for user_id in seen {
+            DbUsers::set_last_seen(&user_id, now);
+        }
+        if let Err(e) = DbUsers::get_last_seen_tree().flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+    }
+
     /// get user by q8id
     pub fn get_user_id_by_q8id(q8id: Vec<u8>) -> Option<PeerId> {
         let store = USERS.get().read().unwrap();
@@ -204,6 +443,7 @@ impl Users {
                     id: value.id.to_bytes(),
                     key: value.key.clone().encode_protobuf(),
                     name: value.name.clone(),
+                    profile: value.profile.as_ref().map(|profile| profile.signed.clone()),
                 };
                 users.info.push(user_info);
             }
@@ -220,6 +460,13 @@ impl Users {
 
             if let (Ok(id), Ok(key)) = (id_result, key_result) {
                 Self::add_with_check(id, key, value.name.clone());
+
+                // update the profile of the user
+                if let Some(profile) = &value.profile {
+                    if let Err(e) = Self::update_profile(&id, profile.clone()) {
+                        log::warn!("profile of user {} rejected: {}", id.to_base58(), e);
+                    }
+                }
             }
         }
     }
@@ -287,156 +534,43 @@ impl Users {
             Ok(users) => {
                 match users.message {
                     Some(proto::users::Message::UserRequest(_user_request)) => {
-                        // get users store
-                        let users = USERS.get().read().unwrap();
-
-                        // create empty user list
-                        let mut user_list = proto::UserList { user: Vec::new() };
-
-                        // get user account
-                        if let Some(account) = UserAccounts::get_default_user() {
-                            // get online users
-                            let online_users = super::RoutingTable::get_online_users_info();
-
-                            // fill them into the list
-                            for (id, user) in &users.users {
-                                // get RPC key values
-                                let (_key_type, key_base58) =
-                                    Self::get_protobuf_public_key(user.key.clone());
-
-                                // create group id
-                                let group_id =
-                                    GroupId::from_peers(&account.id, &user.id).to_bytes();
-
-                                let mut connectivity: i32 = 0;
-                                let mut connections: Vec<proto::RoutingTableConnection> =
-                                    Vec::new();
-
-                                if let Some(entries) = online_users.get(id) {
-                                    for entry in entries {
-                                        connections.push(proto::RoutingTableConnection {
-                                            module: entry.module.as_int(),
-                                            hop_count: entry.hc as u32,
-                                            rtt: entry.rtt,
-                                            via: entry.node.to_bytes(),
-                                        });
-                                    }
-                                    connectivity = 1;
-                                }
-
-                                // create user entry message
-                                let user_entry = proto::UserEntry {
-                                    name: user.name.clone(),
-                                    id: user.id.to_bytes(),
-                                    group_id,
-                                    key_base58,
-                                    connectivity: connectivity,
-                                    verified: user.verified,
-                                    blocked: user.blocked,
-                                    connections,
-                                };
-
-                                // add entry to list
-                                user_list.user.push(user_entry);
-                            }
-                        }
-
-                        // create message
-                        let proto_message = proto::Users {
-                            message: Some(proto::users::Message::UserList(user_list)),
-                        };
-
-                        // encode message
-                        let mut buf = Vec::with_capacity(proto_message.encoded_len());
-                        proto_message
-                            .encode(&mut buf)
-                            .expect("Vec<u8> provides capacity as needed");
-
-                        // send message
-                        Rpc::send_message(
-                            buf,
-                            crate::rpc::proto::Modules::Users.into(),
-                            "".to_string(),
-                            Vec::new(),
-                        );
+                        // send all known users
+                        Self::rpc_send_user_list(|_id, _user| true);
                     }
                     Some(proto::users::Message::UserOnlineRequest(_user_online_request)) => {
-                        // get users store
-                        let users = USERS.get().read().unwrap();
-
-                        // get all online user ids by passing last_sent=0
+                        // send all users currently online in the network
                         let online_user_ids = RoutingTable::get_online_user_ids(0);
-
-                        // create empty user list
-                        let mut user_list = proto::UserList { user: Vec::new() };
-
-                        // get user account
-                        if let Some(account) = UserAccounts::get_default_user() {
-                            // get online uses info
-                            let online_users = super::RoutingTable::get_online_users_info();
-                            // fill them into the list
-                            for id in &online_user_ids {
-                                if let Some(user) = users.users.get(id) {
-                                    // get RPC key values
-                                    let (_key_type, key_base58) =
-                                        Self::get_protobuf_public_key(user.key.clone());
-
-                                    // create group id
-                                    let group_id =
-                                        GroupId::from_peers(&account.id, &user.id).to_bytes();
-
-                                    let mut connectivity: i32 = 0;
-                                    let mut connections: Vec<proto::RoutingTableConnection> =
-                                        vec![];
-                                    if online_users.contains_key(id) {
-                                        connectivity = 1;
-                                        let cnns = online_users.get(id).unwrap();
-                                        for cnn in cnns {
-                                            connections.push(proto::RoutingTableConnection {
-                                                module: cnn.module.as_int(),
-                                                hop_count: cnn.hc as u32,
-                                                rtt: cnn.rtt,
-                                                via: cnn.node.to_bytes(),
-                                            });
-                                        }
-                                    }
-
-                                    // create user entry message
-                                    let user_entry = proto::UserEntry {
-                                        name: user.name.clone(),
-                                        id: user.id.to_bytes(),
-                                        group_id,
-                                        key_base58,
-                                        connectivity,
-                                        verified: user.verified,
-                                        blocked: user.blocked,
-                                        connections,
-                                    };
-
-                                    // add entry to list
-                                    user_list.user.push(user_entry);
-                                }
-                            }
-                        }
-
-                        // create message
-                        let proto_message = proto::Users {
-                            message: Some(proto::users::Message::UserList(user_list)),
+                        Self::rpc_send_user_list(|id, _user| online_user_ids.contains(id));
+                    }
+                    Some(proto::users::Message::BlockedUsersRequest(_blocked_users_request)) => {
+                        // send all blocked users
+                        Self::rpc_send_user_list(|_id, user| user.blocked);
+                    }
+                    Some(proto::users::Message::UserQuery(user_query)) => {
+                        Self::rpc_send_user_query(user_query);
+                    }
+                    Some(proto::users::Message::IdentityExportRequest(_export_request)) => {
+                        let result = match UserAccounts::get_by_id(account_id) {
+                            Some(user_account) => IdentityCard::export(&user_account),
+                            None => Err("user account not found".to_string()),
                         };
-
-                        // encode message
-                        let mut buf = Vec::with_capacity(proto_message.encoded_len());
-                        proto_message
-                            .encode(&mut buf)
-                            .expect("Vec<u8> provides capacity as needed");
-
-                        // send message
-                        Rpc::send_message(
-                            buf,
-                            crate::rpc::proto::Modules::Users.into(),
-                            "".to_string(),
-                            Vec::new(),
-                        );
+                        Self::rpc_send_identity_export(&account_id, result);
+                    }
+                    Some(proto::users::Message::IdentityImport(identity_import)) => {
+                        let result = IdentityCard::import(&identity_import.code);
+                        Self::rpc_send_identity_import_result(result);
+                    }
+                    Some(proto::users::Message::UserProfileUpdate(profile_update)) => {
+                        let result = match UserAccounts::get_by_id(account_id) {
+                            Some(user_account) => Self::set_account_profile(
+                                &user_account,
+                                profile_update.name,
+                                profile_update.status,
+                                profile_update.avatar,
+                            ),
+                            None => Err("user account not found".to_string()),
+                        };
+                        Self::rpc_send_profile_update_result(result);
                     }
                     Some(proto::users::Message::UserUpdate(updated_user)) => {
                         log::trace!("UserUpdate protobuf RPC message");
@@ -457,6 +591,7 @@ impl Users {
                                         name: user_result.name.clone(),
                                         verified: updated_user.verified,
                                         blocked: updated_user.blocked,
+                                        profile: user_result.profile.clone(),
                                     };
 
                                     // update list
@@ -518,6 +653,17 @@ impl Users {
                             }
                         }
                     }
+                    Some(proto::users::Message::UserPingRequest(ping_request)) => {
+                        match PeerId::from_bytes(&ping_request.user_id) {
+                            Ok(user_id) => MessagingProbe::start(
+                                account_id,
+                                user_id,
+                                ping_request.count,
+                                ping_request.trace,
+                            ),
+                            Err(e) => log::error!("invalid user id to ping: {}", e),
+                        }
+                    }
                     _ => {}
                 }
             }
@@ -527,6 +673,334 @@ impl Users {
         }
     }
 
+    /// send a list of the users matching the filter to the RPC
+    ///
+    /// The filter is called with the q8id and the user entry
+    /// of every known user.
+    fn rpc_send_user_list<F>(filter: F)
+    where
+        F: Fn(&Vec<u8>, &User) -> bool,
+    {
+        // create empty user list
+        let mut user_list = proto::UserList { user: Vec::new() };
+
+        // get user account
+        if let Some(account) = UserAccounts::get_default_user() {
+            // get online users
+            let online_users = RoutingTable::get_online_users_info();
+
+            let now = Timestamp::get_timestamp();
+
+            // get users store
+            let users = USERS.get().read().unwrap();
+
+            // fill them into the list
+            for (id, user) in users.users.iter().filter(|(id, user)| filter(id, user)) {
+                // online users are seen right now
+                let connections = online_users.get(id);
+                let last_seen = match connections {
+                    Some(_) => now,
+                    None => users.last_seen.get(id).copied().unwrap_or(0),
+                };
+                user_list.user.push(Self::create_user_entry(
+                    &account.id,
+                    user,
+                    connections,
+                    last_seen,
+                ));
+            }
+        }
+
+        // create message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::UserList(user_list)),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// send a page of the users matching the query to the RPC
+    fn rpc_send_user_query(query: proto::UserQuery) {
+        let limit = match query.limit {
+            0 => DEFAULT_QUERY_LIMIT,
+            limit => limit.min(MAX_QUERY_LIMIT),
+        };
+        let verified =
+            proto::UserFilter::try_from(query.verified).unwrap_or(proto::UserFilter::Any);
+        let blocked = proto::UserFilter::try_from(query.blocked).unwrap_or(proto::UserFilter::Any);
+        let online = proto::UserFilter::try_from(query.online).unwrap_or(proto::UserFilter::Any);
+        let search = query.search.to_lowercase();
+
+        let mut result = proto::UserQueryResult {
+            offset: query.offset,
+            limit,
+            total: 0,
+            user: Vec::new(),
+        };
+
+        if let Some(account) = UserAccounts::get_default_user() {
+            let online_users = RoutingTable::get_online_users_info();
+            let now = Timestamp::get_timestamp();
+            let users = USERS.get().read().unwrap();
+
+            // collect the matching users with their last seen time
+            let mut matches: Vec<(&Vec<u8>, &User, u64)> = Vec::new();
+            for (id, user) in users.users.iter() {
+                let connections = online_users.get(id);
+                if !Self::filter_matches(verified, user.verified)
+                    || !Self::filter_matches(blocked, user.blocked)
+                    || !Self::filter_matches(online, connections.is_some())
+                {
+                    continue;
+                }
+                if query.module != 0 {
+                    match connections {
+                        Some(entries)
+                            if entries
+                                .iter()
+                                .any(|entry| entry.module.as_int() == query.module) => {}
+                        _ => continue,
+                    }
+                }
+                if !search.is_empty() && !user.name.to_lowercase().contains(&search) {
+                    continue;
+                }
+
+                // online users are seen right now
+                let last_seen = match connections {
+                    Some(_) => now,
+                    None => users.last_seen.get(id).copied().unwrap_or(0),
+                };
+                matches.push((id, user, last_seen));
+            }
+
+            // sort the users
+            match proto::UserSort::try_from(query.sort) {
+                Ok(proto::UserSort::LastSeen) => {
+                    matches.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.1.name.cmp(&b.1.name)))
+                }
+                _ => matches.sort_by_cached_key(|(id, user, _)| (user.name.to_lowercase(), *id)),
+            }
+
+            // create the page
+            result.total = matches.len() as u64;
+            for (id, user, last_seen) in matches
+                .into_iter()
+                .skip(query.offset as usize)
+                .take(limit as usize)
+            {
+                result.user.push(Self::create_user_entry(
+                    &account.id,
+                    user,
+                    online_users.get(id),
+                    last_seen,
+                ));
+            }
+        }
+
+        // create message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::UserQueryResult(result)),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// check whether a user property matches a query filter
+    fn filter_matches(filter: proto::UserFilter, value: bool) -> bool {
+        match filter {
+            proto::UserFilter::Any => true,
+            proto::UserFilter::Only => value,
+            proto::UserFilter::Exclude => !value,
+        }
+    }
+
+    /// create the RPC user entry of a user
+    ///
+    /// The connections are the routing table entries
+    /// of the user, if the user is online.
+    fn create_user_entry(
+        account_id: &PeerId,
+        user: &User,
+        connections: Option<&Vec<RoutingConnectionEntry>>,
+        last_seen: u64,
+    ) -> proto::UserEntry {
+        // get RPC key values
+        let (_key_type, key_base58) = Self::get_protobuf_public_key(user.key.clone());
+
+        // create group id
+        let group_id = GroupId::from_peers(account_id, &user.id).to_bytes();
+
+        let mut connectivity: i32 = 0;
+        let mut connection_entries: Vec<proto::RoutingTableConnection> = Vec::new();
+
+        if let Some(entries) = connections {
+            for entry in entries {
+                connection_entries.push(proto::RoutingTableConnection {
+                    module: entry.module.as_int(),
+                    hop_count: entry.hc as u32,
+                    rtt: entry.rtt,
+                    via: entry.node.to_bytes(),
+                });
+            }
+            connectivity = 1;
+        }
+
+        // create user entry message
+        let mut user_entry = proto::UserEntry {
+            name: user.name.clone(),
+            id: user.id.to_bytes(),
+            group_id,
+            key_base58,
+            connectivity,
+            verified: user.verified,
+            blocked: user.blocked,
+            connections: connection_entries,
+            status: String::new(),
+            avatar: Vec::new(),
+            profile_revision: 0,
+            last_seen,
+        };
+        if let Some(profile) = &user.profile {
+            user_entry.status = profile.status.clone();
+            user_entry.avatar = profile.avatar.clone();
+            user_entry.profile_revision = profile.revision;
+        }
+
+        user_entry
+    }
+
+    /// send the identity code of a user account to the RPC
+    fn rpc_send_identity_export(account_id: &PeerId, result: Result<String, String>) {
+        let (code, error) = match result {
+            Ok(code) => (code, String::new()),
+            Err(error) => (String::new(), error),
+        };
+
+        // create message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::IdentityExport(
+                proto::IdentityExport {
+                    user_id: account_id.to_bytes(),
+                    code,
+                    error,
+                },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// send the result of an identity import to the RPC
+    fn rpc_send_identity_import_result(result: Result<IdentityCard, String>) {
+        let import_result = match result {
+            Ok(card) => proto::IdentityImportResult {
+                success: true,
+                error: String::new(),
+                user_id: card.id.to_bytes(),
+                name: card.name,
+            },
+            Err(error) => proto::IdentityImportResult {
+                success: false,
+                error,
+                user_id: Vec::new(),
+                name: String::new(),
+            },
+        };
+
+        // create message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::IdentityImportResult(import_result)),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// send the result of a profile update to the RPC
+    fn rpc_send_profile_update_result(result: Result<u32, String>) {
+        let update_result = match result {
+            Ok(revision) => proto::UserProfileUpdateResult {
+                success: true,
+                error: String::new(),
+                revision,
+            },
+            Err(error) => proto::UserProfileUpdateResult {
+                success: false,
+                error,
+                revision: 0,
+            },
+        };
+
+        // create message
+        let proto_message = proto::Users {
+            message: Some(proto::users::Message::UserProfileUpdateResult(
+                update_result,
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
     /// create the qaul RPC definitions of a public key
     ///
     /// Returns a tuple with the key type & the base58 encoded
@@ -559,6 +1033,19 @@ pub struct User {
     pub name: String,
     pub verified: bool,
     pub blocked: bool,
+    /// signed profile of the user
+    pub profile: Option<Profile>,
+}
+
+impl User {
+    /// revision of the user profile,
+    /// 0 if the user has no profile
+    pub fn profile_revision(&self) -> u32 {
+        match &self.profile {
+            Some(profile) => profile.revision,
+            None => 0,
+        }
+    }
 }
 
 /// user structure for storing it in the data base
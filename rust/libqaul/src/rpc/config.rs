@@ -0,0 +1,352 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RPC Configuration Messages
+//!
+//! Read and change the configuration of libqaul at runtime.
+//!
+//! Changes are validated before anything is applied.
+//! Valid changes are applied and saved to the configuration file.
+//! The following changes only take effect after a restart
+//! of libqaul:
+//!
+//! * LAN module: active state & listening addresses
+//! * Internet module: active state, listening state & listening addresses
+//! * routing: neighbour ping period
+//! * maintenance: period
+//!
+//! Errors found in the configuration file on startup are
+//! sent once to the UI and can be requested at any time.
+
+use super::Rpc;
+use crate::connections::internet::Internet;
+use crate::router::Router;
+use crate::storage::configuration::{
+    Configuration, InternetPeer, LinkQualityMetric, MaintenanceOptions, MessagingOptions,
+    RoutingOptions, StorageOptions,
+};
+use crate::utilities::filelogger::FileLogger;
+use libp2p::Multiaddr;
+use prost::Message;
+
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto {
+    include!("qaul.rpc.config.rs");
+}
+
+/// RPC Configuration Module
+pub struct Config {}
+
+impl Config {
+    /// Process incoming RPC request messages for configuration module
+    pub fn rpc(data: Vec<u8>, internet_opt: Option<&mut Internet>) {
+        match proto::Config::decode(&data[..]) {
+            Ok(config) => match config.message {
+                Some(proto::config::Message::ConfigRequest(_config_request)) => {
+                    let configuration = Self::to_proto(&Configuration::get());
+
+                    Self::rpc_send_message(proto::Config {
+                        message: Some(proto::config::Message::ConfigResponse(
+                            proto::ConfigResponse {
+                                configuration: Some(configuration),
+                            },
+                        )),
+                    });
+                }
+                Some(proto::config::Message::ConfigUpdate(config_update)) => {
+                    let result = Self::update(config_update, internet_opt);
+
+                    Self::rpc_send_message(proto::Config {
+                        message: Some(proto::config::Message::ConfigUpdateResult(result)),
+                    });
+                }
+                Some(proto::config::Message::StartupErrorsRequest(_startup_errors_request)) => {
+                    Self::rpc_send_startup_errors(Configuration::startup_errors());
+                }
+                _ => {
+                    log::error!("Unhandled RPC Config Message");
+                }
+            },
+            Err(e) => {
+                log::error!("{:?}", e);
+            }
+        }
+    }
+
+    /// Report the errors of the configuration file
+    ///
+    /// The errors found on startup are logged and sent
+    /// to the UI via RPC.
+    /// This function is called once the logger is initialized.
+    pub fn report_startup_errors() {
+        let errors = Configuration::startup_errors();
+        if errors.is_empty() {
+            return;
+        }
+
+        for error in &errors {
+            log::error!("configuration error: {}", error);
+        }
+
+        Self::rpc_send_startup_errors(errors);
+    }
+
+    /// send the startup errors via RPC
+    fn rpc_send_startup_errors(errors: Vec<String>) {
+        Self::rpc_send_message(proto::Config {
+            message: Some(proto::config::Message::StartupErrors(
+                proto::StartupErrors { errors },
+            )),
+        });
+    }
+
+    /// validate, apply and save a configuration update
+    fn update(
+        update: proto::ConfigUpdate,
+        internet_opt: Option<&mut Internet>,
+    ) -> proto::ConfigUpdateResult {
+        let current = Configuration::get().clone();
+        let mut config = current.clone();
+        let mut errors: Vec<String> = Vec::new();
+
+        // create updated configuration
+        if let Some(lan) = update.lan {
+            config.lan.active = lan.active;
+            config.lan.listen = lan.listen;
+        }
+        if let Some(internet) = update.internet {
+            config.internet.active = internet.active;
+            config.internet.do_listen = internet.do_listen;
+            config.internet.listen = internet.listen;
+            config.internet.peers = internet
+                .peers
+                .into_iter()
+                .map(|peer| InternetPeer {
+                    address: peer.address,
+                    name: peer.name,
+                    enabled: peer.enabled,
+                })
+                .collect();
+        }
+        for account_update in update.user_accounts {
+            match config
+                .user_accounts
+                .iter_mut()
+                .find(|account| account.id == account_update.id)
+            {
+                Some(account) => {
+                    if let Some(storage) = account_update.storage {
+                        account.storage = StorageOptions {
+                            users: storage.users,
+                            size_total: storage.size_total,
+                            chat_retention_days: storage.chat_retention_days,
+                            file_store_size: storage.file_store_size,
+                        };
+                    }
+                }
+                None => errors.push(format!("unknown user account {}", account_update.id)),
+            }
+        }
+        if let Some(debug) = update.debug {
+            config.debug.log = debug.log;
+        }
+        if let Some(routing) = update.routing {
+            let link_quality_metric =
+                match proto::LinkQualityMetric::try_from(routing.link_quality_metric) {
+                    Ok(proto::LinkQualityMetric::HopCount) => LinkQualityMetric::HopCount,
+                    Ok(proto::LinkQualityMetric::Etx) => LinkQualityMetric::Etx,
+                    Ok(proto::LinkQualityMetric::Bandwidth) => LinkQualityMetric::Bandwidth,
+                    Err(_) => {
+                        errors.push(format!(
+                            "routing.link_quality_metric: unknown metric {}",
+                            routing.link_quality_metric
+                        ));
+                        config.routing.link_quality_metric
+                    }
+                };
+            config.routing = RoutingOptions {
+                sending_table_period: routing.sending_table_period,
+                ping_neighbour_period: routing.ping_neighbour_period,
+                hop_count_penalty: routing.hop_count_penalty,
+                maintain_period_limit: routing.maintain_period_limit,
+                link_quality_metric,
+                module_preference: config.routing.module_preference.clone(),
+            };
+        }
+        if let Some(maintenance) = update.maintenance {
+            config.maintenance = MaintenanceOptions {
+                period: maintenance.period,
+                feed_max_messages: maintenance.feed_max_messages,
+            };
+        }
+        if let Some(messaging) = update.messaging {
+            config.messaging = MessagingOptions {
+                multipath: messaging.multipath,
+            };
+        }
+
+        // validate updated configuration
+        errors.append(&mut config.validate());
+        if !errors.is_empty() {
+            for error in &errors {
+                log::error!("configuration update: {}", error);
+            }
+            return proto::ConfigUpdateResult {
+                success: false,
+                errors,
+                restart_required: false,
+                configuration: Some(Self::to_proto(&current)),
+            };
+        }
+
+        // check which changes need a restart
+        let restart_required = current.lan.active != config.lan.active
+            || current.lan.listen != config.lan.listen
+            || current.internet.active != config.internet.active
+            || current.internet.do_listen != config.internet.do_listen
+            || current.internet.listen != config.internet.listen
+            || current.routing.ping_neighbour_period != config.routing.ping_neighbour_period
+            || current.maintenance.period != config.maintenance.period;
+
+        // save configuration
+        *Configuration::get_mut() = config.clone();
+        Configuration::save();
+
+        // apply changes
+        if current.debug.log != config.debug.log {
+            if config.debug.log {
+                FileLogger::enable(true);
+                log::info!("starting debug log..");
+            } else {
+                log::info!("stop debug log..");
+                FileLogger::enable(false);
+            }
+        }
+        Router::set_configuration(config.routing.clone());
+        if let Some(internet) = internet_opt {
+            Self::update_peers(&current.internet.peers, &config.internet.peers, internet);
+        }
+
+        proto::ConfigUpdateResult {
+            success: true,
+            errors,
+            restart_required,
+            configuration: Some(Self::to_proto(&config)),
+        }
+    }
+
+    /// connect to newly enabled internet peers
+    /// and disconnect the disabled or removed ones
+    fn update_peers(previous: &[InternetPeer], peers: &[InternetPeer], internet: &mut Internet) {
+        let is_enabled = |list: &[InternetPeer], address: &String| {
+            list.iter()
+                .any(|peer| peer.address == *address && peer.enabled)
+        };
+
+        // disconnect peers
+        for peer in previous {
+            if peer.enabled && !is_enabled(peers, &peer.address) {
+                if let Some(peer_id) = Internet::peerid_from_address(peer.address.clone()) {
+                    if internet.swarm.is_connected(&peer_id) {
+                        if let Err(_) = internet.swarm.disconnect_peer_id(peer_id) {}
+                    }
+                }
+            }
+        }
+
+        // connect peers
+        for peer in peers {
+            if peer.enabled && !is_enabled(previous, &peer.address) {
+                let mut connected = false;
+                if let Some(peer_id) = Internet::peerid_from_address(peer.address.clone()) {
+                    connected = internet.swarm.is_connected(&peer_id);
+                }
+                if connected == false {
+                    if let Ok(address) = peer.address.parse::<Multiaddr>() {
+                        Internet::peer_dial(address, &mut internet.swarm);
+                    }
+                }
+            }
+        }
+    }
+
+    /// create the protobuf message of a configuration
+    ///
+    /// The private keys are not included.
+    fn to_proto(config: &Configuration) -> proto::Configuration {
+        proto::Configuration {
+            node_id: config.node.id.clone(),
+            lan: Some(proto::LanConfig {
+                active: config.lan.active,
+                listen: config.lan.listen.clone(),
+            }),
+            internet: Some(proto::InternetConfig {
+                active: config.internet.active,
+                peers: config
+                    .internet
+                    .peers
+                    .iter()
+                    .map(|peer| proto::InternetPeerConfig {
+                        address: peer.address.clone(),
+                        name: peer.name.clone(),
+                        enabled: peer.enabled,
+                    })
+                    .collect(),
+                do_listen: config.internet.do_listen,
+                listen: config.internet.listen.clone(),
+            }),
+            user_accounts: config
+                .user_accounts
+                .iter()
+                .map(|account| proto::UserAccountConfig {
+                    name: account.name.clone(),
+                    id: account.id.clone(),
+                    storage: Some(proto::StorageConfig {
+                        users: account.storage.users.clone(),
+                        size_total: account.storage.size_total,
+                        chat_retention_days: account.storage.chat_retention_days,
+                        file_store_size: account.storage.file_store_size,
+                    }),
+                })
+                .collect(),
+            debug: Some(proto::DebugConfig {
+                log: config.debug.log,
+            }),
+            routing: Some(proto::RoutingConfig {
+                sending_table_period: config.routing.sending_table_period,
+                ping_neighbour_period: config.routing.ping_neighbour_period,
+                hop_count_penalty: config.routing.hop_count_penalty,
+                maintain_period_limit: config.routing.maintain_period_limit,
+                link_quality_metric: match config.routing.link_quality_metric {
+                    LinkQualityMetric::HopCount => proto::LinkQualityMetric::HopCount,
+                    LinkQualityMetric::Etx => proto::LinkQualityMetric::Etx,
+                    LinkQualityMetric::Bandwidth => proto::LinkQualityMetric::Bandwidth,
+                } as i32,
+            }),
+            encryption_active: config.encryption.active,
+            maintenance: Some(proto::MaintenanceConfig {
+                period: config.maintenance.period,
+                feed_max_messages: config.maintenance.feed_max_messages,
+            }),
+            messaging: Some(proto::MessagingConfig {
+                multipath: config.messaging.multipath,
+            }),
+        }
+    }
+
+    /// encode and send a configuration RPC message
+    fn rpc_send_message(message: proto::Config) {
+        let mut buf = Vec::with_capacity(message.encoded_len());
+        message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Config.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
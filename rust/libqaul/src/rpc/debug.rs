@@ -6,6 +6,7 @@
 //! Messages to debug libqaul
 
 use super::Rpc;
+use crate::storage::check::StorageCheck;
 use crate::storage::configuration::Configuration;
 use crate::storage::Storage;
 use crate::utilities::filelogger::FileLogger;
@@ -95,6 +96,49 @@ impl Debug {
                             Vec::new(),
                         );
                     }
+                    Some(proto::debug::Message::StorageCheckRequest(storage_check_request)) => {
+                        // check the data bases
+                        let report = StorageCheck::check(storage_check_request.repair);
+
+                        // create storage check response message
+                        let problems = report
+                            .problems
+                            .into_iter()
+                            .map(|problem| proto::StorageProblem {
+                                tree: problem.tree,
+                                user_account: match problem.account_id {
+                                    Some(account_id) => account_id.to_bytes(),
+                                    None => Vec::new(),
+                                },
+                                key: problem.key,
+                                description: problem.description,
+                                repairable: problem.repairable,
+                                repaired: problem.repaired,
+                            })
+                            .collect();
+                        let proto_message = proto::Debug {
+                            message: Some(proto::debug::Message::StorageCheckResponse(
+                                proto::StorageCheckResponse {
+                                    entries_checked: report.entries_checked,
+                                    problems,
+                                },
+                            )),
+                        };
+
+                        // encode message
+                        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+                        proto_message
+                            .encode(&mut buf)
+                            .expect("Vec<u8> provides capacity as needed");
+
+                        // send message
+                        Rpc::send_message(
+                            buf,
+                            crate::rpc::proto::Modules::Debug.into(),
+                            "".to_string(),
+                            Vec::new(),
+                        );
+                    }
                     _ => {
                         log::error!("Unhandled RPC Debug Message");
                     }
@@ -6,15 +6,18 @@
 //! The RPC messages are defined in the protobuf format.
 //! The format is then translated to rust program code.
 
+pub mod config;
 pub mod debug;
+pub mod storage;
 pub mod sys;
 
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
-use state::InitCell;
 use std::sync::RwLock;
 
 use prost::Message;
 
+use self::config::Config;
+use self::storage::Storage;
 use crate::connections::ble::Ble;
 use crate::connections::Connections;
 use crate::connections::{internet::Internet, lan::Lan};
@@ -24,10 +27,12 @@ use crate::router::users::Users;
 use crate::router::Router;
 use crate::services::chat::Chat;
 use crate::services::chat::ChatFile;
+use crate::services::contacts::Contacts;
 use crate::services::dtn::Dtn;
 use crate::services::feed::Feed;
 use crate::services::group::Group;
 use crate::services::rtc::Rtc;
+use crate::utilities::instance::InstanceCell;
 use debug::Debug;
 
 /// Import protobuf message definition generated by
@@ -48,14 +53,14 @@ pub struct MessageCounter {
     count: i32,
 }
 /// state of message counter
-static EXTERN_SEND_COUNT: InitCell<RwLock<MessageCounter>> = InitCell::new();
+static EXTERN_SEND_COUNT: InstanceCell<RwLock<MessageCounter>> = InstanceCell::new();
 
 /// receiving end of the mpsc channel
-static EXTERN_RECEIVE: InitCell<Receiver<Vec<u8>>> = InitCell::new();
+static EXTERN_RECEIVE: InstanceCell<Receiver<Vec<u8>>> = InstanceCell::new();
 /// sending end of the mpsc channel
-static EXTERN_SEND: InitCell<Sender<Vec<u8>>> = InitCell::new();
+static EXTERN_SEND: InstanceCell<Sender<Vec<u8>>> = InstanceCell::new();
 /// sending end of th mpsc channel for libqaul to send
-static LIBQAUL_SEND: InitCell<Sender<Vec<u8>>> = InitCell::new();
+static LIBQAUL_SEND: InstanceCell<Sender<Vec<u8>>> = InstanceCell::new();
 
 /// Handling of RPC messages of libqaul
 pub struct Rpc {}
@@ -185,6 +190,18 @@ impl Rpc {
                         log::trace!("Message Modules::Group received");
                         Dtn::rpc(message.data, message.user_id);
                     }
+                    Ok(Modules::Config) => {
+                        Config::rpc(message.data, internet);
+                    }
+                    Ok(Modules::Upgrade) => {
+                        log::error!("Message Modules::Upgrade received");
+                    }
+                    Ok(Modules::Storage) => {
+                        Storage::rpc(message.data);
+                    }
+                    Ok(Modules::Contacts) => {
+                        Contacts::rpc(message.data, message.user_id);
+                    }
                     Ok(Modules::None) => {
                         log::error!("Message Modules::None received");
                     }
@@ -62,6 +62,10 @@ pub struct RoutingInfoEntry {
     /// propagation id
     #[prost(uint32, tag = "5")]
     pub pgid: u32,
+    /// revision of the user profile,
+    /// 0 if the user has no profile
+    #[prost(uint32, tag = "6")]
+    pub profile_revision: u32,
 }
 /// User information table
 #[derive(Clone, PartialEq, ::prost::Message)]
@@ -89,6 +93,65 @@ pub struct UserInfo {
     /// user name
     #[prost(string, tag = "3")]
     pub name: ::prost::alloc::string::String,
+    /// signed user profile
+    #[prost(message, optional, tag = "4")]
+    pub profile: ::core::option::Option<UserProfile>,
+}
+/// Signed user profile
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserProfile {
+    /// encoded UserProfileContent
+    #[prost(bytes = "vec", tag = "1")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+    /// signature of the content by the user's key
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+/// User profile content
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserProfileContent {
+    /// user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    /// profile revision,
+    /// increased with every update of the profile
+    #[prost(uint32, tag = "2")]
+    pub revision: u32,
+    /// user name
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
+    /// status text
+    #[prost(string, tag = "4")]
+    pub status: ::prost::alloc::string::String,
+    /// small avatar image
+    #[prost(bytes = "vec", tag = "5")]
+    pub avatar: ::prost::alloc::vec::Vec<u8>,
+}
+/// Signed identity card
+///
+/// Exchanged out of band, e.g. as text or QR code,
+/// to add and verify a user before meeting on the network.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IdentityCard {
+    /// encoded IdentityCardContent
+    #[prost(bytes = "vec", tag = "1")]
+    pub content: ::prost::alloc::vec::Vec<u8>,
+    /// signature of the content by the user's key
+    #[prost(bytes = "vec", tag = "2")]
+    pub signature: ::prost::alloc::vec::Vec<u8>,
+}
+/// Identity card content
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IdentityCardContent {
+    /// user id
+    #[prost(bytes = "vec", tag = "1")]
+    pub id: ::prost::alloc::vec::Vec<u8>,
+    /// protobuf encoded public key of the user
+    #[prost(bytes = "vec", tag = "2")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    /// user name
+    #[prost(string, tag = "3")]
+    pub name: ::prost::alloc::string::String,
 }
 /// List of feed ID's
 #[derive(Clone, PartialEq, ::prost::Message)]
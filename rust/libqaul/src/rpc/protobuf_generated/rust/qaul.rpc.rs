@@ -64,6 +64,15 @@ pub enum Modules {
     Rtc = 13,
     /// Delay Tolerant Networking
     Dtn = 14,
+    /// libqaul configuration
+    Config = 15,
+    /// upgrade of the stored data
+    /// to a new libqaul version
+    Upgrade = 16,
+    /// storage usage accounting
+    Storage = 17,
+    /// local contact book
+    Contacts = 18,
 }
 impl Modules {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -87,6 +96,10 @@ impl Modules {
             Self::Ble => "BLE",
             Self::Rtc => "RTC",
             Self::Dtn => "DTN",
+            Self::Config => "CONFIG",
+            Self::Upgrade => "UPGRADE",
+            Self::Storage => "STORAGE",
+            Self::Contacts => "CONTACTS",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -107,6 +120,10 @@ impl Modules {
             "BLE" => Some(Self::Ble),
             "RTC" => Some(Self::Rtc),
             "DTN" => Some(Self::Dtn),
+            "CONFIG" => Some(Self::Config),
+            "UPGRADE" => Some(Self::Upgrade),
+            "STORAGE" => Some(Self::Storage),
+            "CONTACTS" => Some(Self::Contacts),
             _ => None,
         }
     }
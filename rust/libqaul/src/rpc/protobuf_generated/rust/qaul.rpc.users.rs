@@ -2,7 +2,7 @@
 /// users rpc message container
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Users {
-    #[prost(oneof = "users::Message", tags = "1, 2, 3, 4, 5, 6")]
+    #[prost(oneof = "users::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17")]
     pub message: ::core::option::Option<users::Message>,
 }
 /// Nested message and enum types in `Users`.
@@ -53,6 +53,67 @@ pub mod users {
         /// 12142 31101 09874 34545
         #[prost(message, tag = "6")]
         SecurityNumberResponse(super::SecurityNumberResponse),
+        /// User Ping Request
+        ///
+        /// Tests the reachability of a user by sending
+        /// probes through the messaging layer.
+        #[prost(message, tag = "7")]
+        UserPingRequest(super::UserPingRequest),
+        /// User Ping Result
+        ///
+        /// Libqaul's response containing the statistics
+        /// of all probes sent to the user.
+        #[prost(message, tag = "8")]
+        UserPingResult(super::UserPingResult),
+        /// Blocked Users Request
+        ///
+        /// Returns a user list of all blocked users.
+        #[prost(message, tag = "9")]
+        BlockedUsersRequest(super::BlockedUsersRequest),
+        /// User Profile Update
+        ///
+        /// Updates the profile of the user account
+        /// and propagates it to the network.
+        #[prost(message, tag = "10")]
+        UserProfileUpdate(super::UserProfileUpdate),
+        /// User Profile Update Result
+        ///
+        /// Libqaul's response to the profile update.
+        #[prost(message, tag = "11")]
+        UserProfileUpdateResult(super::UserProfileUpdateResult),
+        /// User Query
+        ///
+        /// Requests a page of the users matching
+        /// the search and filters of the query.
+        #[prost(message, tag = "12")]
+        UserQuery(super::UserQuery),
+        /// User Query Result
+        ///
+        /// Libqaul's response to the user query.
+        #[prost(message, tag = "13")]
+        UserQueryResult(super::UserQueryResult),
+        /// Identity Export Request
+        ///
+        /// Requests the signed identity code
+        /// of the user account.
+        #[prost(message, tag = "14")]
+        IdentityExportRequest(super::IdentityExportRequest),
+        /// Identity Export
+        ///
+        /// Libqaul's response containing the identity code.
+        #[prost(message, tag = "15")]
+        IdentityExport(super::IdentityExport),
+        /// Identity Import
+        ///
+        /// Imports the identity code of another user,
+        /// the user is added and marked as verified.
+        #[prost(message, tag = "16")]
+        IdentityImport(super::IdentityImport),
+        /// Identity Import Result
+        ///
+        /// Libqaul's response to the identity import.
+        #[prost(message, tag = "17")]
+        IdentityImportResult(super::IdentityImportResult),
     }
 }
 /// UI request for some users
@@ -61,6 +122,9 @@ pub struct UserRequest {}
 /// UI request for some online users
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct UserOnlineRequest {}
+/// UI request for all blocked users
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct BlockedUsersRequest {}
 /// user list
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct UserList {
@@ -97,6 +161,20 @@ pub struct UserEntry {
     /// RoutingTableConnection connections = 11;
     #[prost(message, repeated, tag = "11")]
     pub connections: ::prost::alloc::vec::Vec<RoutingTableConnection>,
+    /// status text of the user profile
+    #[prost(string, tag = "12")]
+    pub status: ::prost::alloc::string::String,
+    /// avatar image of the user profile
+    #[prost(bytes = "vec", tag = "13")]
+    pub avatar: ::prost::alloc::vec::Vec<u8>,
+    /// revision of the user profile,
+    /// 0 if the user has no profile
+    #[prost(uint32, tag = "14")]
+    pub profile_revision: u32,
+    /// time when the user was last seen in the network,
+    /// 0 if the user was never seen
+    #[prost(uint64, tag = "15")]
+    pub last_seen: u64,
 }
 /// Routing table connection entry.
 /// This message contains a connection to a specific user.
@@ -137,6 +215,181 @@ pub struct SecurityNumberResponse {
     #[prost(uint32, repeated, tag = "3")]
     pub security_number_blocks: ::prost::alloc::vec::Vec<u32>,
 }
+/// user ping request
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserPingRequest {
+    /// user id of the probed user
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// number of probes to send
+    #[prost(uint32, tag = "2")]
+    pub count: u32,
+    /// trace the route of the probes
+    #[prost(bool, tag = "3")]
+    pub trace: bool,
+}
+/// user ping result
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserPingResult {
+    /// user id of the probed user
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// number of sent probes
+    #[prost(uint32, tag = "2")]
+    pub sent: u32,
+    /// number of received probe replies
+    #[prost(uint32, tag = "3")]
+    pub received: u32,
+    /// ratio of lost probes
+    /// from 0.0 (no loss) to 1.0 (all probes lost)
+    #[prost(float, tag = "4")]
+    pub loss: f32,
+    /// minimal round trip time in milli seconds
+    #[prost(uint32, tag = "5")]
+    pub rtt_min: u32,
+    /// average round trip time in milli seconds
+    #[prost(uint32, tag = "6")]
+    pub rtt_avg: u32,
+    /// maximal round trip time in milli seconds
+    #[prost(uint32, tag = "7")]
+    pub rtt_max: u32,
+    /// route of the probe requests,
+    /// only filled for traced probes
+    #[prost(message, repeated, tag = "8")]
+    pub forward_route: ::prost::alloc::vec::Vec<UserPingHop>,
+    /// route of the probe replies,
+    /// only filled for traced probes
+    #[prost(message, repeated, tag = "9")]
+    pub return_route: ::prost::alloc::vec::Vec<UserPingHop>,
+}
+/// hop of a traced probe route
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserPingHop {
+    /// node id of the hop
+    #[prost(bytes = "vec", tag = "1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// connection module via which the probe was received
+    #[prost(enumeration = "ConnectionModule", tag = "2")]
+    pub module: i32,
+    /// round trip time to the previous hop in micro seconds
+    #[prost(uint32, tag = "3")]
+    pub rtt: u32,
+}
+/// user profile update
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserProfileUpdate {
+    /// user name
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// status text
+    #[prost(string, tag = "2")]
+    pub status: ::prost::alloc::string::String,
+    /// small avatar image
+    #[prost(bytes = "vec", tag = "3")]
+    pub avatar: ::prost::alloc::vec::Vec<u8>,
+}
+/// user profile update result
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserProfileUpdateResult {
+    /// the profile was updated
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message if the update failed
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// new revision of the profile
+    #[prost(uint32, tag = "3")]
+    pub revision: u32,
+}
+/// user query
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserQuery {
+    /// number of matching users to skip
+    #[prost(uint32, tag = "1")]
+    pub offset: u32,
+    /// maximal number of users to return,
+    /// the default page size is used if 0
+    #[prost(uint32, tag = "2")]
+    pub limit: u32,
+    /// case insensitive part of the user name,
+    /// all users match if empty
+    #[prost(string, tag = "3")]
+    pub search: ::prost::alloc::string::String,
+    /// filter by verification
+    #[prost(enumeration = "UserFilter", tag = "4")]
+    pub verified: i32,
+    /// filter by blocking
+    #[prost(enumeration = "UserFilter", tag = "5")]
+    pub blocked: i32,
+    /// filter by reachability
+    #[prost(enumeration = "UserFilter", tag = "6")]
+    pub online: i32,
+    /// only return the users reachable via this connection module,
+    /// all users match if NONE
+    #[prost(enumeration = "ConnectionModule", tag = "7")]
+    pub module: i32,
+    /// sort order of the users
+    #[prost(enumeration = "UserSort", tag = "8")]
+    pub sort: i32,
+}
+/// user query result
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserQueryResult {
+    /// offset of the page
+    #[prost(uint32, tag = "1")]
+    pub offset: u32,
+    /// page size
+    #[prost(uint32, tag = "2")]
+    pub limit: u32,
+    /// total number of users matching the query
+    #[prost(uint64, tag = "3")]
+    pub total: u64,
+    /// users of the page
+    #[prost(message, repeated, tag = "4")]
+    pub user: ::prost::alloc::vec::Vec<UserEntry>,
+}
+/// identity export request
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct IdentityExportRequest {}
+/// identity export
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IdentityExport {
+    /// user id of the user account
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// signed identity code
+    ///
+    /// The code is a compact text, that can be shared
+    /// out of band as text or QR code.
+    #[prost(string, tag = "2")]
+    pub code: ::prost::alloc::string::String,
+    /// error message if the export failed
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
+}
+/// identity import
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IdentityImport {
+    /// signed identity code of the user
+    #[prost(string, tag = "1")]
+    pub code: ::prost::alloc::string::String,
+}
+/// identity import result
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct IdentityImportResult {
+    /// the identity was imported
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// error message if the import failed
+    #[prost(string, tag = "2")]
+    pub error: ::prost::alloc::string::String,
+    /// user id of the imported user
+    #[prost(bytes = "vec", tag = "3")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// user name of the imported user
+    #[prost(string, tag = "4")]
+    pub name: ::prost::alloc::string::String,
+}
 /// Connection modules
 #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
 #[repr(i32)]
@@ -146,6 +399,7 @@ pub enum ConnectionModule {
     Internet = 2,
     Ble = 3,
     Local = 4,
+    Virtual = 5,
 }
 impl ConnectionModule {
     /// String value of the enum field names used in the ProtoBuf definition.
@@ -159,6 +413,7 @@ impl ConnectionModule {
             Self::Internet => "INTERNET",
             Self::Ble => "BLE",
             Self::Local => "LOCAL",
+            Self::Virtual => "VIRTUAL",
         }
     }
     /// Creates an enum from field names used in the ProtoBuf definition.
@@ -169,6 +424,7 @@ impl ConnectionModule {
             "INTERNET" => Some(Self::Internet),
             "BLE" => Some(Self::Ble),
             "LOCAL" => Some(Self::Local),
+            "VIRTUAL" => Some(Self::Virtual),
             _ => None,
         }
     }
@@ -209,3 +465,65 @@ impl Connectivity {
         }
     }
 }
+/// filter of a user query
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum UserFilter {
+    /// all users match
+    Any = 0,
+    /// only the users with the property match
+    Only = 1,
+    /// only the users without the property match
+    Exclude = 2,
+}
+impl UserFilter {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Any => "ANY",
+            Self::Only => "ONLY",
+            Self::Exclude => "EXCLUDE",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "ANY" => Some(Self::Any),
+            "ONLY" => Some(Self::Only),
+            "EXCLUDE" => Some(Self::Exclude),
+            _ => None,
+        }
+    }
+}
+/// sort order of a user query
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum UserSort {
+    /// alphabetically by user name
+    Name = 0,
+    /// most recently seen users first
+    LastSeen = 1,
+}
+impl UserSort {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Name => "NAME",
+            Self::LastSeen => "LAST_SEEN",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "NAME" => Some(Self::Name),
+            "LAST_SEEN" => Some(Self::LastSeen),
+            _ => None,
+        }
+    }
+}
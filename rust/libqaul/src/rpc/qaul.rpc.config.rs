@@ -0,0 +1,298 @@
+// This file is @generated by prost-build.
+/// Libqaul RPC Configuration Messages
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Config {
+    /// message type
+    #[prost(oneof = "config::Message", tags = "1, 2, 3, 4, 5, 6")]
+    pub message: ::core::option::Option<config::Message>,
+}
+/// Nested message and enum types in `Config`.
+pub mod config {
+    /// message type
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// request the current configuration
+        #[prost(message, tag = "1")]
+        ConfigRequest(super::ConfigRequest),
+        /// current configuration of libqaul
+        #[prost(message, tag = "2")]
+        ConfigResponse(super::ConfigResponse),
+        /// change the configuration
+        #[prost(message, tag = "3")]
+        ConfigUpdate(super::ConfigUpdate),
+        /// result of the configuration change
+        #[prost(message, tag = "4")]
+        ConfigUpdateResult(super::ConfigUpdateResult),
+        /// request the errors of the configuration file
+        #[prost(message, tag = "5")]
+        StartupErrorsRequest(super::StartupErrorsRequest),
+        /// errors of the configuration file
+        #[prost(message, tag = "6")]
+        StartupErrors(super::StartupErrors),
+    }
+}
+/// Request the Configuration
+///
+/// libqaul answers with a ConfigResponse message.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct ConfigRequest {}
+/// Configuration Response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigResponse {
+    /// current configuration
+    #[prost(message, optional, tag = "1")]
+    pub configuration: ::core::option::Option<Configuration>,
+}
+/// Configuration of libqaul
+///
+/// The private keys are never sent via RPC.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Configuration {
+    /// node ID
+    #[prost(string, tag = "1")]
+    pub node_id: ::prost::alloc::string::String,
+    /// LAN connection module
+    #[prost(message, optional, tag = "2")]
+    pub lan: ::core::option::Option<LanConfig>,
+    /// Internet overlay connection module
+    #[prost(message, optional, tag = "3")]
+    pub internet: ::core::option::Option<InternetConfig>,
+    /// local user accounts
+    #[prost(message, repeated, tag = "4")]
+    pub user_accounts: ::prost::alloc::vec::Vec<UserAccountConfig>,
+    /// debugging options
+    #[prost(message, optional, tag = "5")]
+    pub debug: ::core::option::Option<DebugConfig>,
+    /// routing options
+    #[prost(message, optional, tag = "6")]
+    pub routing: ::core::option::Option<RoutingConfig>,
+    /// the storage is encrypted
+    #[prost(bool, tag = "7")]
+    pub encryption_active: bool,
+    /// storage maintenance options
+    #[prost(message, optional, tag = "8")]
+    pub maintenance: ::core::option::Option<MaintenanceConfig>,
+    /// messaging options
+    #[prost(message, optional, tag = "9")]
+    pub messaging: ::core::option::Option<MessagingConfig>,
+}
+/// LAN Connection Module Configuration
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct LanConfig {
+    /// module is active
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    /// listening multiaddresses
+    #[prost(string, repeated, tag = "2")]
+    pub listen: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Internet Overlay Connection Module Configuration
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InternetConfig {
+    /// module is active
+    #[prost(bool, tag = "1")]
+    pub active: bool,
+    /// peers to connect to
+    #[prost(message, repeated, tag = "2")]
+    pub peers: ::prost::alloc::vec::Vec<InternetPeerConfig>,
+    /// listen for incoming connections
+    #[prost(bool, tag = "3")]
+    pub do_listen: bool,
+    /// listening multiaddresses
+    #[prost(string, repeated, tag = "4")]
+    pub listen: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Internet Peer
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct InternetPeerConfig {
+    /// multiaddress of the peer
+    #[prost(string, tag = "1")]
+    pub address: ::prost::alloc::string::String,
+    /// name of the peer
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// connect to this peer
+    #[prost(bool, tag = "3")]
+    pub enabled: bool,
+}
+/// User Account Configuration
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UserAccountConfig {
+    /// name of the user account
+    #[prost(string, tag = "1")]
+    pub name: ::prost::alloc::string::String,
+    /// user ID, base58 encoded
+    #[prost(string, tag = "2")]
+    pub id: ::prost::alloc::string::String,
+    /// storage options of this account
+    #[prost(message, optional, tag = "3")]
+    pub storage: ::core::option::Option<StorageConfig>,
+}
+/// Storage Options of a User Account
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorageConfig {
+    /// storage node users, base58 encoded user IDs
+    #[prost(string, repeated, tag = "1")]
+    pub users: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// total storage size in MB
+    #[prost(uint32, tag = "2")]
+    pub size_total: u32,
+    /// chat messages older than this number of days are deleted,
+    /// 0 keeps all chat messages
+    #[prost(uint32, tag = "3")]
+    pub chat_retention_days: u32,
+    /// maximal size of the file store in MB,
+    /// 0 means no limit
+    #[prost(uint32, tag = "4")]
+    pub file_store_size: u32,
+}
+/// Debugging Options
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct DebugConfig {
+    /// log to file
+    #[prost(bool, tag = "1")]
+    pub log: bool,
+}
+/// Routing Options
+///
+/// All units are seconds.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct RoutingConfig {
+    /// interval in which the routing table is sent to the neighbours
+    #[prost(uint64, tag = "1")]
+    pub sending_table_period: u64,
+    /// interval in which the neighbours are pinged
+    #[prost(uint64, tag = "2")]
+    pub ping_neighbour_period: u64,
+    /// hop count penalty
+    #[prost(uint64, tag = "3")]
+    pub hop_count_penalty: u64,
+    /// how long a route is kept until it is removed
+    #[prost(uint64, tag = "4")]
+    pub maintain_period_limit: u64,
+    /// metric to calculate the link quality of a route
+    #[prost(enumeration = "LinkQualityMetric", tag = "5")]
+    pub link_quality_metric: i32,
+}
+/// Storage Maintenance Options
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct MaintenanceConfig {
+    /// interval of the storage maintenance in seconds
+    #[prost(uint64, tag = "1")]
+    pub period: u64,
+    /// maximal number of feed messages,
+    /// 0 keeps all feed messages
+    #[prost(uint64, tag = "2")]
+    pub feed_max_messages: u64,
+}
+/// Messaging Options
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct MessagingConfig {
+    /// send important messages over the two best routes
+    /// and fail over immediately when a neighbour disappears
+    #[prost(bool, tag = "1")]
+    pub multipath: bool,
+}
+/// Configuration Update
+///
+/// Only the sections that are set are changed.
+/// A set section replaces the entire section.
+///
+/// The node ID, the keys and the encryption options
+/// can't be changed.
+/// Of the user accounts only the storage options can
+/// be changed, the accounts are identified by their ID.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigUpdate {
+    /// LAN connection module
+    #[prost(message, optional, tag = "1")]
+    pub lan: ::core::option::Option<LanConfig>,
+    /// Internet overlay connection module
+    #[prost(message, optional, tag = "2")]
+    pub internet: ::core::option::Option<InternetConfig>,
+    /// storage options of the user accounts
+    #[prost(message, repeated, tag = "3")]
+    pub user_accounts: ::prost::alloc::vec::Vec<UserAccountConfig>,
+    /// debugging options
+    #[prost(message, optional, tag = "4")]
+    pub debug: ::core::option::Option<DebugConfig>,
+    /// routing options
+    #[prost(message, optional, tag = "5")]
+    pub routing: ::core::option::Option<RoutingConfig>,
+    /// storage maintenance options
+    #[prost(message, optional, tag = "6")]
+    pub maintenance: ::core::option::Option<MaintenanceConfig>,
+    /// messaging options
+    #[prost(message, optional, tag = "7")]
+    pub messaging: ::core::option::Option<MessagingConfig>,
+}
+/// Configuration Update Result
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ConfigUpdateResult {
+    /// the update was applied and saved
+    #[prost(bool, tag = "1")]
+    pub success: bool,
+    /// validation errors
+    ///
+    /// Nothing has been changed if there are errors.
+    #[prost(string, repeated, tag = "2")]
+    pub errors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// some changes only take effect after
+    /// libqaul has been restarted
+    #[prost(bool, tag = "3")]
+    pub restart_required: bool,
+    /// configuration after the update
+    #[prost(message, optional, tag = "4")]
+    pub configuration: ::core::option::Option<Configuration>,
+}
+/// Request the Startup Errors
+///
+/// libqaul answers with a StartupErrors message.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StartupErrorsRequest {}
+/// Startup Errors
+///
+/// Errors found in the configuration file on startup.
+/// The invalid values were replaced by their defaults.
+///
+/// libqaul sends this message once on startup,
+/// if errors were found.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StartupErrors {
+    /// error messages
+    #[prost(string, repeated, tag = "1")]
+    pub errors: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Link Quality Metric
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum LinkQualityMetric {
+    /// round trip time plus a penalty per hop
+    HopCount = 0,
+    /// loss aware expected transmission count
+    Etx = 1,
+    /// weighted by the bandwidth of the connection module
+    Bandwidth = 2,
+}
+impl LinkQualityMetric {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::HopCount => "HOP_COUNT",
+            Self::Etx => "ETX",
+            Self::Bandwidth => "BANDWIDTH",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "HOP_COUNT" => Some(Self::HopCount),
+            "ETX" => Some(Self::Etx),
+            "BANDWIDTH" => Some(Self::Bandwidth),
+            _ => None,
+        }
+    }
+}
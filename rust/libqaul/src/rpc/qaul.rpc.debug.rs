@@ -3,7 +3,7 @@
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Debug {
     /// message type
-    #[prost(oneof = "debug::Message", tags = "1, 2, 3, 4, 5, 6, 7")]
+    #[prost(oneof = "debug::Message", tags = "1, 2, 3, 4, 5, 6, 7, 8, 9")]
     pub message: ::core::option::Option<debug::Message>,
 }
 /// Nested message and enum types in `Debug`.
@@ -32,6 +32,12 @@ pub mod debug {
         /// Request for library to delete logs
         #[prost(message, tag = "7")]
         DeleteLibqaulLogsRequest(super::DeleteLibqaulLogsRequest),
+        /// Storage Check Request
+        #[prost(message, tag = "8")]
+        StorageCheckRequest(super::StorageCheckRequest),
+        /// Storage Check Response
+        #[prost(message, tag = "9")]
+        StorageCheckResponse(super::StorageCheckResponse),
     }
 }
 /// Request a Heartbeat from Libqaul
@@ -84,3 +90,50 @@ pub struct StoragePathResponse {
 /// Requests for the log folder to be wiped clean
 #[derive(Clone, Copy, PartialEq, ::prost::Message)]
 pub struct DeleteLibqaulLogsRequest {}
+/// StorageCheckRequest
+///
+/// Check the data bases for inconsistent entries
+/// and optionally repair them.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StorageCheckRequest {
+    /// repair the problems found
+    #[prost(bool, tag = "1")]
+    pub repair: bool,
+}
+/// StorageCheckResponse
+///
+/// Contains the result of the storage check
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorageCheckResponse {
+    /// number of checked entries
+    #[prost(uint32, tag = "1")]
+    pub entries_checked: u32,
+    /// problems found
+    #[prost(message, repeated, tag = "2")]
+    pub problems: ::prost::alloc::vec::Vec<StorageProblem>,
+}
+/// StorageProblem
+///
+/// An inconsistent data base entry
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorageProblem {
+    /// name of the data base tree
+    #[prost(string, tag = "1")]
+    pub tree: ::prost::alloc::string::String,
+    /// user account of the data base,
+    /// empty for the node data base
+    #[prost(bytes = "vec", tag = "2")]
+    pub user_account: ::prost::alloc::vec::Vec<u8>,
+    /// key of the entry
+    #[prost(bytes = "vec", tag = "3")]
+    pub key: ::prost::alloc::vec::Vec<u8>,
+    /// description of the problem
+    #[prost(string, tag = "4")]
+    pub description: ::prost::alloc::string::String,
+    /// the problem can be repaired
+    #[prost(bool, tag = "5")]
+    pub repairable: bool,
+    /// the problem was repaired
+    #[prost(bool, tag = "6")]
+    pub repaired: bool,
+}
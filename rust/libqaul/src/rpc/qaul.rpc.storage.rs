@@ -0,0 +1,69 @@
+// This file is @generated by prost-build.
+/// Libqaul RPC Storage Messages
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Storage {
+    /// message type
+    #[prost(oneof = "storage::Message", tags = "1, 2, 3")]
+    pub message: ::core::option::Option<storage::Message>,
+}
+/// Nested message and enum types in `Storage`.
+pub mod storage {
+    /// message type
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// request the storage usage
+        #[prost(message, tag = "1")]
+        StorageUsageRequest(super::StorageUsageRequest),
+        /// storage usage of the user accounts
+        #[prost(message, tag = "2")]
+        StorageUsageResponse(super::StorageUsageResponse),
+        /// run the storage maintenance now
+        #[prost(message, tag = "3")]
+        MaintenanceRequest(super::MaintenanceRequest),
+    }
+}
+/// Request the Storage Usage
+///
+/// libqaul answers with a StorageUsageResponse message.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct StorageUsageRequest {}
+/// Storage Usage Response
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct StorageUsageResponse {
+    /// storage usage of every user account
+    #[prost(message, repeated, tag = "1")]
+    pub accounts: ::prost::alloc::vec::Vec<AccountStorageUsage>,
+    /// size of all feed messages stored on this node in bytes
+    #[prost(uint64, tag = "2")]
+    pub feed_total: u64,
+}
+/// Storage Usage of a User Account
+///
+/// All sizes are in bytes.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct AccountStorageUsage {
+    /// user account ID
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// chat messages
+    #[prost(uint64, tag = "2")]
+    pub chat: u64,
+    /// file histories, file chunks and stored files
+    #[prost(uint64, tag = "3")]
+    pub files: u64,
+    /// feed messages sent by this user account
+    #[prost(uint64, tag = "4")]
+    pub feed: u64,
+    /// DTN messages stored for other users
+    #[prost(uint64, tag = "5")]
+    pub dtn: u64,
+    /// crypto sessions and crypto cache
+    #[prost(uint64, tag = "6")]
+    pub crypto: u64,
+}
+/// Run the Storage Maintenance
+///
+/// The retention policies are enforced immediately.
+/// libqaul answers with a StorageUsageResponse message.
+#[derive(Clone, Copy, PartialEq, ::prost::Message)]
+pub struct MaintenanceRequest {}
@@ -0,0 +1,81 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # RPC Storage Messages
+//!
+//! Report the storage usage of the user accounts
+//! and run the storage maintenance on request.
+
+use super::Rpc;
+use crate::storage::maintenance::StorageMaintenance;
+use prost::Message;
+
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto {
+    include!("qaul.rpc.storage.rs");
+}
+
+/// RPC Storage Module
+pub struct Storage {}
+
+impl Storage {
+    /// Process incoming RPC request messages for storage module
+    pub fn rpc(data: Vec<u8>) {
+        match proto::Storage::decode(&data[..]) {
+            Ok(storage) => match storage.message {
+                Some(proto::storage::Message::StorageUsageRequest(_storage_usage_request)) => {
+                    Self::rpc_send_usage();
+                }
+                Some(proto::storage::Message::MaintenanceRequest(_maintenance_request)) => {
+                    StorageMaintenance::run();
+                    Self::rpc_send_usage();
+                }
+                _ => {
+                    log::error!("Unhandled RPC Storage Message");
+                }
+            },
+            Err(e) => {
+                log::error!("{:?}", e);
+            }
+        }
+    }
+
+    /// send the storage usage of all user accounts
+    fn rpc_send_usage() {
+        let (usage, feed_total) = StorageMaintenance::usage();
+
+        let proto_message = proto::Storage {
+            message: Some(proto::storage::Message::StorageUsageResponse(
+                proto::StorageUsageResponse {
+                    accounts: usage
+                        .into_iter()
+                        .map(|account| proto::AccountStorageUsage {
+                            user_id: account.account_id.to_bytes(),
+                            chat: account.chat,
+                            files: account.files,
+                            feed: account.feed,
+                            dtn: account.dtn,
+                            crypto: account.crypto,
+                        })
+                        .collect(),
+                    feed_total,
+                },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Storage.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
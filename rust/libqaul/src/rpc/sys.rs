@@ -15,17 +15,17 @@
 use crate::connections::ble::Ble;
 use crate::connections::{internet::Internet, lan::Lan};
 use crossbeam_channel::{unbounded, Receiver, Sender, TryRecvError};
-use state::InitCell;
 
 #[cfg(target_os = "android")]
 use crate::api::android::Android;
+use crate::utilities::instance::InstanceCell;
 
 /// receiving end of the mpsc channel
-static EXTERN_RECEIVE: InitCell<Receiver<Vec<u8>>> = InitCell::new();
+static EXTERN_RECEIVE: InstanceCell<Receiver<Vec<u8>>> = InstanceCell::new();
 /// sending end of the mpsc channel
-static EXTERN_SEND: InitCell<Sender<Vec<u8>>> = InitCell::new();
+static EXTERN_SEND: InstanceCell<Sender<Vec<u8>>> = InstanceCell::new();
 /// sending end of th mpsc channel for libqaul to send
-static LIBQAUL_SEND: InitCell<Sender<Vec<u8>>> = InitCell::new();
+static LIBQAUL_SEND: InstanceCell<Sender<Vec<u8>>> = InstanceCell::new();
 
 /// Handling of SYS messages of libqaul
 pub struct Sys {}
@@ -9,10 +9,8 @@
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use sled;
-use state::InitCell;
 use std::{
-    collections::BTreeMap,
+    collections::{BTreeMap, BTreeSet},
     convert::TryInto,
     ffi::OsStr,
     fs::{self, File},
@@ -24,6 +22,8 @@ use std::{
 use super::ChatStorage;
 use crate::services::messaging::{self, Messaging, MessagingServiceType};
 use crate::storage::database::DataBase;
+use crate::storage::tree::{Iter, Tree};
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp;
 use crate::utilities::timestamp::Timestamp;
 use crate::{
@@ -46,7 +46,7 @@ pub mod proto_net {
 pub const DEF_PACKAGE_SIZE: u32 = 64000;
 
 /// mutable state of all file
-static ALLFILES: InitCell<RwLock<AllFiles>> = InitCell::new();
+static ALLFILES: InstanceCell<RwLock<AllFiles>> = InstanceCell::new();
 
 /// Structure to management for file histories based on the each user_id.
 pub struct AllFiles {
@@ -60,14 +60,14 @@ pub struct UserFiles {
     ///
     /// key: file ID
     /// value: bincode of `FileHistory`
-    pub histories: sled::Tree,
+    pub histories: Tree,
     /// file data chunks
     ///
     /// Storage of incoming chunks until receiving is completed.
     ///
     /// key: file_id & chunk_index
     /// value: `Vec<u8>`
-    pub file_chunks: sled::Tree,
+    pub file_chunks: Tree,
 }
 
 impl UserFiles {
@@ -87,7 +87,7 @@ impl UserFiles {
     }
 
     /// get file history iterator
-    pub fn get_filehistory_iterator(&self) -> sled::Iter {
+    pub fn get_filehistory_iterator(&self) -> Iter {
         // get key range
         let first_key: u64 = 0;
         let last_key: u64 = u64::MAX;
@@ -170,7 +170,7 @@ impl UserFiles {
     }
 
     /// get all file chunks for a specific id
-    pub fn get_file_chunks(&self, file_id: &Vec<u8>) -> sled::Iter {
+    pub fn get_file_chunks(&self, file_id: &Vec<u8>) -> Iter {
         // get key range
         let (first_key, last_key) = Self::get_chunk_key_range(file_id);
 
@@ -323,8 +323,8 @@ impl ChatFile {
         let db = DataBase::get_user_db(user_id.clone());
 
         // open trees
-        let histories: sled::Tree = db.open_tree("chat_file").unwrap();
-        let file_chunks: sled::Tree = db.open_tree("file_chunks").unwrap();
+        let histories: Tree = db.open_tree("chat_file").unwrap();
+        let file_chunks: Tree = db.open_tree("file_chunks").unwrap();
 
         let user_files = UserFiles {
             histories,
@@ -400,6 +400,100 @@ impl ChatFile {
         files_storage_path.join(file_name.clone())
     }
 
+    /// Limit the size of the file store of a user account
+    ///
+    /// The oldest files are deleted from the file store,
+    /// until the stored files are not bigger than `max_size` bytes.
+    /// The file histories and data base chunks of the deleted files
+    /// are removed as well, the chat messages of the deleted files
+    /// are marked as pruned.
+    ///
+    /// Returns the number of deleted files.
+    pub fn limit_file_store(account_id: &PeerId, max_size: u64) -> usize {
+        let user_files = Self::get_db_ref(account_id);
+
+        // collect stored files
+        // (time, file_id, path, size)
+        let mut files: Vec<(u64, u64, PathBuf, u64)> = Vec::new();
+        let mut total_size: u64 = 0;
+        for res in user_files.get_filehistory_iterator() {
+            match res {
+                Ok((_id, history_bytes)) => {
+                    let file_history: FileHistory = match bincode::deserialize(&history_bytes) {
+                        Ok(file_history) => file_history,
+                        Err(e) => {
+                            log::error!("{}", e);
+                            continue;
+                        }
+                    };
+                    let path = Self::create_file_path(
+                        account_id.to_owned(),
+                        file_history.file_id,
+                        &file_history.file_extension,
+                    );
+                    if let Ok(metadata) = fs::metadata(&path) {
+                        total_size += metadata.len();
+                        files.push((
+                            file_history.sent_at.max(file_history.received_at),
+                            file_history.file_id,
+                            path,
+                            metadata.len(),
+                        ));
+                    }
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        // delete oldest files
+        files.sort_by_key(|(time, _, _, _)| *time);
+        let mut deleted = BTreeSet::new();
+        for (_time, file_id, path, size) in files {
+            if total_size <= max_size {
+                break;
+            }
+
+            if let Err(e) = fs::remove_file(&path) {
+                log::error!("deleting file {} failed: {}", path.display(), e);
+                continue;
+            }
+            total_size -= size;
+            deleted.insert(file_id);
+
+            // remove file history
+            if let Err(e) = user_files.histories.remove(file_id.to_be_bytes().to_vec()) {
+                log::error!("Error removing file history: {}", e);
+            }
+
+            // remove file chunks
+            let chunk_keys: Vec<Vec<u8>> = user_files
+                .get_file_chunks(&file_id.to_be_bytes().to_vec())
+                .filter_map(|res| res.ok())
+                .map(|(key, _chunk)| key.to_vec())
+                .collect();
+            for key in chunk_keys {
+                if let Err(e) = user_files.file_chunks.remove(key) {
+                    log::error!("Error removing file chunk: {}", e);
+                }
+            }
+        }
+
+        // flush trees to disk
+        if deleted.len() > 0 {
+            if let Err(e) = user_files.histories.flush() {
+                log::error!("Error file history flush: {}", e);
+            }
+            if let Err(e) = user_files.file_chunks.flush() {
+                log::error!("Error file chunks flush: {}", e);
+            }
+
+            // mark the chat messages of the deleted files
+            ChatStorage::mark_files_pruned(account_id, &deleted);
+        }
+
+        deleted.len()
+    }
+
     /// Getting file histories from table.
     /// This function is called from RPC command (file history [offset limit])
     pub fn file_history(
@@ -946,6 +1040,12 @@ impl ChatFile {
         sent_at: u64,
         data: &Vec<u8>,
     ) {
+        // drop files of blocked users
+        if Users::is_blocked(&sender_id) {
+            log::debug!("file message of blocked user {} dropped", sender_id);
+            return;
+        }
+
         // decode protobuf file message container
         match proto_net::ChatFileContainer::decode(&data[..]) {
             Ok(messaging) => match messaging.message {
@@ -136,6 +136,12 @@ pub struct FileContent {
     /// file description
     #[prost(string, tag = "5")]
     pub file_description: ::prost::alloc::string::String,
+    /// the file was deleted from the file store
+    ///
+    /// Files are deleted by the storage maintenance,
+    /// when the file store exceeds its configured size.
+    #[prost(bool, tag = "6")]
+    pub pruned: bool,
 }
 /// Group event information
 /// this message is purely informational
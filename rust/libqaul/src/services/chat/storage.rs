@@ -7,19 +7,19 @@
 //! and their overview from the data base.
 
 use libp2p::PeerId;
-use sled;
-use state::InitCell;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, BTreeSet};
 use std::sync::RwLock;
 
 use super::rpc_proto;
 use crate::services::group::{group_id::GroupId, GroupStorage};
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp::Timestamp;
 use prost::Message;
 
 /// mutable state of chat messages
-static CHAT: InitCell<RwLock<ChatStorage>> = InitCell::new();
+static CHAT: InstanceCell<RwLock<ChatStorage>> = InstanceCell::new();
 
 /// chat DB references per user account
 #[derive(Clone)]
@@ -27,11 +27,11 @@ pub struct ChatAccountDb {
     /// messages sled data base tree
     ///
     /// value: Vec<u8> bincode of rpc_proto::ChatMessage
-    pub messages: sled::Tree,
+    pub messages: Tree,
     /// message id => db key
     ///
     /// value: Vec<u8> of db key
-    pub message_ids: sled::Tree,
+    pub message_ids: Tree,
 }
 
 /// qaul Chat Conversation Storage
@@ -247,6 +247,133 @@ impl ChatStorage {
         }
     }
 
+    /// Remove all chat messages older than the timestamp
+    ///
+    /// The last message of every conversation is kept,
+    /// so that the message index of the conversation continues.
+    ///
+    /// Returns the number of removed messages.
+    pub fn remove_messages_before(account_id: &PeerId, timestamp: u64) -> usize {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+
+        // collect the old messages, except the last message of every conversation
+        //
+        // The messages are ordered by conversation and index.
+        // The previous message is old, if it is older than the timestamp
+        // and the current message belongs to the same conversation.
+        let mut old_messages: Vec<(Vec<u8>, Vec<u8>)> = Vec::new();
+        let mut previous: Option<(Vec<u8>, Vec<u8>, Vec<u8>, bool)> = None;
+        for res in db_ref.messages.iter() {
+            match res {
+                Ok((key, chat_msg_bytes)) => {
+                    let chat_msg: rpc_proto::ChatMessage =
+                        match bincode::deserialize(&chat_msg_bytes) {
+                            Ok(chat_msg) => chat_msg,
+                            Err(e) => {
+                                log::error!("{}", e);
+                                continue;
+                            }
+                        };
+
+                    if let Some((group_id, previous_key, message_id, old)) = previous.take() {
+                        if old && group_id == chat_msg.group_id {
+                            old_messages.push((previous_key, message_id));
+                        }
+                    }
+
+                    let old = chat_msg.sent_at.max(chat_msg.received_at) < timestamp;
+                    previous = Some((chat_msg.group_id, key.to_vec(), chat_msg.message_id, old));
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        // remove messages
+        for (key, message_id) in &old_messages {
+            if let Err(e) = db_ref.messages.remove(key) {
+                log::error!("Error removing chat message: {}", e);
+            }
+            if message_id.len() > 0 {
+                if let Err(e) = db_ref.message_ids.remove(message_id) {
+                    log::error!("Error removing chat message id: {}", e);
+                }
+            }
+        }
+
+        // flush trees to disk
+        if old_messages.len() > 0 {
+            if let Err(e) = db_ref.messages.flush() {
+                log::error!("Error chat messages flush: {}", e);
+            }
+            if let Err(e) = db_ref.message_ids.flush() {
+                log::error!("Error chat message ids flush: {}", e);
+            }
+        }
+
+        old_messages.len()
+    }
+
+    /// Mark the file messages of deleted files as pruned
+    ///
+    /// The messages stay in their conversation,
+    /// the UI shows that the file is not available anymore.
+    ///
+    /// Returns the number of marked messages.
+    pub fn mark_files_pruned(account_id: &PeerId, file_ids: &BTreeSet<u64>) -> usize {
+        let db_ref = Self::get_db_ref(account_id.to_owned());
+
+        // collect the file messages of the deleted files
+        let mut pruned_messages: Vec<(Vec<u8>, rpc_proto::ChatMessage)> = Vec::new();
+        for res in db_ref.messages.iter() {
+            match res {
+                Ok((key, chat_msg_bytes)) => {
+                    let mut chat_msg: rpc_proto::ChatMessage =
+                        match bincode::deserialize(&chat_msg_bytes) {
+                            Ok(chat_msg) => chat_msg,
+                            Err(e) => {
+                                log::error!("{}", e);
+                                continue;
+                            }
+                        };
+
+                    let mut content =
+                        match rpc_proto::ChatContentMessage::decode(&chat_msg.content[..]) {
+                            Ok(content) => content,
+                            Err(_) => continue,
+                        };
+                    if let Some(rpc_proto::chat_content_message::Message::FileContent(
+                        ref mut file_content,
+                    )) = content.message
+                    {
+                        if file_ids.contains(&file_content.file_id) && !file_content.pruned {
+                            file_content.pruned = true;
+                            chat_msg.content = content.encode_to_vec();
+                            pruned_messages.push((key.to_vec(), chat_msg));
+                        }
+                    }
+                }
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        // save marked messages
+        for (key, chat_msg) in &pruned_messages {
+            let chat_msg_bytes = bincode::serialize(chat_msg).unwrap();
+            if let Err(e) = db_ref.messages.insert(key.clone(), chat_msg_bytes) {
+                log::error!("Error saving chat message to data base: {}", e);
+            }
+        }
+
+        // flush trees to disk
+        if pruned_messages.len() > 0 {
+            if let Err(e) = db_ref.messages.flush() {
+                log::error!("Error chat messages flush: {}", e);
+            }
+        }
+
+        pruned_messages.len()
+    }
+
     /// Get chat messages of a specific conversation from data base
     pub fn get_messages(account_id: PeerId, group_id: Vec<u8>) -> rpc_proto::ChatConversationList {
         // create empty messages list
@@ -354,8 +481,8 @@ impl ChatStorage {
         let db = DataBase::get_user_db(account_id);
 
         // open trees
-        let messages: sled::Tree = db.open_tree("chat_messages").unwrap();
-        let message_ids: sled::Tree = db.open_tree("chat_message_ids").unwrap();
+        let messages: Tree = db.open_tree("chat_messages").unwrap();
+        let message_ids: Tree = db.open_tree("chat_message_ids").unwrap();
 
         let chat_user = ChatAccountDb {
             messages,
@@ -372,3 +499,151 @@ impl ChatStorage {
         chat_user
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use crate::utilities::instance::Instance;
+
+    /// initialize an ephemeral chat storage
+    fn init_chat() -> PeerId {
+        let mut config = BTreeMap::new();
+        config.insert("storage".to_string(), "memory".to_string());
+        crate::DEFCONFIGS.set(config);
+        Storage::init("chat_test".to_string()).unwrap();
+        ChatStorage::init();
+        PeerId::random()
+    }
+
+    /// save a chat message to the data base
+    fn insert_message(
+        account_id: &PeerId,
+        group_id: u8,
+        index: u64,
+        time: u64,
+        content: rpc_proto::ChatContentMessage,
+    ) {
+        let db_ref = ChatStorage::get_db_ref(account_id.to_owned());
+        let group_id = vec![group_id; 16];
+        let message_id = vec![group_id[0], index as u8];
+        let chat_message = rpc_proto::ChatMessage {
+            index,
+            sender_id: Vec::new(),
+            message_id: message_id.clone(),
+            status: rpc_proto::MessageStatus::Received as i32,
+            message_reception_confirmed: Vec::new(),
+            group_id: group_id.clone(),
+            sent_at: time,
+            received_at: time,
+            content: content.encode_to_vec(),
+        };
+        let db_key = ChatStorage::get_db_key_from_vec(&group_id, index);
+        db_ref
+            .messages
+            .insert(db_key.clone(), bincode::serialize(&chat_message).unwrap())
+            .unwrap();
+        db_ref.message_ids.insert(message_id, db_key).unwrap();
+    }
+
+    fn text(text: &str) -> rpc_proto::ChatContentMessage {
+        rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::ChatContent(
+                rpc_proto::ChatContent {
+                    text: text.to_string(),
+                },
+            )),
+        }
+    }
+
+    fn file(file_id: u64) -> rpc_proto::ChatContentMessage {
+        rpc_proto::ChatContentMessage {
+            message: Some(rpc_proto::chat_content_message::Message::FileContent(
+                rpc_proto::FileContent {
+                    file_id,
+                    ..Default::default()
+                },
+            )),
+        }
+    }
+
+    /// stored messages of a conversation
+    fn messages(account_id: &PeerId, group_id: u8) -> Vec<rpc_proto::ChatMessage> {
+        let db_ref = ChatStorage::get_db_ref(account_id.to_owned());
+        let (first_key, last_key) = ChatStorage::get_db_key_range(&vec![group_id; 16]);
+        db_ref
+            .messages
+            .range(first_key.as_slice()..last_key.as_slice())
+            .map(|res| bincode::deserialize(&res.unwrap().1).unwrap())
+            .collect()
+    }
+
+    /// indexes of the stored messages of a conversation
+    fn indexes(account_id: &PeerId, group_id: u8) -> Vec<u64> {
+        messages(account_id, group_id)
+            .iter()
+            .map(|message| message.index)
+            .collect()
+    }
+
+    #[test]
+    fn messages_before_timestamp_are_removed() {
+        Instance::with(Instance::create(), || {
+            let account_id = init_chat();
+            insert_message(&account_id, 1, 1, 100, text("a"));
+            insert_message(&account_id, 1, 2, 299, text("b"));
+            insert_message(&account_id, 1, 3, 300, text("c"));
+            insert_message(&account_id, 1, 4, 400, text("d"));
+
+            // messages at the timestamp are kept
+            assert_eq!(ChatStorage::remove_messages_before(&account_id, 300), 2);
+            assert_eq!(indexes(&account_id, 1), vec![3, 4]);
+            assert!(!ChatStorage::messages_exist(&account_id, &vec![vec![1, 2]]));
+            assert!(ChatStorage::messages_exist(&account_id, &vec![vec![1, 3]]));
+        });
+    }
+
+    #[test]
+    fn last_message_of_conversation_is_kept() {
+        Instance::with(Instance::create(), || {
+            let account_id = init_chat();
+            insert_message(&account_id, 1, 1, 100, text("a"));
+            insert_message(&account_id, 1, 2, 200, text("b"));
+            insert_message(&account_id, 2, 1, 100, text("c"));
+
+            assert_eq!(ChatStorage::remove_messages_before(&account_id, 1000), 1);
+            assert_eq!(indexes(&account_id, 1), vec![2]);
+            assert_eq!(indexes(&account_id, 2), vec![1]);
+        });
+    }
+
+    #[test]
+    fn file_messages_are_marked_pruned() {
+        Instance::with(Instance::create(), || {
+            let account_id = init_chat();
+            insert_message(&account_id, 1, 1, 100, file(7));
+            insert_message(&account_id, 1, 2, 200, file(8));
+            insert_message(&account_id, 1, 3, 300, text("a"));
+
+            let file_ids = BTreeSet::from([7]);
+            assert_eq!(ChatStorage::mark_files_pruned(&account_id, &file_ids), 1);
+            assert_eq!(ChatStorage::mark_files_pruned(&account_id, &file_ids), 0);
+
+            let pruned: Vec<bool> = messages(&account_id, 1)
+                .iter()
+                .filter_map(|message| {
+                    match rpc_proto::ChatContentMessage::decode(&message.content[..])
+                        .unwrap()
+                        .message
+                    {
+                        Some(rpc_proto::chat_content_message::Message::FileContent(file)) => {
+                            Some(file.pruned)
+                        }
+                        _ => None,
+                    }
+                })
+                .collect();
+            assert_eq!(pruned, vec![true, false]);
+        });
+    }
+}
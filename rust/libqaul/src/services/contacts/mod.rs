@@ -0,0 +1,456 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Contact Book
+//!
+//! Every user account has its own contact book, layered on top
+//! of the users known in the network.
+//!
+//! A contact holds local information about a user, which is
+//! never sent to the network:
+//!
+//! * a nickname
+//! * free text notes
+//! * the favourite flag
+//! * the contact groups the contact belongs to
+//! * the time when the user was last seen on the network
+//!
+//! The last seen time is updated by the router events,
+//! whenever a contact becomes reachable or unreachable.
+
+use libp2p::PeerId;
+use prost::Message;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::RwLock;
+
+use crate::node::user_accounts::UserAccounts;
+use crate::router::{table::RoutingTable, users::Users};
+use crate::rpc::Rpc;
+use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
+use crate::utilities::qaul_id::QaulId;
+use crate::utilities::timestamp::Timestamp;
+
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto {
+    include!("qaul.rpc.contacts.rs");
+}
+
+/// mutable state of the contact books
+static CONTACTS: InstanceCell<RwLock<Contacts>> = InstanceCell::new();
+
+/// maximal length of a nickname in bytes
+pub const MAX_NICKNAME_LENGTH: usize = 64;
+
+/// maximal length of the notes in bytes
+pub const MAX_NOTES_LENGTH: usize = 4096;
+
+/// maximal length of a contact group name in bytes
+pub const MAX_GROUP_LENGTH: usize = 64;
+
+/// maximal number of contact groups per contact
+pub const MAX_GROUPS: usize = 32;
+
+/// Contact
+///
+/// Saved bincode encoded in the contacts tree
+/// of the user account.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Contact {
+    /// user ID
+    pub user_id: Vec<u8>,
+    /// local nickname
+    pub nickname: String,
+    /// local notes
+    pub notes: String,
+    /// the contact is a favourite
+    pub favourite: bool,
+    /// contact groups the contact belongs to
+    pub groups: Vec<String>,
+    /// time when the contact was added
+    pub added_at: u64,
+    /// time when the user was last seen on the network
+    pub last_seen: u64,
+}
+
+impl Contact {
+    /// check the size limits of the contact fields
+    fn validate(&self) -> Result<(), String> {
+        if self.nickname.len() > MAX_NICKNAME_LENGTH {
+            return Err(format!(
+                "the nickname can be at most {} bytes",
+                MAX_NICKNAME_LENGTH
+            ));
+        }
+        if self.notes.len() > MAX_NOTES_LENGTH {
+            return Err(format!(
+                "the notes can be at most {} bytes",
+                MAX_NOTES_LENGTH
+            ));
+        }
+        if self.groups.len() > MAX_GROUPS {
+            return Err(format!("a contact can be in at most {} groups", MAX_GROUPS));
+        }
+        for group in &self.groups {
+            Self::validate_group(group)?;
+        }
+
+        Ok(())
+    }
+
+    /// check a contact group name
+    fn validate_group(group: &str) -> Result<(), String> {
+        if group.is_empty() {
+            return Err("the group name can't be empty".to_string());
+        }
+        if group.len() > MAX_GROUP_LENGTH {
+            return Err(format!(
+                "the group name can be at most {} bytes",
+                MAX_GROUP_LENGTH
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// Contact Books of all user accounts
+pub struct Contacts {
+    /// contacts tree per user account
+    db_ref: BTreeMap<Vec<u8>, Tree>,
+}
+
+impl Contacts {
+    /// initialize the contact books
+    pub fn init() {
+        let contacts = Contacts {
+            db_ref: BTreeMap::new(),
+        };
+        CONTACTS.set(RwLock::new(contacts));
+    }
+
+    /// get the contacts tree of a user account
+    fn get_db_ref(account_id: PeerId) -> Tree {
+        {
+            let contacts = CONTACTS.get().read().unwrap();
+            if let Some(tree) = contacts.db_ref.get(&account_id.to_bytes()) {
+                return tree.clone();
+            }
+        }
+
+        // open the tree in the data base of the user account
+        let db = DataBase::get_user_db(account_id);
+        let tree: Tree = db.open_tree("contacts").unwrap();
+
+        let mut contacts = CONTACTS.get().write().unwrap();
+        contacts.db_ref.insert(account_id.to_bytes(), tree.clone());
+
+        tree
+    }
+
+    /// get a contact of a user account
+    pub fn get(account_id: PeerId, user_id: &PeerId) -> Option<Contact> {
+        let tree = Self::get_db_ref(account_id);
+
+        match tree.get(user_id.to_bytes()) {
+            Ok(Some(contact_bytes)) => match bincode::deserialize(&contact_bytes) {
+                Ok(contact) => Some(contact),
+                Err(e) => {
+                    log::error!("contact decoding error: {}", e);
+                    None
+                }
+            },
+            Ok(None) => None,
+            Err(e) => {
+                log::error!("{}", e);
+                None
+            }
+        }
+    }
+
+    /// save a contact of a user account
+    fn save(tree: &Tree, contact: &Contact) {
+        let contact_bytes = bincode::serialize(contact).unwrap();
+        if let Err(e) = tree.insert(contact.user_id.clone(), contact_bytes) {
+            log::error!("Error saving contact to data base: {}", e);
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error contacts flush: {}", e);
+        }
+    }
+
+    /// get all contacts of a user account
+    pub fn list(account_id: PeerId) -> Vec<Contact> {
+        let tree = Self::get_db_ref(account_id);
+        let mut contacts = Vec::new();
+
+        for entry in tree.iter() {
+            match entry {
+                Ok((_, contact_bytes)) => match bincode::deserialize(&contact_bytes) {
+                    Ok(contact) => contacts.push(contact),
+                    Err(e) => log::error!("contact decoding error: {}", e),
+                },
+                Err(e) => log::error!("{}", e),
+            }
+        }
+
+        contacts
+    }
+
+    /// add a user to the contact book of a user account
+    pub fn add(account_id: PeerId, contact_add: proto::ContactAdd) -> Result<(), String> {
+        let user_id = match PeerId::from_bytes(&contact_add.user_id) {
+            Ok(user_id) => user_id,
+            Err(e) => return Err(format!("invalid user id: {}", e)),
+        };
+        if user_id == account_id {
+            return Err("a user account can't be its own contact".to_string());
+        }
+
+        let tree = Self::get_db_ref(account_id);
+        if let Ok(true) = tree.contains_key(user_id.to_bytes()) {
+            return Err("the user is already a contact".to_string());
+        }
+
+        // an online user is seen right now
+        let now = Timestamp::get_timestamp();
+        let q8id = QaulId::to_q8id(user_id);
+        let last_seen = match RoutingTable::get_online_users().contains_key(&q8id) {
+            true => now,
+            false => 0,
+        };
+
+        let mut groups: Vec<String> = Vec::new();
+        for group in contact_add.groups {
+            if !groups.contains(&group) {
+                groups.push(group);
+            }
+        }
+
+        let contact = Contact {
+            user_id: user_id.to_bytes(),
+            nickname: contact_add.nickname,
+            notes: contact_add.notes,
+            favourite: contact_add.favourite,
+            groups,
+            added_at: now,
+            last_seen,
+        };
+        contact.validate()?;
+
+        Self::save(&tree, &contact);
+        Ok(())
+    }
+
+    /// update a field of a contact
+    pub fn update(account_id: PeerId, contact_update: proto::ContactUpdate) -> Result<(), String> {
+        let user_id = match PeerId::from_bytes(&contact_update.user_id) {
+            Ok(user_id) => user_id,
+            Err(e) => return Err(format!("invalid user id: {}", e)),
+        };
+        let mut contact = match Self::get(account_id, &user_id) {
+            Some(contact) => contact,
+            None => return Err("contact not found".to_string()),
+        };
+
+        match contact_update.update {
+            Some(proto::contact_update::Update::Nickname(nickname)) => {
+                contact.nickname = nickname;
+            }
+            Some(proto::contact_update::Update::Notes(notes)) => {
+                contact.notes = notes;
+            }
+            Some(proto::contact_update::Update::Favourite(favourite)) => {
+                contact.favourite = favourite;
+            }
+            Some(proto::contact_update::Update::AddGroup(group)) => {
+                if !contact.groups.contains(&group) {
+                    contact.groups.push(group);
+                }
+            }
+            Some(proto::contact_update::Update::RemoveGroup(group)) => {
+                contact.groups.retain(|g| *g != group);
+            }
+            None => return Err("no field to update".to_string()),
+        }
+        contact.validate()?;
+
+        let tree = Self::get_db_ref(account_id);
+        Self::save(&tree, &contact);
+        Ok(())
+    }
+
+    /// remove a contact from the contact book of a user account
+    pub fn remove(account_id: PeerId, user_id: &PeerId) -> Result<(), String> {
+        let tree = Self::get_db_ref(account_id);
+
+        match tree.remove(user_id.to_bytes()) {
+            Ok(Some(_)) => {}
+            Ok(None) => return Err("contact not found".to_string()),
+            Err(e) => return Err(format!("Error removing contact from data base: {}", e)),
+        }
+        if let Err(e) = tree.flush() {
+            log::error!("Error contacts flush: {}", e);
+        }
+
+        Ok(())
+    }
+
+    /// update the last seen time of the contacts
+    /// whose reachability changed
+    ///
+    /// This function is called by the router events,
+    /// when users become reachable or unreachable.
+    pub fn users_seen(q8ids: &Vec<Vec<u8>>) {
+        // the router may publish events before the services are initialized
+        if CONTACTS.try_get().is_none() {
+            return;
+        }
+
+        let user_ids: Vec<Vec<u8>> = q8ids
+            .iter()
+            .filter_map(|q8id| Users::get_user_id_by_q8id(q8id.clone()))
+            .map(|user_id| user_id.to_bytes())
+            .collect();
+        if user_ids.is_empty() {
+            return;
+        }
+
+        let now = Timestamp::get_timestamp();
+        for account in UserAccounts::get_user_info() {
+            let tree = Self::get_db_ref(account.id);
+            for user_id in &user_ids {
+                if let Ok(Some(contact_bytes)) = tree.get(user_id) {
+                    if let Ok(mut contact) = bincode::deserialize::<Contact>(&contact_bytes) {
+                        contact.last_seen = now;
+                        Self::save(&tree, &contact);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Process incoming RPC request messages for the contacts module
+    pub fn rpc(data: Vec<u8>, user_id: Vec<u8>) {
+        let account_id = match PeerId::from_bytes(&user_id) {
+            Ok(account_id) => account_id,
+            Err(e) => {
+                log::error!("invalid user account id: {}", e);
+                return;
+            }
+        };
+
+        match proto::Contacts::decode(&data[..]) {
+            Ok(contacts) => match contacts.message {
+                Some(proto::contacts::Message::ContactListRequest(list_request)) => {
+                    Self::rpc_send_contact_list(account_id, list_request);
+                }
+                Some(proto::contacts::Message::ContactAdd(contact_add)) => {
+                    let contact_id = contact_add.user_id.clone();
+                    let result = Self::add(account_id, contact_add);
+                    Self::rpc_send_result(contact_id, result);
+                }
+                Some(proto::contacts::Message::ContactUpdate(contact_update)) => {
+                    let contact_id = contact_update.user_id.clone();
+                    let result = Self::update(account_id, contact_update);
+                    Self::rpc_send_result(contact_id, result);
+                }
+                Some(proto::contacts::Message::ContactRemove(contact_remove)) => {
+                    let result = match PeerId::from_bytes(&contact_remove.user_id) {
+                        Ok(contact_id) => Self::remove(account_id, &contact_id),
+                        Err(e) => Err(format!("invalid user id: {}", e)),
+                    };
+                    Self::rpc_send_result(contact_remove.user_id, result);
+                }
+                _ => {
+                    log::error!("Unhandled RPC Contacts Message");
+                }
+            },
+            Err(e) => {
+                log::error!("{:?}", e);
+            }
+        }
+    }
+
+    /// send the contacts of a user account matching the request
+    fn rpc_send_contact_list(account_id: PeerId, list_request: proto::ContactListRequest) {
+        let online_users = RoutingTable::get_online_users();
+        let now = Timestamp::get_timestamp();
+
+        let mut contacts = Vec::new();
+        for contact in Self::list(account_id) {
+            if list_request.favourites && !contact.favourite {
+                continue;
+            }
+            if !list_request.group.is_empty() && !contact.groups.contains(&list_request.group) {
+                continue;
+            }
+
+            let (name, online) = match PeerId::from_bytes(&contact.user_id) {
+                Ok(user_id) => (
+                    Users::get_name(&user_id).unwrap_or_default(),
+                    online_users.contains_key(&QaulId::to_q8id(user_id)),
+                ),
+                Err(_) => (String::new(), false),
+            };
+
+            contacts.push(proto::ContactEntry {
+                user_id: contact.user_id,
+                name,
+                nickname: contact.nickname,
+                notes: contact.notes,
+                favourite: contact.favourite,
+                groups: contact.groups,
+                added_at: contact.added_at,
+                last_seen: if online { now } else { contact.last_seen },
+                online,
+            });
+        }
+
+        let proto_message = proto::Contacts {
+            message: Some(proto::contacts::Message::ContactList(proto::ContactList {
+                contacts,
+            })),
+        };
+        Self::rpc_send(proto_message);
+    }
+
+    /// send the result of an add, update or remove request
+    fn rpc_send_result(user_id: Vec<u8>, result: Result<(), String>) {
+        let (success, error) = match result {
+            Ok(()) => (true, String::new()),
+            Err(e) => (false, e),
+        };
+
+        let proto_message = proto::Contacts {
+            message: Some(proto::contacts::Message::ContactResult(
+                proto::ContactResult {
+                    user_id,
+                    success,
+                    error,
+                },
+            )),
+        };
+        Self::rpc_send(proto_message);
+    }
+
+    /// encode and send a contacts RPC message
+    fn rpc_send(proto_message: proto::Contacts) {
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Contacts.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
@@ -0,0 +1,164 @@
+// This file is @generated by prost-build.
+/// Libqaul RPC Contacts Messages
+///
+/// The contact book is stored per user account.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Contacts {
+    /// message type
+    #[prost(oneof = "contacts::Message", tags = "1, 2, 3, 4, 5, 6")]
+    pub message: ::core::option::Option<contacts::Message>,
+}
+/// Nested message and enum types in `Contacts`.
+pub mod contacts {
+    /// message type
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// request the contacts of the user account
+        #[prost(message, tag = "1")]
+        ContactListRequest(super::ContactListRequest),
+        /// list of contacts
+        #[prost(message, tag = "2")]
+        ContactList(super::ContactList),
+        /// add a user to the contact book
+        #[prost(message, tag = "3")]
+        ContactAdd(super::ContactAdd),
+        /// update a field of a contact
+        #[prost(message, tag = "4")]
+        ContactUpdate(super::ContactUpdate),
+        /// remove a contact from the contact book
+        #[prost(message, tag = "5")]
+        ContactRemove(super::ContactRemove),
+        /// result of an add, update or remove request
+        #[prost(message, tag = "6")]
+        ContactResult(super::ContactResult),
+    }
+}
+/// Request the Contact List
+///
+/// libqaul answers with a ContactList message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactListRequest {
+    /// only send the favourites
+    #[prost(bool, tag = "1")]
+    pub favourites: bool,
+    /// only send the contacts of this contact group,
+    /// all contacts are sent if empty
+    #[prost(string, tag = "2")]
+    pub group: ::prost::alloc::string::String,
+}
+/// Contact List
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactList {
+    /// contacts
+    #[prost(message, repeated, tag = "1")]
+    pub contacts: ::prost::alloc::vec::Vec<ContactEntry>,
+}
+/// Contact Entry
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactEntry {
+    /// user ID
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// user name, as announced by the user
+    #[prost(string, tag = "2")]
+    pub name: ::prost::alloc::string::String,
+    /// local nickname
+    #[prost(string, tag = "3")]
+    pub nickname: ::prost::alloc::string::String,
+    /// local notes
+    #[prost(string, tag = "4")]
+    pub notes: ::prost::alloc::string::String,
+    /// the contact is a favourite
+    #[prost(bool, tag = "5")]
+    pub favourite: bool,
+    /// contact groups the contact belongs to
+    #[prost(string, repeated, tag = "6")]
+    pub groups: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+    /// time when the contact was added
+    #[prost(uint64, tag = "7")]
+    pub added_at: u64,
+    /// time when the user was last seen on the network,
+    /// 0 if never seen
+    #[prost(uint64, tag = "8")]
+    pub last_seen: u64,
+    /// the user is currently reachable
+    #[prost(bool, tag = "9")]
+    pub online: bool,
+}
+/// Add a user to the Contact Book
+///
+/// libqaul answers with a ContactResult message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactAdd {
+    /// user ID
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// local nickname
+    #[prost(string, tag = "2")]
+    pub nickname: ::prost::alloc::string::String,
+    /// local notes
+    #[prost(string, tag = "3")]
+    pub notes: ::prost::alloc::string::String,
+    /// the contact is a favourite
+    #[prost(bool, tag = "4")]
+    pub favourite: bool,
+    /// contact groups the contact belongs to
+    #[prost(string, repeated, tag = "5")]
+    pub groups: ::prost::alloc::vec::Vec<::prost::alloc::string::String>,
+}
+/// Update a Field of a Contact
+///
+/// libqaul answers with a ContactResult message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactUpdate {
+    /// user ID
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// field to update
+    #[prost(oneof = "contact_update::Update", tags = "2, 3, 4, 5, 6")]
+    pub update: ::core::option::Option<contact_update::Update>,
+}
+/// Nested message and enum types in `ContactUpdate`.
+pub mod contact_update {
+    /// field to update
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Update {
+        /// set the local nickname
+        #[prost(string, tag = "2")]
+        Nickname(::prost::alloc::string::String),
+        /// set the local notes
+        #[prost(string, tag = "3")]
+        Notes(::prost::alloc::string::String),
+        /// set or unset favourite
+        #[prost(bool, tag = "4")]
+        Favourite(bool),
+        /// add the contact to a contact group
+        #[prost(string, tag = "5")]
+        AddGroup(::prost::alloc::string::String),
+        /// remove the contact from a contact group
+        #[prost(string, tag = "6")]
+        RemoveGroup(::prost::alloc::string::String),
+    }
+}
+/// Remove a Contact
+///
+/// libqaul answers with a ContactResult message.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactRemove {
+    /// user ID
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+}
+/// Contact Result
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct ContactResult {
+    /// user ID of the contact
+    #[prost(bytes = "vec", tag = "1")]
+    pub user_id: ::prost::alloc::vec::Vec<u8>,
+    /// the request succeeded
+    #[prost(bool, tag = "2")]
+    pub success: bool,
+    /// error message if the request failed
+    #[prost(string, tag = "3")]
+    pub error: ::prost::alloc::string::String,
+}
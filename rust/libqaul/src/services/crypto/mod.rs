@@ -415,6 +415,7 @@ impl Crypto {
                                         &user_account,
                                         &remote_id,
                                         encrypted_message,
+                                        messaging::MessagingServiceType::Crypto,
                                         message_id,
                                         true,
                                     ) {
@@ -6,17 +6,17 @@
 //! Handling of the data base access for the crypto handshake and session state.
 
 use libp2p::PeerId;
-use sled;
-use state::InitCell;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 use super::CryptoState;
 use crate::services::messaging::proto;
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 
 /// mutable state of messages, scheduled for sending
-pub static CRYPTOSTORAGE: InitCell<RwLock<CryptoStorage>> = InitCell::new();
+pub static CRYPTOSTORAGE: InstanceCell<RwLock<CryptoStorage>> = InstanceCell::new();
 
 /// Group DB links for user account
 #[derive(Clone)]
@@ -24,12 +24,12 @@ pub struct CryptoAccount {
     /// user crypto session state storage
     ///
     /// value: bincode of `CryptoState`
-    pub state: sled::Tree,
+    pub state: Tree,
     /// unprocessable out of order handshake
     /// state messages
     ///
     /// value: bincode of `proto::Encrypted`
-    pub cache: sled::Tree,
+    pub cache: Tree,
 }
 
 impl CryptoAccount {
@@ -215,8 +215,8 @@ impl CryptoStorage {
         let db = DataBase::get_user_db(account_id);
 
         // open trees
-        let state: sled::Tree = db.open_tree("crypto_state").unwrap();
-        let cache: sled::Tree = db.open_tree("crypto_cache").unwrap();
+        let state: Tree = db.open_tree("crypto_state").unwrap();
+        let cache: Tree = db.open_tree("crypto_cache").unwrap();
 
         let crypto_account = CryptoAccount { state, cache };
 
@@ -9,15 +9,16 @@
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use sled;
-use state::InitCell;
 use std::{convert::TryInto, fmt, sync::RwLock};
 
 use super::messaging::{proto, MessagingServiceType};
 use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::router::users::Users;
 use crate::rpc::Rpc;
 use crate::storage::configuration::Configuration;
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 
 /// Import protobuf message definition generated by
 /// the rust module prost-build.
@@ -44,15 +45,15 @@ pub struct DtnStorageState {
     /// DTN message table ref
     ///
     /// value: bincode of `DtnMessageEntry`
-    pub db_ref: sled::Tree,
+    pub db_ref: Tree,
     /// DTN message id table ref (org_dtn_sig => new_dtn_sig)
     /// This is used to prevent dup DTN message incoming
     /// saved as `Vec<u8>`
-    pub db_ref_id: sled::Tree,
+    pub db_ref_id: Tree,
 }
 
 /// mutable state of storge
-pub static STORAGESTATE: InitCell<RwLock<DtnStorageState>> = InitCell::new();
+pub static STORAGESTATE: InstanceCell<RwLock<DtnStorageState>> = InstanceCell::new();
 
 /// qaul Delayed
 ///
@@ -65,8 +66,8 @@ impl Dtn {
         let db = DataBase::get_node_db();
 
         // open trees
-        let dtn_messages: sled::Tree = db.open_tree("dtn-messages").unwrap();
-        let db_ref_id: sled::Tree = db.open_tree("dtn-messages-ids").unwrap();
+        let dtn_messages: Tree = db.open_tree("dtn-messages").unwrap();
+        let db_ref_id: Tree = db.open_tree("dtn-messages-ids").unwrap();
 
         // calc current used size
         let mut used_size: u64 = 0;
@@ -131,6 +132,20 @@ impl Dtn {
         None
     }
 
+    /// check whether the original sender or the receiver
+    /// of a DTN message is blocked
+    ///
+    /// Messages of or for blocked users are not stored.
+    fn is_blocked(envelope: &proto::Envelope, receiver_id: &PeerId) -> bool {
+        if Users::is_blocked(receiver_id) {
+            return true;
+        }
+        match PeerId::from_bytes(&envelope.sender_id) {
+            Ok(sender_id) => Users::is_blocked(&sender_id),
+            Err(_) => false,
+        }
+    }
+
     /// process DTN message by role as stroage node
     fn process_storage_node_message(
         user_account: &UserAccount,
@@ -234,6 +249,7 @@ impl Dtn {
             let container = super::messaging::proto::Container {
                 signature: signature.clone(),
                 envelope: Some(envelop),
+                trace: Vec::new(),
             };
             super::messaging::Messaging::save_unconfirmed_message(
                 MessagingServiceType::DtnStored,
@@ -315,6 +331,16 @@ impl Dtn {
                                 user_account.clone(),
                                 container,
                             );
+                        } else if Self::is_blocked(envelope, &receiver_id) {
+                            // don't store messages of or for blocked users
+                            res = (
+                                super::messaging::proto::dtn_response::ResponseType::Rejected
+                                    .try_into()
+                                    .unwrap(),
+                                super::messaging::proto::dtn_response::Reason::UserNotAccepted
+                                    .try_into()
+                                    .unwrap(),
+                            );
                         } else {
                             res = Self::process_storage_node_message(
                                 &user_account,
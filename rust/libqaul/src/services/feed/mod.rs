@@ -14,8 +14,6 @@ use libp2p::{
 };
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use sled;
-use state::InitCell;
 use std::collections::BTreeMap;
 use std::{convert::TryInto, sync::RwLock};
 
@@ -27,8 +25,11 @@ use crate::node::{
 use crate::connections::{internet::Internet, lan::Lan, ConnectionModule};
 use crate::router;
 use crate::router::flooder::Flooder;
+use crate::router::rate_limit::{RateLimitClass, RateLimiter};
 use crate::rpc::Rpc;
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp;
 
 /// Import protobuf message definition generated by
@@ -41,7 +42,7 @@ pub mod proto_net {
 }
 
 /// mutable state of feed messages
-static FEED: InitCell<RwLock<Feed>> = InitCell::new();
+static FEED: InstanceCell<RwLock<Feed>> = InstanceCell::new();
 
 /// For storing in data base
 #[derive(Serialize, Deserialize, Clone)]
@@ -90,12 +91,12 @@ pub struct Feed {
     /// sled data base tree for message_id to last index
     ///
     /// value: bincode of `u64`
-    pub tree_ids: sled::Tree,
+    pub tree_ids: Tree,
 
     /// sled data base tree of
     ///
     /// value: bincode of `FeedMessageData`
-    pub tree: sled::Tree,
+    pub tree: Tree,
     /// last recent message
     pub last_message: u64,
 }
@@ -105,8 +106,8 @@ impl Feed {
     pub fn init() {
         // get database and initialize tree
         let db = DataBase::get_node_db();
-        let tree: sled::Tree = db.open_tree("feed").unwrap();
-        let tree_ids: sled::Tree = db.open_tree("feed_id").unwrap();
+        let tree: Tree = db.open_tree("feed").unwrap();
+        let tree_ids: Tree = db.open_tree("feed_id").unwrap();
 
         // get last key
         let last_message: u64;
@@ -198,20 +199,35 @@ impl Feed {
                 .floodsub
                 .publish(Node::get_topic(), buf.clone());
         }
+        crate::connections::virtual_net::VirtualNet::send_feed_message(
+            Node::get_topic(),
+            buf.clone(),
+        );
         crate::connections::ble::Ble::send_feed_message(Node::get_topic(), buf);
     }
 
     /// Process a received message
     pub fn received(
         via_conn: ConnectionModule,
-        _via_node: PeerId,
+        via_node: PeerId,
         feed_container: proto_net::FeedContainer,
     ) {
+        // limit the feed messages per neighbour
+        if !RateLimiter::check(&via_node, RateLimitClass::Feed) {
+            return;
+        }
+
         match proto_net::FeedMessageContent::decode(&feed_container.message[..]) {
             Ok(feed_content) => {
                 let message = feed_content.clone();
 
                 if let Ok(user_id_decoded) = PeerId::from_bytes(&message.sender) {
+                    // drop posts of blocked users
+                    if router::users::Users::is_blocked(&user_id_decoded) {
+                        log::debug!("feed message of blocked user {} dropped", user_id_decoded);
+                        return;
+                    }
+
                     // check if sending user public is in user store
                     let result = router::users::Users::get_pub_key(&user_id_decoded);
 
@@ -288,6 +304,13 @@ impl Feed {
         content: String,
         time: u64,
     ) {
+        // drop posts of blocked users
+        if let Ok(user_id) = PeerId::from_bytes(sender_id) {
+            if router::users::Users::is_blocked(&user_id) {
+                return;
+            }
+        }
+
         let mut feed = FEED.get().write().unwrap();
         if let Some(_index) = feed.tree_ids.get(&message_id[..]).unwrap() {
             return;
@@ -396,6 +419,58 @@ impl Feed {
         feed.last_message = last_message;
     }
 
+    /// Limit the number of stored feed messages
+    ///
+    /// The oldest messages are removed, until there are
+    /// not more than `max_messages` messages stored.
+    /// The IDs of the removed messages are kept,
+    /// so that they are not received and stored again.
+    ///
+    /// Returns the number of removed messages.
+    pub fn limit_messages(max_messages: u64) -> usize {
+        let mut feed = FEED.get().write().unwrap();
+
+        let count = feed.tree.len() as u64;
+        if count <= max_messages {
+            return 0;
+        }
+
+        // collect oldest messages
+        let old_messages: Vec<(Vec<u8>, FeedMessageData)> = feed
+            .tree
+            .iter()
+            .take((count - max_messages) as usize)
+            .filter_map(|res| match res {
+                Ok((key, message_bytes)) => match bincode::deserialize(&message_bytes) {
+                    Ok(message) => Some((key.to_vec(), message)),
+                    Err(e) => {
+                        log::error!("{}", e);
+                        None
+                    }
+                },
+                Err(e) => {
+                    log::error!("Error retrieving feed message from data base: {}", e);
+                    None
+                }
+            })
+            .collect();
+
+        // remove messages
+        for (key, message) in &old_messages {
+            feed.messages.remove(&message.message_id);
+            if let Err(e) = feed.tree.remove(key) {
+                log::error!("Error removing feed message from data base: {}", e);
+            }
+        }
+
+        // flush tree to disk
+        if let Err(e) = feed.tree.flush() {
+            log::error!("Error when flushing data base to disk: {}", e);
+        }
+
+        old_messages.len()
+    }
+
     pub fn get_latest_message_ids(count: usize) -> Vec<Vec<u8>> {
         let mut ids: Vec<Vec<u8>> = vec![];
 
@@ -610,3 +685,62 @@ impl Feed {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+    use crate::utilities::instance::Instance;
+
+    /// initialize an ephemeral feed with `count` messages
+    fn init_feed(count: u8) {
+        let mut config = BTreeMap::new();
+        config.insert("storage".to_string(), "memory".to_string());
+        crate::DEFCONFIGS.set(config);
+        Storage::init("feed_test".to_string()).unwrap();
+        Feed::init();
+
+        for i in 0..count {
+            Feed::save_message_by_sync(&vec![i], &vec![0], format!("message {}", i), i as u64);
+        }
+    }
+
+    #[test]
+    fn limit_is_not_exceeded() {
+        Instance::with(Instance::create(), || {
+            init_feed(5);
+
+            assert_eq!(Feed::limit_messages(5), 0);
+            assert_eq!(Feed::limit_messages(6), 0);
+            assert_eq!(FEED.get().read().unwrap().tree.len(), 5);
+        });
+    }
+
+    #[test]
+    fn oldest_messages_are_removed() {
+        Instance::with(Instance::create(), || {
+            init_feed(5);
+
+            assert_eq!(Feed::limit_messages(3), 2);
+
+            let ids = Feed::get_messges_by_ids(&vec![vec![0], vec![1], vec![2], vec![4]]);
+            let ids: Vec<Vec<u8>> = ids.into_iter().map(|(id, _, _, _)| id).collect();
+            assert_eq!(ids, vec![vec![2], vec![4]]);
+        });
+    }
+
+    #[test]
+    fn removed_messages_are_not_stored_again() {
+        Instance::with(Instance::create(), || {
+            init_feed(5);
+            Feed::limit_messages(3);
+
+            // the ids of the removed messages are not requested
+            let missing = Feed::process_received_feed_ids(&vec![vec![0], vec![4], vec![9]]);
+            assert_eq!(missing, vec![vec![9]]);
+
+            Feed::save_message_by_sync(&vec![0], &vec![0], "message 0".to_string(), 0);
+            assert_eq!(FEED.get().read().unwrap().tree.len(), 3);
+        });
+    }
+}
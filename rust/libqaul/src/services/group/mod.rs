@@ -17,6 +17,7 @@ use self::proto_net::GroupMemberRole;
 use super::chat::{self, Chat};
 use super::messaging::{proto, Messaging, MessagingServiceType};
 use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::router::users::Users;
 use crate::rpc::Rpc;
 use crate::utilities::timestamp::Timestamp;
 
@@ -329,6 +330,12 @@ impl Group {
 
     /// Process incoming NET messages for group chat module
     pub fn net(sender_id: &PeerId, receiver_id: &PeerId, data: &Vec<u8>) {
+        // drop group messages & invites of blocked users
+        if Users::is_blocked(sender_id) {
+            log::debug!("group message of blocked user {} dropped", sender_id);
+            return;
+        }
+
         // check receiver id is in users list
         let user;
         match UserAccounts::get_by_id(receiver_id.clone()) {
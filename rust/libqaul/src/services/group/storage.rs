@@ -6,26 +6,26 @@
 //! Saves and retrieves groups from data base.
 
 use libp2p::PeerId;
-use sled;
-use state::InitCell;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
 use super::{Group, GroupInvited};
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 
 /// mutable state of all user groups
-pub static GROUPSTORAGE: InitCell<RwLock<GroupStorage>> = InitCell::new();
+pub static GROUPSTORAGE: InstanceCell<RwLock<GroupStorage>> = InstanceCell::new();
 
 /// Group DB links for user account
 #[derive(Clone)]
 pub struct GroupAccountDb {
     /// group DB reference
     /// bincode of `Group`
-    pub groups: sled::Tree,
+    pub groups: Tree,
     /// invited DB ref
     /// bincode of `GroupInvited`
-    pub invited: sled::Tree,
+    pub invited: Tree,
 }
 
 /// qaul Chat Conversation Storage
@@ -76,8 +76,8 @@ impl GroupStorage {
         let db = DataBase::get_user_db(account_id);
 
         // open trees
-        let groups: sled::Tree = db.open_tree("groups").unwrap();
-        let invited: sled::Tree = db.open_tree("invited").unwrap();
+        let groups: Tree = db.open_tree("groups").unwrap();
+        let invited: Tree = db.open_tree("invited").unwrap();
 
         let group_account_db = GroupAccountDb { groups, invited };
 
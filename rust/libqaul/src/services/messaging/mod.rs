@@ -5,18 +5,27 @@
 //!
 //! The messaging service is used for sending, receiving and
 //! relay chat messages.
+//!
+//! If multipath is activated in the messaging options, chat,
+//! group management and crypto handshake messages are sent
+//! over the two best routes simultaneously.
+//! When the neighbours of all routes of such a message disappear
+//! before the message is confirmed, it is immediately
+//! retransmitted over the next available route.
+//!
+//! Messages with a route trace are traced by all nodes
+//! forwarding them. This is used by the reachability probes.
 
 use libp2p::PeerId;
 use prost::Message;
 use serde::{Deserialize, Serialize};
-use sled;
-use state::InitCell;
-use std::collections::VecDeque;
+use std::collections::{HashMap, VecDeque};
 use std::sync::RwLock;
 
 #[cfg(emulate)]
 mod network_emul;
 
+pub mod probe;
 pub mod process;
 pub mod retransmit;
 
@@ -24,8 +33,12 @@ use super::chat::{ChatFile, ChatStorage};
 use super::crypto::Crypto;
 use crate::connections::ConnectionModule;
 use crate::node::user_accounts::{UserAccount, UserAccounts};
-use crate::router::table::RoutingTable;
+use crate::node::Node;
+use crate::router::{neighbours::Neighbours, table::RoutingTable};
+use crate::storage::configuration::Configuration;
 use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp::Timestamp;
 use process::MessagingProcess;
 use qaul_messaging::QaulMessagingReceived;
@@ -37,7 +50,7 @@ pub mod proto {
 }
 
 /// mutable state of messages, scheduled for sending
-pub static MESSAGING: InitCell<RwLock<Messaging>> = InitCell::new();
+pub static MESSAGING: InstanceCell<RwLock<Messaging>> = InstanceCell::new();
 
 /// Messaging Scheduling Structure
 pub struct ScheduledMessage {
@@ -47,10 +60,11 @@ pub struct ScheduledMessage {
     is_forward: bool,
     scheduled_dtn: bool,
     is_dtn: bool,
+    multipath: bool,
 }
 
 /// mutable state of messages, scheduled for sending
-pub static UNCONFIRMED: InitCell<RwLock<UnConfirmedMessages>> = InitCell::new();
+pub static UNCONFIRMED: InstanceCell<RwLock<UnConfirmedMessages>> = InstanceCell::new();
 
 // TODO: check if it wouldn't be easier to store
 // the message
@@ -99,18 +113,33 @@ pub enum MessagingServiceType {
     Rtc,
 }
 
+impl MessagingServiceType {
+    /// messages of this type are sent over multiple routes,
+    /// when multipath is activated in the messaging options
+    pub fn is_multipath(&self) -> bool {
+        matches!(
+            self,
+            MessagingServiceType::Crypto | MessagingServiceType::Group | MessagingServiceType::Chat
+        )
+    }
+}
+
 /// Unconfirmed Messages Structure
 pub struct UnConfirmedMessages {
     /// signature => UnConfirmedMessage
     ///
     /// value: bincode of `UnConfirmedMessage`
-    pub unconfirmed: sled::Tree,
+    pub unconfirmed: Tree,
 }
 
 /// Qaul Messaging Structure
 pub struct Messaging {
     /// ring buffer of messages scheduled for sending
     pub to_send: VecDeque<ScheduledMessage>,
+    /// routes of the unconfirmed multipath messages
+    ///
+    /// signature => neighbour & connection module of each route
+    pub multipath_routes: HashMap<Vec<u8>, Vec<(PeerId, ConnectionModule)>>,
 }
 
 /// Qaul Failed Message Structure
@@ -133,15 +162,19 @@ impl Messaging {
 
         let messaging = Messaging {
             to_send: VecDeque::new(),
+            multipath_routes: HashMap::new(),
         };
         MESSAGING.set(RwLock::new(messaging));
 
         let db = DataBase::get_node_db();
 
         // open trees
-        let unconfirmed: sled::Tree = db.open_tree("unconfirmed").unwrap();
+        let unconfirmed: Tree = db.open_tree("unconfirmed").unwrap();
         let unconfirmed_messages = UnConfirmedMessages { unconfirmed };
         UNCONFIRMED.set(RwLock::new(unconfirmed_messages));
+
+        // initialize reachability probes
+        probe::MessagingProbe::init();
     }
 
     /// Save a message to the data base to wait for confirmation
@@ -194,6 +227,9 @@ impl Messaging {
             bs58::encode(signature).into_string()
         );
 
+        // the routes of a multipath message aren't needed anymore
+        Self::remove_multipath_routes(signature);
+
         let unconfirmed = UNCONFIRMED.get().write().unwrap();
 
         // check and remove unconfirmed from DB
@@ -335,7 +371,7 @@ impl Messaging {
         user_account: &UserAccount,
         receiver: &PeerId,
         data: Vec<u8>,
-        _message_type: MessagingServiceType,
+        message_type: MessagingServiceType,
         message_id: &Vec<u8>,
         message_needs_confirmation: bool,
     ) -> Result<Vec<u8>, String> {
@@ -356,6 +392,7 @@ impl Messaging {
             user_account,
             receiver,
             encrypted_message,
+            message_type,
             message_id,
             message_needs_confirmation,
         );
@@ -369,6 +406,7 @@ impl Messaging {
         user_account: &UserAccount,
         receiver: &PeerId,
         encrypted_message: proto::Encrypted,
+        message_type: MessagingServiceType,
         message_id: &Vec<u8>,
         message_needs_confirmation: bool,
     ) -> Result<Vec<u8>, String> {
@@ -379,6 +417,44 @@ impl Messaging {
             message_needs_confirmation
         );
 
+        // create signed container
+        let container = Self::create_container(user_account, receiver, encrypted_message)?;
+        let signature = container.signature.clone();
+
+        // in common message case, save into unconfirmed table
+        if message_needs_confirmation {
+            Self::save_unconfirmed_message(
+                MessagingServiceType::Chat,
+                message_id,
+                receiver,
+                &container,
+                false,
+            );
+        }
+
+        // schedule message for sending
+        Self::schedule_message(
+            receiver.clone(),
+            container,
+            message_needs_confirmation,
+            false,
+            false,
+            false,
+            message_type.is_multipath(),
+        );
+
+        // return signature
+        Ok(signature)
+    }
+
+    /// pack encrypted message data into a signed container
+    ///
+    /// The container is created without route trace.
+    pub fn create_container(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        encrypted_message: proto::Encrypted,
+    ) -> Result<proto::Container, String> {
         let envelop_payload = proto::EnvelopPayload {
             payload: Some(proto::envelop_payload::Payload::Encrypted(
                 encrypted_message,
@@ -401,34 +477,11 @@ impl Messaging {
         // sign message
         if let Ok(signature) = user_account.keys.sign(&envelope_buf) {
             // create container
-            let container = proto::Container {
-                signature: signature.clone(),
+            Ok(proto::Container {
+                signature,
                 envelope: Some(envelope),
-            };
-
-            // in common message case, save into unconfirmed table
-            if message_needs_confirmation {
-                Self::save_unconfirmed_message(
-                    MessagingServiceType::Chat,
-                    message_id,
-                    receiver,
-                    &container,
-                    false,
-                );
-            }
-
-            // schedule message for sending
-            Self::schedule_message(
-                receiver.clone(),
-                container,
-                message_needs_confirmation,
-                false,
-                false,
-                false,
-            );
-
-            // return signature
-            Ok(signature)
+                trace: Vec::new(),
+            })
         } else {
             return Err("messaging signing error".to_string());
         }
@@ -457,6 +510,7 @@ impl Messaging {
             let container_dtn = proto::Container {
                 signature: signature_dtn.clone(),
                 envelope: Some(envelope_dtn),
+                trace: Vec::new(),
             };
 
             // in common message case, save into unconfirmed table
@@ -476,6 +530,7 @@ impl Messaging {
                 false,
                 true,
                 true,
+                false,
             );
 
             // return signature
@@ -491,6 +546,9 @@ impl Messaging {
     /// This function adds the message to the ring buffer for sending.
     /// This buffer is checked regularly by libqaul for sending.
     ///
+    /// Messages flagged as `multipath` are sent over multiple routes,
+    /// when multipath is activated in the messaging options.
+    ///
     pub fn schedule_message(
         receiver: PeerId,
         container: proto::Container,
@@ -498,6 +556,7 @@ impl Messaging {
         is_forward: bool,
         scheduled_dtn: bool,
         is_dtn: bool,
+        multipath: bool,
     ) {
         #[cfg(emulate)]
         if network_emul::NetworkEmulator::is_lost() {
@@ -515,6 +574,7 @@ impl Messaging {
             is_forward,
             scheduled_dtn,
             is_dtn,
+            multipath,
         };
 
         // add it to sending queue
@@ -526,7 +586,10 @@ impl Messaging {
     ///
     /// Check if there is a message scheduled for sending.
     ///
-    pub fn check_scheduler() -> Option<(PeerId, ConnectionModule, Vec<u8>)> {
+    /// Returns the neighbour, the connection module and the
+    /// binary message of each route the message shall be sent over.
+    ///
+    pub fn check_scheduler() -> Vec<(PeerId, ConnectionModule, Vec<u8>)> {
         let message_item: Option<ScheduledMessage>;
 
         // get scheduled messaging buffer
@@ -536,16 +599,40 @@ impl Messaging {
         }
 
         if let Some(message) = message_item {
-            // check for route
-            if let Some(route) = RoutingTable::get_route_to_user(message.receiver) {
+            // check for routes
+            let multipath = message.multipath && Configuration::get().messaging.multipath;
+            let routes = if multipath {
+                RoutingTable::get_routes_to_user(message.receiver, 2)
+            } else {
+                RoutingTable::get_route_to_user(message.receiver)
+                    .into_iter()
+                    .collect()
+            };
+
+            if !routes.is_empty() {
                 // update unconfirmed table set scheduled flag.
                 Self::on_scheduled_message(&message.container.signature);
 
+                // remember the routes of a multipath message for fail over
+                if multipath && message.is_common {
+                    let mut messaging = MESSAGING.get().write().unwrap();
+                    messaging.multipath_routes.insert(
+                        message.container.signature.clone(),
+                        routes
+                            .iter()
+                            .map(|route| (route.node, route.module))
+                            .collect(),
+                    );
+                }
+
                 // create binary message
                 let data = message.container.encode_to_vec();
 
                 // return information
-                return Some((route.node, route.module, data));
+                return routes
+                    .into_iter()
+                    .map(|route| (route.node, route.module, data.clone()))
+                    .collect();
             } else {
                 // user is offline we schedule through DTN service
                 if !message.is_forward
@@ -579,7 +666,26 @@ impl Messaging {
             }
         }
 
-        None
+        Vec::new()
+    }
+
+    /// Check if all neighbours over which a multipath message
+    /// was sent disappeared
+    ///
+    /// Returns `None` if the message isn't a multipath message.
+    pub fn multipath_routes_lost(signature: &Vec<u8>) -> Option<bool> {
+        let messaging = MESSAGING.get().read().unwrap();
+        messaging.multipath_routes.get(signature).map(|routes| {
+            routes
+                .iter()
+                .all(|(node, module)| Neighbours::get_rtt(node, module).is_none())
+        })
+    }
+
+    /// Remove the routes of a multipath message
+    pub fn remove_multipath_routes(signature: &Vec<u8>) {
+        let mut messaging = MESSAGING.get().write().unwrap();
+        messaging.multipath_routes.remove(signature);
     }
 
     /// Send a confirmation message for a received message
@@ -633,7 +739,17 @@ impl Messaging {
     pub fn received(received: QaulMessagingReceived) {
         // decode message container
         match proto::Container::decode(&received.data[..]) {
-            Ok(container) => {
+            Ok(mut container) => {
+                // add this node to the route trace of traced messages
+                if !container.trace.is_empty() {
+                    let module = Neighbours::is_neighbour(&received.received_from);
+                    container.trace.push(proto::TraceHop {
+                        node_id: Node::get_id().to_bytes(),
+                        module: module.as_int() as u32,
+                        rtt: Neighbours::get_rtt(&received.received_from, &module).unwrap_or(0),
+                    });
+                }
+
                 if let Some(envelope) = container.envelope.clone() {
                     match PeerId::from_bytes(&envelope.receiver_id) {
                         Ok(receiver_id) => {
@@ -654,6 +770,7 @@ impl Messaging {
                                     true,
                                     false,
                                     false,
+                                    false,
                                 ),
                             }
                         }
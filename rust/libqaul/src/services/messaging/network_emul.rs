@@ -1,8 +1,8 @@
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp::{self, Timestamp};
-use state::InitCell;
 use std::sync::RwLock;
 
-static STATE: InitCell<RwLock<NetworkEmulatorStat>> = InitCell::new();
+static STATE: InstanceCell<RwLock<NetworkEmulatorStat>> = InstanceCell::new();
 
 pub struct NetworkEmulatorStat {
     pub loss_rate: u64,
@@ -0,0 +1,333 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Reachability Probes
+//!
+//! Tests the reachability of a remote user by sending probes
+//! through the messaging layer, comparable to ping.
+//!
+//! The probed user answers each probe request with a probe reply.
+//! The round trip time of every probe is measured, and the
+//! loss ratio is calculated over all probes of a probe session.
+//!
+//! Traced probes additionally record the route of the requests
+//! and of the replies: every node forwarding a probe appends its
+//! node ID, the connection module it received the probe on, and the
+//! round trip time to the previous hop to the route trace
+//! of the message container.
+
+use libp2p::PeerId;
+use prost::Message;
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use super::{proto, Messaging};
+use crate::connections::ConnectionModule;
+use crate::node::user_accounts::{UserAccount, UserAccounts};
+use crate::node::Node;
+use crate::router::users::proto as proto_users;
+use crate::rpc::Rpc;
+use crate::services::crypto::Crypto;
+use crate::utilities::instance::InstanceCell;
+use crate::utilities::timestamp::Timestamp;
+
+/// mutable state of the running probe sessions
+static PROBES: InstanceCell<RwLock<MessagingProbe>> = InstanceCell::new();
+
+/// number of probes sent, if no count is requested
+const DEFAULT_COUNT: u32 = 4;
+
+/// maximal number of probes per session
+const MAX_COUNT: u32 = 100;
+
+/// interval between two probes of a session in milli seconds
+const PROBE_INTERVAL: u64 = 1000;
+
+/// time to wait for the replies after the last probe in milli seconds
+const PROBE_TIMEOUT: u64 = 5 * 1000;
+
+/// Probe Session
+struct ProbeSession {
+    /// user account sending the probes
+    account_id: PeerId,
+    /// probed user
+    user_id: PeerId,
+    /// number of probes to send
+    count: u32,
+    /// trace the route of the probes
+    trace: bool,
+    /// number of sent probes
+    sent: u32,
+    /// time when the last probe was sent
+    last_sent: u64,
+    /// sequence number => sent time of all unanswered probes
+    pending: HashMap<u32, u64>,
+    /// round trip times of the answered probes in milli seconds
+    rtts: Vec<u32>,
+    /// route of the last answered probe request
+    forward_route: Vec<proto::TraceHop>,
+    /// route of the last probe reply
+    return_route: Vec<proto::TraceHop>,
+}
+
+/// Reachability Probes
+pub struct MessagingProbe {
+    /// running probe sessions
+    ///
+    /// session id => probe session
+    sessions: HashMap<Vec<u8>, ProbeSession>,
+}
+
+impl MessagingProbe {
+    /// initialize probe sessions state
+    pub fn init() {
+        let probes = MessagingProbe {
+            sessions: HashMap::new(),
+        };
+        PROBES.set(RwLock::new(probes));
+    }
+
+    /// start a new probe session
+    ///
+    /// The first probe is sent immediately,
+    /// the following ones are sent by `process`.
+    pub fn start(account_id: PeerId, user_id: PeerId, count: u32, trace: bool) {
+        let count = match count {
+            0 => DEFAULT_COUNT,
+            count => count.min(MAX_COUNT),
+        };
+
+        let session_id = uuid::Uuid::new_v4().as_bytes().to_vec();
+        {
+            let mut probes = PROBES.get().write().unwrap();
+            probes.sessions.insert(
+                session_id.clone(),
+                ProbeSession {
+                    account_id,
+                    user_id,
+                    count,
+                    trace,
+                    sent: 0,
+                    last_sent: 0,
+                    pending: HashMap::new(),
+                    rtts: Vec::new(),
+                    forward_route: Vec::new(),
+                    return_route: Vec::new(),
+                },
+            );
+        }
+
+        Self::process();
+    }
+
+    /// send the due probes of all sessions
+    /// and finish the timed out sessions
+    ///
+    /// This function is called periodically from the main loop.
+    pub fn process() {
+        let now = Timestamp::get_timestamp();
+        let mut probes_to_send = Vec::new();
+        let mut finished = Vec::new();
+
+        {
+            let mut probes = PROBES.get().write().unwrap();
+            for (session_id, session) in probes.sessions.iter_mut() {
+                if session.sent < session.count {
+                    if now >= session.last_sent + PROBE_INTERVAL {
+                        session.pending.insert(session.sent, now);
+                        probes_to_send.push((
+                            session.account_id,
+                            session.user_id,
+                            proto::Probe {
+                                session_id: session_id.clone(),
+                                sequence: session.sent,
+                                reply: false,
+                                trace: Vec::new(),
+                            },
+                            session.trace,
+                        ));
+                        session.sent += 1;
+                        session.last_sent = now;
+                    }
+                } else if session.pending.is_empty() || now >= session.last_sent + PROBE_TIMEOUT {
+                    finished.push(session_id.clone());
+                }
+            }
+        }
+
+        // send probes
+        for (account_id, user_id, probe, trace) in probes_to_send {
+            match UserAccounts::get_by_id(account_id) {
+                Some(user_account) => {
+                    if let Err(e) = Self::send(&user_account, &user_id, probe, trace) {
+                        log::error!("sending probe failed: {}", e);
+                    }
+                }
+                None => log::error!("probing user account not found"),
+            }
+        }
+
+        // report finished sessions
+        for session_id in finished {
+            let session = {
+                let mut probes = PROBES.get().write().unwrap();
+                probes.sessions.remove(&session_id)
+            };
+            if let Some(session) = session {
+                Self::rpc_send_result(session);
+            }
+        }
+    }
+
+    /// process a received probe
+    pub fn received(
+        sender_id: &PeerId,
+        user_account: &UserAccount,
+        probe: proto::Probe,
+        trace: &Vec<proto::TraceHop>,
+    ) {
+        if !probe.reply {
+            // answer the probe request and return its route trace
+            let traced = !trace.is_empty();
+            let reply = proto::Probe {
+                session_id: probe.session_id,
+                sequence: probe.sequence,
+                reply: true,
+                trace: trace.clone(),
+            };
+            if let Err(e) = Self::send(user_account, sender_id, reply, traced) {
+                log::error!("sending probe reply failed: {}", e);
+            }
+            return;
+        }
+
+        // register the probe reply
+        let now = Timestamp::get_timestamp();
+        let mut finished = None;
+        {
+            let mut probes = PROBES.get().write().unwrap();
+            if let Some(session) = probes.sessions.get_mut(&probe.session_id) {
+                if session.user_id != *sender_id {
+                    return;
+                }
+                if let Some(sent_at) = session.pending.remove(&probe.sequence) {
+                    session.rtts.push(now.saturating_sub(sent_at) as u32);
+                    if session.trace {
+                        session.forward_route = probe.trace;
+                        session.return_route = trace.clone();
+                    }
+                }
+                if session.sent == session.count && session.pending.is_empty() {
+                    finished = probes.sessions.remove(&probe.session_id);
+                }
+            }
+        }
+
+        if let Some(session) = finished {
+            Self::rpc_send_result(session);
+        }
+    }
+
+    /// encrypt a probe and schedule it for sending
+    ///
+    /// Traced probes start their route trace with this node.
+    fn send(
+        user_account: &UserAccount,
+        receiver: &PeerId,
+        probe: proto::Probe,
+        trace: bool,
+    ) -> Result<(), String> {
+        let message = proto::Messaging {
+            message: Some(proto::messaging::Message::Probe(probe)),
+        };
+
+        let encrypted = match Crypto::encrypt(
+            message.encode_to_vec(),
+            user_account.to_owned(),
+            receiver.clone(),
+        ) {
+            Some(encrypted) => encrypted,
+            None => return Err("Encryption error occurred".to_string()),
+        };
+
+        let mut container = Messaging::create_container(user_account, receiver, encrypted)?;
+        if trace {
+            container.trace.push(proto::TraceHop {
+                node_id: Node::get_id().to_bytes(),
+                module: ConnectionModule::Local.as_int() as u32,
+                rtt: 0,
+            });
+        }
+
+        // probes are neither confirmed nor sent via DTN
+        Messaging::schedule_message(
+            receiver.clone(),
+            container,
+            false,
+            false,
+            false,
+            false,
+            false,
+        );
+
+        Ok(())
+    }
+
+    /// send the result of a probe session to the RPC
+    fn rpc_send_result(session: ProbeSession) {
+        let received = session.rtts.len() as u32;
+        let loss = if session.sent > 0 {
+            1.0 - received as f32 / session.sent as f32
+        } else {
+            0.0
+        };
+        let rtt_avg = if received > 0 {
+            (session.rtts.iter().map(|rtt| *rtt as u64).sum::<u64>() / received as u64) as u32
+        } else {
+            0
+        };
+
+        let result = proto_users::UserPingResult {
+            user_id: session.user_id.to_bytes(),
+            sent: session.sent,
+            received,
+            loss,
+            rtt_min: session.rtts.iter().copied().min().unwrap_or(0),
+            rtt_avg,
+            rtt_max: session.rtts.iter().copied().max().unwrap_or(0),
+            forward_route: Self::route_to_proto(&session.forward_route),
+            return_route: Self::route_to_proto(&session.return_route),
+        };
+
+        // create message
+        let proto_message = proto_users::Users {
+            message: Some(proto_users::users::Message::UserPingResult(result)),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Users.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+
+    /// convert a route trace to the RPC hops
+    fn route_to_proto(route: &Vec<proto::TraceHop>) -> Vec<proto_users::UserPingHop> {
+        route
+            .iter()
+            .map(|hop| proto_users::UserPingHop {
+                node_id: hop.node_id.clone(),
+                module: hop.module as i32,
+                rtt: hop.rtt,
+            })
+            .collect()
+    }
+}
@@ -29,6 +29,7 @@ impl MessagingProcess {
         user_account: UserAccount,
         data: &Vec<u8>,
         signature: &Vec<u8>,
+        trace: &Vec<super::proto::TraceHop>,
     ) {
         log::trace!("on_decrypted_message arrived");
 
@@ -93,6 +94,10 @@ impl MessagingProcess {
                     },
                 );
             }
+            Some(super::proto::messaging::Message::Probe(probe)) => {
+                // answer probe request or register probe reply
+                super::probe::MessagingProbe::received(sender_id, &user_account, probe, trace);
+            }
             Some(super::proto::messaging::Message::RtcStreamMessage(_rtc_stream)) => {}
             Some(super::proto::messaging::Message::GroupInviteMessage(group_invite)) => {
                 // TODO: pass on user_account
@@ -108,6 +113,18 @@ impl MessagingProcess {
                 }
             }
             Some(super::proto::messaging::Message::CommonMessage(common)) => {
+                // drop messages of blocked users,
+                // but confirm them so that the sender stops retransmitting
+                if router::users::Users::is_blocked(sender_id) {
+                    log::debug!("message of blocked user {} dropped", sender_id.to_base58());
+                    if let Err(e) =
+                        super::Messaging::send_confirmation(&user_account.id, sender_id, signature)
+                    {
+                        log::error!("send confirmation failed {}", e);
+                    }
+                    return;
+                }
+
                 // create group id
                 let group_id;
                 match GroupId::from_bytes(&common.group_id) {
@@ -302,6 +319,7 @@ impl MessagingProcess {
                             user_account,
                             &decrypted,
                             &container.signature,
+                            &container.trace,
                         );
                     }
                     Some(super::proto::envelop_payload::Payload::Dtn(dtn)) => {
@@ -12,6 +12,28 @@ pub struct Container {
     /// Message envelope
     #[prost(message, optional, tag = "2")]
     pub envelope: ::core::option::Option<Envelope>,
+    /// route trace of traced messages
+    ///
+    /// Every node forwarding a traced message appends
+    /// itself to the trace. The trace is not signed.
+    /// Untraced messages have an empty trace.
+    #[prost(message, repeated, tag = "3")]
+    pub trace: ::prost::alloc::vec::Vec<TraceHop>,
+}
+/// hop of a message route trace
+#[derive(serde::Serialize, serde::Deserialize)]
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct TraceHop {
+    /// node id of the hop
+    #[prost(bytes = "vec", tag = "1")]
+    pub node_id: ::prost::alloc::vec::Vec<u8>,
+    /// connection module via which the message was received,
+    /// the values are the ones of the RPC ConnectionModule enum
+    #[prost(uint32, tag = "2")]
+    pub module: u32,
+    /// round trip time to the previous hop in micro seconds
+    #[prost(uint32, tag = "3")]
+    pub rtt: u32,
 }
 /// message envelop with sender and receiver
 #[derive(serde::Serialize, serde::Deserialize)]
@@ -79,7 +101,7 @@ pub struct Data {
 /// messaging unified message
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct Messaging {
-    #[prost(oneof = "messaging::Message", tags = "1, 2, 3, 4, 5, 6")]
+    #[prost(oneof = "messaging::Message", tags = "1, 2, 3, 4, 5, 6, 7")]
     pub message: ::core::option::Option<messaging::Message>,
 }
 /// Nested message and enum types in `Messaging`.
@@ -104,6 +126,9 @@ pub mod messaging {
         /// common message
         #[prost(message, tag = "6")]
         CommonMessage(super::CommonMessage),
+        /// reachability probe
+        #[prost(message, tag = "7")]
+        Probe(super::Probe),
     }
 }
 /// message received confirmation
@@ -131,6 +156,26 @@ pub struct CryptoService {
     #[prost(bytes = "vec", tag = "1")]
     pub content: ::prost::alloc::vec::Vec<u8>,
 }
+/// reachability probe
+///
+/// Probe requests are answered by the probed user
+/// with a probe reply.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Probe {
+    /// probe session id
+    #[prost(bytes = "vec", tag = "1")]
+    pub session_id: ::prost::alloc::vec::Vec<u8>,
+    /// sequence number of the probe within the session
+    #[prost(uint32, tag = "2")]
+    pub sequence: u32,
+    /// this is the reply of the probed user
+    #[prost(bool, tag = "3")]
+    pub reply: bool,
+    /// route trace of the probe request,
+    /// returned in the probe reply
+    #[prost(message, repeated, tag = "4")]
+    pub trace: ::prost::alloc::vec::Vec<TraceHop>,
+}
 /// rtc stream mesasge
 #[derive(Clone, PartialEq, ::prost::Message)]
 pub struct RtcStreamMessage {
@@ -4,6 +4,12 @@
 //! # Retransmit Qaul Messages
 //!
 //! Messages that couldn't be sent to a user are scheduled for retransmission.
+//!
+//! Multipath messages are retransmitted immediately, when all
+//! neighbours of the routes they were sent over disappeared.
+//!
+//! Messages to users that become reachable again are
+//! retransmitted without waiting for their retransmission timeout.
 
 use libp2p::PeerId;
 use prost::Message;
@@ -21,6 +27,18 @@ impl MessagingRetransmit {
     pub fn process() {
         // get unconfirmed table
         let unconfirmed = super::UNCONFIRMED.get().write().unwrap();
+
+        // forget the routes of confirmed multipath messages
+        {
+            let mut messaging = super::MESSAGING.get().write().unwrap();
+            messaging.multipath_routes.retain(|signature, _| {
+                unconfirmed
+                    .unconfirmed
+                    .contains_key(signature)
+                    .unwrap_or(true)
+            });
+        }
+
         if unconfirmed.unconfirmed.len() == 0 {
             // there are no message to retransmit
             return;
@@ -49,8 +67,9 @@ impl MessagingRetransmit {
                 let qaul_id = QaulId::bytes_to_q8id(unconfirmed_message.receiver_id.clone());
                 //1. check receiver is online
                 if let Some(_hc) = online_users.get(&qaul_id) {
+                    let routes_lost = super::Messaging::multipath_routes_lost(&signature.to_vec());
                     let mut timeout: u64 = 0;
-                    if unconfirmed_message.scheduled {
+                    if unconfirmed_message.scheduled && routes_lost != Some(true) {
                         timeout = 20 * 1000;
                     }
 
@@ -74,6 +93,7 @@ impl MessagingRetransmit {
                                 false,
                                 unconfirmed_message.scheduled_dtn,
                                 unconfirmed_message.is_dtn,
+                                routes_lost.is_some(),
                             );
 
                             // update entry
@@ -106,4 +126,51 @@ impl MessagingRetransmit {
             }
         }
     }
+
+    /// retransmit the unconfirmed messages of users that became reachable
+    ///
+    /// The messages are marked as due,
+    /// for that the next `process` run retransmits them.
+    pub fn users_reachable(q8ids: &[Vec<u8>]) {
+        // the messaging service is initialized after the router
+        let unconfirmed = match super::UNCONFIRMED.try_get() {
+            Some(unconfirmed) => unconfirmed.write().unwrap(),
+            None => return,
+        };
+
+        let mut updated = false;
+        for entry in unconfirmed.unconfirmed.iter() {
+            if let Ok((signature, unconfirmed_message_bytes)) = entry {
+                let mut unconfirmed_message: UnConfirmedMessage =
+                    bincode::deserialize(&unconfirmed_message_bytes).unwrap();
+
+                // message scheduled via DTN, ignore retrans
+                if unconfirmed_message.scheduled_dtn {
+                    continue;
+                }
+
+                let qaul_id = QaulId::bytes_to_q8id(unconfirmed_message.receiver_id.clone());
+                if !q8ids.contains(&qaul_id) {
+                    continue;
+                }
+
+                unconfirmed_message.last_sent = 0;
+                let unconfirmed_message_todb = bincode::serialize(&unconfirmed_message).unwrap();
+                if let Err(_e) = unconfirmed
+                    .unconfirmed
+                    .insert(signature, unconfirmed_message_todb)
+                {
+                    log::error!("updating unconfirmed table error!");
+                } else {
+                    updated = true;
+                }
+            }
+        }
+
+        if updated {
+            if let Err(_e) = unconfirmed.unconfirmed.flush() {
+                log::error!("updating unconfirmed table error!");
+            }
+        }
+    }
 }
@@ -11,8 +11,10 @@
 //! * Messaging: sends and receives direct messages in qaul network.
 //!   It can be accessed by other services.
 //! * Chat: Sends and receives direct chat messages via messaging.
+//! * Contacts: local contact book of every user account.
 
 pub mod chat;
+pub mod contacts;
 pub mod crypto;
 pub mod dtn;
 pub mod feed;
@@ -32,6 +34,7 @@ impl Services {
         feed::Feed::init();
         messaging::Messaging::init();
         chat::Chat::init();
+        contacts::Contacts::init();
         group::Group::init();
         rtc::Rtc::init();
         dtn::Dtn::init();
@@ -8,9 +8,9 @@
 use libp2p::PeerId;
 
 use crate::node::user_accounts::UserAccount;
+use crate::router::users::Users;
 use crate::rpc::Rpc;
 use prost::Message;
-use state::InitCell;
 use std::collections::BTreeMap;
 use std::sync::RwLock;
 
@@ -21,6 +21,7 @@ use super::chat::Chat;
 use super::group;
 use super::group::group_id::GroupId;
 use super::messaging::{proto, Messaging, MessagingServiceType};
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::timestamp;
 use rtc_managing::RtcManaging;
 use rtc_messaging::RtcMessaging;
@@ -58,7 +59,7 @@ pub struct RtcSessions {
 }
 
 /// mutable state for sessions
-pub static RTCSESSIONS: InitCell<RwLock<RtcSessions>> = InitCell::new();
+pub static RTCSESSIONS: InstanceCell<RwLock<RtcSessions>> = InstanceCell::new();
 
 /// Real Time Communication Module
 pub struct Rtc {}
@@ -155,6 +156,12 @@ impl Rtc {
 
     /// Process incoming NET messages for rtc module
     pub fn net(sender_id: &PeerId, receiver_id: &PeerId, data: &Vec<u8>) {
+        // drop RTC requests of blocked users
+        if Users::is_blocked(sender_id) {
+            log::debug!("RTC message of blocked user {} dropped", sender_id);
+            return;
+        }
+
         match proto_net::RtcContainer::decode(&data[..]) {
             Ok(messaging) => match messaging.message {
                 Some(proto_net::rtc_container::Message::RtcSessionRequest(session_req)) => {
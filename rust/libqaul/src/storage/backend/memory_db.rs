@@ -0,0 +1,249 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Memory Storage Backend
+//!
+//! Ephemeral data base, that is only held in memory.
+//! Nothing is written to disk, all data is lost when
+//! libqaul stops.
+
+use sled::IVec;
+use std::{
+    collections::BTreeMap,
+    ops::Bound,
+    sync::{Arc, RwLock},
+};
+
+use super::{BackendIter, DbBackend, TreeBackend};
+
+/// Memory Data Base
+#[derive(Clone, Debug, Default)]
+pub struct MemoryDb {
+    /// all trees of this data base by name
+    trees: Arc<RwLock<BTreeMap<Vec<u8>, MemoryTree>>>,
+}
+
+impl MemoryDb {
+    /// create a new empty memory data base
+    pub fn new() -> MemoryDb {
+        MemoryDb::default()
+    }
+}
+
+impl DbBackend for MemoryDb {
+    fn open_tree(&self, name: &[u8]) -> sled::Result<Arc<dyn TreeBackend>> {
+        let mut trees = self.trees.write().unwrap();
+        let tree = trees
+            .entry(name.to_vec())
+            .or_insert_with(MemoryTree::default);
+        Ok(Arc::new(tree.clone()))
+    }
+
+    fn tree_names(&self) -> Vec<IVec> {
+        let trees = self.trees.read().unwrap();
+        trees
+            .keys()
+            .map(|name| IVec::from(name.as_slice()))
+            .collect()
+    }
+}
+
+/// Memory Data Base Tree
+#[derive(Clone, Debug, Default)]
+pub struct MemoryTree {
+    entries: Arc<RwLock<BTreeMap<Vec<u8>, IVec>>>,
+}
+
+impl MemoryTree {
+    /// create an iterator from a list of entries
+    ///
+    /// The entries are copied, the iterator therefore
+    /// doesn't block the tree.
+    fn to_iter(entries: Vec<(IVec, IVec)>) -> BackendIter {
+        Box::new(entries.into_iter().map(Ok))
+    }
+}
+
+impl TreeBackend for MemoryTree {
+    fn insert(&self, key: &[u8], value: IVec) -> sled::Result<Option<IVec>> {
+        let mut entries = self.entries.write().unwrap();
+        Ok(entries.insert(key.to_vec(), value))
+    }
+
+    fn get(&self, key: &[u8]) -> sled::Result<Option<IVec>> {
+        let entries = self.entries.read().unwrap();
+        Ok(entries.get(key).cloned())
+    }
+
+    fn get_lt(&self, key: &[u8]) -> sled::Result<Option<(IVec, IVec)>> {
+        let entries = self.entries.read().unwrap();
+        let result = entries
+            .range::<[u8], _>((Bound::Unbounded, Bound::Excluded(key)))
+            .next_back()
+            .map(|(k, v)| (IVec::from(k.as_slice()), v.clone()));
+        Ok(result)
+    }
+
+    fn remove(&self, key: &[u8]) -> sled::Result<Option<IVec>> {
+        let mut entries = self.entries.write().unwrap();
+        Ok(entries.remove(key))
+    }
+
+    fn contains_key(&self, key: &[u8]) -> sled::Result<bool> {
+        let entries = self.entries.read().unwrap();
+        Ok(entries.contains_key(key))
+    }
+
+    fn first(&self) -> sled::Result<Option<(IVec, IVec)>> {
+        let entries = self.entries.read().unwrap();
+        let result = entries
+            .iter()
+            .next()
+            .map(|(k, v)| (IVec::from(k.as_slice()), v.clone()));
+        Ok(result)
+    }
+
+    fn last(&self) -> sled::Result<Option<(IVec, IVec)>> {
+        let entries = self.entries.read().unwrap();
+        let result = entries
+            .iter()
+            .next_back()
+            .map(|(k, v)| (IVec::from(k.as_slice()), v.clone()));
+        Ok(result)
+    }
+
+    fn range(&self, range: (Bound<Vec<u8>>, Bound<Vec<u8>>)) -> BackendIter {
+        let entries = self.entries.read().unwrap();
+
+        // an empty or inverted range returns no entries,
+        // BTreeMap would panic
+        let empty = match (&range.0, &range.1) {
+            (Bound::Included(start), Bound::Included(end)) => start > end,
+            (
+                Bound::Included(start) | Bound::Excluded(start),
+                Bound::Included(end) | Bound::Excluded(end),
+            ) => start >= end,
+            _ => false,
+        };
+        if empty {
+            return Self::to_iter(Vec::new());
+        }
+
+        let result = entries
+            .range(range)
+            .map(|(k, v)| (IVec::from(k.as_slice()), v.clone()))
+            .collect();
+        Self::to_iter(result)
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> BackendIter {
+        let entries = self.entries.read().unwrap();
+        let result = entries
+            .range::<[u8], _>((Bound::Included(prefix), Bound::Unbounded))
+            .take_while(|(k, _v)| k.starts_with(prefix))
+            .map(|(k, v)| (IVec::from(k.as_slice()), v.clone()))
+            .collect();
+        Self::to_iter(result)
+    }
+
+    fn len(&self) -> usize {
+        let entries = self.entries.read().unwrap();
+        entries.len()
+    }
+
+    fn clear(&self) -> sled::Result<()> {
+        let mut entries = self.entries.write().unwrap();
+        entries.clear();
+        Ok(())
+    }
+
+    fn flush(&self) -> sled::Result<usize> {
+        // nothing to write to disk
+        Ok(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// collect the keys of an iterator
+    fn keys(iter: BackendIter) -> Vec<Vec<u8>> {
+        iter.map(|res| res.unwrap().0.to_vec()).collect()
+    }
+
+    #[test]
+    fn trees_are_shared() {
+        let db = MemoryDb::new();
+        let tree = db.open_tree(b"tree").unwrap();
+        tree.insert(b"key", IVec::from(b"value".as_slice()))
+            .unwrap();
+
+        let reopened = db.open_tree(b"tree").unwrap();
+        assert_eq!(reopened.get(b"key").unwrap().unwrap(), b"value".as_slice());
+        assert_eq!(db.tree_names(), vec![IVec::from(b"tree".as_slice())]);
+    }
+
+    #[test]
+    fn keys_are_ordered() {
+        let tree = MemoryDb::new().open_tree(b"tree").unwrap();
+        for key in [b"b".as_slice(), b"a\xff", b"a", b"ab"] {
+            tree.insert(key, IVec::from(key)).unwrap();
+        }
+
+        assert_eq!(
+            keys(tree.range((Bound::Unbounded, Bound::Unbounded))),
+            vec![
+                b"a".to_vec(),
+                b"ab".to_vec(),
+                b"a\xff".to_vec(),
+                b"b".to_vec()
+            ]
+        );
+        assert_eq!(tree.first().unwrap().unwrap().0, b"a".as_slice());
+        assert_eq!(tree.last().unwrap().unwrap().0, b"b".as_slice());
+        assert_eq!(tree.get_lt(b"b").unwrap().unwrap().0, b"a\xff".as_slice());
+        assert!(tree.get_lt(b"a").unwrap().is_none());
+        assert_eq!(
+            keys(tree.scan_prefix(b"a")),
+            vec![b"a".to_vec(), b"ab".to_vec(), b"a\xff".to_vec()]
+        );
+    }
+
+    #[test]
+    fn empty_ranges() {
+        let tree = MemoryDb::new().open_tree(b"tree").unwrap();
+        tree.insert(b"a", IVec::from(b"a".as_slice())).unwrap();
+
+        let inverted = (
+            Bound::Included(b"b".to_vec()),
+            Bound::Included(b"a".to_vec()),
+        );
+        assert!(keys(tree.range(inverted)).is_empty());
+        let empty = (
+            Bound::Included(b"a".to_vec()),
+            Bound::Excluded(b"a".to_vec()),
+        );
+        assert!(keys(tree.range(empty)).is_empty());
+        let single = (
+            Bound::Included(b"a".to_vec()),
+            Bound::Included(b"a".to_vec()),
+        );
+        assert_eq!(keys(tree.range(single)), vec![b"a".to_vec()]);
+    }
+
+    #[test]
+    fn remove_and_clear() {
+        let tree = MemoryDb::new().open_tree(b"tree").unwrap();
+        tree.insert(b"a", IVec::from(b"1".as_slice())).unwrap();
+        tree.insert(b"b", IVec::from(b"2".as_slice())).unwrap();
+
+        assert_eq!(tree.remove(b"a").unwrap().unwrap(), b"1".as_slice());
+        assert!(!tree.contains_key(b"a").unwrap());
+        assert_eq!(tree.len(), 1);
+
+        tree.clear().unwrap();
+        assert_eq!(tree.len(), 0);
+        assert!(tree.first().unwrap().is_none());
+    }
+}
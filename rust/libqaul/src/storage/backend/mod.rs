@@ -0,0 +1,76 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage Backends
+//!
+//! Trait layer over the key value data bases used by libqaul.
+//!
+//! The following backends exist:
+//!
+//! * sled: persistent data base on disk (default)
+//! * memory: ephemeral data base in memory, all data is lost
+//!   when libqaul stops.
+//!
+//! All keys are ordered lexicographically by their bytes,
+//! the services rely on this order for range queries.
+
+pub mod memory_db;
+pub mod sled_db;
+
+use sled::IVec;
+use std::{fmt::Debug, ops::Bound, sync::Arc};
+
+/// Iterator over key value pairs of a tree backend
+pub type BackendIter = Box<dyn DoubleEndedIterator<Item = sled::Result<(IVec, IVec)>>>;
+
+/// Data Base Backend
+///
+/// A data base contains named trees.
+pub trait DbBackend: Send + Sync + Debug {
+    /// open a tree, the tree is created if it does not exist
+    fn open_tree(&self, name: &[u8]) -> sled::Result<Arc<dyn TreeBackend>>;
+
+    /// names of all trees in this data base
+    fn tree_names(&self) -> Vec<IVec>;
+}
+
+/// Tree Backend
+///
+/// An ordered key value store.
+pub trait TreeBackend: Send + Sync + Debug {
+    /// insert a value, returns the previous value
+    fn insert(&self, key: &[u8], value: IVec) -> sled::Result<Option<IVec>>;
+
+    /// get a value
+    fn get(&self, key: &[u8]) -> sled::Result<Option<IVec>>;
+
+    /// get the entry with the greatest key, that is smaller than `key`
+    fn get_lt(&self, key: &[u8]) -> sled::Result<Option<(IVec, IVec)>>;
+
+    /// remove a value, returns the removed value
+    fn remove(&self, key: &[u8]) -> sled::Result<Option<IVec>>;
+
+    /// check if the tree contains a key
+    fn contains_key(&self, key: &[u8]) -> sled::Result<bool>;
+
+    /// get first entry
+    fn first(&self) -> sled::Result<Option<(IVec, IVec)>>;
+
+    /// get last entry
+    fn last(&self) -> sled::Result<Option<(IVec, IVec)>>;
+
+    /// iterate over a range of keys
+    fn range(&self, range: (Bound<Vec<u8>>, Bound<Vec<u8>>)) -> BackendIter;
+
+    /// iterate over all keys starting with `prefix`
+    fn scan_prefix(&self, prefix: &[u8]) -> BackendIter;
+
+    /// number of entries
+    fn len(&self) -> usize;
+
+    /// remove all entries
+    fn clear(&self) -> sled::Result<()>;
+
+    /// flush tree to disk, returns the number of bytes flushed
+    fn flush(&self) -> sled::Result<usize>;
+}
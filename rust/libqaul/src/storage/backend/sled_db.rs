@@ -0,0 +1,92 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Sled Storage Backend
+//!
+//! Persistent embedded sled data base on disk.
+
+use sled::IVec;
+use std::{ops::Bound, path::Path, sync::Arc};
+
+use super::{BackendIter, DbBackend, TreeBackend};
+
+/// Sled Data Base
+#[derive(Clone, Debug)]
+pub struct SledDb {
+    db: sled::Db,
+}
+
+impl SledDb {
+    /// open sled data base from disk
+    pub fn open(path: &Path) -> sled::Result<SledDb> {
+        let db = sled::Config::default().path(path).open()?;
+        Ok(SledDb { db })
+    }
+}
+
+impl DbBackend for SledDb {
+    fn open_tree(&self, name: &[u8]) -> sled::Result<Arc<dyn TreeBackend>> {
+        let tree = self.db.open_tree(name)?;
+        Ok(Arc::new(SledTree { tree }))
+    }
+
+    fn tree_names(&self) -> Vec<IVec> {
+        self.db.tree_names()
+    }
+}
+
+/// Sled Data Base Tree
+#[derive(Clone, Debug)]
+pub struct SledTree {
+    tree: sled::Tree,
+}
+
+impl TreeBackend for SledTree {
+    fn insert(&self, key: &[u8], value: IVec) -> sled::Result<Option<IVec>> {
+        self.tree.insert(key, value)
+    }
+
+    fn get(&self, key: &[u8]) -> sled::Result<Option<IVec>> {
+        self.tree.get(key)
+    }
+
+    fn get_lt(&self, key: &[u8]) -> sled::Result<Option<(IVec, IVec)>> {
+        self.tree.get_lt(key)
+    }
+
+    fn remove(&self, key: &[u8]) -> sled::Result<Option<IVec>> {
+        self.tree.remove(key)
+    }
+
+    fn contains_key(&self, key: &[u8]) -> sled::Result<bool> {
+        self.tree.contains_key(key)
+    }
+
+    fn first(&self) -> sled::Result<Option<(IVec, IVec)>> {
+        self.tree.first()
+    }
+
+    fn last(&self) -> sled::Result<Option<(IVec, IVec)>> {
+        self.tree.last()
+    }
+
+    fn range(&self, range: (Bound<Vec<u8>>, Bound<Vec<u8>>)) -> BackendIter {
+        Box::new(self.tree.range(range))
+    }
+
+    fn scan_prefix(&self, prefix: &[u8]) -> BackendIter {
+        Box::new(self.tree.scan_prefix(prefix))
+    }
+
+    fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    fn clear(&self) -> sled::Result<()> {
+        self.tree.clear()
+    }
+
+    fn flush(&self) -> sled::Result<usize> {
+        self.tree.flush()
+    }
+}
@@ -0,0 +1,401 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage Consistency Check
+//!
+//! Scans the data bases of the node and of all user accounts
+//! for inconsistent entries, and optionally repairs them.
+//!
+//! The following inconsistencies are found:
+//!
+//! * node data base
+//!   * `unconfirmed`: unreadable entries, and chat & file messages
+//!     which were already confirmed by the receiver
+//!   * `dtn-messages-ids`: IDs without a stored DTN message
+//!   * `dtn-messages`: unreadable entries, and messages without an ID entry
+//! * user account data bases
+//!   * `file_chunks`: chunks without a file history in `chat_file`
+//!   * `chat_message_ids`: IDs without a chat message in `chat_messages`
+//!   * `groups`: unreadable groups and members which are unknown users
+//!
+//! Repairing removes the orphaned and invalid entries.
+//! Missing DTN message IDs are recreated.
+//! Unreadable groups and unknown group members are only reported.
+//!
+//! File chunks arriving before the file info message have no file
+//! history yet. Repairing while files are being received
+//! removes these chunks, and the file needs to be sent again.
+
+use libp2p::PeerId;
+use prost::Message;
+use sled::IVec;
+
+use crate::node::user_accounts::UserAccounts;
+use crate::router::users::Users;
+use crate::services::chat::{file::FileHistory, rpc_proto::ChatMessage};
+use crate::services::dtn::DtnMessageEntry;
+use crate::services::group::Group;
+use crate::services::messaging::{proto, MessagingServiceType, UnConfirmedMessage};
+use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+
+/// Storage Problem
+pub struct StorageProblem {
+    /// name of the data base tree
+    pub tree: String,
+    /// user account of the data base,
+    /// `None` for the node data base
+    pub account_id: Option<PeerId>,
+    /// key of the entry
+    pub key: Vec<u8>,
+    /// description of the problem
+    pub description: String,
+    /// the problem can be repaired
+    pub repairable: bool,
+    /// the problem was repaired
+    pub repaired: bool,
+}
+
+/// Storage Check Report
+#[derive(Default)]
+pub struct StorageCheckReport {
+    /// number of checked entries
+    pub entries_checked: u32,
+    /// problems found
+    pub problems: Vec<StorageProblem>,
+}
+
+impl StorageCheckReport {
+    /// add a problem to the report
+    fn add(
+        &mut self,
+        tree: &Tree,
+        tree_name: &str,
+        account_id: Option<PeerId>,
+        key: &[u8],
+        description: String,
+        repair: Option<bool>,
+    ) {
+        let repairable = repair.is_some();
+        let mut repaired = false;
+        if repair == Some(true) {
+            match tree.remove(key) {
+                Ok(_) => repaired = true,
+                Err(e) => log::error!("storage check: removing entry failed: {}", e),
+            }
+        }
+
+        self.problems.push(StorageProblem {
+            tree: tree_name.to_string(),
+            account_id,
+            key: key.to_vec(),
+            description,
+            repairable,
+            repaired,
+        });
+    }
+}
+
+/// Storage Consistency Check
+pub struct StorageCheck {}
+
+impl StorageCheck {
+    /// check all data bases
+    ///
+    /// If `repair` is true, the problems found are repaired.
+    pub fn check(repair: bool) -> StorageCheckReport {
+        let mut report = StorageCheckReport::default();
+
+        // node data base
+        Self::check_unconfirmed(&mut report, repair);
+        Self::check_dtn(&mut report, repair);
+
+        // user account data bases
+        for account in UserAccounts::get_user_info() {
+            Self::check_file_chunks(&mut report, account.id, repair);
+            Self::check_chat_message_ids(&mut report, account.id, repair);
+            Self::check_groups(&mut report, account.id);
+        }
+
+        log::info!(
+            "storage check: {} entries checked, {} problems found",
+            report.entries_checked,
+            report.problems.len()
+        );
+
+        report
+    }
+
+    /// read all entries of a tree
+    ///
+    /// Entries which can't be read are added to the report.
+    fn entries(
+        report: &mut StorageCheckReport,
+        tree: &Tree,
+        tree_name: &str,
+        account_id: Option<PeerId>,
+    ) -> Vec<(IVec, IVec)> {
+        let mut entries = Vec::new();
+        for res in tree.iter() {
+            report.entries_checked += 1;
+            match res {
+                Ok(entry) => entries.push(entry),
+                Err(e) => report.problems.push(StorageProblem {
+                    tree: tree_name.to_string(),
+                    account_id,
+                    key: Vec::new(),
+                    description: format!("entry can't be read: {}", e),
+                    repairable: false,
+                    repaired: false,
+                }),
+            }
+        }
+        entries
+    }
+
+    /// flush a repaired tree
+    fn flush(tree: &Tree, repair: bool) {
+        if repair {
+            if let Err(e) = tree.flush() {
+                log::error!("storage check: flush error: {}", e);
+            }
+        }
+    }
+
+    /// check the unconfirmed messages
+    fn check_unconfirmed(report: &mut StorageCheckReport, repair: bool) {
+        let tree_name = "unconfirmed";
+        let tree = DataBase::get_node_db().open_tree(tree_name).unwrap();
+
+        for (key, value) in Self::entries(report, &tree, tree_name, None) {
+            let unconfirmed: UnConfirmedMessage = match bincode::deserialize(&value) {
+                Ok(unconfirmed) => unconfirmed,
+                Err(e) => {
+                    let description = format!("invalid unconfirmed message: {}", e);
+                    report.add(&tree, tree_name, None, &key, description, Some(repair));
+                    continue;
+                }
+            };
+
+            if Self::is_confirmed(&unconfirmed) {
+                let description = "message was already confirmed".to_string();
+                report.add(&tree, tree_name, None, &key, description, Some(repair));
+            }
+        }
+
+        Self::flush(&tree, repair);
+    }
+
+    /// check if the receiver already confirmed a chat or file message
+    fn is_confirmed(unconfirmed: &UnConfirmedMessage) -> bool {
+        // get sending user account
+        let sender_id = match proto::Container::decode(&unconfirmed.container[..]) {
+            Ok(container) => match container.envelope {
+                Some(envelope) => match PeerId::from_bytes(&envelope.sender_id) {
+                    Ok(sender_id) => sender_id,
+                    Err(_) => return false,
+                },
+                None => return false,
+            },
+            Err(_) => return false,
+        };
+        if !UserAccounts::is_account(sender_id) {
+            return false;
+        }
+        let db = DataBase::get_user_db(sender_id);
+
+        match unconfirmed.message_type {
+            MessagingServiceType::Chat => {
+                let ids = db.open_tree("chat_message_ids").unwrap();
+                let messages = db.open_tree("chat_messages").unwrap();
+                if let Ok(Some(db_key)) = ids.get(&unconfirmed.message_id) {
+                    if let Ok(Some(message_bytes)) = messages.get(&db_key) {
+                        if let Ok(message) = bincode::deserialize::<ChatMessage>(&message_bytes) {
+                            return message
+                                .message_reception_confirmed
+                                .iter()
+                                .any(|confirmed| confirmed.user_id == unconfirmed.receiver_id);
+                        }
+                    }
+                }
+            }
+            MessagingServiceType::ChatFile => {
+                let histories = db.open_tree("chat_file").unwrap();
+                if let Ok(Some(history_bytes)) = histories.get(&unconfirmed.message_id) {
+                    if let Ok(history) = bincode::deserialize::<FileHistory>(&history_bytes) {
+                        if let Some(tracking) =
+                            history.reception_tracking.get(&unconfirmed.receiver_id)
+                        {
+                            return tracking.received;
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        false
+    }
+
+    /// check the DTN messages and their IDs
+    fn check_dtn(report: &mut StorageCheckReport, repair: bool) {
+        let db = DataBase::get_node_db();
+        let messages_name = "dtn-messages";
+        let ids_name = "dtn-messages-ids";
+        let messages = db.open_tree(messages_name).unwrap();
+        let ids = db.open_tree(ids_name).unwrap();
+
+        // IDs without a message
+        for (org_sig, signature) in Self::entries(report, &ids, ids_name, None) {
+            if !messages.contains_key(&signature).unwrap_or(true) {
+                let description = "DTN message ID without DTN message".to_string();
+                report.add(&ids, ids_name, None, &org_sig, description, Some(repair));
+            }
+        }
+
+        // messages without an ID
+        for (signature, value) in Self::entries(report, &messages, messages_name, None) {
+            let entry: DtnMessageEntry = match bincode::deserialize(&value) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    let description = format!("invalid DTN message entry: {}", e);
+                    report.add(
+                        &messages,
+                        messages_name,
+                        None,
+                        &signature,
+                        description,
+                        Some(repair),
+                    );
+                    continue;
+                }
+            };
+
+            if !ids.contains_key(&entry.org_sig).unwrap_or(true) {
+                let mut repaired = false;
+                if repair {
+                    match ids.insert(entry.org_sig.clone(), signature.to_vec()) {
+                        Ok(_) => repaired = true,
+                        Err(e) => log::error!("storage check: DTN ID insert error: {}", e),
+                    }
+                }
+                report.problems.push(StorageProblem {
+                    tree: messages_name.to_string(),
+                    account_id: None,
+                    key: signature.to_vec(),
+                    description: "DTN message without DTN message ID".to_string(),
+                    repairable: true,
+                    repaired,
+                });
+            }
+        }
+
+        Self::flush(&messages, repair);
+        Self::flush(&ids, repair);
+    }
+
+    /// check the file chunks of a user account
+    fn check_file_chunks(report: &mut StorageCheckReport, account_id: PeerId, repair: bool) {
+        let db = DataBase::get_user_db(account_id);
+        let tree_name = "file_chunks";
+        let chunks = db.open_tree(tree_name).unwrap();
+        let histories = db.open_tree("chat_file").unwrap();
+
+        for (key, _chunk) in Self::entries(report, &chunks, tree_name, Some(account_id)) {
+            // the key consists of the file ID and the chunk index
+            let orphaned = match key.len() {
+                12 => !histories.contains_key(&key[..8]).unwrap_or(true),
+                _ => true,
+            };
+
+            if orphaned {
+                let description = "file chunk without file history".to_string();
+                report.add(
+                    &chunks,
+                    tree_name,
+                    Some(account_id),
+                    &key,
+                    description,
+                    Some(repair),
+                );
+            }
+        }
+
+        Self::flush(&chunks, repair);
+    }
+
+    /// check the chat message IDs of a user account
+    fn check_chat_message_ids(report: &mut StorageCheckReport, account_id: PeerId, repair: bool) {
+        let db = DataBase::get_user_db(account_id);
+        let tree_name = "chat_message_ids";
+        let ids = db.open_tree(tree_name).unwrap();
+        let messages = db.open_tree("chat_messages").unwrap();
+
+        for (message_id, db_key) in Self::entries(report, &ids, tree_name, Some(account_id)) {
+            if !messages.contains_key(&db_key).unwrap_or(true) {
+                let description = "chat message ID without chat message".to_string();
+                report.add(
+                    &ids,
+                    tree_name,
+                    Some(account_id),
+                    &message_id,
+                    description,
+                    Some(repair),
+                );
+            }
+        }
+
+        Self::flush(&ids, repair);
+    }
+
+    /// check the groups of a user account
+    fn check_groups(report: &mut StorageCheckReport, account_id: PeerId) {
+        let db = DataBase::get_user_db(account_id);
+        let tree_name = "groups";
+        let groups = db.open_tree(tree_name).unwrap();
+
+        for (group_id, value) in Self::entries(report, &groups, tree_name, Some(account_id)) {
+            let group: Group = match bincode::deserialize(&value) {
+                Ok(group) => group,
+                Err(e) => {
+                    let description = format!("invalid group: {}", e);
+                    report.add(
+                        &groups,
+                        tree_name,
+                        Some(account_id),
+                        &group_id,
+                        description,
+                        None,
+                    );
+                    continue;
+                }
+            };
+
+            for member_id in group.members.keys() {
+                let known = match PeerId::from_bytes(member_id) {
+                    Ok(member_id) => {
+                        UserAccounts::is_account(member_id)
+                            || Users::get_pub_key(&member_id).is_some()
+                    }
+                    Err(_) => false,
+                };
+
+                if !known {
+                    let description = format!(
+                        "group {} references unknown user {}",
+                        group.name,
+                        bs58::encode(member_id).into_string()
+                    );
+                    report.add(
+                        &groups,
+                        tree_name,
+                        Some(account_id),
+                        &group_id,
+                        description,
+                        None,
+                    );
+                }
+            }
+        }
+    }
+}
@@ -8,23 +8,49 @@
 //! On the first startup a `config.yaml` file is saved.
 //! It can be configured and will be read on the next startup.
 //! All options are configurable from the command-line too.
-
-use config::{Config, File};
-use serde::{Deserialize, Serialize};
-use state::InitCell;
+//!
+//! Ephemeral nodes keep their configuration only in memory.
+//!
+//! Missing options are filled in with their default values.
+//! Errors in the configuration file don't stop libqaul,
+//! they are reported via the log and the configuration RPC module.
+//! Only invalid node keys or encryption options stop libqaul,
+//! user accounts with invalid keys are skipped.
+
+use base64::Engine;
+use config::{Config, ConfigError, File, Value};
+use libp2p::{
+    identity::{ed25519, Keypair},
+    multiaddr::Protocol,
+    Multiaddr, PeerId,
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
 use std::{
     fs,
     path::Path,
     sync::{RwLock, RwLockReadGuard, RwLockWriteGuard},
 };
 
+use crate::connections::ConnectionModule;
+use crate::utilities::instance::InstanceCell;
+
 /// make configuration globally accessible mutable state
-static CONFIG: InitCell<RwLock<Configuration>> = InitCell::new();
+static CONFIG: InstanceCell<RwLock<Configuration>> = InstanceCell::new();
+
+/// errors found in the configuration file on startup
+static STARTUP_ERRORS: InstanceCell<RwLock<Vec<String>>> = InstanceCell::new();
+
+/// maximal hop count penalty in seconds
+///
+/// The link quality of a route over 255 hops needs
+/// to fit into an u32 in micro seconds.
+const MAX_HOP_COUNT_PENALTY: u64 = 15;
 
 /// Configuration of the local Node
 ///
 /// Here the keys and identity are stored
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct Node {
     pub initialized: u8,
     pub id: String,
@@ -43,6 +69,7 @@ impl Default for Node {
 
 /// LAN Connection Module
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct Lan {
     pub active: bool,
     pub listen: Vec<String>,
@@ -65,12 +92,15 @@ impl Default for Lan {
 #[derive(Debug, Deserialize, Clone, Serialize)]
 pub struct InternetPeer {
     pub address: String,
+    #[serde(default)]
     pub name: String,
+    #[serde(default)]
     pub enabled: bool,
 }
 
 /// Internet Overlay Connection Module
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct Internet {
     pub active: bool,
     pub peers: Vec<InternetPeer>,
@@ -129,6 +159,7 @@ impl Default for Internet {
 
 /// local user accounts that are stored on this node
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct UserAccount {
     pub name: String,
     pub id: String,
@@ -153,6 +184,7 @@ impl Default for UserAccount {
 ///
 /// * logging to file
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct DebugOption {
     pub log: bool,
 }
@@ -170,6 +202,7 @@ impl Default for DebugOption {
 /// because rtt is measured as micro seconds
 /// * routing options
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct RoutingOptions {
     //Sending the table every 10 seconds to direct neighbours.
     pub sending_table_period: u64,
@@ -179,6 +212,11 @@ pub struct RoutingOptions {
     pub hop_count_penalty: u64,
     //How long a route is stored until it is removed.
     pub maintain_period_limit: u64,
+    //Metric to calculate the link quality of a route.
+    pub link_quality_metric: LinkQualityMetric,
+    //Preference of the connection modules, from most to least preferred.
+    //Only used to decide between routes of equal quality.
+    pub module_preference: Vec<ConnectionModule>,
 }
 
 impl Default for RoutingOptions {
@@ -188,29 +226,298 @@ impl Default for RoutingOptions {
             ping_neighbour_period: 5,   //5  seconds, unit: seconds
             hop_count_penalty: 10,      //10 seconds, unit: second
             maintain_period_limit: 300, //5min, unit: second
+            link_quality_metric: LinkQualityMetric::HopCount,
+            module_preference: vec![
+                ConnectionModule::Local,
+                ConnectionModule::Lan,
+                ConnectionModule::Internet,
+                ConnectionModule::Ble,
+                ConnectionModule::Virtual,
+            ],
+        }
+    }
+}
+
+impl RoutingOptions {
+    /// Validate the routing options
+    ///
+    /// Returns a list of all errors found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        // periods can't be zero
+        if self.sending_table_period == 0 {
+            errors.push("routing.sending_table_period needs to be at least 1 second".to_string());
+        }
+        if self.ping_neighbour_period == 0 {
+            errors.push("routing.ping_neighbour_period needs to be at least 1 second".to_string());
+        }
+
+        // routes need to be refreshed before they are removed
+        if self.maintain_period_limit <= self.sending_table_period {
+            errors.push(format!(
+                "routing.maintain_period_limit needs to be longer than routing.sending_table_period ({} seconds)",
+                self.sending_table_period
+            ));
+        }
+
+        // the link quality is calculated in micro seconds as u32
+        if self.hop_count_penalty > MAX_HOP_COUNT_PENALTY {
+            errors.push(format!(
+                "routing.hop_count_penalty can be at most {} seconds",
+                MAX_HOP_COUNT_PENALTY
+            ));
+        }
+
+        // every connection module can only be listed once
+        for (i, module) in self.module_preference.iter().enumerate() {
+            if *module == ConnectionModule::None {
+                errors.push("routing.module_preference can't contain None".to_string());
+            } else if self.module_preference[..i].contains(module) {
+                errors.push(format!(
+                    "routing.module_preference contains {:?} more than once",
+                    module
+                ));
+            }
         }
+
+        errors
     }
 }
 
+/// Link Quality Metric
+///
+/// The metric used by the router to calculate
+/// the link quality of a route.
+#[derive(Debug, Deserialize, Clone, Copy, Serialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkQualityMetric {
+    /// round trip time plus a penalty per hop
+    HopCount,
+    /// loss aware expected transmission count
+    Etx,
+    /// weighted by the bandwidth of the connection module
+    Bandwidth,
+}
+
 /// Storage Configuration Options
 ///
 /// The following options can be configured:
-/// size_total units are MB
+/// size_total & file_store_size units are MB
 /// * storage options
+/// * retention policies, 0 disables a policy
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct StorageOptions {
     //storage node users
     pub users: Vec<String>,
     //Sending the table every 10 seconds to direct neighbours.
     pub size_total: u32,
+    //Chat messages older than this number of days are deleted.
+    pub chat_retention_days: u32,
+    //The oldest files are deleted when the file store exceeds this size.
+    pub file_store_size: u32,
 }
 
 impl Default for StorageOptions {
     fn default() -> Self {
         StorageOptions {
             users: vec![],
-            size_total: 1024, //1024 MB
+            size_total: 1024,       //1024 MB
+            chat_retention_days: 0, //keep all chat messages
+            file_store_size: 0,     //no limit
+        }
+    }
+}
+
+/// Storage Maintenance Options
+///
+/// The storage maintenance enforces the retention policies
+/// of the user accounts periodically.
+///
+/// The following options can be configured:
+/// * period: interval of the maintenance in seconds
+/// * feed_max_messages: the oldest feed messages are deleted
+///   when there are more messages, 0 keeps all messages
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct MaintenanceOptions {
+    pub period: u64,
+    pub feed_max_messages: u64,
+}
+
+impl Default for MaintenanceOptions {
+    fn default() -> Self {
+        MaintenanceOptions {
+            period: 3600,         //1 hour, unit: seconds
+            feed_max_messages: 0, //keep all feed messages
+        }
+    }
+}
+
+impl MaintenanceOptions {
+    /// Validate the maintenance options
+    ///
+    /// Returns a list of all errors found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        if self.period == 0 {
+            errors.push("maintenance.period needs to be at least 1 second".to_string());
+        }
+
+        errors
+    }
+}
+
+/// Messaging Options
+///
+/// The following options can be configured:
+/// * multipath: important messages, such as chat messages,
+///   group management and crypto handshakes, are sent over
+///   the two best routes simultaneously, and are retransmitted
+///   immediately when the neighbours of their routes disappear
+#[derive(Debug, Deserialize, Clone, Default, Serialize)]
+#[serde(default)]
+pub struct MessagingOptions {
+    pub multipath: bool,
+}
+
+/// Rate Limit
+///
+/// Token bucket limiting the messages of a message class per neighbour.
+///
+/// * rate: messages per second the bucket is refilled with,
+///   0 disables the limit
+/// * burst: size of the bucket, the number of messages
+///   a neighbour can send at once
+#[derive(Debug, Deserialize, Clone, Copy, Default, Serialize, PartialEq)]
+#[serde(default)]
+pub struct RateLimit {
+    pub rate: u32,
+    pub burst: u32,
+}
+
+/// Rate Limit Options
+///
+/// Protects the node against neighbours flooding it with messages.
+/// The messages exceeding a limit are dropped.
+///
+/// The following message classes are limited per neighbour:
+/// * router_info: routing information messages
+/// * feed: received feed messages, that are flooded further
+/// * feed_request: feed requests of the neighbour
+/// * user_request: user requests of the neighbour
+///
+/// The responses to the requests this node sends are not limited.
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct RateLimitOptions {
+    pub router_info: RateLimit,
+    pub feed: RateLimit,
+    pub feed_request: RateLimit,
+    pub user_request: RateLimit,
+}
+
+impl Default for RateLimitOptions {
+    fn default() -> Self {
+        RateLimitOptions {
+            router_info: RateLimit {
+                rate: 10,  //10 messages per second
+                burst: 50, //50 messages
+            },
+            feed: RateLimit {
+                rate: 20,   //20 messages per second
+                burst: 100, //100 messages
+            },
+            feed_request: RateLimit {
+                rate: 1,   //1 request per second
+                burst: 10, //10 requests
+            },
+            user_request: RateLimit {
+                rate: 1,   //1 request per second
+                burst: 10, //10 requests
+            },
+        }
+    }
+}
+
+impl RateLimitOptions {
+    /// Validate the rate limit options
+    ///
+    /// Returns a list of all errors found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let limits = [
+            ("router_info", &self.router_info),
+            ("feed", &self.feed),
+            ("feed_request", &self.feed_request),
+            ("user_request", &self.user_request),
+        ];
+        for (name, limit) in limits {
+            // an active limit needs to let messages pass
+            if limit.rate > 0 && limit.burst == 0 {
+                errors.push(format!(
+                    "rate_limits.{}.burst needs to be at least 1 message",
+                    name
+                ));
+            }
+        }
+
+        errors
+    }
+}
+
+/// Storage Encryption Options
+///
+/// The encryption is activated by providing a passphrase
+/// on startup. All fields are managed by libqaul.
+///
+/// * active: data bases and private keys are encrypted
+/// * salt: base64 encoded salt of the key derivation
+/// * check: base64 encoded encrypted value to verify the passphrase
+#[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
+pub struct EncryptionOptions {
+    pub active: bool,
+    pub salt: String,
+    pub check: String,
+}
+
+impl Default for EncryptionOptions {
+    fn default() -> Self {
+        EncryptionOptions {
+            active: false,
+            salt: String::from(""),
+            check: String::from(""),
+        }
+    }
+}
+
+impl EncryptionOptions {
+    /// Validate the encryption options
+    ///
+    /// Returns a list of all errors found.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        let values = [("salt", &self.salt), ("check", &self.check)];
+        for (name, value) in values {
+            // an encrypted storage can't be unlocked without them
+            if value.is_empty() {
+                if self.active {
+                    errors.push(format!(
+                        "encryption.{} is missing for the active encryption",
+                        name
+                    ));
+                }
+            } else if let Err(e) = base64::engine::general_purpose::STANDARD.decode(value) {
+                errors.push(format!("encryption.{} is not base64 encoded: {}", name, e));
+            }
         }
+
+        errors
     }
 }
 
@@ -218,6 +525,7 @@ impl Default for StorageOptions {
 ///
 /// This structure contains the entire configuration of libqaul.
 #[derive(Debug, Deserialize, Clone, Serialize)]
+#[serde(default)]
 pub struct Configuration {
     pub node: Node,
     pub lan: Lan,
@@ -225,6 +533,10 @@ pub struct Configuration {
     pub user_accounts: Vec<UserAccount>,
     pub debug: DebugOption,
     pub routing: RoutingOptions,
+    pub encryption: EncryptionOptions,
+    pub maintenance: MaintenanceOptions,
+    pub messaging: MessagingOptions,
+    pub rate_limits: RateLimitOptions,
 }
 
 impl Default for Configuration {
@@ -236,6 +548,10 @@ impl Default for Configuration {
             user_accounts: Vec::new(),
             debug: DebugOption::default(),
             routing: RoutingOptions::default(),
+            encryption: EncryptionOptions::default(),
+            maintenance: MaintenanceOptions::default(),
+            messaging: MessagingOptions::default(),
+            rate_limits: RateLimitOptions::default(),
         }
     }
 }
@@ -243,25 +559,190 @@ impl Default for Configuration {
 /// Configuration implementation of libqaul
 impl Configuration {
     /// Initialize configuration
+    ///
+    /// An invalid configuration file does not stop libqaul.
+    /// The invalid file is kept as `config.yaml.invalid` and
+    /// the invalid sections are replaced by their default values.
+    /// All errors found are available via `startup_errors()`.
     pub fn init() {
         // create configuration path
         let path_string = super::Storage::get_path();
         let path = Path::new(path_string.as_str());
         let config_path = path.join("config.yaml");
 
-        let config: Configuration = match Config::builder()
+        // ephemeral nodes always start with a new configuration
+        if super::Storage::is_ephemeral() {
+            CONFIG.set(RwLock::new(Configuration::default()));
+            STARTUP_ERRORS.set(RwLock::new(Vec::new()));
+            return;
+        }
+
+        let mut errors: Vec<String> = Vec::new();
+
+        let mut config: Configuration = match Config::builder()
             .add_source(File::with_name(&config_path.to_str().unwrap()))
             .build()
         {
-            Err(_) => {
-                log::error!("no configuration file found, creating one.");
+            Err(e) => {
+                if config_path.exists() {
+                    errors.push(format!("configuration file is not readable: {}", e));
+                    Self::keep_invalid_file(&config_path, &mut errors);
+                } else {
+                    log::error!("no configuration file found, creating one.");
+                }
                 Configuration::default()
             }
-            Ok(c) => c.try_deserialize::<Configuration>().unwrap(),
+            Ok(c) => match c.clone().try_deserialize::<Configuration>() {
+                Ok(config) => config,
+                Err(e) => {
+                    errors.push(format!("invalid configuration file: {}", e));
+                    Self::keep_invalid_file(&config_path, &mut errors);
+                    Self::recover(&c, &mut errors)
+                }
+            },
         };
 
+        // validate the configuration values
+        errors.append(&mut config.validate());
+        if !config.routing.validate().is_empty() {
+            errors.push("the default routing options are used".to_string());
+            config.routing = RoutingOptions::default();
+        }
+        if !config.maintenance.validate().is_empty() {
+            errors.push("the default maintenance options are used".to_string());
+            config.maintenance = MaintenanceOptions::default();
+        }
+        if !config.rate_limits.validate().is_empty() {
+            errors.push("the default rate limits are used".to_string());
+            config.rate_limits = RateLimitOptions::default();
+        }
+
+        // check the port configuration option
+        if let Some(port) = super::super::get_default_config("port") {
+            if port.parse::<u16>().is_err() {
+                errors.push(format!(
+                    "port option: {} is not a valid port, a random port is used",
+                    port
+                ));
+            }
+        }
+
+        for error in &errors {
+            log::error!("configuration error: {}", error);
+        }
+
         // put configuration to state
         CONFIG.set(RwLock::new(config));
+        STARTUP_ERRORS.set(RwLock::new(errors));
+    }
+
+    /// recover the valid sections of an invalid configuration file
+    ///
+    /// Invalid sections are replaced by their default values,
+    /// invalid user accounts are skipped.
+    fn recover(c: &Config, errors: &mut Vec<String>) -> Configuration {
+        let mut config = Configuration::default();
+
+        Self::recover_section(c, "node", &mut config.node, errors);
+        Self::recover_section(c, "lan", &mut config.lan, errors);
+        Self::recover_section(c, "internet", &mut config.internet, errors);
+        Self::recover_section(c, "debug", &mut config.debug, errors);
+        Self::recover_section(c, "routing", &mut config.routing, errors);
+        Self::recover_section(c, "encryption", &mut config.encryption, errors);
+        Self::recover_section(c, "maintenance", &mut config.maintenance, errors);
+        Self::recover_section(c, "messaging", &mut config.messaging, errors);
+        Self::recover_section(c, "rate_limits", &mut config.rate_limits, errors);
+
+        // recover every user account on its own
+        let mut accounts: Vec<Value> = Vec::new();
+        Self::recover_section(c, "user_accounts", &mut accounts, errors);
+        for (i, account) in accounts.into_iter().enumerate() {
+            match account.try_deserialize::<UserAccount>() {
+                Ok(account) => config.user_accounts.push(account),
+                Err(e) => errors.push(format!("user_accounts[{}]: {}, account skipped", i, e)),
+            }
+        }
+
+        config
+    }
+
+    /// recover a section of an invalid configuration file
+    ///
+    /// The section keeps its default value if it is missing or invalid.
+    fn recover_section<T: DeserializeOwned>(
+        c: &Config,
+        key: &str,
+        section: &mut T,
+        errors: &mut Vec<String>,
+    ) {
+        match c.get::<T>(key) {
+            Ok(value) => *section = value,
+            Err(ConfigError::NotFound(_)) => {}
+            Err(e) => errors.push(format!("{}: {}, using default values", key, e)),
+        }
+    }
+
+    /// keep a copy of an invalid configuration file
+    ///
+    /// The configuration file is overwritten on the next save.
+    fn keep_invalid_file(config_path: &Path, errors: &mut Vec<String>) {
+        let invalid_path = config_path.with_extension("yaml.invalid");
+        match fs::copy(config_path, &invalid_path) {
+            Ok(_) => errors.push(format!(
+                "the invalid configuration file was saved to {}",
+                invalid_path.display()
+            )),
+            Err(e) => errors.push(format!(
+                "the invalid configuration file could not be saved: {}",
+                e
+            )),
+        }
+    }
+
+    /// Get the errors found in the configuration file on startup
+    pub fn startup_errors() -> Vec<String> {
+        STARTUP_ERRORS.get().read().unwrap().clone()
+    }
+
+    /// Add an error found on startup
+    ///
+    /// This is used for errors, that can only be found after
+    /// the storage has been unlocked, e.g. in the private keys.
+    pub fn add_startup_error(error: String) {
+        STARTUP_ERRORS.get().write().unwrap().push(error);
+    }
+
+    /// Decode the private keys of the node or a user account
+    ///
+    /// The decrypted keys need to be a base64 encoded ed25519 keypair,
+    /// whose ID is the configured ID.
+    /// Returns an error message if the keys are invalid.
+    pub fn decode_keys(field: &str, id: &str, keys: &str) -> Result<Keypair, String> {
+        let mut key_bytes = match base64::engine::general_purpose::STANDARD.decode(keys) {
+            Ok(key_bytes) => key_bytes,
+            Err(e) => {
+                return Err(format!(
+                    "{}: keys of {} are not base64 encoded: {}",
+                    field, id, e
+                ))
+            }
+        };
+        let ed25519_keys = match ed25519::Keypair::try_from_bytes(&mut key_bytes) {
+            Ok(ed25519_keys) => ed25519_keys,
+            Err(e) => return Err(format!("{}: keys of {} are invalid: {}", field, id, e)),
+        };
+        let keys = Keypair::from(ed25519_keys);
+
+        // check if the configured ID is the ID of the keys
+        let keys_id = PeerId::from(keys.public());
+        if keys_id.to_string() != id {
+            return Err(format!(
+                "{}: keys of {} belong to the ID {}",
+                field, id, keys_id
+            ));
+        }
+
+        Ok(keys)
     }
 
     /// Load a configuration file for upgrading purposes
@@ -269,10 +750,19 @@ impl Configuration {
     /// This function is only to be used for the upgrading procedure.
     /// Libqaul uses the `init()` function to load and initialize the configuration!
     pub fn load(path: &str) -> Option<Configuration> {
-        if let Ok(c) = Config::builder().add_source(File::with_name(path)).build() {
-            return Some(c.try_deserialize::<Configuration>().unwrap());
+        match Config::builder().add_source(File::with_name(path)).build() {
+            Ok(c) => match c.try_deserialize::<Configuration>() {
+                Ok(config) => Some(config),
+                Err(e) => {
+                    log::error!("invalid configuration file {}: {}", path, e);
+                    None
+                }
+            },
+            Err(e) => {
+                log::error!("configuration file {} not readable: {}", path, e);
+                None
+            }
         }
-        None
     }
 
     /// lend configuration for reading
@@ -336,6 +826,50 @@ impl Configuration {
         config_mutable.debug.log
     }
 
+    /// Validate the configuration
+    ///
+    /// Returns a list of all errors found.
+    /// The list is empty if the configuration is valid.
+    pub fn validate(&self) -> Vec<String> {
+        let mut errors = Vec::new();
+
+        // listen addresses & peers need to be valid multiaddresses
+        for address in &self.lan.listen {
+            if let Some(error) = validate_address("lan.listen", address, true) {
+                errors.push(error);
+            }
+        }
+        for address in &self.internet.listen {
+            if let Some(error) = validate_address("internet.listen", address, true) {
+                errors.push(error);
+            }
+        }
+        for peer in &self.internet.peers {
+            if let Some(error) = validate_address("internet.peers", &peer.address, false) {
+                errors.push(error);
+            }
+        }
+
+        // storage users need to be valid user IDs
+        for account in &self.user_accounts {
+            for user in &account.storage.users {
+                if let Err(e) = user.parse::<PeerId>() {
+                    errors.push(format!(
+                        "user_accounts.storage.users: invalid user ID {} of account {}: {}",
+                        user, account.id, e
+                    ));
+                }
+            }
+        }
+
+        errors.append(&mut self.routing.validate());
+        errors.append(&mut self.maintenance.validate());
+        errors.append(&mut self.rate_limits.validate());
+        errors.append(&mut self.encryption.validate());
+
+        errors
+    }
+
     /// Returns true/false whether this node has been initialized,
     /// or needs to be created for the first time.
     pub fn is_node_initialized() -> bool {
@@ -348,6 +882,11 @@ impl Configuration {
 
     /// Save current configuration to config.yaml file
     pub fn save() {
+        // ephemeral nodes don't write anything to disk
+        if super::Storage::is_ephemeral() {
+            return;
+        }
+
         let config = CONFIG.get();
 
         // create yaml configuration format
@@ -364,3 +903,93 @@ impl Configuration {
             .expect(&format!("Could not write config to {:?}.", config_path));
     }
 }
+
+/// Validate a multiaddress of the configuration
+///
+/// Listening addresses can use port 0 for a random port,
+/// addresses to connect to need a port.
+/// Returns an error message if the address is invalid.
+fn validate_address(field: &str, address: &str, listen: bool) -> Option<String> {
+    let multiaddr: Multiaddr = match address.parse() {
+        Ok(multiaddr) => multiaddr,
+        Err(e) => return Some(format!("{}: invalid address {}: {}", field, address, e)),
+    };
+
+    let port = multiaddr.iter().find_map(|protocol| match protocol {
+        Protocol::Tcp(port) | Protocol::Udp(port) => Some(port),
+        _ => None,
+    });
+
+    match port {
+        None => Some(format!(
+            "{}: address {} has no tcp or udp port",
+            field, address
+        )),
+        Some(0) if !listen => Some(format!("{}: address {} needs a port", field, address)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// create the base64 encoded keys and the ID of a new keypair
+    fn create_keys() -> (String, String) {
+        let keys = Keypair::generate_ed25519();
+        let id = PeerId::from(keys.public()).to_string();
+        let encoded = base64::engine::general_purpose::STANDARD
+            .encode(keys.try_into_ed25519().unwrap().to_bytes());
+        (id, encoded)
+    }
+
+    /// create a default configuration
+    fn default_config() -> Configuration {
+        // the default internet options read the default configuration
+        crate::DEFCONFIGS.set(std::collections::BTreeMap::new());
+        Configuration::default()
+    }
+
+    #[test]
+    fn valid_keys_are_decoded() {
+        let (id, keys) = create_keys();
+        let decoded = Configuration::decode_keys("node", &id, &keys).unwrap();
+        assert_eq!(PeerId::from(decoded.public()).to_string(), id);
+    }
+
+    #[test]
+    fn invalid_keys_are_rejected() {
+        let (id, keys) = create_keys();
+        let (other_id, _) = create_keys();
+
+        assert!(Configuration::decode_keys("node", &id, "").is_err());
+        assert!(Configuration::decode_keys("node", &id, "no base64!").is_err());
+        assert!(Configuration::decode_keys("node", &id, "AAAA").is_err());
+        assert!(Configuration::decode_keys("node", &other_id, &keys).is_err());
+    }
+
+    #[test]
+    fn validate_checks_rate_limits() {
+        let mut config = default_config();
+        assert!(config.validate().is_empty());
+
+        config.rate_limits.user_request = RateLimit { rate: 1, burst: 0 };
+        assert_eq!(config.validate().len(), 1);
+    }
+
+    #[test]
+    fn validate_checks_encryption() {
+        let mut config = default_config();
+        config.encryption.active = true;
+        assert_eq!(config.validate().len(), 2);
+
+        config.encryption.salt = "7XlCnvlZ1SEzJ0wD9xHgBQ==".to_string();
+        config.encryption.check = "no base64!".to_string();
+        assert_eq!(config.validate().len(), 1);
+
+        // an interrupted encryption is valid
+        config.encryption.active = false;
+        config.encryption.check = "UUVOQw==".to_string();
+        assert!(config.validate().is_empty());
+    }
+}
@@ -4,28 +4,33 @@
 //! # Libqaul Database
 //!
 //! Embedded sled database.
+//!
+//! When libqaul runs ephemerally, the data bases are
+//! only held in memory.
 
 use libp2p::PeerId;
-use sled;
-use state::InitCell;
 use std::{collections::BTreeMap, path::Path, sync::RwLock};
 
+use super::tree::{Db, Tree};
 use crate::router::users::UserData;
+use crate::utilities::instance::InstanceCell;
 
 /// make database globally accessible
-static DATABASE: InitCell<RwLock<DataBase>> = InitCell::new();
+static DATABASE: InstanceCell<RwLock<DataBase>> = InstanceCell::new();
 
 /// DataBase Module
 #[derive(Clone, Debug)]
 pub struct DataBase {
     /// storage path
     pub path: String,
+    /// data bases are only held in memory
+    pub ephemeral: bool,
     /// node data base
-    pub node: sled::Db,
+    pub node: Db,
     /// user data bases
     /// each user account has an own data base
     /// that is opened on request
-    pub users: BTreeMap<Vec<u8>, sled::Db>,
+    pub users: BTreeMap<Vec<u8>, Db>,
 }
 
 impl DataBase {
@@ -38,14 +43,13 @@ impl DataBase {
         let db_path = path.join("node.db");
 
         // open node data base
-        let node = sled::Config::default()
-            .path(db_path)
-            .open()
-            .expect("Failed to open sled db");
+        let ephemeral = super::Storage::is_ephemeral();
+        let node = Self::open(&db_path, ephemeral);
 
         // create data base structure
         let database = DataBase {
             path: path_string,
+            ephemeral,
             node,
             users: BTreeMap::new(),
         };
@@ -54,8 +58,20 @@ impl DataBase {
         DATABASE.set(RwLock::new(database));
     }
 
+    /// open a data base
+    ///
+    /// The data base is opened from disk, or created in memory
+    /// if libqaul runs ephemerally.
+    fn open(db_path: &Path, ephemeral: bool) -> Db {
+        if ephemeral {
+            return Db::memory();
+        }
+
+        Db::sled(db_path).expect("Failed to open sled db")
+    }
+
     /// get node DB
-    pub fn get_node_db() -> sled::Db {
+    pub fn get_node_db() -> Db {
         let database = DATABASE.get().read().unwrap();
         database.node.clone()
     }
@@ -65,7 +81,7 @@ impl DataBase {
     /// Each user account has an own storage folder
     /// with a data base.
     /// The data base is opened on request.
-    pub fn get_user_db(account_id: PeerId) -> sled::Db {
+    pub fn get_user_db(account_id: PeerId) -> Db {
         // check if user account data base is already open
         if let Some(db) = Self::user_db_opened(account_id) {
             return db;
@@ -80,11 +96,8 @@ impl DataBase {
             let db_folder = path.join(account_id.to_base58());
             let db_path = db_folder.join("user.db");
 
-            // open data base from disk
-            let db = sled::Config::default()
-                .path(db_path)
-                .open()
-                .expect("Failed to open sled db");
+            // open data base
+            let db = Self::open(&db_path, database.ephemeral);
 
             // save data base to state
             database.users.insert(account_id.to_bytes(), db.clone());
@@ -94,8 +107,26 @@ impl DataBase {
         }
     }
 
+    /// open a user account data base at a staging path
+    ///
+    /// The data base is not added to the state.
+    /// This is used to stage the data base of an imported user account,
+    /// before the account folder is moved into place.
+    pub fn open_staged(db_path: &Path) -> sled::Result<Db> {
+        Db::sled(db_path)
+    }
+
+    /// add a staged user account data base to the state
+    ///
+    /// Data bases on disk are opened from the account folder on request,
+    /// this is only needed for data bases held in memory.
+    pub fn add_user_db(account_id: PeerId, db: Db) {
+        let mut database = DATABASE.get().write().unwrap();
+        database.users.insert(account_id.to_bytes(), db);
+    }
+
     /// check if user account data base has already been opened
-    fn user_db_opened(account_id: PeerId) -> Option<sled::Db> {
+    fn user_db_opened(account_id: PeerId) -> Option<Db> {
         // get data base structure
         let database = DATABASE.get().read().unwrap();
 
@@ -119,7 +150,7 @@ impl DbUsers {
         let db = DataBase::get_node_db();
 
         // open tree from data base
-        let tree: sled::Tree = db.open_tree("users").unwrap();
+        let tree: Tree = db.open_tree("users").unwrap();
 
         // clone key
         let key = user.key.clone();
@@ -136,11 +167,57 @@ impl DbUsers {
     }
 
     // get user table
-    pub fn get_tree() -> sled::Tree {
+    pub fn get_tree() -> Tree {
         // get data base
         let db = DataBase::get_node_db();
 
         // open tree from data base
         db.open_tree("users").unwrap()
     }
+
+    /// Save the signed profile of a user to the DB
+    ///
+    /// The profile is saved as encoded protobuf `UserProfile`
+    /// with the user id as key.
+    pub fn add_profile(user_id: &[u8], profile: Vec<u8>) {
+        let tree = Self::get_profiles_tree();
+
+        if let Err(e) = tree.insert(user_id, profile) {
+            log::error!("Error saving user profile to data base: {}", e);
+        } else {
+            if let Err(e) = tree.flush() {
+                log::error!("Error when flushing data base to disk: {}", e);
+            }
+        }
+    }
+
+    // get user profiles table
+    pub fn get_profiles_tree() -> Tree {
+        // get data base
+        let db = DataBase::get_node_db();
+
+        // open tree from data base
+        db.open_tree("user_profiles").unwrap()
+    }
+
+    /// Save the time when a user was last seen to the DB
+    ///
+    /// The time is saved as big endian u64
+    /// with the user id as key.
+    pub fn set_last_seen(user_id: &[u8], time: u64) {
+        let tree = Self::get_last_seen_tree();
+
+        if let Err(e) = tree.insert(user_id, time.to_be_bytes().to_vec()) {
+            log::error!("Error saving user last seen time to data base: {}", e);
+        }
+    }
+
+    // get user last seen table
+    pub fn get_last_seen_tree() -> Tree {
+        // get data base
+        let db = DataBase::get_node_db();
+
+        // open tree from data base
+        db.open_tree("users_last_seen").unwrap()
+    }
 }
@@ -0,0 +1,492 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage Encryption
+//!
+//! **Optional encryption at rest of the data bases and the private keys.**
+//!
+//! When the encryption is active, all values saved in the data bases
+//! (`node.db` and the `user.db` of every user account) and the
+//! private keys in `config.yaml` are encrypted with a key derived
+//! from a passphrase.
+//!
+//! The key is derived with Argon2id from the passphrase and a random salt.
+//! The values are encrypted with ChaCha20Poly1305.
+//! The data base keys stay unencrypted, as the services rely on their
+//! order for range queries.
+//!
+//! The passphrase is handed over via `libqaul::api::unlock_storage()`.
+//! If the storage is encrypted, libqaul waits during the initialization
+//! until the correct passphrase has been provided.
+//! If a passphrase is provided for an unencrypted storage,
+//! the existing data bases and keys are encrypted during startup.
+//! An interrupted encryption is resumed on the next startup,
+//! libqaul therefore also waits for the passphrase in this case.
+//! The migration records each finished tree, see `Db::encrypt_values()`.
+//!
+//! The encryption state is saved in the configuration file:
+//!
+//! ```yaml
+//! encryption:
+//!   active: true
+//!   salt: 7XlCnvlZ1SEzJ0wD9xHgBQ==
+//!   check: UUVOQ7HCzRA2...
+//! ```
+
+use argon2::Argon2;
+use base64::Engine;
+use chacha20poly1305::{
+    aead::{Aead, KeyInit},
+    ChaCha20Poly1305, Key, Nonce,
+};
+use libp2p::PeerId;
+use std::sync::{Condvar, Mutex, RwLock};
+
+use super::configuration::Configuration;
+use super::database::DataBase;
+use crate::utilities::instance::InstanceCell;
+
+/// prefix of every encrypted value
+const MAGIC: &[u8; 4] = b"QENC";
+
+/// length of the ChaCha20Poly1305 nonce in bytes
+const NONCE_LENGTH: usize = 12;
+
+/// length of the key derivation salt in bytes
+const SALT_LENGTH: usize = 16;
+
+/// plain text of the passphrase check value
+const CHECK: &[u8] = b"qaul storage encryption";
+
+/// encryption key of the unlocked storage
+static ENCRYPTION: InstanceCell<RwLock<StorageEncryption>> = InstanceCell::new();
+
+/// unlock state
+///
+/// The passphrase can be handed over before libqaul has
+/// been started, this state is therefore created on first use.
+static UNLOCK: InstanceCell<UnlockState> = InstanceCell::new();
+
+/// Unlock State
+#[derive(Default)]
+struct UnlockState {
+    /// provided passphrase
+    unlock: Mutex<Unlock>,
+    /// wakes up the initialization waiting for the passphrase
+    provided: Condvar,
+}
+
+/// Passphrase State
+#[derive(Default)]
+struct Unlock {
+    /// passphrase provided via the api
+    passphrase: Option<String>,
+    /// libqaul waits for the passphrase
+    waiting: bool,
+}
+
+/// Storage Encryption Module
+pub struct StorageEncryption {
+    /// derived encryption key
+    ///
+    /// The key is `None` if the storage is not encrypted.
+    key: Option<[u8; 32]>,
+    /// key of an interrupted migration
+    ///
+    /// The migration is resumed with this key.
+    resume: Option<[u8; 32]>,
+}
+
+impl StorageEncryption {
+    /// Initialize storage encryption
+    ///
+    /// If the storage is encrypted, or the encryption of the storage
+    /// was interrupted, this function blocks until the correct
+    /// passphrase has been provided.
+    ///
+    /// Returns an error if the encryption options are invalid,
+    /// as the storage can't be unlocked then.
+    pub fn init() -> Result<(), String> {
+        let (active, salt, check) = {
+            let config = Configuration::get();
+            let errors = config.encryption.validate();
+            if !errors.is_empty() {
+                return Err(errors.join(", "));
+            }
+            (
+                config.encryption.active,
+                config.encryption.salt.clone(),
+                config.encryption.check.clone(),
+            )
+        };
+
+        let mut key = None;
+        let mut resume = None;
+        if active {
+            log::info!("storage is encrypted, waiting for passphrase");
+            key = Some(Self::wait_for_unlock(&salt, &check));
+        } else if !salt.is_empty() && !check.is_empty() {
+            // some data bases may already be encrypted
+            log::info!("storage encryption was interrupted, waiting for passphrase");
+            resume = Some(Self::wait_for_unlock(&salt, &check));
+        }
+
+        ENCRYPTION.set(RwLock::new(StorageEncryption { key, resume }));
+
+        Ok(())
+    }
+
+    /// get the unlock state of this instance
+    fn unlock_state<'a>() -> &'a UnlockState {
+        UNLOCK.get_or_set(UnlockState::default)
+    }
+
+    /// provide the storage passphrase
+    pub fn set_passphrase(passphrase: String) {
+        let state = Self::unlock_state();
+        let mut unlock = state.unlock.lock().unwrap();
+        unlock.passphrase = Some(passphrase);
+        unlock.waiting = false;
+        state.provided.notify_all();
+    }
+
+    /// Returns true if libqaul is waiting for the storage passphrase
+    pub fn is_locked() -> bool {
+        let unlock = Self::unlock_state().unlock.lock().unwrap();
+        unlock.waiting
+    }
+
+    /// wait until the correct passphrase has been provided
+    /// and return the derived key
+    fn wait_for_unlock(salt: &str, check: &str) -> [u8; 32] {
+        let state = Self::unlock_state();
+        state.unlock.lock().unwrap().waiting = true;
+
+        loop {
+            // sleep until a passphrase is provided
+            let passphrase = {
+                let mut unlock = state
+                    .provided
+                    .wait_while(state.unlock.lock().unwrap(), |unlock| {
+                        unlock.passphrase.is_none()
+                    })
+                    .unwrap();
+                unlock.passphrase.take()
+            };
+
+            if let Some(passphrase) = passphrase {
+                if let Some(key) = Self::derive_key(&passphrase, salt) {
+                    if Self::verify_check(&key, check) {
+                        log::info!("storage unlocked");
+                        return key;
+                    }
+                }
+            }
+            log::warn!("wrong storage passphrase");
+            state.unlock.lock().unwrap().waiting = true;
+        }
+    }
+
+    /// Encrypt an unencrypted storage
+    ///
+    /// If a passphrase has been provided for an unencrypted storage,
+    /// all values of all data bases and the private keys
+    /// in the configuration are encrypted.
+    ///
+    /// The salt and check value are saved before the data is encrypted.
+    /// An interrupted migration is therefore resumed with the key
+    /// unlocked in `init()` on the next startup.
+    ///
+    /// Returns an error if the storage couldn't be encrypted.
+    pub fn migrate() -> Result<(), String> {
+        // check if the storage is already encrypted
+        if Configuration::get().encryption.active {
+            return Ok(());
+        }
+
+        // resume an interrupted migration,
+        // or start a new one if a passphrase was provided
+        let resume = ENCRYPTION.get().write().unwrap().resume.take();
+        let key = match resume {
+            Some(key) => {
+                log::info!("resuming storage encryption");
+                key
+            }
+            None => {
+                let passphrase;
+                match Self::unlock_state()
+                    .unlock
+                    .lock()
+                    .unwrap()
+                    .passphrase
+                    .take()
+                {
+                    Some(my_passphrase) => passphrase = my_passphrase,
+                    None => return Ok(()),
+                }
+
+                log::info!("encrypting storage");
+
+                let salt_bytes: [u8; SALT_LENGTH] = rand::random();
+                let salt = base64::engine::general_purpose::STANDARD.encode(salt_bytes);
+
+                // derive key
+                let key;
+                match Self::derive_key(&passphrase, &salt) {
+                    Some(my_key) => key = my_key,
+                    None => return Err("storage encryption key derivation failed".to_string()),
+                }
+
+                // save salt & check value
+                {
+                    let mut config = Configuration::get_mut();
+                    config.encryption.salt = salt;
+                    config.encryption.check = base64::engine::general_purpose::STANDARD
+                        .encode(Self::encrypt_with_key(&key, CHECK));
+                }
+                Configuration::save();
+
+                key
+            }
+        };
+
+        // encrypt all data bases
+        let mut dbs = vec![DataBase::get_node_db()];
+        let account_ids: Vec<String> = Configuration::get()
+            .user_accounts
+            .iter()
+            .map(|account| account.id.clone())
+            .collect();
+        for account_id in account_ids {
+            match account_id.parse::<PeerId>() {
+                Ok(id) => dbs.push(DataBase::get_user_db(id)),
+                Err(e) => return Err(format!("invalid user account id {}: {}", account_id, e)),
+            }
+        }
+        for db in dbs {
+            if let Err(e) = db.encrypt_values(&key) {
+                return Err(format!("Error encrypting data base: {}", e));
+            }
+        }
+
+        // encrypt private keys
+        {
+            let mut config = Configuration::get_mut();
+            config.node.keys = Self::encrypt_config_value_with_key(&key, &config.node.keys);
+            for account in config.user_accounts.iter_mut() {
+                account.keys = Self::encrypt_config_value_with_key(&key, &account.keys);
+            }
+            config.encryption.active = true;
+        }
+        Configuration::save();
+
+        // activate encryption
+        let mut encryption = ENCRYPTION.get().write().unwrap();
+        encryption.key = Some(key);
+
+        log::info!("storage encrypted");
+        Ok(())
+    }
+
+    /// derive the encryption key from passphrase and salt
+    fn derive_key(passphrase: &str, salt: &str) -> Option<[u8; 32]> {
+        let salt_bytes;
+        match base64::engine::general_purpose::STANDARD.decode(salt) {
+            Ok(bytes) => salt_bytes = bytes,
+            Err(e) => {
+                log::error!("invalid encryption salt: {}", e);
+                return None;
+            }
+        }
+
+        let mut key = [0u8; 32];
+        if let Err(e) =
+            Argon2::default().hash_password_into(passphrase.as_bytes(), &salt_bytes, &mut key)
+        {
+            log::error!("key derivation error: {}", e);
+            return None;
+        }
+
+        Some(key)
+    }
+
+    /// check if the key decrypts the check value
+    fn verify_check(key: &[u8; 32], check: &str) -> bool {
+        if let Ok(check_bytes) = base64::engine::general_purpose::STANDARD.decode(check) {
+            if let Some(plain) = Self::decrypt_with_key(key, &check_bytes) {
+                return plain == CHECK;
+            }
+        }
+        false
+    }
+
+    /// Returns true if the value is encrypted with key
+    ///
+    /// The authentication tag of the value is verified,
+    /// unencrypted values starting with the magic prefix
+    /// are therefore not mistaken for encrypted ones.
+    pub fn is_encrypted(key: &[u8; 32], data: &[u8]) -> bool {
+        Self::decrypt_with_key(key, data).is_some()
+    }
+
+    /// Returns true if the value has the format of an encrypted value
+    fn has_encrypted_format(data: &[u8]) -> bool {
+        data.len() >= MAGIC.len() + NONCE_LENGTH && data.starts_with(MAGIC)
+    }
+
+    /// encrypt data with key
+    ///
+    /// The encrypted value consists of the magic prefix,
+    /// the random nonce and the cipher text.
+    pub fn encrypt_with_key(key: &[u8; 32], data: &[u8]) -> Vec<u8> {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+        let nonce_bytes: [u8; NONCE_LENGTH] = rand::random();
+        let encrypted = cipher
+            .encrypt(Nonce::from_slice(&nonce_bytes), data)
+            .expect("encryption into Vec<u8> does not fail");
+
+        let mut result = Vec::with_capacity(MAGIC.len() + NONCE_LENGTH + encrypted.len());
+        result.extend_from_slice(MAGIC);
+        result.extend_from_slice(&nonce_bytes);
+        result.extend_from_slice(&encrypted);
+        result
+    }
+
+    /// decrypt data with key
+    fn decrypt_with_key(key: &[u8; 32], data: &[u8]) -> Option<Vec<u8>> {
+        if !Self::has_encrypted_format(data) {
+            return None;
+        }
+
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(key));
+        let (nonce_bytes, encrypted) = data[MAGIC.len()..].split_at(NONCE_LENGTH);
+        cipher
+            .decrypt(Nonce::from_slice(nonce_bytes), encrypted)
+            .ok()
+    }
+
+    /// encrypt data with a passphrase
+    ///
+    /// This is used for data leaving the storage, e.g. exported accounts.
+    /// The key is derived from the passphrase and a random salt,
+    /// which is prepended to the encrypted value.
+    pub fn encrypt_with_passphrase(passphrase: &str, data: &[u8]) -> Option<Vec<u8>> {
+        let salt_bytes: [u8; SALT_LENGTH] = rand::random();
+        let salt = base64::engine::general_purpose::STANDARD.encode(salt_bytes);
+        let key = Self::derive_key(passphrase, &salt)?;
+
+        let mut result = salt_bytes.to_vec();
+        result.extend_from_slice(&Self::encrypt_with_key(&key, data));
+        Some(result)
+    }
+
+    /// decrypt data encrypted with `encrypt_with_passphrase()`
+    ///
+    /// Returns `None` if the passphrase is wrong.
+    pub fn decrypt_with_passphrase(passphrase: &str, data: &[u8]) -> Option<Vec<u8>> {
+        if data.len() < SALT_LENGTH {
+            return None;
+        }
+
+        let (salt_bytes, encrypted) = data.split_at(SALT_LENGTH);
+        let salt = base64::engine::general_purpose::STANDARD.encode(salt_bytes);
+        let key = Self::derive_key(passphrase, &salt)?;
+        Self::decrypt_with_key(&key, encrypted)
+    }
+
+    /// encrypt a value before saving it to the data base
+    ///
+    /// The value is returned unchanged if the storage is not encrypted.
+    pub fn encrypt(data: &[u8]) -> Vec<u8> {
+        let encryption = ENCRYPTION.get().read().unwrap();
+        match &encryption.key {
+            Some(key) => Self::encrypt_with_key(key, data),
+            None => data.to_vec(),
+        }
+    }
+
+    /// decrypt a value loaded from the data base
+    ///
+    /// The value is returned unchanged if the storage is not encrypted.
+    /// Returns `None` if the value can't be decrypted.
+    pub fn decrypt(data: &[u8]) -> Option<Vec<u8>> {
+        let encryption = ENCRYPTION.get().read().unwrap();
+        match &encryption.key {
+            Some(key) => Self::decrypt_with_key(key, data),
+            None => Some(data.to_vec()),
+        }
+    }
+
+    /// encrypt a configuration value with key
+    fn encrypt_config_value_with_key(key: &[u8; 32], value: &str) -> String {
+        base64::engine::general_purpose::STANDARD
+            .encode(Self::encrypt_with_key(key, value.as_bytes()))
+    }
+
+    /// encrypt a secret configuration value
+    ///
+    /// The value is returned unchanged if the storage is not encrypted.
+    pub fn encrypt_config_value(value: &str) -> String {
+        let encryption = ENCRYPTION.get().read().unwrap();
+        match &encryption.key {
+            Some(key) => Self::encrypt_config_value_with_key(key, value),
+            None => value.to_string(),
+        }
+    }
+
+    /// decrypt a secret configuration value
+    ///
+    /// The value is returned unchanged if the storage is not encrypted.
+    pub fn decrypt_config_value(value: &str) -> Option<String> {
+        let encryption = ENCRYPTION.get().read().unwrap();
+        let key;
+        match &encryption.key {
+            Some(my_key) => key = my_key,
+            None => return Some(value.to_string()),
+        }
+
+        let encrypted = base64::engine::general_purpose::STANDARD
+            .decode(value)
+            .ok()?;
+        let plain = Self::decrypt_with_key(key, &encrypted)?;
+        String::from_utf8(plain).ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    #[test]
+    fn encrypted_values_are_detected() {
+        let encrypted = StorageEncryption::encrypt_with_key(&KEY, b"value");
+
+        assert!(StorageEncryption::is_encrypted(&KEY, &encrypted));
+        assert!(!StorageEncryption::is_encrypted(&[8; 32], &encrypted));
+        assert!(!StorageEncryption::is_encrypted(&KEY, b"value"));
+    }
+
+    #[test]
+    fn plain_values_with_magic_prefix_are_not_encrypted() {
+        let mut value = MAGIC.to_vec();
+        value.extend_from_slice(&[0; NONCE_LENGTH + 16]);
+
+        assert!(!StorageEncryption::is_encrypted(&KEY, &value));
+    }
+
+    #[test]
+    fn values_are_decrypted() {
+        let encrypted = StorageEncryption::encrypt_with_key(&KEY, b"value");
+
+        assert_eq!(
+            StorageEncryption::decrypt_with_key(&KEY, &encrypted),
+            Some(b"value".to_vec())
+        );
+        assert_eq!(
+            StorageEncryption::decrypt_with_key(&[8; 32], &encrypted),
+            None
+        );
+    }
+}
@@ -0,0 +1,226 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Storage Maintenance
+//!
+//! Accounts the storage usage of the user accounts and
+//! enforces the retention policies of the configuration.
+//!
+//! The storage usage is accounted by category:
+//!
+//! * chat: chat messages
+//! * files: file histories, file chunks and the stored files
+//! * feed: feed messages sent by the user account
+//! * dtn: DTN messages stored for other users
+//! * crypto: crypto sessions and the crypto cache
+//!
+//! The data base usage is the size of all keys and values.
+//!
+//! The retention policies are enforced periodically from the
+//! main loop. The interval is configured in the maintenance options.
+
+use libp2p::PeerId;
+use prost::Message;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+use crate::node::user_accounts::UserAccounts;
+use crate::services::chat::{ChatFile, ChatStorage};
+use crate::services::dtn::DtnMessageEntry;
+use crate::services::feed::{Feed, FeedMessageData};
+use crate::services::messaging::{proto, UnConfirmedMessage};
+use crate::storage::configuration::Configuration;
+use crate::storage::database::DataBase;
+use crate::storage::tree::Tree;
+use crate::storage::Storage;
+use crate::utilities::timestamp::Timestamp;
+
+/// Storage usage of a user account in bytes
+pub struct StorageUsage {
+    /// user account
+    pub account_id: PeerId,
+    /// chat messages
+    pub chat: u64,
+    /// file histories, file chunks and stored files
+    pub files: u64,
+    /// feed messages sent by this user account
+    pub feed: u64,
+    /// DTN messages stored for other users
+    pub dtn: u64,
+    /// crypto sessions and crypto cache
+    pub crypto: u64,
+}
+
+/// Storage Maintenance
+pub struct StorageMaintenance {}
+
+impl StorageMaintenance {
+    /// Get the storage usage of all user accounts
+    ///
+    /// Returns the usage of every user account and the
+    /// size of all feed messages stored on this node.
+    pub fn usage() -> (Vec<StorageUsage>, u64) {
+        let (feed_usage, feed_total) = Self::feed_usage();
+        let dtn_usage = Self::dtn_usage();
+
+        let mut usage = Vec::new();
+        for account in UserAccounts::get_user_info() {
+            let db = DataBase::get_user_db(account.id);
+            let tree_size = |name: &str| match db.open_tree(name) {
+                Ok(tree) => Self::tree_size(&tree),
+                Err(e) => {
+                    log::error!("storage usage: {}", e);
+                    0
+                }
+            };
+            let account_bytes = account.id.to_bytes();
+
+            usage.push(StorageUsage {
+                account_id: account.id,
+                chat: tree_size("chat_messages") + tree_size("chat_message_ids"),
+                files: tree_size("chat_file")
+                    + tree_size("file_chunks")
+                    + Self::folder_size(&Storage::get_account_path(account.id).join("files")),
+                feed: feed_usage.get(&account_bytes).cloned().unwrap_or(0),
+                dtn: dtn_usage.get(&account_bytes).cloned().unwrap_or(0),
+                crypto: tree_size("crypto_state") + tree_size("crypto_cache"),
+            });
+        }
+
+        (usage, feed_total)
+    }
+
+    /// Enforce the retention policies
+    ///
+    /// This function is called periodically from the main loop.
+    pub fn run() {
+        let now = Timestamp::get_timestamp();
+
+        for account in UserAccounts::get_user_info() {
+            let options = match Configuration::get_user(account.id.to_string()) {
+                Some(user) => user.storage,
+                None => continue,
+            };
+
+            // delete old chat messages
+            if options.chat_retention_days > 0 {
+                let retention = (options.chat_retention_days as u64) * 24 * 60 * 60 * 1000;
+                let removed =
+                    ChatStorage::remove_messages_before(&account.id, now.saturating_sub(retention));
+                if removed > 0 {
+                    log::info!(
+                        "storage maintenance: {} chat messages of {} removed",
+                        removed,
+                        account.id.to_base58()
+                    );
+                }
+            }
+
+            // limit file store
+            if options.file_store_size > 0 {
+                let max_size = (options.file_store_size as u64) * 1024 * 1024;
+                let deleted = ChatFile::limit_file_store(&account.id, max_size);
+                if deleted > 0 {
+                    log::info!(
+                        "storage maintenance: {} files of {} deleted",
+                        deleted,
+                        account.id.to_base58()
+                    );
+                }
+            }
+        }
+
+        // limit feed messages
+        let feed_max_messages = Configuration::get().maintenance.feed_max_messages;
+        if feed_max_messages > 0 {
+            let removed = Feed::limit_messages(feed_max_messages);
+            if removed > 0 {
+                log::info!("storage maintenance: {} feed messages removed", removed);
+            }
+        }
+    }
+
+    /// size of all feed messages by sender
+    ///
+    /// Returns the map of the sizes by sender and the total size.
+    fn feed_usage() -> (BTreeMap<Vec<u8>, u64>, u64) {
+        let mut usage: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+        let mut total: u64 = 0;
+
+        let db = DataBase::get_node_db();
+        let feed = db.open_tree("feed").unwrap();
+        let feed_ids = db.open_tree("feed_id").unwrap();
+
+        for (key, value) in feed.iter().filter_map(|res| res.ok()) {
+            let size = (key.len() + value.len()) as u64;
+            total += size;
+            if let Ok(message) = bincode::deserialize::<FeedMessageData>(&value) {
+                *usage.entry(message.sender_id).or_insert(0) += size;
+            }
+        }
+        total += Self::tree_size(&feed_ids);
+
+        (usage, total)
+    }
+
+    /// size of the stored DTN messages by storing user account
+    ///
+    /// The stored DTN messages are sent by the storing user account
+    /// and are found in the unconfirmed messages.
+    fn dtn_usage() -> BTreeMap<Vec<u8>, u64> {
+        let mut usage: BTreeMap<Vec<u8>, u64> = BTreeMap::new();
+
+        let db = DataBase::get_node_db();
+        let dtn_messages = db.open_tree("dtn-messages").unwrap();
+        let unconfirmed = db.open_tree("unconfirmed").unwrap();
+
+        for (signature, value) in dtn_messages.iter().filter_map(|res| res.ok()) {
+            let entry: DtnMessageEntry = match bincode::deserialize(&value) {
+                Ok(entry) => entry,
+                Err(_) => continue,
+            };
+
+            // get storing user account
+            let unconfirmed_message: UnConfirmedMessage = match unconfirmed.get(&signature) {
+                Ok(Some(bytes)) => match bincode::deserialize(&bytes) {
+                    Ok(unconfirmed_message) => unconfirmed_message,
+                    Err(_) => continue,
+                },
+                _ => continue,
+            };
+            let sender_id = match proto::Container::decode(&unconfirmed_message.container[..]) {
+                Ok(proto::Container {
+                    envelope: Some(envelope),
+                    ..
+                }) => envelope.sender_id,
+                _ => continue,
+            };
+
+            *usage.entry(sender_id).or_insert(0) += entry.size as u64;
+        }
+
+        usage
+    }
+
+    /// size of all keys and values of a data base tree
+    fn tree_size(tree: &Tree) -> u64 {
+        tree.iter()
+            .filter_map(|res| res.ok())
+            .map(|(key, value)| (key.len() + value.len()) as u64)
+            .sum()
+    }
+
+    /// size of all files in a folder
+    fn folder_size(path: &Path) -> u64 {
+        match fs::read_dir(path) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter_map(|entry| entry.metadata().ok())
+                .filter(|metadata| metadata.is_file())
+                .map(|metadata| metadata.len())
+                .sum(),
+            Err(_) => 0,
+        }
+    }
+}
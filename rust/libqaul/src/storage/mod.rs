@@ -7,19 +7,27 @@
 //!
 //! * configuration management
 //! * database handling
+//! * encryption at rest
 
 use libp2p::PeerId;
 use std::path::{Path, PathBuf};
 
+use crate::utilities::instance::InstanceCell;
+
+pub mod backend;
+pub mod check;
 pub mod configuration;
 pub mod database;
+pub mod encryption;
+pub mod maintenance;
+pub mod tree;
 
 use configuration::Configuration;
 use database::DataBase;
-use state;
+use encryption::StorageEncryption;
 
 /// make storage path accessible
-static STORAGE_PATH: state::InitCell<String> = state::InitCell::new();
+static STORAGE_PATH: InstanceCell<String> = InstanceCell::new();
 
 /// storage module structure
 pub struct Storage {}
@@ -27,15 +35,24 @@ pub struct Storage {}
 impl Storage {
     /// initialize storage module
     /// requires the path to the data storage folder
-    pub fn init(path: String) {
+    ///
+    /// Returns an error if the encryption of the storage failed.
+    pub fn init(path: String) -> Result<(), String> {
         // put path to state
         STORAGE_PATH.set(path);
 
         // initialize configuration
         Configuration::init();
 
+        // unlock encrypted storage
+        StorageEncryption::init()?;
+
         // initialize data base
         DataBase::init();
+
+        // encrypt an unencrypted storage,
+        // if a passphrase has been provided
+        StorageEncryption::migrate()
     }
 
     /// get data storage path
@@ -48,6 +65,19 @@ impl Storage {
         STORAGE_PATH.get().clone()
     }
 
+    /// Returns true if libqaul runs ephemerally
+    ///
+    /// Ephemeral storage is activated with the default
+    /// configuration option `storage: memory`.
+    /// The data bases are then only held in memory and
+    /// the configuration is not saved to disk.
+    pub fn is_ephemeral() -> bool {
+        if let Some(storage) = crate::get_default_config("storage") {
+            return storage == "memory";
+        }
+        false
+    }
+
     /// get data storage path for user account
     pub fn get_account_path(account_id: PeerId) -> PathBuf {
         let storage_path_string = STORAGE_PATH.get().clone();
@@ -0,0 +1,336 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Data Base Trees
+//!
+//! Data base and tree handles used by all modules of libqaul.
+//! The data is stored in one of the storage backends,
+//! see `storage::backend`.
+//!
+//! All values are transparently encrypted before they are
+//! handed to the backend and decrypted when they are read,
+//! if the storage encryption is active.
+//! The keys are not encrypted.
+//!
+//! Values which can't be decrypted are corrupt.
+//! They are logged and treated as missing,
+//! iterations skip them.
+
+use sled::IVec;
+use std::{
+    ops::{Bound, RangeBounds},
+    path::Path,
+    sync::Arc,
+};
+
+use super::backend::{memory_db::MemoryDb, sled_db::SledDb, BackendIter, DbBackend, TreeBackend};
+use super::encryption::StorageEncryption;
+
+/// tree recording the progress of the encryption migration
+///
+/// It contains an entry for every tree, which has been encrypted completely.
+/// The tree is internal and not listed in `Db::tree_names()`.
+const MIGRATION_TREE: &[u8] = b"__qaul_encryption_migration";
+
+/// Data Base
+#[derive(Clone, Debug)]
+pub struct Db {
+    db: Arc<dyn DbBackend>,
+}
+
+impl Db {
+    /// open a persistent sled data base from disk
+    pub fn sled(path: &Path) -> sled::Result<Db> {
+        let db = SledDb::open(path)?;
+        Ok(Db { db: Arc::new(db) })
+    }
+
+    /// create an ephemeral in memory data base
+    pub fn memory() -> Db {
+        Db {
+            db: Arc::new(MemoryDb::new()),
+        }
+    }
+
+    /// open a tree in this data base
+    pub fn open_tree<V: AsRef<[u8]>>(&self, name: V) -> sled::Result<Tree> {
+        let tree = self.db.open_tree(name.as_ref())?;
+        Ok(Tree { tree })
+    }
+
+    /// names of all trees in this data base
+    pub fn tree_names(&self) -> Vec<IVec> {
+        self.db
+            .tree_names()
+            .into_iter()
+            .filter(|name| name.as_ref() != MIGRATION_TREE)
+            .collect()
+    }
+
+    /// Encrypt all unencrypted values of all trees in this data base
+    ///
+    /// This is used for the migration of an unencrypted storage.
+    /// Every finished tree is recorded in the migration tree
+    /// and skipped when an interrupted migration is resumed.
+    /// In the interrupted tree, the values already encrypted
+    /// with `key` are skipped.
+    pub fn encrypt_values(&self, key: &[u8; 32]) -> sled::Result<()> {
+        let migration = self.db.open_tree(MIGRATION_TREE)?;
+
+        for name in self.tree_names() {
+            if migration.contains_key(&name)? {
+                continue;
+            }
+
+            // collect the keys before the values are rewritten
+            let tree = self.db.open_tree(&name)?;
+            let mut keys = Vec::new();
+            for res in tree.range((Bound::Unbounded, Bound::Unbounded)) {
+                let (key_bytes, _value) = res?;
+                keys.push(key_bytes);
+            }
+
+            for key_bytes in keys {
+                if let Some(value) = tree.get(&key_bytes)? {
+                    if !StorageEncryption::is_encrypted(key, &value) {
+                        tree.insert(
+                            &key_bytes,
+                            IVec::from(StorageEncryption::encrypt_with_key(key, &value)),
+                        )?;
+                    }
+                }
+            }
+            tree.flush()?;
+
+            // record the finished tree
+            migration.insert(&name, IVec::default())?;
+            migration.flush()?;
+        }
+        Ok(())
+    }
+}
+
+/// Data Base Tree
+#[derive(Clone, Debug)]
+pub struct Tree {
+    tree: Arc<dyn TreeBackend>,
+}
+
+impl Tree {
+    /// decrypt a value read from disk
+    ///
+    /// Returns `None` if the value can't be decrypted.
+    fn decrypt(key: &[u8], value: IVec) -> Option<IVec> {
+        match StorageEncryption::decrypt(&value) {
+            Some(plain) => Some(IVec::from(plain)),
+            None => {
+                log::error!("value of key {:?} can't be decrypted", key);
+                None
+            }
+        }
+    }
+
+    /// decrypt an optional value read from disk
+    fn decrypt_option(key: &[u8], value: Option<IVec>) -> Option<IVec> {
+        value.and_then(|v| Self::decrypt(key, v))
+    }
+
+    /// insert a value, returns the previous value
+    pub fn insert<K: AsRef<[u8]>, V: Into<IVec>>(
+        &self,
+        key: K,
+        value: V,
+    ) -> sled::Result<Option<IVec>> {
+        let value: IVec = value.into();
+        let old = self
+            .tree
+            .insert(key.as_ref(), IVec::from(StorageEncryption::encrypt(&value)))?;
+        Ok(Self::decrypt_option(key.as_ref(), old))
+    }
+
+    /// get a value
+    pub fn get<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<Option<IVec>> {
+        let value = self.tree.get(key.as_ref())?;
+        Ok(Self::decrypt_option(key.as_ref(), value))
+    }
+
+    /// get the entry with the greatest key, that is smaller than `key`
+    pub fn get_lt<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<Option<(IVec, IVec)>> {
+        self.range(..key.as_ref()).next_back().transpose()
+    }
+
+    /// remove a value, returns the removed value
+    pub fn remove<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<Option<IVec>> {
+        let value = self.tree.remove(key.as_ref())?;
+        Ok(Self::decrypt_option(key.as_ref(), value))
+    }
+
+    /// check if the tree contains a key
+    pub fn contains_key<K: AsRef<[u8]>>(&self, key: K) -> sled::Result<bool> {
+        self.tree.contains_key(key.as_ref())
+    }
+
+    /// get first entry
+    pub fn first(&self) -> sled::Result<Option<(IVec, IVec)>> {
+        self.iter().next().transpose()
+    }
+
+    /// get last entry
+    pub fn last(&self) -> sled::Result<Option<(IVec, IVec)>> {
+        self.iter().next_back().transpose()
+    }
+
+    /// iterate over all entries
+    pub fn iter(&self) -> Iter {
+        Iter {
+            iter: self.tree.range((Bound::Unbounded, Bound::Unbounded)),
+        }
+    }
+
+    /// iterate over a range of keys
+    pub fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&self, range: R) -> Iter {
+        let start = Self::to_owned_bound(range.start_bound());
+        let end = Self::to_owned_bound(range.end_bound());
+        Iter {
+            iter: self.tree.range((start, end)),
+        }
+    }
+
+    /// convert a range bound to an owned key bound
+    fn to_owned_bound<K: AsRef<[u8]>>(bound: Bound<&K>) -> Bound<Vec<u8>> {
+        match bound {
+            Bound::Included(key) => Bound::Included(key.as_ref().to_vec()),
+            Bound::Excluded(key) => Bound::Excluded(key.as_ref().to_vec()),
+            Bound::Unbounded => Bound::Unbounded,
+        }
+    }
+
+    /// iterate over all keys starting with `prefix`
+    pub fn scan_prefix<P: AsRef<[u8]>>(&self, prefix: P) -> Iter {
+        Iter {
+            iter: self.tree.scan_prefix(prefix.as_ref()),
+        }
+    }
+
+    /// number of entries
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    /// check if tree is empty
+    pub fn is_empty(&self) -> bool {
+        self.tree.len() == 0
+    }
+
+    /// remove all entries
+    pub fn clear(&self) -> sled::Result<()> {
+        self.tree.clear()
+    }
+
+    /// flush tree to disk
+    pub fn flush(&self) -> sled::Result<usize> {
+        self.tree.flush()
+    }
+}
+
+/// Data Base Tree Iterator
+///
+/// Decrypts the values while iterating.
+/// Entries which can't be decrypted are skipped.
+pub struct Iter {
+    iter: BackendIter,
+}
+
+impl Iter {
+    /// decrypt an entry read from disk
+    ///
+    /// Returns `None` if the entry needs to be skipped.
+    fn decrypt_entry(res: sled::Result<(IVec, IVec)>) -> Option<sled::Result<(IVec, IVec)>> {
+        match res {
+            Ok((key, value)) => Tree::decrypt(&key, value).map(|plain| Ok((key, plain))),
+            Err(e) => Some(Err(e)),
+        }
+    }
+}
+
+impl Iterator for Iter {
+    type Item = sled::Result<(IVec, IVec)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let res = self.iter.next()?;
+            if let Some(entry) = Self::decrypt_entry(res) {
+                return Some(entry);
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for Iter {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        loop {
+            let res = self.iter.next_back()?;
+            if let Some(entry) = Self::decrypt_entry(res) {
+                return Some(entry);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEY: [u8; 32] = [7; 32];
+
+    /// raw value of a key in a tree, as saved in the backend
+    fn raw(db: &Db, tree: &[u8], key: &[u8]) -> IVec {
+        db.db.open_tree(tree).unwrap().get(key).unwrap().unwrap()
+    }
+
+    #[test]
+    fn values_with_magic_prefix_are_encrypted() {
+        let db = Db::memory();
+        let tree = db.db.open_tree(b"tree").unwrap();
+        tree.insert(b"key", IVec::from(b"QENC plain text value".as_slice()))
+            .unwrap();
+
+        db.encrypt_values(&KEY).unwrap();
+
+        let value = raw(&db, b"tree", b"key");
+        assert_ne!(value, b"QENC plain text value".as_slice());
+        assert!(StorageEncryption::is_encrypted(&KEY, &value));
+    }
+
+    #[test]
+    fn migration_is_resumed() {
+        let db = Db::memory();
+
+        // interrupted tree, the first value is already encrypted
+        let tree = db.db.open_tree(b"tree").unwrap();
+        let encrypted = StorageEncryption::encrypt_with_key(&KEY, b"first");
+        tree.insert(b"a", IVec::from(encrypted.clone())).unwrap();
+        tree.insert(b"b", IVec::from(b"second".as_slice())).unwrap();
+
+        db.encrypt_values(&KEY).unwrap();
+
+        // the encrypted value is not encrypted twice
+        assert_eq!(raw(&db, b"tree", b"a"), encrypted);
+        let second = raw(&db, b"tree", b"b");
+        assert!(StorageEncryption::is_encrypted(&KEY, &second));
+
+        // finished trees are skipped
+        db.encrypt_values(&KEY).unwrap();
+        assert_eq!(raw(&db, b"tree", b"b"), second);
+    }
+
+    #[test]
+    fn migration_tree_is_not_listed() {
+        let db = Db::memory();
+        db.db.open_tree(b"tree").unwrap();
+
+        db.encrypt_values(&KEY).unwrap();
+
+        assert_eq!(db.tree_names(), vec![IVec::from(b"tree".as_slice())]);
+    }
+}
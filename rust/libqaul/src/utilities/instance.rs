@@ -0,0 +1,131 @@
+// Copyright (c) 2021 Open Community Project Association https://ocpa.ch
+// This software is published under the AGPLv3 license.
+
+//! # Libqaul Instances
+//!
+//! Several independent libqaul instances can run in the same process.
+//! Each instance runs in its own thread and owns its own state.
+//!
+//! All module state is saved in `InstanceCell` statics,
+//! which hold a separate value per instance.
+//! The instance of the current thread selects the value.
+//! Threads that never selected an instance use the default
+//! instance `0`, which is the first instance started.
+
+use std::{
+    cell::Cell,
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        RwLock,
+    },
+};
+
+/// ID of the next instance to be created
+static NEXT_INSTANCE: AtomicU32 = AtomicU32::new(0);
+
+thread_local! {
+    /// instance of the current thread
+    static CURRENT_INSTANCE: Cell<u32> = const { Cell::new(0) };
+}
+
+/// Libqaul Instance Utility
+pub struct Instance {}
+
+impl Instance {
+    /// create a new instance ID
+    ///
+    /// The first instance created receives the default ID `0`.
+    pub fn create() -> u32 {
+        NEXT_INSTANCE.fetch_add(1, Ordering::SeqCst)
+    }
+
+    /// get the instance ID of the current thread
+    pub fn current() -> u32 {
+        CURRENT_INSTANCE.with(|instance| instance.get())
+    }
+
+    /// set the instance ID of the current thread
+    pub fn set_current(instance_id: u32) {
+        CURRENT_INSTANCE.with(|instance| instance.set(instance_id));
+    }
+
+    /// run a function in the context of an instance
+    pub fn with<F, R>(instance_id: u32, f: F) -> R
+    where
+        F: FnOnce() -> R,
+    {
+        let previous = Self::current();
+        Self::set_current(instance_id);
+        let result = f();
+        Self::set_current(previous);
+        result
+    }
+}
+
+/// Instance Cell
+///
+/// Holds a separate value for each libqaul instance.
+/// The cell is used like `state::InitCell`:
+/// the value of the current instance can be set once
+/// and is then available for the lifetime of the process.
+pub struct InstanceCell<T: 'static> {
+    cells: RwLock<BTreeMap<u32, &'static T>>,
+}
+
+impl<T: Send + Sync + 'static> InstanceCell<T> {
+    /// create a new empty cell
+    pub const fn new() -> Self {
+        InstanceCell {
+            cells: RwLock::new(BTreeMap::new()),
+        }
+    }
+
+    /// set the value of the current instance
+    ///
+    /// Returns false if the value was already set.
+    pub fn set(&self, value: T) -> bool {
+        let instance_id = Instance::current();
+        let mut cells = self.cells.write().unwrap();
+        if cells.contains_key(&instance_id) {
+            return false;
+        }
+
+        // the value lives as long as the process
+        cells.insert(instance_id, Box::leak(Box::new(value)));
+        true
+    }
+
+    /// get the value of the current instance
+    /// if it was set
+    pub fn try_get(&self) -> Option<&T> {
+        let cells = self.cells.read().unwrap();
+        cells.get(&Instance::current()).copied()
+    }
+
+    /// get the value of the current instance
+    ///
+    /// Panics if the value has not been set.
+    pub fn get(&self) -> &T {
+        match self.try_get() {
+            Some(value) => value,
+            None => panic!(
+                "state is not initialized for libqaul instance {}",
+                Instance::current()
+            ),
+        }
+    }
+
+    /// get the value of the current instance,
+    /// set it first if it has not been set yet
+    pub fn get_or_set<F>(&self, f: F) -> &T
+    where
+        F: FnOnce() -> T,
+    {
+        if let Some(value) = self.try_get() {
+            return value;
+        }
+        self.set(f());
+        self.get()
+    }
+}
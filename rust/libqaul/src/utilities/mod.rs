@@ -9,8 +9,10 @@
 //!
 //! * qaul ID conversions
 //! * timestamp handling
+//! * libqaul instance state
 
 pub mod filelogger;
+pub mod instance;
 pub mod qaul_id;
 pub mod timestamp;
 pub mod upgrade;
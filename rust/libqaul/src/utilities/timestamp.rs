@@ -8,9 +8,20 @@
 //! 
 //! All time labels shall use this timestamps.
 //! The timestamp is a u64 in milliseconds since UNIX_EPOCH
+//! 
+//! Instances running in a simulation read the time from
+//! the simulated clock of their virtual network.
 
 use std::convert::TryFrom;
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::sync::{atomic::{AtomicU64, Ordering}, Arc};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::utilities::instance::InstanceCell;
+
+/// simulated clock of this instance
+/// 
+/// Contains the milliseconds since UNIX_EPOCH.
+static SIMULATED_CLOCK: InstanceCell<Arc<AtomicU64>> = InstanceCell::new();
 
 /// Timestamp Utility
 /// 
@@ -25,7 +36,18 @@ pub struct Timestamp {
 
 impl Timestamp {
 
+    /// read the time of this instance from a simulated clock
+    /// 
+    /// All time reads of this instance return the time
+    /// of the clock instead of the system time.
+    pub fn simulate(clock: Arc<AtomicU64>) {
+        SIMULATED_CLOCK.set(clock);
+    }
+
     pub fn create_time() -> SystemTime {
+        if let Some(clock) = SIMULATED_CLOCK.try_get() {
+            return UNIX_EPOCH + Duration::from_millis(clock.load(Ordering::SeqCst));
+        }
         SystemTime::now()
     }
 
@@ -68,9 +68,39 @@ impl Backup {
         true
     }
 
-    /// backup storage folder into /backup/{old_version}
-    pub fn backup(storage_path: &Path, old_version: &str) -> bool {
-        // enumerate all files and folders
+    /// copy file
+    pub fn copy_file(file: &Path, to: &Path) -> bool {
+        let options = fs_extra::file::CopyOptions::new();
+        if let Err(_) = fs_extra::file::copy(file, to, &options) {
+            println!(
+                "Error: copy file {} {}",
+                file.to_string_lossy(),
+                to.to_string_lossy()
+            );
+            return false;
+        }
+        true
+    }
+
+    /// copy folder
+    pub fn copy_folder(folder: &Path, to: &Path) -> bool {
+        let mut options = fs_extra::dir::CopyOptions::new();
+        options.content_only = true;
+        if let Err(_) = fs_extra::dir::copy(folder, to, &options) {
+            println!(
+                "Error: copy folder {} {}",
+                folder.to_string_lossy(),
+                to.to_string_lossy()
+            );
+            return false;
+        }
+        true
+    }
+
+    /// collect the files and folders of the stored data
+    ///
+    /// Returns a tuple with the lists of ( files, folders ).
+    fn data_entries(storage_path: &Path) -> (Vec<String>, Vec<String>) {
         let mut files: Vec<String> = vec![];
         let mut folders: Vec<String> = vec![];
 
@@ -95,6 +125,14 @@ impl Backup {
             }
         }
 
+        (files, folders)
+    }
+
+    /// backup storage folder into /backup/{old_version}
+    pub fn backup(storage_path: &Path, old_version: &str) -> bool {
+        // enumerate all files and folders
+        let (files, folders) = Self::data_entries(storage_path);
+
         // destination path
         let path_dest = Path::new(&storage_path).join("backup").join(old_version);
 
@@ -115,8 +153,92 @@ impl Backup {
         Self::move_folders(&folders, storage_path, path_dest.as_path())
     }
 
+    /// copy the stored data from the source folder to the destination folder
+    ///
+    /// The source folder is not changed.
+    /// This is used to run the upgrade steps on a copy of the data.
+    pub fn copy(src_path: &Path, dst_path: &Path) -> bool {
+        // enumerate all files and folders
+        let (files, folders) = Self::data_entries(src_path);
+
+        // clear destination folder
+        Self::remove_folder(dst_path);
+        // create dest dir
+        if let Err(_) = std::fs::create_dir_all(dst_path) {
+            println!("failed to create destination folder");
+            return false;
+        }
+
+        // copy files
+        for file in files {
+            if !Self::copy_file(&src_path.join(&file), &dst_path.join(&file)) {
+                return false;
+            }
+        }
+        // copy folders
+        for folder in folders {
+            if !Self::copy_folder(&src_path.join(&folder), &dst_path.join(&folder)) {
+                return false;
+            }
+        }
+        true
+    }
+
+    /// move the stored data back from /backup/{old_version}
+    /// to the storage folder after a failed upgrade
+    ///
+    /// In contrast to `restore()` the version file is not changed,
+    /// the data stays on the old version.
+    pub fn rollback(storage_path: &Path, backup_path: &Path) -> bool {
+        let (files, folders) = Self::data_entries(backup_path);
+
+        // remove partially restored data
+        for file in &files {
+            let path = storage_path.join(file);
+            if path.exists() {
+                if let Err(_) = std::fs::remove_file(&path) {
+                    println!("Error: remove file {}", path.to_string_lossy());
+                    return false;
+                }
+            }
+        }
+        for folder in &folders {
+            let path = storage_path.join(folder);
+            if path.exists() && Self::remove_folder(&path) == false {
+                return false;
+            }
+        }
+
+        // move the backup back
+        if Self::restore_content(storage_path, backup_path) == false {
+            return false;
+        }
+
+        // remove empty backup folder
+        Self::remove_folder(backup_path);
+
+        true
+    }
+
     /// restore content from /backup/{version} to storage folder
     pub fn restore(storage_path: &Path, backup_path: &Path) -> bool {
+        if Self::restore_content(storage_path, backup_path) == false {
+            return false;
+        }
+
+        // create version file
+        let version_path = storage_path.join("version");
+        let cur_version: &str = env!("CARGO_PKG_VERSION");
+        if let Err(_) = std::fs::write(version_path, cur_version) {
+            println!("failed to write current version file!");
+            return false;
+        }
+
+        true
+    }
+
+    /// move all content of the backup folder to the storage folder
+    fn restore_content(storage_path: &Path, backup_path: &Path) -> bool {
         let mut files: Vec<String> = vec![];
         let mut folders: Vec<String> = vec![];
 
@@ -140,18 +262,6 @@ impl Backup {
         if Self::move_files(&files, backup_path, storage_path) == false {
             return false;
         }
-        if Self::move_folders(&folders, backup_path, storage_path) == false {
-            return false;
-        }
-
-        // create version file
-        let version_path = storage_path.join("version");
-        let cur_version: &str = env!("CARGO_PKG_VERSION");
-        if let Err(_) = std::fs::write(version_path, cur_version) {
-            println!("failed to write current version file!");
-            return false;
-        }
-
-        true
+        Self::move_folders(&folders, backup_path, storage_path)
     }
 }
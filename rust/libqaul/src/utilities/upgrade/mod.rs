@@ -7,23 +7,168 @@
 //! with incompatible configuration or data base structure.
 //!
 //! The last version an upgrade is possible from is version 2.0.0-beta.18
+//!
+//! ## Upgrade Process
+//!
+//! 1. The stored data is moved to the backup folder `backup/{old_version}`.
+//! 2. The backup is copied to the working folder `backup/upgrade/{old_version}`.
+//! 3. All migration steps of versions newer than the old version are run
+//!    one after the next. Every step reads the data of the previous step
+//!    and creates the upgraded data in the folder `backup/upgrade/{version}`.
+//! 4. The upgraded data is moved to the storage folder.
+//!    The working folder is removed, the backup in `backup/{old_version}`
+//!    is kept to be able to return to the old version.
+//!
+//! If any step fails, the working folder is removed and the backup
+//! is moved back to the storage folder.
+//! The data stays on the old version and libqaul does not start.
+//!
+//! In a dry run, the stored data is copied to the working folder
+//! instead of moved. All migration steps are run and the upgraded
+//! data is removed afterwards. The stored data is never changed.
+//! libqaul does not start after the dry run of an upgrade.
+//!
+//! The progress of the upgrade is reported via RPC.
+//! The messages are queued until the UI reads them.
+//!
+//! ## Add a Migration Step
+//!
+//! Create a module named after the new version, with a function
+//! that reads the data of the previous version (configuration
+//! file and sled data bases) from `old_path` and writes the
+//! upgraded data to the folder `{work_path}/{version}`.
+//! Add it at the end of the `MIGRATIONS` list.
 
+use prost::Message;
 use semver::Version;
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
+use crate::rpc::Rpc;
+use crate::utilities::instance::InstanceCell;
 use crate::utilities::upgrade::backup::Backup;
 
 pub mod backup;
 pub mod v2_0_0_rc_1;
 
+/// Import protobuf message definition generated by
+/// the rust module prost-build.
+pub mod proto {
+    include!("qaul.rpc.upgrade.rs");
+}
+
+/// libqaul was stopped by the upgrade
+static STOPPED: InstanceCell<bool> = InstanceCell::new();
+
+/// Migration Step
+struct Migration {
+    /// version the data is upgraded to
+    version: &'static str,
+    /// upgrade function
+    ///
+    /// Receives the working folder and the folder of the data
+    /// of the previous version.
+    /// Returns a result, containing a tuple with ( new_version, new_path )
+    upgrade: fn(&Path, &Path) -> Result<(String, PathBuf), String>,
+}
+
+/// all migration steps, ordered by version
+///
+/// put new upgrade versions at the end of this list.
+const MIGRATIONS: &[Migration] = &[Migration {
+    version: "2.0.0-rc.1",
+    upgrade: v2_0_0_rc_1::VersionUpgrade::upgrade,
+}];
+
+/// Upgrade progress reporting
+struct Progress {
+    /// version of the stored data
+    from_version: String,
+    /// number of upgrade steps
+    steps: u32,
+    /// this is a dry run
+    dry_run: bool,
+}
+
+impl Progress {
+    /// print the progress and send it via RPC
+    fn report(&self, state: proto::UpgradeState, step: u32, step_version: &str, error: &str) {
+        let to_version: &str = env!("CARGO_PKG_VERSION");
+        match state {
+            proto::UpgradeState::Started => println!(
+                "upgrade from {} to {}: {} steps",
+                self.from_version, to_version, self.steps
+            ),
+            proto::UpgradeState::StepStarted => println!(
+                "upgrade step {}/{}: upgrade to {}",
+                step, self.steps, step_version
+            ),
+            proto::UpgradeState::StepFinished => {
+                println!("upgrade step {}/{}: finished", step, self.steps)
+            }
+            proto::UpgradeState::Finished => println!("upgrade finished"),
+            proto::UpgradeState::Failed => println!("upgrade failed: {}", error),
+            proto::UpgradeState::RolledBack => println!("data restored to {}", self.from_version),
+        }
+
+        let proto_message = proto::Upgrade {
+            message: Some(proto::upgrade::Message::UpgradeProgress(
+                proto::UpgradeProgress {
+                    state: state as i32,
+                    from_version: self.from_version.clone(),
+                    to_version: to_version.to_string(),
+                    step,
+                    steps: self.steps,
+                    step_version: step_version.to_string(),
+                    dry_run: self.dry_run,
+                    error: error.to_string(),
+                },
+            )),
+        };
+
+        // encode message
+        let mut buf = Vec::with_capacity(proto_message.encoded_len());
+        proto_message
+            .encode(&mut buf)
+            .expect("Vec<u8> provides capacity as needed");
+
+        // send message
+        Rpc::send_message(
+            buf,
+            crate::rpc::proto::Modules::Upgrade.into(),
+            "".to_string(),
+            Vec::new(),
+        );
+    }
+}
+
 /// upgrade module
 pub struct Upgrade {}
 impl Upgrade {
     /// initialize and run upgrade module
     ///
-    /// requires the path to the data storage folder
-    pub fn init(storage_path: String) -> bool {
+    /// requires the path to the data storage folder.
+    /// In a dry run the upgrade runs on a copy of the stored data.
+    ///
+    /// Returns false if libqaul can't be started.
+    pub fn init(storage_path: String, dry_run: bool) -> bool {
+        let result = Self::check(storage_path, dry_run);
+        if result == false {
+            STOPPED.set(true);
+        }
+        result
+    }
+
+    /// libqaul was stopped by the upgrade
+    ///
+    /// This is the case if the upgrade failed or after a dry run.
+    pub fn is_stopped() -> bool {
+        STOPPED.try_get().is_some()
+    }
+
+    /// check the version of the stored data and
+    /// run the upgrade if needed
+    fn check(storage_path: String, dry_run: bool) -> bool {
         // get current version from Cargo.toml
         let cur_version: &str = env!("CARGO_PKG_VERSION");
         println!("running libqaul {}", cur_version);
@@ -39,15 +184,23 @@ impl Upgrade {
                 old_version = "2.0.0-beta.8".to_string();
             }
             // create new version file otherwise
-            else if let Err(_) = fs::write(path.clone(), cur_version) {
-                println!(
-                    "failed to creating version file path: {}",
-                    path.to_str().unwrap()
-                );
-                return false;
+            else if dry_run == false {
+                if let Err(_) = fs::write(path.clone(), cur_version) {
+                    println!(
+                        "failed to creating version file path: {}",
+                        path.to_str().unwrap()
+                    );
+                    return false;
+                }
             }
         } else {
-            old_version = fs::read_to_string(path).unwrap();
+            match fs::read_to_string(&path) {
+                Ok(version) => old_version = version.trim().to_string(),
+                Err(e) => {
+                    println!("failed to read version file: {}", e);
+                    return false;
+                }
+            }
         }
 
         // check if old version is equal to new version
@@ -55,16 +208,30 @@ impl Upgrade {
             println!("libqaul data on latest version");
         } else {
             // run upgrade steps if the old and the new version differ
-            return Self::upgrade(&storage_path_buf, &old_version);
+            let result = Self::upgrade(&storage_path_buf, &old_version, dry_run);
+            return result && dry_run == false;
         }
         true
     }
 
     /// process the upgrade steps one after the next
-    fn upgrade(storage_path: &Path, old_version: &str) -> bool {
+    fn upgrade(storage_path: &Path, old_version: &str, dry_run: bool) -> bool {
         println!("running upgrade check for version {}", old_version);
 
-        let mut version = Version::parse(old_version).unwrap();
+        let mut progress = Progress {
+            from_version: old_version.to_string(),
+            steps: 0,
+            dry_run,
+        };
+
+        let version = match Version::parse(old_version) {
+            Ok(version) => version,
+            Err(e) => {
+                let error = format!("invalid version {}: {}", old_version, e);
+                progress.report(proto::UpgradeState::Failed, 0, "", &error);
+                return false;
+            }
+        };
 
         // check if libqaul is upgradable
         // the last possible upgradable version at the moment is 2.0.0-beta.18
@@ -81,42 +248,121 @@ impl Upgrade {
                 last_upgradable_version
             );
 
+            let error = format!(
+                "version {} is too old, upgrade to {} first",
+                version, last_upgradable_version
+            );
+            progress.report(proto::UpgradeState::Failed, 0, "", &error);
             return false;
         }
 
+        // collect the migration steps newer than the stored data
+        let cur_version = Version::parse(env!("CARGO_PKG_VERSION")).unwrap();
+        let migrations: Vec<&Migration> = MIGRATIONS
+            .iter()
+            .filter(|migration| {
+                let migration_version = Version::parse(migration.version).unwrap();
+                version < migration_version && migration_version <= cur_version
+            })
+            .collect();
+
+        progress.steps = migrations.len() as u32;
+        progress.report(proto::UpgradeState::Started, 0, "", "");
+
         // Move the existing content to the backup folder
-        if Backup::backup(storage_path, old_version) == false {
-            println!("backup failed");
-            return false;
+        // or copy it in a dry run
+        let backup_path = storage_path.join("backup").join(old_version);
+        let work_path = storage_path.join("backup").join("upgrade");
+        Backup::remove_folder(&work_path);
+        let mut data_path = work_path.join(old_version);
+        if dry_run {
+            if Backup::copy(storage_path, &data_path) == false {
+                return Self::fail(&progress, "copy of the data failed", storage_path);
+            }
+        } else {
+            if Backup::backup(storage_path, old_version) == false {
+                return Self::fail(&progress, "backup failed", storage_path);
+            }
+            if Backup::copy(&backup_path, &data_path) == false {
+                return Self::fail(&progress, "copy of the backup failed", storage_path);
+            }
         }
-        let mut backup_path = storage_path.join("backup").join(old_version);
 
         // upgrade one version after the other
-        // put new upgrade version below this chain.
-
-        // upgrade to version 2.0.0-rc.1
-        if version < Version::parse("2.0.0-rc.1").unwrap() {
-            match v2_0_0_rc_1::VersionUpgrade::upgrade(storage_path, &backup_path) {
-                Ok((new_version, new_path)) => {
-                    // update values
-                    version = Version::parse(&new_version).unwrap();
-                    backup_path = new_path;
+        for (i, migration) in migrations.iter().enumerate() {
+            let step = i as u32 + 1;
+            progress.report(
+                proto::UpgradeState::StepStarted,
+                step,
+                migration.version,
+                "",
+            );
+
+            match (migration.upgrade)(&work_path, &data_path) {
+                Ok((_new_version, new_path)) => {
+                    data_path = new_path;
+                    progress.report(
+                        proto::UpgradeState::StepFinished,
+                        step,
+                        migration.version,
+                        "",
+                    );
                 }
                 Err(e) => {
-                    println!("Upgrade to 2.0.0-rc.1 failed: {}", e);
-                    return false;
+                    let error = format!("upgrade to {} failed: {}", migration.version, e);
+                    return Self::fail(&progress, &error, storage_path);
                 }
             }
         }
 
-        // restore the upgraded last version
-        if backup::Backup::restore(&storage_path, &backup_path) == true {
-            // remove the latest upgraded
-            backup::Backup::remove_folder(&storage_path.join("backup").join(version.to_string()));
-            println!("libqaul successfully upgraded");
+        // remove the upgraded data in a dry run
+        if dry_run {
+            Backup::remove_folder(&work_path);
+            progress.report(proto::UpgradeState::Finished, progress.steps, "", "");
             return true;
         }
 
+        // restore the upgraded last version
+        if Backup::restore(storage_path, &data_path) == false {
+            return Self::fail(
+                &progress,
+                "restoring the upgraded data failed",
+                storage_path,
+            );
+        }
+
+        // remove the working folder, the backup is kept
+        Backup::remove_folder(&work_path);
+        progress.report(proto::UpgradeState::Finished, progress.steps, "", "");
+        println!("libqaul successfully upgraded");
+
+        true
+    }
+
+    /// report a failed upgrade and roll it back
+    ///
+    /// The upgraded data is removed and the backup is moved
+    /// back to the storage folder.
+    fn fail(progress: &Progress, error: &str, storage_path: &Path) -> bool {
+        progress.report(proto::UpgradeState::Failed, 0, "", error);
+
+        // remove the upgraded data
+        let backup_folder = storage_path.join("backup");
+        Backup::remove_folder(&backup_folder.join("upgrade"));
+
+        // move the backup back to the storage folder
+        let backup_path = backup_folder.join(&progress.from_version);
+        if progress.dry_run == false && backup_path.exists() {
+            if Backup::rollback(storage_path, &backup_path) {
+                progress.report(proto::UpgradeState::RolledBack, 0, "", "");
+            } else {
+                println!(
+                    "rollback failed, the data is saved in {}",
+                    backup_path.to_string_lossy()
+                );
+            }
+        }
+
         false
     }
 }
@@ -0,0 +1,99 @@
+// This file is @generated by prost-build.
+/// Libqaul RPC Upgrade Messages
+///
+/// The upgrade runs on startup, before libqaul has finished
+/// initializing. The messages are queued in the RPC channel
+/// until they are read by the UI.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct Upgrade {
+    /// message type
+    #[prost(oneof = "upgrade::Message", tags = "1")]
+    pub message: ::core::option::Option<upgrade::Message>,
+}
+/// Nested message and enum types in `Upgrade`.
+pub mod upgrade {
+    /// message type
+    #[derive(Clone, PartialEq, ::prost::Oneof)]
+    pub enum Message {
+        /// progress of the upgrade
+        #[prost(message, tag = "1")]
+        UpgradeProgress(super::UpgradeProgress),
+    }
+}
+/// Upgrade Progress
+///
+/// libqaul sends this message whenever the state
+/// of the upgrade changes.
+#[derive(Clone, PartialEq, ::prost::Message)]
+pub struct UpgradeProgress {
+    /// state of the upgrade
+    #[prost(enumeration = "UpgradeState", tag = "1")]
+    pub state: i32,
+    /// version of the stored data before the upgrade
+    #[prost(string, tag = "2")]
+    pub from_version: ::prost::alloc::string::String,
+    /// version of libqaul
+    #[prost(string, tag = "3")]
+    pub to_version: ::prost::alloc::string::String,
+    /// number of the current upgrade step, starting at 1
+    #[prost(uint32, tag = "4")]
+    pub step: u32,
+    /// number of upgrade steps
+    #[prost(uint32, tag = "5")]
+    pub steps: u32,
+    /// version the current step upgrades to
+    #[prost(string, tag = "6")]
+    pub step_version: ::prost::alloc::string::String,
+    /// the upgrade runs on a copy of the data,
+    /// the stored data is not changed
+    #[prost(bool, tag = "7")]
+    pub dry_run: bool,
+    /// error message if the upgrade failed
+    #[prost(string, tag = "8")]
+    pub error: ::prost::alloc::string::String,
+}
+/// Upgrade State
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, ::prost::Enumeration)]
+#[repr(i32)]
+pub enum UpgradeState {
+    /// the upgrade started
+    Started = 0,
+    /// an upgrade step started
+    StepStarted = 1,
+    /// an upgrade step finished successfully
+    StepFinished = 2,
+    /// all upgrade steps finished successfully
+    Finished = 3,
+    /// the upgrade failed
+    Failed = 4,
+    /// the stored data was restored after a failed upgrade
+    RolledBack = 5,
+}
+impl UpgradeState {
+    /// String value of the enum field names used in the ProtoBuf definition.
+    ///
+    /// The values are not transformed in any way and thus are considered stable
+    /// (if the ProtoBuf definition does not change) and safe for programmatic use.
+    pub fn as_str_name(&self) -> &'static str {
+        match self {
+            Self::Started => "STARTED",
+            Self::StepStarted => "STEP_STARTED",
+            Self::StepFinished => "STEP_FINISHED",
+            Self::Finished => "FINISHED",
+            Self::Failed => "FAILED",
+            Self::RolledBack => "ROLLED_BACK",
+        }
+    }
+    /// Creates an enum from field names used in the ProtoBuf definition.
+    pub fn from_str_name(value: &str) -> ::core::option::Option<Self> {
+        match value {
+            "STARTED" => Some(Self::Started),
+            "STEP_STARTED" => Some(Self::StepStarted),
+            "STEP_FINISHED" => Some(Self::StepFinished),
+            "FINISHED" => Some(Self::Finished),
+            "FAILED" => Some(Self::Failed),
+            "ROLLED_BACK" => Some(Self::RolledBack),
+            _ => None,
+        }
+    }
+}
@@ -18,11 +18,13 @@ pub struct VersionUpgrade {}
 impl VersionUpgrade {
     /// Upgrade to new Version
     ///
+    /// The upgraded data is created in a new folder in the `work_path`.
+    ///
     /// Returns a result, containing a tuple with ( new_version, new_path )
-    pub fn upgrade(storage_path: &Path, old_path: &Path) -> Result<(String, PathBuf), String> {
+    pub fn upgrade(work_path: &Path, old_path: &Path) -> Result<(String, PathBuf), String> {
         let version = "2.0.0-rc.1";
         println!("upgrade to version {}", version);
-        let new_path = storage_path.join("backup").join(version);
+        let new_path = work_path.join(version);
 
         // cleanup dest
         backup::Backup::remove_folder(&new_path);
@@ -107,6 +109,7 @@ impl VersionUpgrade {
                     storage: crate::storage::configuration::StorageOptions {
                         users: user.storage.users.clone(),
                         size_total: user.storage.size_total,
+                        ..Default::default()
                     },
                 });
             }
@@ -122,6 +125,7 @@ impl VersionUpgrade {
                 ping_neighbour_period: old_cfg.routing.ping_neighbour_period,
                 hop_count_penalty: old_cfg.routing.hop_count_penalty,
                 maintain_period_limit: old_cfg.routing.maintain_period_limit,
+                ..Default::default()
             };
 
             // create new configuration structure
@@ -132,6 +136,10 @@ impl VersionUpgrade {
                 user_accounts,
                 debug,
                 routing,
+                encryption: crate::storage::configuration::EncryptionOptions::default(),
+                maintenance: crate::storage::configuration::MaintenanceOptions::default(),
+                messaging: crate::storage::configuration::MessagingOptions::default(),
+                rate_limits: crate::storage::configuration::RateLimitOptions::default(),
             };
 
             // save new configuration to file